@@ -30,6 +30,7 @@ use agi_core::{
     ContextRequest,
     ContextResponse,
     ContextSummarySchema,
+    CountTokensRequest,
     EnrichedContext,
     GetStateRequest,
     GetUserRequest,
@@ -356,6 +357,37 @@ impl ContextManagerServer {
         }
     }
 
+    // Count tokens for a batch of texts with the LLM service's model tokenizer,
+    // falling back to the 4 chars = 1 token approximation if it is unavailable
+    async fn count_tokens(&self, texts: &[&str]) -> Vec<i32> {
+        if texts.is_empty() {
+            return Vec::new();
+        }
+        if let Some(mut llm_client) = self.get_llm_client().await {
+            let count_request = CountTokensRequest {
+                texts: texts.iter().map(|text| text.to_string()).collect(),
+                model: String::new(),
+                provider: String::new(),
+            };
+            match llm_client
+                .count_tokens(tonic::Request::new(count_request))
+                .await
+            {
+                Ok(response) => {
+                    let inner = response.into_inner();
+                    if inner.token_counts.len() == texts.len() {
+                        return inner.token_counts.into_iter().map(|n| n as i32).collect();
+                    }
+                    log::warn!("CountTokens returned a mismatched batch; estimating tokens");
+                }
+                Err(e) => {
+                    log::warn!("CountTokens failed, estimating tokens: {}", e);
+                }
+            }
+        }
+        texts.iter().map(|text| self.estimate_tokens(text)).collect()
+    }
+
     // Estimate token count (simple approximation: 4 chars = 1 token)
     fn estimate_tokens(&self, text: &str) -> i32 {
         (text.len() / 4) as i32
@@ -439,8 +471,10 @@ impl ContextManagerService for ContextManagerServer {
         let mut selected_entries = Vec::new();
         let mut token_count = 0;
 
-        for entry in all_entries {
-            let entry_tokens = self.estimate_tokens(&entry.content);
+        let contents: Vec<&str> = all_entries.iter().map(|e| e.content.as_str()).collect();
+        let entry_token_counts = self.count_tokens(&contents).await;
+
+        for (entry, entry_tokens) in all_entries.into_iter().zip(entry_token_counts) {
             if token_count + entry_tokens <= max_tokens {
                 token_count += entry_tokens;
                 selected_entries.push(entry);
@@ -30,6 +30,9 @@ pub mod agi_core {
 use agi_core::{
     EmergencyDirective,
     // LLM Service types
+    CountTokensRequest,
+    DescribeToolsRequest,
+    GenerateFromTemplateRequest,
     GenerateRequest,
     GenerateResponse,
     HealthRequest,
@@ -38,6 +41,7 @@ use agi_core::{
     ListToolsResponse,
     LlmProcessRequest,
     LlmProcessResponse,
+    RenderPromptRequest,
     // Logging Service types
     LogEntry,
     LogResponse,
@@ -388,6 +392,121 @@ impl DataRouterServer {
                     }
                 }
             }
+            "render_prompt" => {
+                // Deserialize RenderPromptRequest from payload
+                let render_req = RenderPromptRequest::decode(payload.as_slice()).map_err(|e| {
+                    Status::invalid_argument(format!("Failed to decode RenderPromptRequest: {}", e))
+                })?;
+
+                // Prepare client
+                let mut client_clone = client.clone();
+
+                // Execute with circuit breaker protection
+                match cb
+                    .execute("llm", async move {
+                        // Actual service call
+                        let response = client_clone
+                            .render_prompt(tonic::Request::new(render_req))
+                            .await
+                            .map_err(|e| {
+                                std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
+                            })?;
+                        Ok::<_, std::io::Error>(response.into_inner())
+                    })
+                    .await
+                {
+                    Ok(render_resp) => {
+                        // Success - serialize response
+                        let mut buf = Vec::new();
+                        render_resp.encode(&mut buf).map_err(|e| {
+                            Status::internal(format!("Failed to encode RenderPromptResponse: {}", e))
+                        })?;
+                        buf
+                    }
+                    Err(e) => {
+                        // Error already recorded by circuit breaker
+                        return Err(Status::internal(format!("LLM Service error: {}", e)));
+                    }
+                }
+            }
+            "generate_from_template" => {
+                // Deserialize GenerateFromTemplateRequest from payload
+                let template_req =
+                    GenerateFromTemplateRequest::decode(payload.as_slice()).map_err(|e| {
+                        Status::invalid_argument(format!(
+                            "Failed to decode GenerateFromTemplateRequest: {}",
+                            e
+                        ))
+                    })?;
+
+                // Prepare client
+                let mut client_clone = client.clone();
+
+                // Execute with circuit breaker protection
+                match cb
+                    .execute("llm", async move {
+                        // Actual service call
+                        let response = client_clone
+                            .generate_from_template(tonic::Request::new(template_req))
+                            .await
+                            .map_err(|e| {
+                                std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
+                            })?;
+                        Ok::<_, std::io::Error>(response.into_inner())
+                    })
+                    .await
+                {
+                    Ok(generate_resp) => {
+                        // Success - serialize response
+                        let mut buf = Vec::new();
+                        generate_resp.encode(&mut buf).map_err(|e| {
+                            Status::internal(format!("Failed to encode GenerateResponse: {}", e))
+                        })?;
+                        buf
+                    }
+                    Err(e) => {
+                        // Error already recorded by circuit breaker
+                        return Err(Status::internal(format!("LLM Service error: {}", e)));
+                    }
+                }
+            }
+            "count_tokens" => {
+                // Deserialize CountTokensRequest from payload
+                let count_req = CountTokensRequest::decode(payload.as_slice()).map_err(|e| {
+                    Status::invalid_argument(format!("Failed to decode CountTokensRequest: {}", e))
+                })?;
+
+                // Prepare client
+                let mut client_clone = client.clone();
+
+                // Execute with circuit breaker protection
+                match cb
+                    .execute("llm", async move {
+                        // Actual service call
+                        let response = client_clone
+                            .count_tokens(tonic::Request::new(count_req))
+                            .await
+                            .map_err(|e| {
+                                std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
+                            })?;
+                        Ok::<_, std::io::Error>(response.into_inner())
+                    })
+                    .await
+                {
+                    Ok(count_resp) => {
+                        // Success - serialize response
+                        let mut buf = Vec::new();
+                        count_resp.encode(&mut buf).map_err(|e| {
+                            Status::internal(format!("Failed to encode CountTokensResponse: {}", e))
+                        })?;
+                        buf
+                    }
+                    Err(e) => {
+                        // Error already recorded by circuit breaker
+                        return Err(Status::internal(format!("LLM Service error: {}", e)));
+                    }
+                }
+            }
             "embed_text" => {
                 // Deserialize LLMProcessRequest from payload
                 let embed_req = LlmProcessRequest::decode(payload.as_slice()).map_err(|e| {
@@ -527,6 +646,32 @@ impl DataRouterServer {
                 })?;
                 buf
             }
+            "describe_tools" => {
+                // Deserialize DescribeToolsRequest from payload
+                let describe_req =
+                    DescribeToolsRequest::decode(payload.as_slice()).map_err(|e| {
+                        Status::invalid_argument(format!(
+                            "Failed to decode DescribeToolsRequest: {}",
+                            e
+                        ))
+                    })?;
+
+                // Call the client
+                let mut client = client;
+                let response = client
+                    .describe_tools(tonic::Request::new(describe_req))
+                    .await
+                    .map_err(|e| Status::internal(format!("Tools Service error: {}", e)))?;
+
+                let describe_resp = response.into_inner();
+
+                // Serialize response back to bytes
+                let mut buf = Vec::new();
+                describe_resp.encode(&mut buf).map_err(|e| {
+                    Status::internal(format!("Failed to encode DescribeToolsResponse: {}", e))
+                })?;
+                buf
+            }
             _ => {
                 return Err(Status::invalid_argument(format!(
                     "Unknown Tools Service method: {}",
@@ -0,0 +1,715 @@
+// executor-rs/src/artifacts.rs
+// Per-execution workspaces and the content-addressed artifact store
+// PHOENIX ORCH: The Ashen Guard Edition AGI
+//
+// UploadFiles stages input files in a workspace and returns its id. The
+// execution naming that id takes the workspace over: the command runs with it
+// as working directory (`/scratch` in the Linux sandbox), files matching the
+// request's output globs are copied into the artifact store afterwards and
+// the workspace is deleted. Executions without a workspace id get an empty
+// one. Artifacts are stored under the SHA-256 of their content, so identical
+// outputs share one file. A periodic cleanup deletes unused staged
+// workspaces, artifacts past their retention and, when the store grows past
+// its size limit, the least recently produced artifacts.
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+use crate::config::ExecutorConfig;
+
+/// URL prefix of artifact references; the rest is the artifact id
+pub const ARTIFACT_URL_SCHEME: &str = "artifact://";
+
+/// Most artifacts collected from one execution
+const MAX_ARTIFACTS_PER_EXECUTION: usize = 100;
+
+/// Interval of the background cleanup
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(300);
+
+// Name prefixes of the directories in the workspace root
+const STAGED_PREFIX: &str = "staged-";
+const EXECUTION_PREFIX: &str = "exec-";
+
+// Name prefix of partially written artifacts
+const TEMP_PREFIX: &str = ".tmp-";
+
+// Execution workspaces in use; cleanup never touches them
+static ACTIVE_WORKSPACES: Lazy<Mutex<HashSet<PathBuf>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Workspace and artifact store errors
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArtifactError {
+    InvalidArgument(String),
+    NotFound(String),
+    Internal(String),
+}
+
+impl fmt::Display for ArtifactError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArtifactError::InvalidArgument(msg) => write!(f, "Invalid argument: {}", msg),
+            ArtifactError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            ArtifactError::Internal(msg) => write!(f, "Internal error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ArtifactError {}
+
+fn internal(context: &str, e: io::Error) -> ArtifactError {
+    ArtifactError::Internal(format!("{}: {}", context, e))
+}
+
+/// An output file collected into the store
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArtifactInfo {
+    /// SHA-256 of the content, hex encoded
+    pub id: String,
+    /// Path of the file relative to the workspace
+    pub path: String,
+    pub size_bytes: u64,
+}
+
+impl ArtifactInfo {
+    pub fn url(&self) -> String {
+        format!("{}{}", ARTIFACT_URL_SCHEME, self.id)
+    }
+}
+
+/// Content of a stored artifact
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    pub id: String,
+    pub content: Vec<u8>,
+    /// Unix seconds when the artifact was last produced
+    pub stored_at: i64,
+}
+
+/// Result of an UploadFiles call
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadSummary {
+    pub workspace_id: String,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// What a cleanup pass deleted
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CleanupReport {
+    pub workspaces_removed: usize,
+    pub artifacts_removed: usize,
+    pub bytes_freed: u64,
+}
+
+/// Workspace of one execution; deleted when dropped
+#[derive(Debug)]
+pub struct ExecutionWorkspace {
+    path: PathBuf,
+}
+
+impl ExecutionWorkspace {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ExecutionWorkspace {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_dir_all(&self.path) {
+            log::warn!("Failed to remove workspace {}: {}", self.path.display(), e);
+        }
+        ACTIVE_WORKSPACES.lock().unwrap().remove(&self.path);
+    }
+}
+
+/// Workspaces and the artifact store, with locations and limits from ExecutorConfig
+#[derive(Debug, Clone)]
+pub struct ArtifactStore {
+    workspace_dir: PathBuf,
+    artifact_dir: PathBuf,
+    workspace_ttl: Duration,
+    max_upload_bytes: u64,
+    retention: Duration,
+    max_store_bytes: u64,
+}
+
+impl ArtifactStore {
+    pub fn from_config(config: &ExecutorConfig) -> Self {
+        let workspace_dir = if config.workspace_dir.is_empty() {
+            // Next to the sandbox directories when that is a path on this host
+            let sandbox_dir = Path::new(&config.sandbox_dir);
+            if sandbox_dir.is_absolute() {
+                sandbox_dir.join("workspaces")
+            } else {
+                std::env::temp_dir()
+                    .join("phoenix_sandbox")
+                    .join("workspaces")
+            }
+        } else {
+            PathBuf::from(&config.workspace_dir)
+        };
+        let artifact_dir = if config.artifact_dir.is_empty() {
+            std::env::temp_dir().join("phoenix_artifacts")
+        } else {
+            PathBuf::from(&config.artifact_dir)
+        };
+
+        Self {
+            workspace_dir,
+            artifact_dir,
+            workspace_ttl: Duration::from_secs(config.workspace_ttl_seconds),
+            max_upload_bytes: config.max_upload_mb * 1024 * 1024,
+            retention: Duration::from_secs(config.artifact_retention_seconds),
+            max_store_bytes: config.artifact_store_max_mb * 1024 * 1024,
+        }
+    }
+
+    /// Write files into a staged workspace, creating it when `workspace_id` is empty
+    ///
+    /// File paths are relative to the workspace and may create subdirectories.
+    /// The files of a workspace may not exceed `max_upload_mb` in total.
+    pub fn upload_files(
+        &self,
+        workspace_id: &str,
+        files: &[(String, Vec<u8>)],
+    ) -> Result<UploadSummary, ArtifactError> {
+        let targets = files
+            .iter()
+            .map(|(path, _)| relative_path(path))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (workspace_id, dir, existing_bytes) = if workspace_id.is_empty() {
+            let workspace_id = uuid::Uuid::new_v4().to_string();
+            let dir = self.staged_dir(&workspace_id)?;
+            (workspace_id, dir, 0)
+        } else {
+            let dir = self.staged_dir(workspace_id)?;
+            if !dir.is_dir() {
+                return Err(ArtifactError::NotFound(format!(
+                    "Workspace {}",
+                    workspace_id
+                )));
+            }
+            (workspace_id.to_string(), dir.clone(), directory_size(&dir))
+        };
+
+        let bytes: u64 = files.iter().map(|(_, content)| content.len() as u64).sum();
+        if existing_bytes + bytes > self.max_upload_bytes {
+            return Err(ArtifactError::InvalidArgument(format!(
+                "Workspace files exceed the upload limit of {} MB",
+                self.max_upload_bytes / (1024 * 1024)
+            )));
+        }
+
+        fs::create_dir_all(&dir).map_err(|e| internal("Failed to create workspace", e))?;
+        for (target, (_, content)) in targets.iter().zip(files) {
+            let path = dir.join(target);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| internal("Failed to create workspace directory", e))?;
+            }
+            fs::write(&path, content).map_err(|e| internal("Failed to write file", e))?;
+        }
+
+        log::info!(
+            "Uploaded {} files ({} bytes) to workspace {}",
+            files.len(),
+            bytes,
+            workspace_id
+        );
+        Ok(UploadSummary {
+            workspace_id,
+            files: files.len(),
+            bytes,
+        })
+    }
+
+    /// Workspace for one execution: the staged workspace `workspace_id`, which
+    /// can only be used once, or a new empty one when the id is empty
+    pub fn open_workspace(&self, workspace_id: &str) -> Result<ExecutionWorkspace, ArtifactError> {
+        let path = self
+            .workspace_dir
+            .join(format!("{}{}", EXECUTION_PREFIX, uuid::Uuid::new_v4()));
+        ACTIVE_WORKSPACES.lock().unwrap().insert(path.clone());
+        // From here on dropping the workspace unregisters and removes it
+        let workspace = ExecutionWorkspace { path };
+
+        if workspace_id.is_empty() {
+            fs::create_dir_all(&workspace.path)
+                .map_err(|e| internal("Failed to create workspace", e))?;
+        } else {
+            // Renaming claims the staged workspace atomically
+            fs::rename(self.staged_dir(workspace_id)?, &workspace.path).map_err(|e| {
+                if e.kind() == io::ErrorKind::NotFound {
+                    ArtifactError::NotFound(format!("Workspace {}", workspace_id))
+                } else {
+                    internal("Failed to open workspace", e)
+                }
+            })?;
+        }
+        Ok(workspace)
+    }
+
+    /// Copy the regular files of `workspace` matching `outputs` into the store
+    ///
+    /// Symlinks are not followed.
+    pub fn collect(
+        &self,
+        workspace: &ExecutionWorkspace,
+        outputs: &GlobSet,
+    ) -> Result<Vec<ArtifactInfo>, ArtifactError> {
+        let mut artifacts = Vec::new();
+        if outputs.is_empty() {
+            return Ok(artifacts);
+        }
+
+        for entry in WalkDir::new(&workspace.path)
+            .follow_links(false)
+            .sort_by_file_name()
+            .into_iter()
+            .flatten()
+        {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Some(path) = entry
+                .path()
+                .strip_prefix(&workspace.path)
+                .ok()
+                .and_then(slash_path)
+            else {
+                continue;
+            };
+            if !outputs.is_match(&path) {
+                continue;
+            }
+            if artifacts.len() == MAX_ARTIFACTS_PER_EXECUTION {
+                log::warn!(
+                    "Workspace has more than {} output files; ignoring the rest",
+                    MAX_ARTIFACTS_PER_EXECUTION
+                );
+                break;
+            }
+
+            let (id, size_bytes) = self
+                .store_file(entry.path())
+                .map_err(|e| internal("Failed to store artifact", e))?;
+            log::debug!(
+                "Stored artifact {} ({} bytes) from {}",
+                id,
+                size_bytes,
+                path
+            );
+            artifacts.push(ArtifactInfo {
+                id,
+                path,
+                size_bytes,
+            });
+        }
+        Ok(artifacts)
+    }
+
+    /// Read a stored artifact
+    pub fn get_artifact(&self, id: &str) -> Result<Artifact, ArtifactError> {
+        if !is_artifact_id(id) {
+            return Err(ArtifactError::InvalidArgument(format!(
+                "Malformed artifact id: {}",
+                id
+            )));
+        }
+        let path = self.artifact_dir.join(id);
+        let not_found = || ArtifactError::NotFound(format!("Artifact {}", id));
+
+        let modified = match fs::metadata(&path).and_then(|meta| meta.modified()) {
+            Ok(modified) => modified,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(not_found()),
+            Err(e) => return Err(internal("Failed to read artifact", e)),
+        };
+        // Expired artifacts are gone even before the next cleanup removes them
+        if age(modified) > self.retention {
+            let _ = fs::remove_file(&path);
+            return Err(not_found());
+        }
+
+        let content = fs::read(&path).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => not_found(),
+            _ => internal("Failed to read artifact", e),
+        })?;
+        Ok(Artifact {
+            id: id.to_string(),
+            content,
+            stored_at: unix_seconds(modified),
+        })
+    }
+
+    /// Delete unused staged workspaces, leftover execution workspaces,
+    /// expired artifacts and, beyond the store size limit, the oldest artifacts
+    pub fn cleanup(&self) -> CleanupReport {
+        let mut report = CleanupReport::default();
+
+        if let Ok(entries) = fs::read_dir(&self.workspace_dir) {
+            let active = ACTIVE_WORKSPACES.lock().unwrap().clone();
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let name = entry.file_name().to_string_lossy().to_string();
+                let expired = if name.starts_with(STAGED_PREFIX) {
+                    modified_age(&path).is_some_and(|age| age > self.workspace_ttl)
+                } else {
+                    // Execution workspaces not in use were left behind by a restart
+                    name.starts_with(EXECUTION_PREFIX) && !active.contains(&path)
+                };
+                if expired && fs::remove_dir_all(&path).is_ok() {
+                    report.workspaces_removed += 1;
+                }
+            }
+        }
+
+        let mut stored = Vec::new();
+        if let Ok(entries) = fs::read_dir(&self.artifact_dir) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let Ok(meta) = entry.metadata() else {
+                    continue;
+                };
+                let Ok(modified) = meta.modified() else {
+                    continue;
+                };
+                let expired = if name.starts_with(TEMP_PREFIX) {
+                    age(modified) > self.workspace_ttl
+                } else if is_artifact_id(&name) {
+                    age(modified) > self.retention
+                } else {
+                    continue;
+                };
+
+                if expired {
+                    if fs::remove_file(entry.path()).is_ok() {
+                        report.artifacts_removed += 1;
+                        report.bytes_freed += meta.len();
+                    }
+                } else if is_artifact_id(&name) {
+                    stored.push((modified, meta.len(), entry.path()));
+                }
+            }
+        }
+
+        let mut total: u64 = stored.iter().map(|(_, size, _)| size).sum();
+        stored.sort();
+        for (_, size, path) in stored {
+            if total <= self.max_store_bytes {
+                break;
+            }
+            if fs::remove_file(&path).is_ok() {
+                total -= size;
+                report.artifacts_removed += 1;
+                report.bytes_freed += size;
+            }
+        }
+
+        report
+    }
+
+    // Copy a file into the store while hashing it; returns (id, size)
+    fn store_file(&self, source: &Path) -> io::Result<(String, u64)> {
+        fs::create_dir_all(&self.artifact_dir)?;
+        let temp = self
+            .artifact_dir
+            .join(format!("{}{}", TEMP_PREFIX, uuid::Uuid::new_v4()));
+
+        let copied = (|| {
+            let mut input = File::open(source)?;
+            let mut output = File::create(&temp)?;
+            let mut hasher = Sha256::new();
+            let mut buf = vec![0u8; 64 * 1024];
+            let mut size = 0u64;
+            loop {
+                let read = input.read(&mut buf)?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buf[..read]);
+                output.write_all(&buf[..read])?;
+                size += read as u64;
+            }
+            output.sync_all()?;
+            Ok::<_, io::Error>((format!("{:x}", hasher.finalize()), size))
+        })();
+        let (id, size) = match copied {
+            Ok(copied) => copied,
+            Err(e) => {
+                let _ = fs::remove_file(&temp);
+                return Err(e);
+            }
+        };
+
+        let target = self.artifact_dir.join(&id);
+        if target.exists() {
+            // Same content already stored; producing it again restarts its retention
+            fs::remove_file(&temp)?;
+            File::options()
+                .append(true)
+                .open(&target)?
+                .set_modified(SystemTime::now())?;
+        } else {
+            fs::rename(&temp, &target)?;
+        }
+        Ok((id, size))
+    }
+
+    fn staged_dir(&self, workspace_id: &str) -> Result<PathBuf, ArtifactError> {
+        if uuid::Uuid::parse_str(workspace_id).is_err() {
+            return Err(ArtifactError::InvalidArgument(format!(
+                "Malformed workspace id: {}",
+                workspace_id
+            )));
+        }
+        Ok(self
+            .workspace_dir
+            .join(format!("{}{}", STAGED_PREFIX, workspace_id)))
+    }
+}
+
+/// Matcher for the output globs of an execution
+///
+/// Globs match paths relative to the workspace with `/` separators; `*` stays
+/// within one directory and `**` crosses directories.
+pub fn output_matcher(globs: &[String]) -> Result<GlobSet, ArtifactError> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        let glob = GlobBuilder::new(glob)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| ArtifactError::InvalidArgument(format!("Invalid output glob: {}", e)))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| ArtifactError::InvalidArgument(format!("Invalid output globs: {}", e)))
+}
+
+/// Run `ArtifactStore::cleanup` periodically with the current configuration
+pub fn start_cleanup_task() {
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+        loop {
+            interval.tick().await;
+            let store = ArtifactStore::from_config(&crate::config::load_env_config());
+            let report = tokio::task::spawn_blocking(move || store.cleanup())
+                .await
+                .unwrap_or_default();
+            if report != CleanupReport::default() {
+                log::info!(
+                    "Artifact cleanup removed {} workspaces and {} artifacts ({} bytes)",
+                    report.workspaces_removed,
+                    report.artifacts_removed,
+                    report.bytes_freed
+                );
+            }
+        }
+    });
+}
+
+// Validate an uploaded file path: relative, no `..`, not empty
+fn relative_path(path: &str) -> Result<PathBuf, ArtifactError> {
+    let invalid = || ArtifactError::InvalidArgument(format!("Invalid file path: {}", path));
+    let mut relative = PathBuf::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => return Err(invalid()),
+        }
+    }
+    if relative.as_os_str().is_empty() {
+        return Err(invalid());
+    }
+    Ok(relative)
+}
+
+// A relative path with `/` separators, for glob matching and reporting
+fn slash_path(path: &Path) -> Option<String> {
+    let parts = path
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+    Some(parts.join("/"))
+}
+
+fn is_artifact_id(id: &str) -> bool {
+    id.len() == 64 && id.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+fn directory_size(dir: &Path) -> u64 {
+    WalkDir::new(dir)
+        .follow_links(false)
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+fn age(time: SystemTime) -> Duration {
+    SystemTime::now().duration_since(time).unwrap_or_default()
+}
+
+fn modified_age(path: &Path) -> Option<Duration> {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .map(age)
+}
+
+fn unix_seconds(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_store(name: &str) -> ArtifactStore {
+        let base = std::env::temp_dir().join(format!("artifacts-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        ArtifactStore::from_config(&ExecutorConfig {
+            workspace_dir: base.join("workspaces").to_string_lossy().to_string(),
+            artifact_dir: base.join("store").to_string_lossy().to_string(),
+            max_upload_mb: 1,
+            ..ExecutorConfig::default()
+        })
+    }
+
+    fn files(entries: &[(&str, &str)]) -> Vec<(String, Vec<u8>)> {
+        entries
+            .iter()
+            .map(|(path, content)| (path.to_string(), content.as_bytes().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn test_upload_execute_and_collect() {
+        let store = test_store("collect");
+        let upload = store
+            .upload_files("", &files(&[("input.txt", "in"), ("data/rows.csv", "a,b")]))
+            .unwrap();
+        assert_eq!((upload.files, upload.bytes), (2, 5));
+        store
+            .upload_files(&upload.workspace_id, &files(&[("extra.txt", "more")]))
+            .unwrap();
+
+        let workspace = store.open_workspace(&upload.workspace_id).unwrap();
+        assert_eq!(
+            fs::read_to_string(workspace.path().join("data/rows.csv")).unwrap(),
+            "a,b"
+        );
+        // A staged workspace belongs to a single execution
+        assert!(matches!(
+            store.open_workspace(&upload.workspace_id),
+            Err(ArtifactError::NotFound(_))
+        ));
+
+        fs::create_dir_all(workspace.path().join("out/nested")).unwrap();
+        fs::write(workspace.path().join("out/report.txt"), "result").unwrap();
+        fs::write(workspace.path().join("out/nested/copy.txt"), "result").unwrap();
+        fs::write(workspace.path().join("out/skip.log"), "log").unwrap();
+        let outputs = output_matcher(&["out/**/*.txt".to_string()]).unwrap();
+        let artifacts = store.collect(&workspace, &outputs).unwrap();
+
+        let paths: Vec<&str> = artifacts.iter().map(|a| a.path.as_str()).collect();
+        assert_eq!(paths, ["out/nested/copy.txt", "out/report.txt"]);
+        // Identical content is stored once under its SHA-256
+        assert_eq!(artifacts[0].id, artifacts[1].id);
+        assert_eq!(artifacts[0].id, format!("{:x}", Sha256::digest(b"result")));
+        assert_eq!(artifacts[0].size_bytes, 6);
+        let artifact = store.get_artifact(&artifacts[0].id).unwrap();
+        assert_eq!(artifact.content, b"result");
+
+        let path = workspace.path().to_path_buf();
+        drop(workspace);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        let store = test_store("reject");
+        for path in ["../escape.txt", "/etc/passwd", "", "a/../../b"] {
+            assert!(matches!(
+                store.upload_files("", &files(&[(path, "x")])),
+                Err(ArtifactError::InvalidArgument(_))
+            ));
+        }
+        let too_large = vec![("big.bin".to_string(), vec![0u8; 2 * 1024 * 1024])];
+        assert!(matches!(
+            store.upload_files("", &too_large),
+            Err(ArtifactError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            store.open_workspace("../../etc"),
+            Err(ArtifactError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            store.get_artifact("../secret"),
+            Err(ArtifactError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            store.get_artifact(&"0".repeat(64)),
+            Err(ArtifactError::NotFound(_))
+        ));
+        assert!(output_matcher(&["out/[".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_cleanup_applies_retention_and_size_limit() {
+        let mut store = test_store("cleanup");
+        store.max_store_bytes = 10;
+        let workspace = store.open_workspace("").unwrap();
+        for (name, content) in [("a.txt", "aaaaaa"), ("b.txt", "bbbbbb")] {
+            fs::write(workspace.path().join(name), content).unwrap();
+        }
+        let artifacts = store
+            .collect(&workspace, &output_matcher(&["*.txt".to_string()]).unwrap())
+            .unwrap();
+
+        // Make a.txt the older artifact
+        let old = SystemTime::now() - Duration::from_secs(60);
+        File::options()
+            .append(true)
+            .open(store.artifact_dir.join(&artifacts[0].id))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        // The workspace in use survives; the store is over its 10 byte limit
+        let report = store.cleanup();
+        assert_eq!(report.workspaces_removed, 0);
+        assert_eq!((report.artifacts_removed, report.bytes_freed), (1, 6));
+        assert!(store.get_artifact(&artifacts[0].id).is_err());
+        assert!(store.get_artifact(&artifacts[1].id).is_ok());
+
+        store.retention = Duration::ZERO;
+        let report = store.cleanup();
+        assert_eq!(report.artifacts_removed, 1);
+        drop(workspace);
+
+        let staged = store.upload_files("", &files(&[("x", "y")])).unwrap();
+        store.workspace_ttl = Duration::ZERO;
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(store.cleanup().workspaces_removed, 1);
+        assert!(matches!(
+            store.open_workspace(&staged.workspace_id),
+            Err(ArtifactError::NotFound(_))
+        ));
+    }
+}
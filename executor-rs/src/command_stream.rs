@@ -0,0 +1,661 @@
+// executor-rs/src/command_stream.rs
+// Streaming command execution for ExecuteCommandStream
+// PHOENIX ORCH: The Ashen Guard Edition AGI
+//
+// Output is forwarded in chunks as the process produces it instead of being
+// buffered until exit. The event channel is bounded and the pipe readers wait
+// for room in it, so a slow client stalls the readers, the pipes fill up and
+// the process blocks on write. Resource samples are dropped rather than
+// queued when the client lags behind.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::mpsc;
+
+use crate::config::ExecutorConfig;
+use crate::execution_logic::{sanitize_error, validate_command};
+
+#[cfg(target_os = "linux")]
+use crate::linux_sandbox;
+
+/// Capacity of the event channel between the process and the client
+pub const STREAM_BUFFER: usize = 32;
+
+// Largest output chunk sent in one event
+const CHUNK_SIZE: usize = 8192;
+
+// Interval between resource samples
+#[cfg(not(target_os = "windows"))]
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Output stream of the process an output chunk came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+impl OutputStream {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputStream::Stdout => "stdout",
+            OutputStream::Stderr => "stderr",
+        }
+    }
+}
+
+/// Resource usage of the process tree at one point in time
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourceSample {
+    pub memory_bytes: u64,
+    pub cpu_time_ms: u64,
+    /// CPU usage since the previous sample; 100 is one fully used core
+    pub cpu_percent: f32,
+    pub processes: u32,
+}
+
+/// How a streamed execution ended
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExitSummary {
+    /// Process exit code, -1 when it could not be started or timed out
+    pub exit_code: i32,
+    pub error: Option<String>,
+    /// Output stopped at the byte cap
+    pub output_truncated: bool,
+    /// Total stdout + stderr bytes forwarded
+    pub output_bytes: u64,
+    pub duration: Duration,
+}
+
+impl ExitSummary {
+    fn failed(error: String) -> Self {
+        Self {
+            exit_code: -1,
+            error: Some(error),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEventKind {
+    Output { stream: OutputStream, data: Vec<u8> },
+    Resources(ResourceSample),
+    Exit(ExitSummary),
+}
+
+/// One event of a streamed execution
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamEvent {
+    /// Unix milliseconds when the event was produced
+    pub timestamp_ms: i64,
+    pub kind: StreamEventKind,
+}
+
+impl StreamEvent {
+    fn now(kind: StreamEventKind) -> Self {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as i64)
+            .unwrap_or(0);
+        Self { timestamp_ms, kind }
+    }
+}
+
+/// Run a command and send its events to `events`, ending with one exit event
+///
+/// `max_output_bytes` caps the stdout + stderr bytes forwarded; 0 or values
+/// above `stream_max_output_bytes` use the configured limit. Once the cap is
+/// hit the process's pipes are closed. Returns early, killing the process,
+/// when the receiver is dropped.
+pub async fn execute_command_stream(
+    cmd: &str,
+    args: &[String],
+    env_vars: &HashMap<String, String>,
+    max_output_bytes: u64,
+    events: mpsc::Sender<StreamEvent>,
+) {
+    let config = crate::config::load_env_config();
+    stream_command(cmd, args, env_vars, max_output_bytes, &config, &events).await;
+}
+
+async fn stream_command(
+    cmd: &str,
+    args: &[String],
+    env_vars: &HashMap<String, String>,
+    max_output_bytes: u64,
+    config: &ExecutorConfig,
+    events: &mpsc::Sender<StreamEvent>,
+) {
+    log::info!("Streaming command: {} {:?}", cmd, args);
+    let started = Instant::now();
+
+    let cap = match max_output_bytes {
+        0 => config.stream_max_output_bytes,
+        requested => requested.min(config.stream_max_output_bytes),
+    };
+    let budget = OutputBudget::new(cap);
+
+    let summary = match validate_command(cmd) {
+        Err(e) => Some(ExitSummary::failed(sanitize_error(e))),
+        Ok(()) => run(cmd, args, env_vars, config, &budget, events).await,
+    };
+    let Some(mut summary) = summary else {
+        log::info!("ExecuteCommandStream client disconnected; process killed");
+        return;
+    };
+
+    summary.output_truncated = budget.truncated.load(Ordering::Relaxed);
+    summary.output_bytes = cap - budget.remaining.load(Ordering::Relaxed);
+    summary.duration = started.elapsed();
+    log::debug!(
+        "Streamed command exited with {} after {} output bytes",
+        summary.exit_code,
+        summary.output_bytes
+    );
+    let _ = events
+        .send(StreamEvent::now(StreamEventKind::Exit(summary)))
+        .await;
+}
+
+// Job Object execution collects the output in one piece; it is replayed as
+// chunks so clients see the same event sequence on every platform
+#[cfg(target_os = "windows")]
+async fn run(
+    cmd: &str,
+    args: &[String],
+    env_vars: &HashMap<String, String>,
+    _config: &ExecutorConfig,
+    budget: &OutputBudget,
+    events: &mpsc::Sender<StreamEvent>,
+) -> Option<ExitSummary> {
+    match crate::execution_logic::execute_shell_command(cmd, args, env_vars).await {
+        Ok((stdout, stderr, exit_code)) => {
+            let (stdout_sent, stderr_sent) = tokio::join!(
+                forward_output(
+                    Some(stdout.as_bytes()),
+                    OutputStream::Stdout,
+                    budget,
+                    events
+                ),
+                forward_output(
+                    Some(stderr.as_bytes()),
+                    OutputStream::Stderr,
+                    budget,
+                    events
+                ),
+            );
+            (stdout_sent && stderr_sent).then(|| ExitSummary {
+                exit_code,
+                ..ExitSummary::default()
+            })
+        }
+        Err(e) => Some(ExitSummary::failed(e)),
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+async fn run(
+    cmd: &str,
+    args: &[String],
+    env_vars: &HashMap<String, String>,
+    config: &ExecutorConfig,
+    budget: &OutputBudget,
+    events: &mpsc::Sender<StreamEvent>,
+) -> Option<ExitSummary> {
+    let timeout = Duration::from_secs(config.execution_timeout_seconds);
+    let mut process = match StreamedProcess::spawn(cmd, args, env_vars, config) {
+        Ok(process) => process,
+        Err(e) => return Some(ExitSummary::failed(sanitize_error(e))),
+    };
+    let stdout = process.child.stdout.take();
+    let stderr = process.child.stderr.take();
+    let mut sampler = ProcessSampler::new(process.child.id());
+
+    let mut ticker = tokio::time::interval(SAMPLE_INTERVAL);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    ticker.tick().await;
+
+    let outcome = {
+        let child = &mut process.child;
+        let completion = async {
+            let (stdout_sent, stderr_sent) = tokio::join!(
+                forward_output(stdout, OutputStream::Stdout, budget, events),
+                forward_output(stderr, OutputStream::Stderr, budget, events),
+            );
+            if !(stdout_sent && stderr_sent) {
+                return None;
+            }
+            Some(child.wait().await)
+        };
+        let deadline = tokio::time::sleep(timeout);
+        tokio::pin!(completion, deadline);
+
+        loop {
+            tokio::select! {
+                status = &mut completion => break Ok(status),
+                _ = &mut deadline => break Err(()),
+                _ = events.closed() => break Ok(None),
+                _ = ticker.tick() => {
+                    if let Some(sample) = sampler.sample() {
+                        let _ = events.try_send(StreamEvent::now(StreamEventKind::Resources(sample)));
+                    }
+                }
+            }
+        }
+    };
+
+    match outcome {
+        Ok(Some(Ok(status))) => Some(ExitSummary {
+            exit_code: process.exit_code(status),
+            ..ExitSummary::default()
+        }),
+        Ok(Some(Err(e))) => Some(ExitSummary::failed(sanitize_error(format!(
+            "Failed to wait for process: {}",
+            e
+        )))),
+        Ok(None) => {
+            process.kill();
+            None
+        }
+        Err(()) => {
+            process.kill();
+            Some(ExitSummary::failed(format!(
+                "Execution timed out after {} seconds",
+                timeout.as_secs()
+            )))
+        }
+    }
+}
+
+// A running process with piped output, plus the sandbox it runs in
+#[cfg(not(target_os = "windows"))]
+struct StreamedProcess {
+    child: tokio::process::Child,
+    #[cfg(target_os = "linux")]
+    sandbox: Option<linux_sandbox::Sandbox>,
+}
+
+#[cfg(not(target_os = "windows"))]
+impl StreamedProcess {
+    fn spawn(
+        cmd: &str,
+        args: &[String],
+        env_vars: &HashMap<String, String>,
+        config: &ExecutorConfig,
+    ) -> Result<Self, String> {
+        let is_python = cmd == "python" || cmd == "python3";
+
+        #[cfg(target_os = "linux")]
+        {
+            if linux_sandbox::use_sandbox(config.sandbox_backend)? {
+                let sandbox = linux_sandbox::Sandbox::create(config)?;
+                let child = if is_python {
+                    // Same convention as ExecuteCommand: the arguments are the code
+                    let (interpreter, interpreter_args) =
+                        sandbox.python_command(&args.join(" "))?;
+                    sandbox.spawn(interpreter, &interpreter_args, env_vars)?
+                } else {
+                    sandbox.spawn(cmd, args, env_vars)?
+                };
+                return Ok(Self {
+                    child,
+                    sandbox: Some(sandbox),
+                });
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = config;
+
+        if is_python {
+            return Err("Python sandboxed execution requires a sandbox backend".to_string());
+        }
+        log::warn!("No sandbox backend in use, streaming basic process execution");
+        let child = tokio::process::Command::new(cmd)
+            .args(args)
+            .envs(env_vars)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to execute command: {}", e))?;
+        Ok(Self {
+            child,
+            #[cfg(target_os = "linux")]
+            sandbox: None,
+        })
+    }
+
+    fn exit_code(&self, status: std::process::ExitStatus) -> i32 {
+        #[cfg(target_os = "linux")]
+        if self.sandbox.is_some() {
+            return linux_sandbox::exit_code(status);
+        }
+        status.code().unwrap_or(-1)
+    }
+
+    fn kill(&mut self) {
+        let _ = self.child.start_kill();
+        #[cfg(target_os = "linux")]
+        if let Some(sandbox) = &self.sandbox {
+            sandbox.kill();
+        }
+    }
+}
+
+// Bytes the execution may still forward, shared by the stdout and stderr readers
+struct OutputBudget {
+    remaining: AtomicU64,
+    truncated: AtomicBool,
+}
+
+impl OutputBudget {
+    fn new(cap: u64) -> Self {
+        Self {
+            remaining: AtomicU64::new(cap),
+            truncated: AtomicBool::new(false),
+        }
+    }
+
+    // Reserve up to `len` bytes and return how many were granted
+    fn take(&self, len: usize) -> usize {
+        let previous = self
+            .remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |remaining| {
+                Some(remaining.saturating_sub(len as u64))
+            })
+            .unwrap_or(0);
+        let granted = previous.min(len as u64) as usize;
+        if granted < len {
+            self.truncated.store(true, Ordering::Relaxed);
+        }
+        granted
+    }
+}
+
+// Forward a pipe as output events until EOF or the budget runs out, then drop
+// it. Returns false when the client went away.
+async fn forward_output<R: AsyncRead + Unpin>(
+    pipe: Option<R>,
+    stream: OutputStream,
+    budget: &OutputBudget,
+    events: &mpsc::Sender<StreamEvent>,
+) -> bool {
+    let Some(mut pipe) = pipe else {
+        return true;
+    };
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let read = match pipe.read(&mut buf).await {
+            Ok(0) => return true,
+            Ok(read) => read,
+            Err(e) => {
+                log::warn!("Failed to read {}: {}", stream.as_str(), e);
+                return true;
+            }
+        };
+        let granted = budget.take(read);
+        if granted > 0 {
+            let data = buf[..granted].to_vec();
+            let event = StreamEvent::now(StreamEventKind::Output { stream, data });
+            if events.send(event).await.is_err() {
+                return false;
+            }
+        }
+        if granted < read {
+            return true;
+        }
+    }
+}
+
+// Samples the memory and CPU use of a process and its live descendants
+#[cfg(not(target_os = "windows"))]
+struct ProcessSampler {
+    root: Option<u32>,
+    last: Option<(Instant, u64)>,
+}
+
+#[cfg(not(target_os = "windows"))]
+impl ProcessSampler {
+    fn new(root: Option<u32>) -> Self {
+        Self { root, last: None }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn sample(&mut self) -> Option<ResourceSample> {
+        let root = self.root?;
+        let processes = proc_stats();
+        let mut tree = vec![root];
+        let mut index = 0;
+        while index < tree.len() {
+            let parent = tree[index];
+            tree.extend(
+                processes
+                    .iter()
+                    .filter(|stat| stat.ppid == parent)
+                    .map(|stat| stat.pid),
+            );
+            index += 1;
+        }
+
+        let members: Vec<&ProcStat> = processes
+            .iter()
+            .filter(|stat| tree.contains(&stat.pid))
+            .collect();
+        if members.is_empty() {
+            return None;
+        }
+        let (ticks_per_second, page_size) = unsafe {
+            (
+                libc::sysconf(libc::_SC_CLK_TCK),
+                libc::sysconf(libc::_SC_PAGESIZE),
+            )
+        };
+        let ticks: u64 = members.iter().map(|stat| stat.cpu_ticks).sum();
+        let pages: u64 = members.iter().map(|stat| stat.rss_pages).sum();
+        let cpu_time_ms = ticks * 1000 / ticks_per_second.max(1) as u64;
+
+        let now = Instant::now();
+        let cpu_percent = match self.last {
+            Some((at, previous_ms)) => {
+                let wall_ms = now.duration_since(at).as_millis().max(1) as f32;
+                cpu_time_ms.saturating_sub(previous_ms) as f32 / wall_ms * 100.0
+            }
+            None => 0.0,
+        };
+        self.last = Some((now, cpu_time_ms));
+
+        Some(ResourceSample {
+            memory_bytes: pages * page_size.max(0) as u64,
+            cpu_time_ms,
+            cpu_percent,
+            processes: members.len() as u32,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn sample(&mut self) -> Option<ResourceSample> {
+        let _ = (self.root, self.last);
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+struct ProcStat {
+    pid: u32,
+    ppid: u32,
+    cpu_ticks: u64,
+    rss_pages: u64,
+}
+
+// Parse /proc/<pid>/stat of every visible process
+#[cfg(target_os = "linux")]
+fn proc_stats() -> Vec<ProcStat> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            let stat = std::fs::read_to_string(entry.path().join("stat")).ok()?;
+            // The command name may contain spaces and parentheses; fields follow the last ')'
+            let fields: Vec<&str> = stat
+                .get(stat.rfind(')')? + 1..)?
+                .split_whitespace()
+                .collect();
+            let field = |index: usize| fields.get(index)?.parse::<u64>().ok();
+            Some(ProcStat {
+                pid,
+                ppid: field(1)? as u32,
+                cpu_ticks: field(11)? + field(12)?,
+                rss_pages: field(21)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::config::SandboxBackend;
+
+    fn test_config(name: &str) -> ExecutorConfig {
+        ExecutorConfig {
+            sandbox_dir: std::env::temp_dir()
+                .join(format!("command-stream-{}-{}", name, std::process::id()))
+                .to_string_lossy()
+                .to_string(),
+            execution_timeout_seconds: 5,
+            sandbox_backend: SandboxBackend::Namespaces,
+            ..ExecutorConfig::default()
+        }
+    }
+
+    async fn collect(
+        code: &str,
+        max_output_bytes: u64,
+        config: &ExecutorConfig,
+    ) -> Vec<StreamEvent> {
+        let (events_tx, mut events_rx) = mpsc::channel(STREAM_BUFFER);
+        let args = vec![code.to_string()];
+        let env_vars = HashMap::new();
+        let run = stream_command(
+            "python3",
+            &args,
+            &env_vars,
+            max_output_bytes,
+            config,
+            &events_tx,
+        );
+        let mut events = Vec::new();
+        let receive = async {
+            while let Some(event) = events_rx.recv().await {
+                let finished = matches!(event.kind, StreamEventKind::Exit(_));
+                events.push(event);
+                if finished {
+                    break;
+                }
+            }
+        };
+        tokio::join!(run, receive);
+        events
+    }
+
+    fn exit_summary(events: &[StreamEvent]) -> &ExitSummary {
+        match &events.last().unwrap().kind {
+            StreamEventKind::Exit(summary) => summary,
+            other => panic!("last event is not an exit event: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_interleaves_output_and_samples() {
+        if !linux_sandbox::is_supported() || !std::path::Path::new("/usr/bin/python3").exists() {
+            eprintln!("skipping: user namespaces or python3 unavailable");
+            return;
+        }
+        let mut config = test_config("interleave");
+        config.execution_timeout_seconds = 10;
+        // Busy for three sample intervals, so samples land while the buffer is held
+        let code = "import sys, time\n\
+                    print('first', flush=True)\n\
+                    sys.stderr.write('warning\\n'); sys.stderr.flush()\n\
+                    data = b'x' * (32 * 1024 * 1024)\n\
+                    end = time.time() + 3\n\
+                    while time.time() < end: pass\n\
+                    print('last', flush=True)\n\
+                    sys.exit(4)\n";
+        let events = collect(code, 0, &config).await;
+
+        // Chunks keep their order within a pipe; there is none across the two pipes
+        let output = |wanted: OutputStream| -> Vec<String> {
+            events
+                .iter()
+                .filter_map(|event| match &event.kind {
+                    StreamEventKind::Output { stream, data } if *stream == wanted => {
+                        Some(String::from_utf8_lossy(data).to_string())
+                    }
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(output(OutputStream::Stdout).concat(), "first\nlast\n");
+        assert!(output(OutputStream::Stderr).contains(&"warning\n".to_string()));
+
+        let samples: Vec<ResourceSample> = events
+            .iter()
+            .filter_map(|event| match &event.kind {
+                StreamEventKind::Resources(sample) => Some(sample.clone()),
+                _ => None,
+            })
+            .collect();
+        assert!(samples.len() >= 2, "too few resource samples: {:?}", samples);
+        let peak = samples.iter().map(|sample| sample.memory_bytes).max().unwrap();
+        assert!(peak > 32 * 1024 * 1024, "peak memory {} bytes", peak);
+        assert!(samples.iter().all(|sample| sample.processes >= 1));
+
+        assert!(events
+            .windows(2)
+            .all(|pair| pair[0].timestamp_ms <= pair[1].timestamp_ms));
+        let summary = exit_summary(&events);
+        assert_eq!(summary.exit_code, 4);
+        assert_eq!(summary.output_bytes, "first\nwarning\nlast\n".len() as u64);
+        assert!(!summary.output_truncated);
+    }
+
+    #[tokio::test]
+    async fn test_stream_output_cap_and_failures() {
+        if !linux_sandbox::is_supported() || !std::path::Path::new("/usr/bin/python3").exists() {
+            eprintln!("skipping: user namespaces or python3 unavailable");
+            return;
+        }
+        let config = test_config("cap");
+        let events = collect("while True: print('x' * 1000)", 5000, &config).await;
+        let streamed: usize = events
+            .iter()
+            .map(|event| match &event.kind {
+                StreamEventKind::Output { data, .. } => data.len(),
+                _ => 0,
+            })
+            .sum();
+        let summary = exit_summary(&events);
+        assert_eq!(streamed, 5000);
+        assert_eq!(summary.output_bytes, 5000);
+        assert!(summary.output_truncated);
+
+        let (events_tx, mut events_rx) = mpsc::channel(STREAM_BUFFER);
+        stream_command("rm", &[], &HashMap::new(), 0, &config, &events_tx).await;
+        let event = events_rx.recv().await.unwrap();
+        match event.kind {
+            StreamEventKind::Exit(summary) => {
+                assert_eq!(summary.exit_code, -1);
+                assert!(summary.error.unwrap().contains("not permitted"));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+}
@@ -9,10 +9,11 @@
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
-use config_management_rs::{
+use config_management::{
     ConfigManager, ConfigChange, ConfigError, ConfigSource, ConfigValidator, ConfigBuilder,
     examples::example_executor_config,
 };
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 use tracing::{debug, error, info, warn};
@@ -49,6 +50,110 @@ pub struct ExecutorConfig {
 
     /// Enable detailed resource logging
     pub enable_resource_logging: bool,
+
+    /// Process isolation backend for commands on Linux
+    #[serde(default)]
+    pub sandbox_backend: SandboxBackend,
+
+    /// Give Linux sandboxes network access (they get an empty network namespace otherwise)
+    #[serde(default)]
+    pub sandbox_allow_network: bool,
+
+    /// Delegated cgroup v2 directory that sandbox cgroups are created in (empty: the service's own cgroup)
+    #[serde(default)]
+    pub sandbox_cgroup_parent: String,
+
+    /// Maximum bytes of stdout and of stderr captured from a sandboxed execution
+    #[serde(default = "default_max_output_bytes")]
+    pub max_output_bytes: u64,
+
+    /// Maximum total stdout + stderr bytes forwarded by ExecuteCommandStream
+    #[serde(default = "default_stream_max_output_bytes")]
+    pub stream_max_output_bytes: u64,
+
+    /// Directory holding per-execution workspaces (empty: a directory in the system temp dir)
+    #[serde(default)]
+    pub workspace_dir: String,
+
+    /// Directory of the content-addressed artifact store (empty: a directory in the system temp dir)
+    #[serde(default)]
+    pub artifact_dir: String,
+
+    /// Seconds an uploaded workspace waits for its execution before it is deleted
+    #[serde(default = "default_workspace_ttl_seconds")]
+    pub workspace_ttl_seconds: u64,
+
+    /// Maximum total size of the files uploaded to one workspace in MB
+    #[serde(default = "default_max_upload_mb")]
+    pub max_upload_mb: u64,
+
+    /// Seconds an artifact is kept after it was last produced
+    #[serde(default = "default_artifact_retention_seconds")]
+    pub artifact_retention_seconds: u64,
+
+    /// Maximum size of the artifact store in MB; the oldest artifacts are evicted beyond it
+    #[serde(default = "default_artifact_store_max_mb")]
+    pub artifact_store_max_mb: u64,
+}
+
+fn default_max_output_bytes() -> u64 {
+    1024 * 1024
+}
+
+fn default_stream_max_output_bytes() -> u64 {
+    64 * 1024 * 1024
+}
+
+fn default_workspace_ttl_seconds() -> u64 {
+    3600
+}
+
+fn default_max_upload_mb() -> u64 {
+    64
+}
+
+fn default_artifact_retention_seconds() -> u64 {
+    86400
+}
+
+fn default_artifact_store_max_mb() -> u64 {
+    1024
+}
+
+/// Linux process isolation backend
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SandboxBackend {
+    /// Namespace sandbox when the host supports it, unisolated execution otherwise
+    Auto,
+    /// Always use the namespace sandbox; commands fail on hosts without it
+    #[default]
+    Namespaces,
+    /// Run commands without isolation (development only)
+    Disabled,
+}
+
+impl std::str::FromStr for SandboxBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(SandboxBackend::Auto),
+            "namespaces" => Ok(SandboxBackend::Namespaces),
+            "disabled" | "none" => Ok(SandboxBackend::Disabled),
+            other => Err(format!("Unknown sandbox backend: {}", other)),
+        }
+    }
+}
+
+impl std::fmt::Display for SandboxBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SandboxBackend::Auto => "auto",
+            SandboxBackend::Namespaces => "namespaces",
+            SandboxBackend::Disabled => "disabled",
+        })
+    }
 }
 
 /// Default configuration implementation
@@ -70,13 +175,24 @@ impl Default for ExecutorConfig {
             enable_low_integrity: true,
             enable_watchdog: true,
             enable_resource_logging: true,
+            sandbox_backend: SandboxBackend::Namespaces,
+            sandbox_allow_network: false,
+            sandbox_cgroup_parent: String::new(),
+            max_output_bytes: default_max_output_bytes(),
+            stream_max_output_bytes: default_stream_max_output_bytes(),
+            workspace_dir: String::new(),
+            artifact_dir: String::new(),
+            workspace_ttl_seconds: default_workspace_ttl_seconds(),
+            max_upload_mb: default_max_upload_mb(),
+            artifact_retention_seconds: default_artifact_retention_seconds(),
+            artifact_store_max_mb: default_artifact_store_max_mb(),
         }
     }
 }
 
-/// Configuration validator implementation
-impl ConfigValidator for ExecutorConfig {
-    fn validate(&self) -> Result<(), String> {
+/// Configuration validation
+impl ExecutorConfig {
+    pub fn validate(&self) -> Result<(), String> {
         // Validate sandbox directory
         if self.sandbox_dir.is_empty() {
             return Err("Sandbox directory cannot be empty".to_string());
@@ -102,6 +218,29 @@ impl ConfigValidator for ExecutorConfig {
             return Err("Process count must be between 1 and 20".to_string());
         }
 
+        // Validate output limit
+        if self.max_output_bytes < 1024 || self.max_output_bytes > 64 * 1024 * 1024 {
+            return Err("Output limit must be between 1KB and 64MB".to_string());
+        }
+        if self.stream_max_output_bytes < 1024 || self.stream_max_output_bytes > 1024 * 1024 * 1024
+        {
+            return Err("Streamed output limit must be between 1KB and 1GB".to_string());
+        }
+
+        // Validate workspace and artifact limits
+        if self.workspace_ttl_seconds < 60 || self.workspace_ttl_seconds > 86400 {
+            return Err("Workspace TTL must be between 60 and 86400 seconds".to_string());
+        }
+        if self.max_upload_mb < 1 || self.max_upload_mb > 1024 {
+            return Err("Upload limit must be between 1MB and 1024MB".to_string());
+        }
+        if self.artifact_retention_seconds < 60 {
+            return Err("Artifact retention must be at least 60 seconds".to_string());
+        }
+        if self.artifact_store_max_mb < 1 {
+            return Err("Artifact store limit must be at least 1MB".to_string());
+        }
+
         // Validate allowed commands
         if self.allowed_commands.is_empty() {
             return Err("At least one command must be allowed".to_string());
@@ -112,13 +251,58 @@ impl ConfigValidator for ExecutorConfig {
 }
 
 /// Global configuration manager
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct GlobalConfigManager {
     inner: Arc<Mutex<ConfigManager<ExecutorConfig>>>,
 }
 
+impl std::fmt::Debug for GlobalConfigManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GlobalConfigManager").finish_non_exhaustive()
+    }
+}
+
+impl GlobalConfigManager {
+    /// Get current configuration
+    pub async fn get_config(&self) -> ExecutorConfig {
+        self.inner.lock().await.get_config().await
+    }
+
+    /// Replace the configuration and notify subscribers
+    pub async fn update_config(
+        &self,
+        config: ExecutorConfig,
+        source: ConfigSource,
+    ) -> Result<(), ConfigError> {
+        config.validate().map_err(ConfigError::ValidationError)?;
+        self.inner.lock().await.update_config(config, source).await
+    }
+
+    /// Subscribe to configuration changes
+    pub async fn subscribe_to_changes(
+        &self,
+    ) -> tokio::sync::broadcast::Receiver<ConfigChange<ExecutorConfig>> {
+        self.inner.lock().await.subscribe_to_changes()
+    }
+
+    /// Write the configuration to the configuration file
+    pub async fn save_config(&self, config: &ExecutorConfig) -> Result<(), ConfigError> {
+        self.inner.lock().await.save_to_file(config).await
+    }
+}
+
+// Set once by init_config_manager
+static GLOBAL_CONFIG: OnceCell<GlobalConfigManager> = OnceCell::new();
+
 /// Initialize the global configuration manager
+///
+/// Loads the configuration file, applies it and starts watching the file.
+/// Later calls return the manager created by the first one.
 pub async fn init_config_manager() -> Result<GlobalConfigManager, ConfigError> {
+    if let Some(manager) = GLOBAL_CONFIG.get() {
+        return Ok(manager.clone());
+    }
+
     info!("Initializing executor configuration manager");
 
     // Determine configuration file path
@@ -128,16 +312,22 @@ pub async fn init_config_manager() -> Result<GlobalConfigManager, ConfigError> {
     let default_config = ExecutorConfig::default();
 
     // Create configuration manager
-    let config_manager = ConfigManager::new(config_path, default_config).await?;
+    let mut config_manager = ConfigManager::new(config_path, default_config).await?;
+
+    // Apply the loaded configuration before anything reads it
+    let config = config_manager.get_config().await;
+    config.validate().map_err(ConfigError::ValidationError)?;
+    apply_config(&config).await?;
 
     // Start watching for changes
     config_manager.start_watching()?;
 
     info!("Executor configuration manager initialized successfully");
 
-    Ok(GlobalConfigManager {
+    let manager = GlobalConfigManager {
         inner: Arc::new(Mutex::new(config_manager)),
-    })
+    };
+    Ok(GLOBAL_CONFIG.get_or_init(|| manager).clone())
 }
 
 /// Get the configuration file path
@@ -151,19 +341,9 @@ fn get_config_path() -> PathBuf {
     PathBuf::from("config/executor.json")
 }
 
-/// Get the global configuration manager instance
+/// Get the global configuration manager instance, once initialized
 pub fn get_config_manager() -> Option<GlobalConfigManager> {
-    static mut GLOBAL_CONFIG: Option<GlobalConfigManager> = None;
-    static INIT: std::sync::Once = std::sync::Once::new();
-
-    unsafe {
-        INIT.call_once(|| {
-            let runtime = tokio::runtime::Runtime::new().unwrap();
-            GLOBAL_CONFIG = Some(runtime.block_on(init_config_manager()).expect("Failed to initialize config manager"));
-        });
-
-        GLOBAL_CONFIG.clone()
-    }
+    GLOBAL_CONFIG.get().cloned()
 }
 
 /// Get current configuration
@@ -187,9 +367,9 @@ pub async fn update_config(config: ExecutorConfig) -> Result<(), ConfigError> {
 }
 
 /// Subscribe to configuration changes
-pub fn subscribe_to_changes() -> tokio::sync::broadcast::Receiver<ConfigChange<ExecutorConfig>> {
+pub async fn subscribe_to_changes() -> tokio::sync::broadcast::Receiver<ConfigChange<ExecutorConfig>> {
     if let Some(manager) = get_config_manager() {
-        manager.subscribe_to_changes()
+        manager.subscribe_to_changes().await
     } else {
         // Create a dummy receiver if manager not initialized
         let (_, receiver) = tokio::sync::broadcast::channel(1);
@@ -220,6 +400,41 @@ pub async fn apply_config(config: &ExecutorConfig) -> Result<(), ConfigError> {
     std::env::set_var("EXECUTOR_MAX_CPU_PERCENT", config.max_cpu_percent.to_string());
     std::env::set_var("EXECUTOR_TIMEOUT_SECONDS", config.execution_timeout_seconds.to_string());
     std::env::set_var("EXECUTOR_MAX_PROCESSES", config.max_processes.to_string());
+    std::env::set_var(
+        "EXECUTOR_SANDBOX_BACKEND",
+        config.sandbox_backend.to_string(),
+    );
+    std::env::set_var(
+        "EXECUTOR_SANDBOX_ALLOW_NETWORK",
+        config.sandbox_allow_network.to_string(),
+    );
+    std::env::set_var(
+        "EXECUTOR_SANDBOX_CGROUP_PARENT",
+        &config.sandbox_cgroup_parent,
+    );
+    std::env::set_var(
+        "EXECUTOR_MAX_OUTPUT_BYTES",
+        config.max_output_bytes.to_string(),
+    );
+    std::env::set_var(
+        "EXECUTOR_STREAM_MAX_OUTPUT_BYTES",
+        config.stream_max_output_bytes.to_string(),
+    );
+    std::env::set_var("EXECUTOR_WORKSPACE_DIR", &config.workspace_dir);
+    std::env::set_var("EXECUTOR_ARTIFACT_DIR", &config.artifact_dir);
+    std::env::set_var(
+        "EXECUTOR_WORKSPACE_TTL_SECONDS",
+        config.workspace_ttl_seconds.to_string(),
+    );
+    std::env::set_var("EXECUTOR_MAX_UPLOAD_MB", config.max_upload_mb.to_string());
+    std::env::set_var(
+        "EXECUTOR_ARTIFACT_RETENTION_SECONDS",
+        config.artifact_retention_seconds.to_string(),
+    );
+    std::env::set_var(
+        "EXECUTOR_ARTIFACT_STORE_MAX_MB",
+        config.artifact_store_max_mb.to_string(),
+    );
 
     // Log the allowed commands
     debug!("Allowed commands: {:?}", config.allowed_commands);
@@ -269,6 +484,67 @@ pub fn load_env_config() -> ExecutorConfig {
             .collect();
     }
 
+    if let Ok(backend) = std::env::var("EXECUTOR_SANDBOX_BACKEND") {
+        match backend.parse() {
+            Ok(backend) => config.sandbox_backend = backend,
+            Err(e) => warn!("Ignoring EXECUTOR_SANDBOX_BACKEND: {}", e),
+        }
+    }
+
+    if let Ok(allow) = std::env::var("EXECUTOR_SANDBOX_ALLOW_NETWORK") {
+        if let Ok(allow_val) = allow.parse() {
+            config.sandbox_allow_network = allow_val;
+        }
+    }
+
+    if let Ok(parent) = std::env::var("EXECUTOR_SANDBOX_CGROUP_PARENT") {
+        config.sandbox_cgroup_parent = parent;
+    }
+
+    if let Ok(output) = std::env::var("EXECUTOR_MAX_OUTPUT_BYTES") {
+        if let Ok(output_val) = output.parse() {
+            config.max_output_bytes = output_val;
+        }
+    }
+
+    if let Ok(output) = std::env::var("EXECUTOR_STREAM_MAX_OUTPUT_BYTES") {
+        if let Ok(output_val) = output.parse() {
+            config.stream_max_output_bytes = output_val;
+        }
+    }
+
+    if let Ok(dir) = std::env::var("EXECUTOR_WORKSPACE_DIR") {
+        config.workspace_dir = dir;
+    }
+
+    if let Ok(dir) = std::env::var("EXECUTOR_ARTIFACT_DIR") {
+        config.artifact_dir = dir;
+    }
+
+    if let Ok(ttl) = std::env::var("EXECUTOR_WORKSPACE_TTL_SECONDS") {
+        if let Ok(ttl_val) = ttl.parse() {
+            config.workspace_ttl_seconds = ttl_val;
+        }
+    }
+
+    if let Ok(upload) = std::env::var("EXECUTOR_MAX_UPLOAD_MB") {
+        if let Ok(upload_val) = upload.parse() {
+            config.max_upload_mb = upload_val;
+        }
+    }
+
+    if let Ok(retention) = std::env::var("EXECUTOR_ARTIFACT_RETENTION_SECONDS") {
+        if let Ok(retention_val) = retention.parse() {
+            config.artifact_retention_seconds = retention_val;
+        }
+    }
+
+    if let Ok(store) = std::env::var("EXECUTOR_ARTIFACT_STORE_MAX_MB") {
+        if let Ok(store_val) = store.parse() {
+            config.artifact_store_max_mb = store_val;
+        }
+    }
+
     config
 }
 
@@ -313,7 +589,7 @@ pub mod migration {
 
 /// Configuration monitoring
 pub async fn start_config_monitoring() {
-    let mut receiver = subscribe_to_changes();
+    let mut receiver = subscribe_to_changes().await;
 
     tokio::spawn(async move {
         while let Ok(change) = receiver.recv().await {
@@ -359,23 +635,35 @@ pub mod utils {
 #[cfg(test)]
 pub mod test_utils {
     use super::*;
-    use config_management_rs::test_utils::create_test_config;
+    use tempfile::TempDir;
 
     /// Create a test configuration
-    pub async fn create_test_config_manager() -> ConfigManager<ExecutorConfig> {
-        let config = ExecutorConfig::default();
-        create_test_config(&config).await.0
+    ///
+    /// The manager reads and saves `executor.json` in the returned directory,
+    /// which is deleted when dropped.
+    pub async fn create_test_config_manager() -> (ConfigManager<ExecutorConfig>, TempDir) {
+        create_manager(ExecutorConfig::default()).await
     }
 
     /// Create a test configuration with custom values
     pub async fn create_custom_test_config(
         sandbox_dir: &str,
         max_memory: u64,
-    ) -> ConfigManager<ExecutorConfig> {
-        let mut config = ExecutorConfig::default();
-        config.sandbox_dir = sandbox_dir.to_string();
-        config.max_memory_mb = max_memory;
-        create_test_config(&config).await.0
+    ) -> (ConfigManager<ExecutorConfig>, TempDir) {
+        create_manager(ExecutorConfig {
+            sandbox_dir: sandbox_dir.to_string(),
+            max_memory_mb: max_memory,
+            ..Default::default()
+        })
+        .await
+    }
+
+    async fn create_manager(config: ExecutorConfig) -> (ConfigManager<ExecutorConfig>, TempDir) {
+        let dir = TempDir::new().expect("Failed to create config directory");
+        let manager = ConfigManager::new(dir.path().join("executor.json"), config)
+            .await
+            .expect("Failed to create config manager");
+        (manager, dir)
     }
 }
 
@@ -402,6 +690,17 @@ pub mod examples {
             enable_low_integrity: true,
             enable_watchdog: true,
             enable_resource_logging: true,
+            sandbox_backend: SandboxBackend::Namespaces,
+            sandbox_allow_network: false,
+            sandbox_cgroup_parent: String::new(),
+            max_output_bytes: default_max_output_bytes(),
+            stream_max_output_bytes: default_stream_max_output_bytes(),
+            workspace_dir: String::new(),
+            artifact_dir: String::new(),
+            workspace_ttl_seconds: default_workspace_ttl_seconds(),
+            max_upload_mb: default_max_upload_mb(),
+            artifact_retention_seconds: default_artifact_retention_seconds(),
+            artifact_store_max_mb: default_artifact_store_max_mb(),
         }
     }
 
@@ -422,6 +721,17 @@ pub mod examples {
             enable_low_integrity: false,
             enable_watchdog: true,
             enable_resource_logging: true,
+            sandbox_backend: SandboxBackend::Auto,
+            sandbox_allow_network: false,
+            sandbox_cgroup_parent: String::new(),
+            max_output_bytes: default_max_output_bytes(),
+            stream_max_output_bytes: default_stream_max_output_bytes(),
+            workspace_dir: String::new(),
+            artifact_dir: String::new(),
+            workspace_ttl_seconds: default_workspace_ttl_seconds(),
+            max_upload_mb: default_max_upload_mb(),
+            artifact_retention_seconds: default_artifact_retention_seconds(),
+            artifact_store_max_mb: default_artifact_store_max_mb(),
         }
     }
 }
@@ -439,4 +749,38 @@ macro_rules! sandbox_dir {
     () => {
         $crate::config::utils::get_sandbox_dir()
     };
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::{create_custom_test_config, create_test_config_manager};
+    use super::{ExecutorConfig, SandboxBackend};
+
+    #[tokio::test]
+    async fn test_default_config_is_valid() {
+        let (manager, _dir) = create_test_config_manager().await;
+        assert!(manager.get_config().await.validate().is_ok());
+    }
+
+    #[test]
+    fn test_sandbox_backend_defaults_to_namespaces() {
+        assert_eq!(ExecutorConfig::default().sandbox_backend, SandboxBackend::Namespaces);
+
+        // Configuration files without the field fail closed as well
+        let mut json = serde_json::to_value(ExecutorConfig::default()).unwrap();
+        json.as_object_mut().unwrap().remove("sandbox_backend");
+        let config: ExecutorConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(config.sandbox_backend, SandboxBackend::Namespaces);
+    }
+
+    #[tokio::test]
+    async fn test_custom_config_validation() {
+        let (manager, _dir) = create_custom_test_config("/tmp/phoenix_sandbox", 256).await;
+        let config = manager.get_config().await;
+        assert_eq!(config.max_memory_mb, 256);
+        assert!(config.validate().is_ok());
+
+        let (manager, _dir) = create_custom_test_config("", 256).await;
+        assert!(manager.get_config().await.validate().is_err());
+    }
+}
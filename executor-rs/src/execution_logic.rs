@@ -2,21 +2,26 @@
 // Core logic for executing commands with Windows native control
 // PHOENIX ORCH: The Ashen Guard Edition AGI
 
+#[cfg(target_os = "windows")]
 use enigo::{Enigo, Key, KeyboardControllable, MouseButton, MouseControllable};
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
+#[cfg(target_os = "windows")]
 use std::fs::File;
+#[cfg(target_os = "windows")]
 use std::io::Write;
 use std::path::Path;
 use std::process::Command;
-use std::sync::Arc;
 use std::sync::RwLock;
-use tokio::sync::Mutex;
 
 // Import Windows executor module
 #[cfg(target_os = "windows")]
 use crate::windows_executor;
 
+// Import Linux sandbox backend
+#[cfg(target_os = "linux")]
+use crate::linux_sandbox;
+
 // Allowlist of permitted commands
 static ALLOWED_COMMANDS: Lazy<RwLock<Vec<String>>> = Lazy::new(|| {
     RwLock::new(vec![
@@ -42,7 +47,7 @@ static ALLOWED_COMMANDS: Lazy<RwLock<Vec<String>>> = Lazy::new(|| {
 });
 
 /// Validate if command is permitted based on allowlist
-fn validate_command(cmd: &str) -> Result<(), String> {
+pub(crate) fn validate_command(cmd: &str) -> Result<(), String> {
     let allowed_commands = ALLOWED_COMMANDS.read().unwrap();
 
     if allowed_commands.iter().any(|allowed| allowed == cmd) {
@@ -53,7 +58,7 @@ fn validate_command(cmd: &str) -> Result<(), String> {
 }
 
 /// Sanitize error messages to prevent information leakage
-fn sanitize_error(error_message: String) -> String {
+pub(crate) fn sanitize_error(error_message: String) -> String {
     // Log the original error for debugging, but don't return it to the client
     log::debug!("Original error: {}", error_message);
 
@@ -91,6 +96,16 @@ pub async fn execute_shell_command(
     cmd: &str,
     args: &[String],
     env_vars: &HashMap<String, String>,
+) -> Result<(String, String, i32), String> {
+    execute_shell_command_in(cmd, args, env_vars, None).await
+}
+
+/// Execute a shell command with `workspace` (if any) as its working directory
+pub async fn execute_shell_command_in(
+    cmd: &str,
+    args: &[String],
+    env_vars: &HashMap<String, String>,
+    workspace: Option<&Path>,
 ) -> Result<(String, String, i32), String> {
     log::info!("Executing command: {} {:?}", cmd, args);
 
@@ -113,14 +128,18 @@ pub async fn execute_shell_command(
 
     // If the command is python or python3, use the windows sandboxed execution
     if cmd == "python" || cmd == "python3" {
-        return execute_python_sandboxed(&args.join(" "), env_vars).await;
+        return execute_python_sandboxed_in(&args.join(" "), env_vars, workspace).await;
     }
 
     // For other commands, use Windows native execution with Job Object control
     #[cfg(target_os = "windows")]
     {
         log::info!("Using Windows native execution control");
-        match windows_executor::execute_with_windows_control(cmd, args, env_vars, "shell").await {
+        match windows_executor::execute_with_windows_control_in(
+            cmd, args, env_vars, "shell", workspace,
+        )
+        .await
+        {
             Ok(result) => {
                 // Log output details for debugging
                 log::debug!("Command stdout length: {} bytes", result.0.len());
@@ -135,11 +154,33 @@ pub async fn execute_shell_command(
         }
     }
 
+    #[cfg(target_os = "linux")]
+    {
+        let config = crate::config::load_env_config();
+        if linux_sandbox::use_sandbox(config.sandbox_backend).map_err(sanitize_error)? {
+            log::info!("Using Linux namespace sandbox");
+            return match linux_sandbox::execute_in_sandbox(cmd, args, env_vars, &config, workspace)
+                .await
+            {
+                Ok(result) => {
+                    log::debug!("Command stdout length: {} bytes", result.0.len());
+                    log::debug!("Command stderr length: {} bytes", result.1.len());
+                    log::debug!("Command exit code: {}", result.2);
+                    Ok(result)
+                }
+                Err(e) => {
+                    log::error!("Linux sandbox execution failed: {}", e);
+                    Err(sanitize_error(e))
+                }
+            };
+        }
+    }
+
     #[cfg(not(target_os = "windows"))]
     {
-        // Fallback for non-Windows systems (for development)
-        log::warn!("Non-Windows system detected, using basic process execution");
-        execute_basic_command(cmd, args, env_vars).await
+        // Fallback for systems without a sandbox backend (for development)
+        log::warn!("No sandbox backend in use, using basic process execution");
+        execute_basic_command(cmd, args, env_vars, workspace).await
     }
 }
 
@@ -148,10 +189,19 @@ pub async fn execute_python_sandboxed(
     code: &str,
     env_vars: &HashMap<String, String>,
 ) -> Result<(String, String, i32), String> {
-    log::info!("Executing Python code in Windows sandboxed environment");
+    execute_python_sandboxed_in(code, env_vars, None).await
+}
 
+/// Execute Python code with `workspace` (if any) as its working directory
+pub async fn execute_python_sandboxed_in(
+    code: &str,
+    env_vars: &HashMap<String, String>,
+    workspace: Option<&Path>,
+) -> Result<(String, String, i32), String> {
     #[cfg(target_os = "windows")]
     {
+        log::info!("Executing Python code in Windows sandboxed environment");
+
         // Write Python code to a temporary file
         let temp_dir = std::env::temp_dir();
 
@@ -167,11 +217,12 @@ pub async fn execute_python_sandboxed(
             .map_err(|e| sanitize_error(format!("Failed to write script: {}", e)))?;
 
         // Execute using Windows native control
-        let result = windows_executor::execute_with_windows_control(
+        let result = windows_executor::execute_with_windows_control_in(
             "python",
             &vec![script_path.to_string_lossy().to_string()],
             env_vars,
             "python",
+            workspace,
         )
         .await;
 
@@ -188,10 +239,32 @@ pub async fn execute_python_sandboxed(
         }
     }
 
-    #[cfg(not(target_os = "windows"))]
+    #[cfg(target_os = "linux")]
+    {
+        // Never run untrusted code unisolated: require the namespace sandbox
+        let config = crate::config::load_env_config();
+        if !linux_sandbox::use_sandbox(config.sandbox_backend).map_err(sanitize_error)? {
+            return Err(
+                "Python sandboxed execution requires the Linux namespace sandbox".to_string(),
+            );
+        }
+
+        log::info!("Executing Python code in Linux sandboxed environment");
+        match linux_sandbox::execute_python_in_sandbox(code, env_vars, &config, workspace).await {
+            Ok(output) => {
+                log::debug!("Python execution stdout: {} bytes", output.0.len());
+                log::debug!("Python execution stderr: {} bytes", output.1.len());
+                Ok(output)
+            }
+            Err(e) => Err(sanitize_error(e)),
+        }
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
     {
-        // Fallback for non-Windows systems
-        Err("Python sandboxed execution only supported on Windows".to_string())
+        let _ = workspace;
+        // Fallback for other systems
+        Err("Python sandboxed execution only supported on Windows and Linux".to_string())
     }
 }
 
@@ -201,15 +274,20 @@ async fn execute_basic_command(
     cmd: &str,
     args: &[String],
     env_vars: &HashMap<String, String>,
+    workspace: Option<&Path>,
 ) -> Result<(String, String, i32), String> {
     let cmd_owned = cmd.to_string();
     let args_owned = args.to_vec();
     let env_owned = env_vars.clone();
+    let workspace_owned = workspace.map(Path::to_path_buf);
 
     let result = tokio::task::spawn_blocking(move || {
         let mut command = Command::new(cmd_owned);
         command.args(args_owned);
         command.envs(env_owned);
+        if let Some(workspace) = workspace_owned {
+            command.current_dir(workspace);
+        }
         command.output()
     })
     .await
@@ -276,6 +354,21 @@ pub fn simulate_input(input_type: &str, params: &HashMap<String, String>) -> Res
     // Check permissions before allowing input simulation
     check_input_permissions()?;
 
+    send_input(input_type, params)?;
+
+    // Log successful input simulation for audit purposes
+    log::info!(
+        "Successfully simulated input: {} with params: {:?}",
+        input_type,
+        params
+    );
+
+    Ok(())
+}
+
+/// Drive the mouse and keyboard through enigo
+#[cfg(target_os = "windows")]
+fn send_input(input_type: &str, params: &HashMap<String, String>) -> Result<(), String> {
     // Get screen dimensions for boundary checking
     let screen_width = 1920; // Default fallback value
     let screen_height = 1080; // Default fallback value
@@ -400,16 +493,15 @@ pub fn simulate_input(input_type: &str, params: &HashMap<String, String>) -> Res
         }
     }
 
-    // Log successful input simulation for audit purposes
-    log::info!(
-        "Successfully simulated input: {} with params: {:?}",
-        input_type,
-        params
-    );
-
     Ok(())
 }
 
+/// Input simulation drives the interactive desktop, which only the Windows service has
+#[cfg(not(target_os = "windows"))]
+fn send_input(_input_type: &str, _params: &HashMap<String, String>) -> Result<(), String> {
+    Err("Input simulation is only supported on Windows".to_string())
+}
+
 /// Get execution statistics (for monitoring)
 pub fn get_execution_stats() -> HashMap<String, String> {
     let mut stats = HashMap::new();
@@ -435,6 +527,44 @@ pub fn get_execution_stats() -> HashMap<String, String> {
         );
     }
 
+    #[cfg(target_os = "linux")]
+    {
+        let config = crate::config::load_env_config();
+        if linux_sandbox::use_sandbox(config.sandbox_backend).unwrap_or(false) {
+            stats.insert(
+                "executor_type".to_string(),
+                "Linux_Namespace_Sandbox".to_string(),
+            );
+            stats.insert(
+                "sandbox_dir".to_string(),
+                linux_sandbox::sandbox_base_dir(&config)
+                    .to_string_lossy()
+                    .to_string(),
+            );
+            stats.insert(
+                "max_process_memory_mb".to_string(),
+                config.max_memory_mb.to_string(),
+            );
+            stats.insert(
+                "max_processes".to_string(),
+                config.max_processes.to_string(),
+            );
+            stats.insert(
+                "execution_timeout_seconds".to_string(),
+                config.execution_timeout_seconds.to_string(),
+            );
+            stats.insert(
+                "cpu_limit_percent".to_string(),
+                config.max_cpu_percent.to_string(),
+            );
+            stats.insert(
+                "network_access".to_string(),
+                config.sandbox_allow_network.to_string(),
+            );
+            return stats;
+        }
+    }
+
     #[cfg(not(target_os = "windows"))]
     {
         stats.insert("executor_type".to_string(), "Basic_Process".to_string());
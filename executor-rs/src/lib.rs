@@ -2,16 +2,33 @@
 //! Provides Windows native execution control and process management functionality
 
 mod execution_logic;
+mod command_stream;
+mod artifacts;
+#[cfg(target_os = "windows")]
 mod windows_executor;
+#[cfg(target_os = "linux")]
+mod linux_sandbox;
 mod config;
 mod monitoring;
 mod performance;
 mod security;
 
 pub use execution_logic::{
-    execute_python_sandboxed, execute_shell_command, get_execution_stats, simulate_input,
+    execute_python_sandboxed, execute_shell_command, execute_shell_command_in,
+    get_execution_stats, simulate_input,
 };
 
+pub use command_stream::{
+    execute_command_stream, ExitSummary, OutputStream, ResourceSample, StreamEvent,
+    StreamEventKind, STREAM_BUFFER,
+};
+
+pub use artifacts::{
+    output_matcher, start_cleanup_task, Artifact, ArtifactError, ArtifactInfo, ArtifactStore,
+    CleanupReport, ExecutionWorkspace, UploadSummary,
+};
+
+#[cfg(target_os = "windows")]
 pub use windows_executor::{execute_with_windows_control, validate_path, JobObjectManager, check_sandbox_integrity};
 pub use config::{get_config, update_config, subscribe_to_changes, check_config_health};
 pub use monitoring::{init_monitoring, get_monitoring_stats, get_health_status, start_execution_monitoring};
@@ -36,20 +53,16 @@ pub struct Executor {
 impl Executor {
     /// Create a new Executor instance
     pub fn new() -> Self {
-        // Create service health tracking
-        let service_health = Arc::new(tokio::sync::RwLock::new(HashMap::new()));
-        let service_health_clone = Arc::clone(&service_health);
-
         // Initialize known services as healthy
         let service_names = ["shell", "python", "input_simulation", "process_watchdog"];
-        tokio::spawn(async move {
-            let mut health = service_health_clone.write().await;
-            for &service in &service_names {
-                health.insert(service.to_string(), true);
-            }
-        });
-
-        Self { service_health }
+        let health = service_names
+            .iter()
+            .map(|&service| (service.to_string(), true))
+            .collect();
+
+        Self {
+            service_health: Arc::new(tokio::sync::RwLock::new(health)),
+        }
     }
 
     /// Execute a shell command with Windows native control
@@ -113,7 +126,18 @@ impl Executor {
             dependencies.insert("sandbox_directory".to_string(), "CONFIGURED".to_string());
         }
 
+        #[cfg(target_os = "linux")]
+        {
+            let status = if linux_sandbox::is_supported() {
+                "AVAILABLE"
+            } else {
+                "UNAVAILABLE"
+            };
+            dependencies.insert("linux_sandbox".to_string(), status.to_string());
+        }
+
         dependencies.insert("shell".to_string(), "AVAILABLE".to_string());
+        #[cfg(target_os = "windows")]
         dependencies.insert("input_simulation".to_string(), "AVAILABLE".to_string());
 
         dependencies
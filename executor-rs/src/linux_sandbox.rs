@@ -0,0 +1,1101 @@
+// executor-rs/src/linux_sandbox.rs
+// Linux sandbox backend: namespaces, cgroups v2, seccomp and rlimits
+// PHOENIX ORCH: The Ashen Guard Edition AGI
+//
+// Each execution gets a fresh user namespace (the caller's uid mapped to root)
+// with its own mount, PID, IPC, UTS and - unless network access is allowed -
+// network namespaces. The root filesystem is a read-only skeleton holding
+// read-only binds of the host system directories, a minimal /dev, a private
+// /proc and a writable scratch directory at /scratch. Resource limits come
+// from ExecutorConfig and are enforced by a per-execution cgroup v2 (when the
+// service has a delegated cgroup) plus rlimits, and a seccomp filter blocks
+// syscalls that could escape or attack the kernel.
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Once;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::{Child, Command};
+
+use crate::config::{ExecutorConfig, SandboxBackend};
+
+/// Mount point of the writable scratch directory inside the sandbox
+pub const SCRATCH_MOUNT: &str = "/scratch";
+
+// Host directories bind-mounted read-only into the sandbox root
+const SYSTEM_DIRS: &[&str] = &["/usr", "/bin", "/sbin", "/lib", "/lib64", "/lib32", "/etc"];
+
+// Device nodes bound into the sandbox /dev
+const DEVICES: &[&str] = &[
+    "/dev/null",
+    "/dev/zero",
+    "/dev/full",
+    "/dev/random",
+    "/dev/urandom",
+];
+
+// Interpreter and script name used for sandboxed Python executions
+const PYTHON_INTERPRETER: &str = "python3";
+const PYTHON_SCRIPT: &str = ".phoenix_script.py";
+
+// PATH of sandboxed processes
+const SANDBOX_PATH: &str = "/usr/local/bin:/usr/bin:/bin:/usr/sbin:/sbin";
+
+// Resource limits not covered by ExecutorConfig
+const MAX_FILE_BYTES: u64 = 64 * 1024 * 1024; // 64 MB per written file
+const MAX_OPEN_FILES: u64 = 256;
+
+const NAMESPACES: libc::c_int = libc::CLONE_NEWUSER
+    | libc::CLONE_NEWNS
+    | libc::CLONE_NEWPID
+    | libc::CLONE_NEWIPC
+    | libc::CLONE_NEWUTS;
+
+static NEXT_SANDBOX_ID: AtomicU64 = AtomicU64::new(1);
+
+// Whether this host lets the service create the sandbox namespaces
+static NAMESPACES_SUPPORTED: Lazy<bool> = Lazy::new(|| {
+    if seccomp::AUDIT_ARCH.is_none() {
+        return false;
+    }
+
+    // Probe in a forked child so the service itself keeps its namespaces
+    unsafe {
+        match libc::fork() {
+            -1 => false,
+            0 => {
+                let status = if libc::unshare(NAMESPACES | libc::CLONE_NEWNET) == 0 {
+                    0
+                } else {
+                    1
+                };
+                libc::_exit(status)
+            }
+            pid => {
+                let mut status = 0;
+                libc::waitpid(pid, &mut status, 0) == pid
+                    && libc::WIFEXITED(status)
+                    && libc::WEXITSTATUS(status) == 0
+            }
+        }
+    }
+});
+
+/// Check if the namespace sandbox can run on this host
+pub fn is_supported() -> bool {
+    *NAMESPACES_SUPPORTED
+}
+
+/// Decide whether commands run in the namespace sandbox for a configured backend
+pub fn use_sandbox(backend: SandboxBackend) -> Result<bool, String> {
+    static UNSUPPORTED_WARNING: Once = Once::new();
+
+    match backend {
+        SandboxBackend::Disabled => Ok(false),
+        SandboxBackend::Namespaces if !is_supported() => {
+            Err("Linux sandbox backend is not available on this host".to_string())
+        }
+        SandboxBackend::Namespaces => Ok(true),
+        SandboxBackend::Auto => {
+            if !is_supported() {
+                UNSUPPORTED_WARNING.call_once(|| {
+                    log::warn!(
+                        "User namespaces are unavailable, commands run WITHOUT isolation; \
+                         set sandbox_backend to \"namespaces\" to refuse instead"
+                    );
+                });
+            }
+            Ok(is_supported())
+        }
+    }
+}
+
+/// Resource limits of one sandboxed execution
+#[derive(Debug, Clone)]
+pub struct SandboxLimits {
+    pub memory_bytes: u64,
+    pub cpu_percent: u32,
+    pub max_processes: u32,
+    pub timeout: Duration,
+    pub allow_network: bool,
+    pub max_output_bytes: u64,
+}
+
+impl SandboxLimits {
+    pub fn from_config(config: &ExecutorConfig) -> Self {
+        Self {
+            memory_bytes: config.max_memory_mb * 1024 * 1024,
+            cpu_percent: config.max_cpu_percent,
+            max_processes: config.max_processes,
+            timeout: Duration::from_secs(config.execution_timeout_seconds),
+            allow_network: config.sandbox_allow_network,
+            max_output_bytes: config.max_output_bytes,
+        }
+    }
+}
+
+/// Directory the per-execution sandbox directories are created in
+///
+/// `sandbox_dir` is used when it is an absolute path on this host; the
+/// Windows default falls back to the temp directory.
+pub fn sandbox_base_dir(config: &ExecutorConfig) -> PathBuf {
+    let configured = Path::new(&config.sandbox_dir);
+    if configured.is_absolute() {
+        configured.to_path_buf()
+    } else {
+        std::env::temp_dir().join("phoenix_sandbox")
+    }
+}
+
+/// A prepared sandbox: root skeleton, scratch directory and cgroup
+///
+/// Dropping it kills anything left in the cgroup and removes its directories.
+pub struct Sandbox {
+    dir: PathBuf,
+    root: PathBuf,
+    scratch: PathBuf,
+    limits: SandboxLimits,
+    cgroup: Option<Cgroup>,
+}
+
+impl Sandbox {
+    /// Prepare a sandbox with limits from `config`
+    pub fn create(config: &ExecutorConfig) -> Result<Self, String> {
+        Self::prepare(config, None)
+    }
+
+    /// Prepare a sandbox that mounts `workspace` at `/scratch`
+    ///
+    /// The workspace is left in place when the sandbox is dropped.
+    pub fn with_workspace(config: &ExecutorConfig, workspace: &Path) -> Result<Self, String> {
+        let workspace = workspace
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve workspace: {}", e))?;
+        Self::prepare(config, Some(workspace))
+    }
+
+    fn prepare(config: &ExecutorConfig, workspace: Option<PathBuf>) -> Result<Self, String> {
+        let name = format!(
+            "sbx-{}-{}",
+            std::process::id(),
+            NEXT_SANDBOX_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        let dir = sandbox_base_dir(config).join(&name);
+        let limits = SandboxLimits::from_config(config);
+
+        let mut sandbox = Self {
+            root: dir.join("root"),
+            scratch: workspace.unwrap_or_else(|| dir.join("scratch")),
+            dir,
+            limits,
+            cgroup: None,
+        };
+        sandbox
+            .build_root()
+            .map_err(|e| format!("Failed to prepare sandbox directory: {}", e))?;
+
+        sandbox.cgroup = match Cgroup::create(&config.sandbox_cgroup_parent, &name, &sandbox.limits)
+        {
+            Ok(cgroup) => Some(cgroup),
+            Err(e) => {
+                log::warn!("Sandbox cgroup unavailable, relying on rlimits only: {}", e);
+                None
+            }
+        };
+
+        Ok(sandbox)
+    }
+
+    /// Host path of the directory mounted writable at `/scratch` in the sandbox
+    pub fn scratch_dir(&self) -> &Path {
+        &self.scratch
+    }
+
+    /// Start a command in the sandbox with piped stdout and stderr
+    ///
+    /// The command starts in `/scratch` with a clean environment plus
+    /// `env_vars`. Dropping the child kills it and, as it is PID 1 of the
+    /// sandbox, everything it started.
+    pub fn spawn(
+        &self,
+        command: &str,
+        args: &[String],
+        env_vars: &HashMap<String, String>,
+    ) -> Result<Child, String> {
+        let plan = self
+            .child_plan()
+            .map_err(|e| format!("Failed to prepare sandbox: {}", e))?;
+
+        let mut cmd = Command::new(command);
+        cmd.args(args)
+            .env_clear()
+            .env("PATH", SANDBOX_PATH)
+            .env("HOME", SCRATCH_MOUNT)
+            .env("TMPDIR", SCRATCH_MOUNT)
+            .env("LANG", "C.UTF-8")
+            .envs(env_vars)
+            .current_dir("/")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        // Runs in the forked child; only async-signal-safe calls on prepared data
+        unsafe {
+            cmd.pre_exec(move || plan.enter());
+        }
+
+        cmd.spawn()
+            .map_err(|e| format!("Failed to start sandboxed process: {}", e))
+    }
+
+    /// Kill every process in the sandbox cgroup
+    pub fn kill(&self) {
+        if let Some(cgroup) = &self.cgroup {
+            cgroup.kill();
+        }
+    }
+
+    /// Write `code` to the scratch directory and return the interpreter
+    /// command line that runs it
+    ///
+    /// The interpreter runs in isolated mode, so `PYTHON*` variables and user
+    /// site-packages are ignored.
+    pub fn python_command(&self, code: &str) -> Result<(&'static str, Vec<String>), String> {
+        fs::write(self.scratch.join(PYTHON_SCRIPT), code)
+            .map_err(|e| format!("Failed to write script: {}", e))?;
+        let script = format!("{}/{}", SCRATCH_MOUNT, PYTHON_SCRIPT);
+        Ok((
+            PYTHON_INTERPRETER,
+            vec!["-I".to_string(), "-B".to_string(), script],
+        ))
+    }
+
+    /// Run a command in the sandbox and collect (stdout, stderr, exit code)
+    ///
+    /// The command is killed with everything it started when the timeout
+    /// expires. Each stream is capped at `max_output_bytes`; once a cap is hit
+    /// the pipe is closed, so further writes fail with EPIPE/SIGPIPE. Exit
+    /// codes above 128 report the terminating signal.
+    pub async fn run(
+        &self,
+        command: &str,
+        args: &[String],
+        env_vars: &HashMap<String, String>,
+    ) -> Result<(String, String, i32), String> {
+        let mut child = self.spawn(command, args, env_vars)?;
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let cap = self.limits.max_output_bytes;
+
+        let collect = async {
+            let (stdout, stderr) = tokio::join!(read_capped(stdout, cap), read_capped(stderr, cap));
+            let status = child.wait().await;
+            (stdout, stderr, status)
+        };
+
+        let (stdout, stderr, status) =
+            match tokio::time::timeout(self.limits.timeout, collect).await {
+                Ok(output) => output,
+                Err(_) => {
+                    // Dropping the child killed it; its PID namespace dies with it
+                    self.kill();
+                    return Err(format!(
+                        "Execution timed out after {} seconds",
+                        self.limits.timeout.as_secs()
+                    ));
+                }
+            };
+        let status = status.map_err(|e| format!("Sandboxed process failed: {}", e))?;
+
+        Ok((
+            captured_text(stdout, cap),
+            captured_text(stderr, cap),
+            exit_code(status),
+        ))
+    }
+
+    // Create the root skeleton: mount points for the binds and symlinks for
+    // merged-/usr system directories
+    fn build_root(&self) -> io::Result<()> {
+        fs::create_dir_all(&self.scratch)?;
+        fs::create_dir_all(self.root.join("dev"))?;
+        fs::create_dir_all(self.root.join("proc"))?;
+        fs::create_dir_all(self.root.join(SCRATCH_MOUNT.trim_start_matches('/')))?;
+
+        for dir in SYSTEM_DIRS {
+            let target = self.root.join(dir.trim_start_matches('/'));
+            match fs::symlink_metadata(dir) {
+                Ok(meta) if meta.file_type().is_symlink() => {
+                    std::os::unix::fs::symlink(fs::read_link(dir)?, target)?
+                }
+                Ok(meta) if meta.is_dir() => fs::create_dir_all(target)?,
+                _ => {}
+            }
+        }
+
+        for device in DEVICES {
+            if Path::new(device).exists() {
+                File::create(self.root.join(device.trim_start_matches('/')))?;
+            }
+        }
+        for (link, target) in [
+            ("dev/fd", "/proc/self/fd"),
+            ("dev/stdin", "/proc/self/fd/0"),
+            ("dev/stdout", "/proc/self/fd/1"),
+            ("dev/stderr", "/proc/self/fd/2"),
+        ] {
+            std::os::unix::fs::symlink(target, self.root.join(link))?;
+        }
+
+        Ok(())
+    }
+
+    // Everything the child needs, allocated before fork
+    fn child_plan(&self) -> io::Result<ChildPlan> {
+        let mut binds = Vec::new();
+        for dir in SYSTEM_DIRS {
+            let target = self.root.join(dir.trim_start_matches('/'));
+            if fs::symlink_metadata(&target).is_ok_and(|meta| meta.is_dir()) {
+                binds.push(Bind::new(Path::new(dir), &target, true)?);
+            }
+        }
+        for device in DEVICES {
+            let target = self.root.join(device.trim_start_matches('/'));
+            if target.exists() {
+                binds.push(Bind::new(Path::new(device), &target, false)?);
+            }
+        }
+        binds.push(Bind::new(
+            &self.scratch,
+            &self.root.join(SCRATCH_MOUNT.trim_start_matches('/')),
+            false,
+        )?);
+
+        let mut namespaces = NAMESPACES;
+        if !self.limits.allow_network {
+            namespaces |= libc::CLONE_NEWNET;
+        }
+
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let cpu_seconds = self.limits.timeout.as_secs().max(1) + 1;
+
+        Ok(ChildPlan {
+            namespaces,
+            uid_map: CString::new(format!("0 {} 1\n", uid))?,
+            gid_map: CString::new(format!("0 {} 1\n", gid))?,
+            cgroup_procs: self.cgroup.as_ref().map(|cgroup| cgroup.procs.as_raw_fd()),
+            root: path_cstring(&self.root)?,
+            proc_target: path_cstring(&self.root.join("proc"))?,
+            binds,
+            workdir: CString::new(SCRATCH_MOUNT)?,
+            hostname: CString::new("sandbox")?,
+            rlimits: vec![
+                (libc::RLIMIT_AS, self.limits.memory_bytes),
+                (libc::RLIMIT_CPU, cpu_seconds),
+                (libc::RLIMIT_FSIZE, MAX_FILE_BYTES),
+                (libc::RLIMIT_NOFILE, MAX_OPEN_FILES),
+                (libc::RLIMIT_CORE, 0),
+            ],
+            filter: seccomp::build_filter(),
+        })
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        if let Some(cgroup) = self.cgroup.take() {
+            cgroup.remove();
+        }
+        if let Err(e) = fs::remove_dir_all(&self.dir) {
+            log::warn!(
+                "Failed to remove sandbox directory {}: {}",
+                self.dir.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Run a command in a fresh Linux sandbox configured by `config`, with
+/// `workspace` (if any) as its `/scratch` directory
+pub async fn execute_in_sandbox(
+    command: &str,
+    args: &[String],
+    env_vars: &HashMap<String, String>,
+    config: &ExecutorConfig,
+    workspace: Option<&Path>,
+) -> Result<(String, String, i32), String> {
+    let sandbox = open_sandbox(config, workspace)?;
+    sandbox.run(command, args, env_vars).await
+}
+
+/// Run a Python script in a fresh Linux sandbox configured by `config`
+///
+/// The script is written to the sandbox's scratch directory, run by an
+/// isolated-mode interpreter and removed afterwards.
+pub async fn execute_python_in_sandbox(
+    code: &str,
+    env_vars: &HashMap<String, String>,
+    config: &ExecutorConfig,
+    workspace: Option<&Path>,
+) -> Result<(String, String, i32), String> {
+    let sandbox = open_sandbox(config, workspace)?;
+    let (interpreter, args) = sandbox.python_command(code)?;
+    let result = sandbox.run(interpreter, &args, env_vars).await;
+    let _ = fs::remove_file(sandbox.scratch_dir().join(PYTHON_SCRIPT));
+    result
+}
+
+fn open_sandbox(config: &ExecutorConfig, workspace: Option<&Path>) -> Result<Sandbox, String> {
+    match workspace {
+        Some(workspace) => Sandbox::with_workspace(config, workspace),
+        None => Sandbox::create(config),
+    }
+}
+
+/// Exit code of a sandboxed process; 128 + signal number when it was killed
+pub fn exit_code(status: ExitStatus) -> i32 {
+    status
+        .code()
+        .unwrap_or_else(|| 128 + status.signal().unwrap_or(0))
+}
+
+// Read a pipe until EOF or until more than `cap` bytes have arrived
+async fn read_capped<R: AsyncRead + Unpin>(pipe: Option<R>, cap: u64) -> Vec<u8> {
+    let mut buf = Vec::new();
+    if let Some(pipe) = pipe {
+        if let Err(e) = pipe.take(cap + 1).read_to_end(&mut buf).await {
+            log::warn!("Failed to read sandbox output: {}", e);
+        }
+    }
+    buf
+}
+
+fn captured_text(mut bytes: Vec<u8>, cap: u64) -> String {
+    if bytes.len() as u64 <= cap {
+        return String::from_utf8_lossy(&bytes).to_string();
+    }
+    bytes.truncate(cap as usize);
+    format!(
+        "{}\n[output truncated at {} bytes]",
+        String::from_utf8_lossy(&bytes),
+        cap
+    )
+}
+
+fn path_cstring(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes()).map_err(io::Error::from)
+}
+
+// A bind mount into the sandbox root
+struct Bind {
+    source: CString,
+    target: CString,
+    read_only: bool,
+}
+
+impl Bind {
+    fn new(source: &Path, target: &Path, read_only: bool) -> io::Result<Self> {
+        Ok(Self {
+            source: path_cstring(source)?,
+            target: path_cstring(target)?,
+            read_only,
+        })
+    }
+}
+
+// Sandbox setup performed in the forked child before exec
+struct ChildPlan {
+    namespaces: libc::c_int,
+    uid_map: CString,
+    gid_map: CString,
+    cgroup_procs: Option<RawFd>,
+    root: CString,
+    proc_target: CString,
+    binds: Vec<Bind>,
+    workdir: CString,
+    hostname: CString,
+    rlimits: Vec<(libc::__rlimit_resource_t, u64)>,
+    filter: Vec<libc::sock_filter>,
+}
+
+fn check(result: libc::c_int) -> io::Result<()> {
+    if result == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+unsafe fn write_file(path: &CStr, data: &[u8]) -> io::Result<()> {
+    let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+    check(fd)?;
+    let written = libc::write(fd, data.as_ptr().cast(), data.len());
+    let error = io::Error::last_os_error();
+    libc::close(fd);
+    if written == data.len() as isize {
+        Ok(())
+    } else {
+        Err(error)
+    }
+}
+
+unsafe fn mount(
+    source: Option<&CStr>,
+    target: &CStr,
+    fstype: Option<&CStr>,
+    flags: libc::c_ulong,
+) -> io::Result<()> {
+    check(libc::mount(
+        source.map_or(std::ptr::null(), CStr::as_ptr),
+        target.as_ptr(),
+        fstype.map_or(std::ptr::null(), CStr::as_ptr),
+        flags,
+        std::ptr::null(),
+    ))
+}
+
+// Flags a read-only remount must keep: mounts inherited from the parent user
+// namespace have them locked
+unsafe fn locked_mount_flags(target: &CStr) -> libc::c_ulong {
+    let mut stat: libc::statvfs = std::mem::zeroed();
+    if libc::statvfs(target.as_ptr(), &mut stat) != 0 {
+        return 0;
+    }
+    [
+        (libc::ST_NOSUID, libc::MS_NOSUID),
+        (libc::ST_NODEV, libc::MS_NODEV),
+        (libc::ST_NOEXEC, libc::MS_NOEXEC),
+        (libc::ST_NOATIME, libc::MS_NOATIME),
+        (libc::ST_NODIRATIME, libc::MS_NODIRATIME),
+        (libc::ST_RELATIME, libc::MS_RELATIME),
+    ]
+    .iter()
+    .filter(|(st, _)| stat.f_flag & st != 0)
+    .fold(0, |flags, (_, ms)| flags | ms)
+}
+
+unsafe fn remount_read_only(target: &CStr) -> io::Result<()> {
+    let flags = libc::MS_BIND
+        | libc::MS_REMOUNT
+        | libc::MS_RDONLY
+        | libc::MS_NOSUID
+        | locked_mount_flags(target);
+    mount(None, target, None, flags)
+}
+
+// Wait for the sandboxed process and exit with its status
+unsafe fn forward_exit(pid: libc::pid_t) -> ! {
+    let mut status = 0;
+    while libc::waitpid(pid, &mut status, 0) == -1 {
+        if io::Error::last_os_error().raw_os_error() != Some(libc::EINTR) {
+            libc::_exit(1);
+        }
+    }
+    if libc::WIFEXITED(status) {
+        libc::_exit(libc::WEXITSTATUS(status))
+    } else if libc::WIFSIGNALED(status) {
+        libc::_exit(128 + libc::WTERMSIG(status))
+    } else {
+        libc::_exit(1)
+    }
+}
+
+impl ChildPlan {
+    fn enter(&self) -> io::Result<()> {
+        unsafe {
+            if let Some(fd) = self.cgroup_procs {
+                // "0" moves the writing process
+                if libc::write(fd, b"0".as_ptr().cast(), 1) != 1 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+
+            check(libc::unshare(self.namespaces))?;
+            match write_file(c"/proc/self/setgroups", b"deny") {
+                Err(e) if e.raw_os_error() != Some(libc::ENOENT) => return Err(e),
+                _ => {}
+            }
+            write_file(c"/proc/self/uid_map", self.uid_map.as_bytes())?;
+            write_file(c"/proc/self/gid_map", self.gid_map.as_bytes())?;
+
+            // A new PID namespace applies to children: the command runs in a
+            // second fork as PID 1, this process only relays its exit status.
+            match libc::fork() {
+                -1 => return Err(io::Error::last_os_error()),
+                0 => {}
+                pid => {
+                    // Close every inherited descriptor (including the spawn
+                    // error pipe) so the parent only waits on the command
+                    if libc::syscall(
+                        libc::SYS_close_range,
+                        0 as libc::c_uint,
+                        libc::c_uint::MAX,
+                        0 as libc::c_uint,
+                    ) != 0
+                    {
+                        for fd in 0..1024 {
+                            libc::close(fd);
+                        }
+                    }
+                    forward_exit(pid)
+                }
+            }
+            check(libc::prctl(
+                libc::PR_SET_PDEATHSIG,
+                libc::SIGKILL as libc::c_ulong,
+                0 as libc::c_ulong,
+                0 as libc::c_ulong,
+                0 as libc::c_ulong,
+            ))?;
+
+            self.mount_root()?;
+            check(libc::sethostname(
+                self.hostname.as_ptr(),
+                self.hostname.as_bytes().len(),
+            ))?;
+
+            for &(resource, value) in &self.rlimits {
+                let limit = libc::rlimit {
+                    rlim_cur: value,
+                    rlim_max: value,
+                };
+                check(libc::setrlimit(resource, &limit))?;
+            }
+
+            check(libc::chdir(self.workdir.as_ptr()))?;
+
+            check(libc::prctl(
+                libc::PR_SET_NO_NEW_PRIVS,
+                1 as libc::c_ulong,
+                0 as libc::c_ulong,
+                0 as libc::c_ulong,
+                0 as libc::c_ulong,
+            ))?;
+            let program = libc::sock_fprog {
+                len: self.filter.len() as libc::c_ushort,
+                filter: self.filter.as_ptr() as *mut libc::sock_filter,
+            };
+            check(libc::prctl(
+                libc::PR_SET_SECCOMP,
+                libc::SECCOMP_MODE_FILTER as libc::c_ulong,
+                &program as *const libc::sock_fprog,
+            ))?;
+        }
+        Ok(())
+    }
+
+    unsafe fn mount_root(&self) -> io::Result<()> {
+        // Keep every mount change inside the new mount namespace
+        mount(None, c"/", None, libc::MS_REC | libc::MS_PRIVATE)?;
+        mount(Some(&self.root), &self.root, None, libc::MS_BIND)?;
+
+        for bind in &self.binds {
+            mount(
+                Some(&bind.source),
+                &bind.target,
+                None,
+                libc::MS_BIND | libc::MS_REC,
+            )?;
+            if bind.read_only {
+                remount_read_only(&bind.target)?;
+            }
+        }
+
+        // A private /proc needs the host /proc still visible; hosts that mask
+        // parts of it (containers) refuse, and the sandbox runs without one
+        let _ = mount(
+            Some(c"proc"),
+            &self.proc_target,
+            Some(c"proc"),
+            libc::MS_NOSUID | libc::MS_NODEV | libc::MS_NOEXEC,
+        );
+
+        remount_read_only(&self.root)?;
+
+        check(libc::chdir(self.root.as_ptr()))?;
+        check(libc::syscall(libc::SYS_pivot_root, c".".as_ptr(), c".".as_ptr()) as libc::c_int)?;
+        check(libc::umount2(c".".as_ptr(), libc::MNT_DETACH))?;
+        check(libc::chdir(c"/".as_ptr()))
+    }
+}
+
+// cgroup v2 group limiting one execution
+struct Cgroup {
+    path: PathBuf,
+    // cgroup.procs, written by the child to join
+    procs: File,
+}
+
+impl Cgroup {
+    fn create(parent: &str, name: &str, limits: &SandboxLimits) -> io::Result<Self> {
+        let parent = if parent.is_empty() {
+            own_cgroup()?
+        } else {
+            PathBuf::from(parent)
+        };
+
+        // Fails when the parent still holds processes; a delegated parent
+        // should already have the controllers enabled
+        let _ = fs::write(parent.join("cgroup.subtree_control"), "+memory +cpu +pids");
+
+        let path = parent.join(name);
+        fs::create_dir(&path)?;
+        let cgroup = Self {
+            procs: OpenOptions::new()
+                .write(true)
+                .open(path.join("cgroup.procs"))
+                .inspect_err(|_| {
+                    let _ = fs::remove_dir(&path);
+                })?,
+            path,
+        };
+
+        let cpu_quota = 100_000 * u64::from(limits.cpu_percent) / 100;
+        for (file, value) in [
+            ("memory.max", limits.memory_bytes.to_string()),
+            ("memory.swap.max", "0".to_string()),
+            ("cpu.max", format!("{} 100000", cpu_quota)),
+            ("pids.max", limits.max_processes.to_string()),
+        ] {
+            if let Err(e) = fs::write(cgroup.path.join(file), value) {
+                cgroup.remove();
+                return Err(io::Error::new(e.kind(), format!("{}: {}", file, e)));
+            }
+        }
+
+        Ok(cgroup)
+    }
+
+    fn kill(&self) {
+        // cgroup.kill needs Linux 5.14; the PID namespace teardown covers older kernels
+        let _ = fs::write(self.path.join("cgroup.kill"), "1");
+    }
+
+    fn remove(&self) {
+        self.kill();
+        // Killed processes leave the cgroup asynchronously
+        for _ in 0..50 {
+            match fs::remove_dir(&self.path) {
+                Ok(()) => return,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return,
+                Err(_) => std::thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        log::warn!("Failed to remove sandbox cgroup {}", self.path.display());
+    }
+}
+
+// Directory of the service's own cgroup v2
+fn own_cgroup() -> io::Result<PathBuf> {
+    let mountinfo = fs::read_to_string("/proc/self/mountinfo")?;
+    let mount_point = mountinfo
+        .lines()
+        .find_map(|line| {
+            let (mount, fs) = line.split_once(" - ")?;
+            fs.starts_with("cgroup2 ")
+                .then(|| mount.split(' ').nth(4))
+                .flatten()
+        })
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "cgroup v2 is not mounted"))?;
+
+    let cgroups = fs::read_to_string("/proc/self/cgroup")?;
+    let own = cgroups
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no cgroup v2 membership"))?;
+
+    Ok(Path::new(mount_point).join(own.trim_start_matches('/')))
+}
+
+mod seccomp {
+    // Classic BPF opcodes (linux/bpf_common.h): class | size/op | mode/source
+    const BPF_LD_W_ABS: u16 = 0x20; // BPF_LD | BPF_W | BPF_ABS
+    const BPF_JMP_JEQ_K: u16 = 0x15; // BPF_JMP | BPF_JEQ | BPF_K
+    const BPF_JMP_JGE_K: u16 = 0x35; // BPF_JMP | BPF_JGE | BPF_K
+    const BPF_JMP_JSET_K: u16 = 0x45; // BPF_JMP | BPF_JSET | BPF_K
+    const BPF_RET_K: u16 = 0x06; // BPF_RET | BPF_K
+
+    const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+    const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+    const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+
+    // struct seccomp_data offsets
+    const NR_OFFSET: u32 = 0;
+    const ARCH_OFFSET: u32 = 4;
+    const ARG0_OFFSET: u32 = 16;
+
+    #[cfg(target_arch = "x86_64")]
+    pub const AUDIT_ARCH: Option<u32> = Some(0xc000_003e);
+    #[cfg(target_arch = "aarch64")]
+    pub const AUDIT_ARCH: Option<u32> = Some(0xc000_00b7);
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    pub const AUDIT_ARCH: Option<u32> = None;
+
+    // Syscalls that change namespaces, mounts or kernel state, trace other
+    // processes or expose large kernel attack surface
+    const DENIED: &[libc::c_long] = &[
+        libc::SYS_mount,
+        libc::SYS_umount2,
+        libc::SYS_pivot_root,
+        libc::SYS_chroot,
+        libc::SYS_open_tree,
+        libc::SYS_move_mount,
+        libc::SYS_fsopen,
+        libc::SYS_fsconfig,
+        libc::SYS_fsmount,
+        libc::SYS_fspick,
+        libc::SYS_mount_setattr,
+        libc::SYS_unshare,
+        libc::SYS_setns,
+        libc::SYS_ptrace,
+        libc::SYS_process_vm_readv,
+        libc::SYS_process_vm_writev,
+        libc::SYS_kcmp,
+        libc::SYS_bpf,
+        libc::SYS_perf_event_open,
+        libc::SYS_userfaultfd,
+        libc::SYS_keyctl,
+        libc::SYS_add_key,
+        libc::SYS_request_key,
+        libc::SYS_open_by_handle_at,
+        libc::SYS_name_to_handle_at,
+        libc::SYS_init_module,
+        libc::SYS_finit_module,
+        libc::SYS_delete_module,
+        libc::SYS_kexec_load,
+        libc::SYS_kexec_file_load,
+        libc::SYS_reboot,
+        libc::SYS_swapon,
+        libc::SYS_swapoff,
+        libc::SYS_acct,
+        libc::SYS_quotactl,
+        libc::SYS_syslog,
+        libc::SYS_settimeofday,
+        libc::SYS_clock_settime,
+        libc::SYS_clock_adjtime,
+        libc::SYS_adjtimex,
+        libc::SYS_sethostname,
+        libc::SYS_setdomainname,
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_iopl,
+        #[cfg(target_arch = "x86_64")]
+        libc::SYS_ioperm,
+    ];
+
+    // clone flags that would create namespaces
+    const CLONE_NAMESPACE_FLAGS: u32 = (libc::CLONE_NEWUSER
+        | libc::CLONE_NEWNS
+        | libc::CLONE_NEWPID
+        | libc::CLONE_NEWNET
+        | libc::CLONE_NEWIPC
+        | libc::CLONE_NEWUTS
+        | libc::CLONE_NEWCGROUP) as u32;
+
+    fn stmt(code: u16, k: u32) -> libc::sock_filter {
+        libc::sock_filter {
+            code,
+            jt: 0,
+            jf: 0,
+            k,
+        }
+    }
+
+    fn jump(code: u16, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+        libc::sock_filter { code, jt, jf, k }
+    }
+
+    /// Build the filter: denied syscalls fail with EPERM, clone3 with ENOSYS
+    /// (libc falls back to clone, whose flags can be inspected) and foreign
+    /// architectures kill the process
+    pub fn build_filter() -> Vec<libc::sock_filter> {
+        let arch = AUDIT_ARCH.unwrap_or(0);
+        let mut filter = vec![
+            stmt(BPF_LD_W_ABS, ARCH_OFFSET),
+            jump(BPF_JMP_JEQ_K, arch, 1, 0),
+            stmt(BPF_RET_K, SECCOMP_RET_KILL_PROCESS),
+            stmt(BPF_LD_W_ABS, NR_OFFSET),
+        ];
+
+        // Tail layout: [clone arg check (3)] ALLOW, EPERM, ENOSYS
+        let mut checks: Vec<(u32, u8)> = Vec::new();
+        #[cfg(target_arch = "x86_64")]
+        {
+            // x32 ABI syscall numbers
+            filter.push(jump(BPF_JMP_JGE_K, 0x4000_0000, 0, 0));
+            checks.push((filter.len() as u32 - 1, 0));
+        }
+        for &nr in DENIED {
+            filter.push(jump(BPF_JMP_JEQ_K, nr as u32, 0, 0));
+            checks.push((filter.len() as u32 - 1, 0));
+        }
+        filter.push(jump(BPF_JMP_JEQ_K, libc::SYS_clone3 as u32, 0, 0));
+        checks.push((filter.len() as u32 - 1, 1));
+        filter.push(jump(BPF_JMP_JEQ_K, libc::SYS_clone as u32, 0, 2));
+        filter.push(stmt(BPF_LD_W_ABS, ARG0_OFFSET));
+        filter.push(jump(BPF_JMP_JSET_K, CLONE_NAMESPACE_FLAGS, 1, 0));
+
+        let allow = filter.len() as u32;
+        filter.push(stmt(BPF_RET_K, SECCOMP_RET_ALLOW));
+        filter.push(stmt(BPF_RET_K, SECCOMP_RET_ERRNO | libc::EPERM as u32));
+        filter.push(stmt(BPF_RET_K, SECCOMP_RET_ERRNO | libc::ENOSYS as u32));
+
+        for (index, target) in checks {
+            let destination = allow + 1 + u32::from(target);
+            filter[index as usize].jt = (destination - index - 1) as u8;
+        }
+
+        filter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config(name: &str) -> ExecutorConfig {
+        ExecutorConfig {
+            sandbox_dir: std::env::temp_dir()
+                .join(format!("linux-sandbox-{}-{}", name, std::process::id()))
+                .to_string_lossy()
+                .to_string(),
+            execution_timeout_seconds: 5,
+            sandbox_backend: SandboxBackend::Namespaces,
+            ..ExecutorConfig::default()
+        }
+    }
+
+    fn shell(script: &str) -> Vec<String> {
+        vec!["-c".to_string(), script.to_string()]
+    }
+
+    #[test]
+    fn test_seccomp_filter_jumps_stay_in_program() {
+        let filter = seccomp::build_filter();
+        assert!(filter.len() < 255);
+        for (index, instruction) in filter.iter().enumerate() {
+            if instruction.code & 0x07 == 0x05 {
+                assert!(index + 1 + (instruction.jt as usize) < filter.len());
+                assert!(index + 1 + (instruction.jf as usize) < filter.len());
+            }
+        }
+        // Every path ends in a return
+        assert_eq!(filter.last().unwrap().code, 0x06);
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_isolation() {
+        if !is_supported() {
+            eprintln!("skipping: user namespaces unavailable");
+            return;
+        }
+        let config = test_config("isolation");
+        let script = "echo pid=$$; uname -n; \
+                      echo data > /scratch/out && cat /scratch/out; \
+                      touch /etc/sandbox-test 2>/dev/null || echo root=readonly; \
+                      [ -e /proc/net/dev ] && echo interfaces=$(grep -c : /proc/net/dev); \
+                      command -v unshare >/dev/null && unshare --user true 2>/dev/null \
+                          && echo unshare=allowed; \
+                      true";
+        let (stdout, _stderr, code) =
+            execute_in_sandbox("sh", &shell(script), &HashMap::new(), &config, None)
+                .await
+                .unwrap();
+
+        assert_eq!(code, 0, "stdout: {}", stdout);
+        assert!(stdout.contains("pid=1"));
+        assert!(stdout.contains("sandbox"));
+        assert!(stdout.contains("data"));
+        assert!(stdout.contains("root=readonly"));
+        // Only loopback in the network namespace (when /proc could be mounted)
+        assert!(!stdout.contains("interfaces=") || stdout.contains("interfaces=1"));
+        assert!(!stdout.contains("unshare=allowed"));
+        assert!(!Path::new("/etc/sandbox-test").exists());
+        // The sandbox directory is removed afterwards
+        assert_eq!(fs::read_dir(sandbox_base_dir(&config)).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_sandbox_timeout_and_environment() {
+        if !is_supported() {
+            eprintln!("skipping: user namespaces unavailable");
+            return;
+        }
+        let mut config = test_config("timeout");
+        config.execution_timeout_seconds = 1;
+
+        let err = execute_in_sandbox("sh", &shell("sleep 30"), &HashMap::new(), &config, None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("timed out"));
+
+        std::env::set_var("LINUX_SANDBOX_SECRET", "leak");
+        let env_vars = HashMap::from([("GREETING".to_string(), "hi".to_string())]);
+        let (stdout, _, code) = execute_in_sandbox(
+            "sh",
+            &shell("echo $GREETING ${LINUX_SANDBOX_SECRET:-clean} $PWD; exit 3"),
+            &env_vars,
+            &config,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(code, 3);
+        assert_eq!(stdout.trim(), "hi clean /scratch");
+
+        // A workspace is mounted as /scratch and outlives the sandbox
+        let workspace = sandbox_base_dir(&config).join("workspace");
+        fs::create_dir_all(&workspace).unwrap();
+        fs::write(workspace.join("input.txt"), "uploaded").unwrap();
+        let (_, _, code) = execute_in_sandbox(
+            "sh",
+            &shell("cat input.txt > output.txt"),
+            &HashMap::new(),
+            &config,
+            Some(&workspace),
+        )
+        .await
+        .unwrap();
+        assert_eq!(code, 0);
+        assert_eq!(
+            fs::read_to_string(workspace.join("output.txt")).unwrap(),
+            "uploaded"
+        );
+        fs::remove_dir_all(&workspace).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_python_sandbox_output_cap() {
+        if !is_supported() || !Path::new("/usr/bin/python3").exists() {
+            eprintln!("skipping: user namespaces or python3 unavailable");
+            return;
+        }
+        let mut config = test_config("python");
+        config.max_output_bytes = 1024;
+
+        let code = "import os, sys, socket\n\
+                    print(os.getcwd(), os.environ.get('PYTHONPATH', 'none'))\n\
+                    try:\n    socket.create_connection(('1.1.1.1', 53), timeout=1)\n\
+                    except OSError:\n    print('network=blocked')\n\
+                    sys.stderr.write('x' * 100000)\n";
+        let env_vars = HashMap::from([("PYTHONPATH".to_string(), "/tmp".to_string())]);
+        let (stdout, stderr, _) = execute_python_in_sandbox(code, &env_vars, &config, None)
+            .await
+            .unwrap();
+
+        assert!(stdout.starts_with("/scratch"), "stdout: {}", stdout);
+        assert!(stdout.contains("network=blocked"));
+        assert!(stderr.len() < 1100);
+        assert!(stderr.ends_with("[output truncated at 1024 bytes]"));
+    }
+}
@@ -7,40 +7,63 @@ use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::env;
 use std::net::SocketAddr;
+use std::pin::Pin;
 use std::sync::Arc;
+#[cfg(target_os = "windows")]
 use std::time::Duration;
 use std::time::Instant;
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, Stream, StreamExt};
 use tonic::{transport::Server, Request, Response, Status};
+#[cfg(target_os = "windows")]
 use windows_service::{
     service::{
-        ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState, ServiceStatus,
-        ServiceType,
+        ServiceAccess, ServiceAction, ServiceActionType, ServiceControl, ServiceControlAccept,
+        ServiceExitCode, ServiceFailureActions, ServiceFailureResetPeriod, ServiceState,
+        ServiceStatus, ServiceType,
     },
-    service_control_handler::{self, ServiceControlHandler},
+    service_control_handler::{self, ServiceControlHandlerResult},
     service_dispatcher,
+    service_manager::{ServiceManager, ServiceManagerAccess},
 };
 
 // Import configuration module
 mod config;
 use config::{init_config_manager, start_config_monitoring};
 
+#[cfg(target_os = "windows")]
 const SERVICE_NAME: &str = "PhoenixExecutorService";
-const SERVICE_DISPLAY_NAME: &str = "Phoenix Executor Service";
-const SERVICE_DESCRIPTION: &str = "Phoenix ORCH Native Execution Service";
 
 // Service recovery settings
+#[cfg(target_os = "windows")]
 const RESTART_DELAY_MS: u32 = 30000; // 30 seconds
+#[cfg(target_os = "windows")]
 const MAX_RESTART_ATTEMPTS: u32 = 3;
 
+#[cfg(target_os = "windows")]
+const HEALTH_SERVICE_NAME: &str = "executor-service-windows-native";
+#[cfg(not(target_os = "windows"))]
+const HEALTH_SERVICE_NAME: &str = "executor-service";
+
+// Shared with the library, which also exposes the workspace-less entry points
+#[allow(dead_code)]
 mod execution_logic;
-use execution_logic::{
-    execute_python_sandboxed, execute_shell_command, get_execution_stats, simulate_input,
-};
+use execution_logic::{execute_shell_command_in, get_execution_stats, simulate_input};
+
+mod artifacts;
+use artifacts::{output_matcher, ArtifactError, ArtifactStore};
+
+mod command_stream;
+use command_stream::{execute_command_stream, StreamEvent, StreamEventKind, STREAM_BUFFER};
 
 // Windows executor module for native control
 #[cfg(target_os = "windows")]
 mod windows_executor;
 
+// Linux namespace sandbox backend
+#[cfg(target_os = "linux")]
+mod linux_sandbox;
+
 static START_TIME: Lazy<Instant> = Lazy::new(Instant::now);
 
 // Import monitoring, performance, and security modules
@@ -48,8 +71,8 @@ mod monitoring;
 mod performance;
 mod security;
 use monitoring::{init_monitoring, get_monitoring_stats};
-use performance::{init_performance_optimizer, get_performance_stats};
-use security::{init_security_manager, get_security_stats};
+use performance::init_performance_optimizer;
+use security::init_security_manager;
 
 pub mod agi_core {
     tonic::include_proto!("agi_core");
@@ -58,7 +81,9 @@ pub mod agi_core {
 use agi_core::{
     executor_service_server::{ExecutorService, ExecutorServiceServer},
     health_service_server::{HealthService, HealthServiceServer},
-    CommandRequest, CommandResponse, HealthRequest, HealthResponse, InputRequest, InputResponse,
+    ArtifactInfo, CommandRequest, CommandResponse, CommandStreamEvent, GetArtifactRequest,
+    GetArtifactResponse, HealthRequest, HealthResponse, InputRequest, InputResponse,
+    ResourceSample, UploadFilesRequest, UploadFilesResponse,
 };
 
 // Define the Executor Server Structure
@@ -75,23 +100,158 @@ impl ExecutorService for ExecutorServer {
 
         log::info!("Received ExecuteCommand request: {}", req.command);
 
-        match execute_shell_command(&req.command, &req.args, &req.env).await {
-            Ok((stdout, stderr, exit_code)) => Ok(Response::new(CommandResponse {
-                stdout,
-                stderr,
-                exit_code,
-            })),
+        // Every execution runs in its own workspace, deleted when it is dropped
+        let store = ArtifactStore::from_config(&config::load_env_config());
+        let outputs = output_matcher(&req.output_globs).map_err(artifact_status)?;
+        let workspace = {
+            let store = store.clone();
+            let workspace_id = req.workspace_id.clone();
+            tokio::task::spawn_blocking(move || store.open_workspace(&workspace_id))
+                .await
+                .map_err(|e| Status::internal(format!("Workspace task failed: {}", e)))?
+                .map_err(artifact_status)?
+        };
+
+        let result =
+            execute_shell_command_in(&req.command, &req.args, &req.env, Some(workspace.path()))
+                .await;
+
+        // Collecting copies the outputs and dropping the workspace deletes it, both off the runtime
+        let succeeded = result.is_ok();
+        let collected = tokio::task::spawn_blocking(move || {
+            let collected = succeeded.then(|| store.collect(&workspace, &outputs));
+            drop(workspace);
+            collected
+        })
+        .await
+        .map_err(|e| Status::internal(format!("Artifact collection task failed: {}", e)))?;
+
+        match result {
+            Ok((stdout, mut stderr, exit_code)) => {
+                let artifacts = match collected {
+                    Some(Ok(artifacts)) => artifacts,
+                    Some(Err(e)) => {
+                        log::error!("Artifact collection failed: {}", e);
+                        stderr.push_str("\n[artifact collection failed]");
+                        Vec::new()
+                    }
+                    None => Vec::new(),
+                };
+                Ok(Response::new(CommandResponse {
+                    stdout,
+                    stderr,
+                    exit_code,
+                    artifacts: artifacts
+                        .into_iter()
+                        .map(|artifact| ArtifactInfo {
+                            url: artifact.url(),
+                            artifact_id: artifact.id,
+                            path: artifact.path,
+                            size_bytes: artifact.size_bytes,
+                        })
+                        .collect(),
+                }))
+            }
             Err(e) => {
                 log::error!("Command execution failed: {}", e);
                 Ok(Response::new(CommandResponse {
                     stdout: "".to_string(),
                     stderr: e,
                     exit_code: -1,
+                    artifacts: Vec::new(),
                 }))
             }
         }
     }
 
+    type ExecuteCommandStreamStream =
+        Pin<Box<dyn Stream<Item = Result<CommandStreamEvent, Status>> + Send + 'static>>;
+
+    async fn execute_command_stream(
+        &self,
+        request: Request<CommandRequest>,
+    ) -> Result<Response<Self::ExecuteCommandStreamStream>, Status> {
+        let req = request.into_inner();
+
+        log::info!("Received ExecuteCommandStream request: {}", req.command);
+
+        if !req.workspace_id.is_empty() || !req.output_globs.is_empty() {
+            return Err(Status::invalid_argument(
+                "ExecuteCommandStream does not support workspaces; use ExecuteCommand",
+            ));
+        }
+
+        // The bounded channel is the backpressure: a slow client stalls the output readers
+        let (event_tx, event_rx) = mpsc::channel(STREAM_BUFFER);
+        tokio::spawn(async move {
+            execute_command_stream(
+                &req.command,
+                &req.args,
+                &req.env,
+                req.max_output_bytes,
+                event_tx,
+            )
+            .await;
+        });
+
+        let stream =
+            ReceiverStream::new(event_rx).map(|event| Ok::<_, Status>(stream_event(event)));
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn upload_files(
+        &self,
+        request: Request<UploadFilesRequest>,
+    ) -> Result<Response<UploadFilesResponse>, Status> {
+        let req = request.into_inner();
+
+        log::info!(
+            "Received UploadFiles request: {} files for workspace '{}'",
+            req.files.len(),
+            req.workspace_id
+        );
+
+        let files: Vec<(String, Vec<u8>)> = req
+            .files
+            .into_iter()
+            .map(|file| (file.path, file.content))
+            .collect();
+        let store = ArtifactStore::from_config(&config::load_env_config());
+        let summary =
+            tokio::task::spawn_blocking(move || store.upload_files(&req.workspace_id, &files))
+                .await
+                .map_err(|e| Status::internal(format!("Upload task failed: {}", e)))?
+                .map_err(artifact_status)?;
+
+        Ok(Response::new(UploadFilesResponse {
+            workspace_id: summary.workspace_id,
+            files: summary.files as u32,
+            bytes: summary.bytes,
+        }))
+    }
+
+    async fn get_artifact(
+        &self,
+        request: Request<GetArtifactRequest>,
+    ) -> Result<Response<GetArtifactResponse>, Status> {
+        let artifact_id = request.into_inner().artifact_id;
+
+        log::info!("Received GetArtifact request: {}", artifact_id);
+
+        let store = ArtifactStore::from_config(&config::load_env_config());
+        let artifact = tokio::task::spawn_blocking(move || store.get_artifact(&artifact_id))
+            .await
+            .map_err(|e| Status::internal(format!("Artifact read task failed: {}", e)))?
+            .map_err(artifact_status)?;
+
+        Ok(Response::new(GetArtifactResponse {
+            artifact_id: artifact.id,
+            size_bytes: artifact.content.len() as u64,
+            content: artifact.content,
+            stored_at: artifact.stored_at,
+        }))
+    }
+
     async fn simulate_input(
         &self,
         request: Request<InputRequest>,
@@ -116,110 +276,103 @@ impl ExecutorService for ExecutorServer {
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn artifact_status(error: ArtifactError) -> Status {
+    match error {
+        ArtifactError::InvalidArgument(msg) => Status::invalid_argument(msg),
+        ArtifactError::NotFound(msg) => Status::not_found(format!("{} not found", msg)),
+        ArtifactError::Internal(msg) => {
+            log::error!("Workspace or artifact store failure: {}", msg);
+            Status::internal("Workspace or artifact store failure")
+        }
+    }
+}
+
+fn stream_event(event: StreamEvent) -> CommandStreamEvent {
+    let mut message = CommandStreamEvent {
+        timestamp_ms: event.timestamp_ms,
+        ..Default::default()
+    };
+    match event.kind {
+        StreamEventKind::Output { stream, data } => {
+            message.kind = stream.as_str().to_string();
+            message.data = data;
+        }
+        StreamEventKind::Resources(sample) => {
+            message.kind = "resources".to_string();
+            message.resources = Some(ResourceSample {
+                memory_bytes: sample.memory_bytes,
+                cpu_time_ms: sample.cpu_time_ms,
+                cpu_percent: sample.cpu_percent,
+                processes: sample.processes,
+            });
+        }
+        StreamEventKind::Exit(summary) => {
+            message.kind = "exit".to_string();
+            message.exit_code = summary.exit_code;
+            message.error = summary.error.unwrap_or_default();
+            message.output_truncated = summary.output_truncated;
+            message.output_bytes = summary.output_bytes;
+            message.duration_ms = summary.duration.as_millis() as u64;
+        }
+    }
+    message
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     if let Err(e) = run_service() {
         log::error!("Service error: {}", e);
-        return Err(e.into());
+        return Err(e);
     }
     Ok(())
 }
 
+#[cfg(target_os = "windows")]
 fn run_service() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize Windows service dispatcher
     service_dispatcher::start(SERVICE_NAME, ffi_service_main)?;
     Ok(())
 }
 
-// Windows service entry point
-extern "system" fn ffi_service_main(_: u32, _: *mut *mut u16) {
-    // Initialize logging
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+/// Run the server in the foreground until Ctrl-C or SIGTERM
+#[cfg(not(target_os = "windows"))]
+fn run_service() -> Result<(), Box<dyn std::error::Error>> {
+    init_logging();
 
-    log::info!("Starting PHOENIX ORCH Executor Service - Windows Native Edition");
+    log::info!("Starting PHOENIX ORCH Executor Service");
 
-    // Initialize monitoring system
-    init_monitoring();
-    log::info!("Enhanced monitoring system initialized");
+    let addr = listen_addr()?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        init_services().await;
+        serve(addr, shutdown_signal()).await
+    })?;
 
-    // Initialize performance optimizer
-    init_performance_optimizer(performance::PerformanceConfig::default());
-    log::info!("Performance optimization system initialized");
-
-    // Initialize security manager
-    init_security_manager(security::SecurityConfig::default());
-    log::info!("Comprehensive security system initialized");
-
-    // Check if running on Windows and initialize
-    #[cfg(target_os = "windows")]
-    {
-        log::info!("Windows platform detected - Using native Job Object control");
-
-        // Initialize configuration system
-        if let Err(e) = init_config_manager().await {
-            log::error!("Failed to initialize configuration manager: {}", e);
-            // Fallback to default sandbox directory
-            let sandbox_path = std::path::Path::new(r"C:\phoenix_sandbox");
-            if !sandbox_path.exists() {
-                std::fs::create_dir_all(sandbox_path).expect("Failed to create sandbox directory");
-                log::info!("Created fallback sandbox directory at: {}", sandbox_path.display());
-            }
-        } else {
-            // Start configuration monitoring
-            start_config_monitoring().await;
+    log::info!("Executor service stopped");
+    Ok(())
+}
 
-            // Get sandbox directory from configuration
-            let config = config::get_config().await;
-            let sandbox_path = std::path::Path::new(&config.sandbox_dir);
+// Windows service entry point
+#[cfg(target_os = "windows")]
+extern "system" fn ffi_service_main(_: u32, _: *mut *mut u16) {
+    init_logging();
 
-            // Create sandbox directory if it doesn't exist
-            if !sandbox_path.exists() {
-                std::fs::create_dir_all(sandbox_path).expect("Failed to create sandbox directory");
-                log::info!("Created sandbox directory at: {}", sandbox_path.display());
-            }
+    log::info!("Starting PHOENIX ORCH Executor Service - Windows Native Edition");
 
-            // Perform sandbox integrity check
-            match check_sandbox_integrity() {
-                Ok(_) => log::info!("Sandbox integrity check passed"),
-                Err(e) => {
-                    log::error!("Sandbox integrity check failed: {}", e);
-                    // In production, you might want to fail startup here
-                }
-            }
+    let addr = match listen_addr() {
+        Ok(addr) => addr,
+        Err(e) => {
+            log::error!("Invalid EXECUTOR_ADDR: {}", e);
+            return;
         }
-    }
-
-    #[cfg(not(target_os = "windows"))]
-    {
-        log::warn!("Non-Windows platform detected - Limited execution capabilities");
-    }
-
-    // Read address from environment variable or use the default port 50055 (as per requirements)
-    let addr_str = env::var("EXECUTOR_ADDR").unwrap_or_else(|_| "0.0.0.0:50055".to_string());
-
-    let addr: SocketAddr = if addr_str.starts_with("http://") {
-        addr_str
-            .strip_prefix("http://")
-            .unwrap_or(&addr_str)
-            .parse()?
-    } else {
-        addr_str.parse()?
     };
 
-    let executor_server = ExecutorServer::default();
-
-    log::info!("PHOENIX ORCH Executor Service starting on {}", addr);
-    println!(
-        "PHOENIX ORCH Executor Service (Windows Native) listening on {}",
-        addr
-    );
-
-    // Log execution configuration
-    let exec_stats = execution_logic::get_execution_stats();
-    log::info!("Execution configuration:");
-    for (key, value) in exec_stats.iter() {
-        log::info!("  {}: {}", key, value);
-    }
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            log::error!("Failed to start the async runtime: {}", e);
+            return;
+        }
+    };
 
     // Initialize service status handle
     let status_handle = match service_control_handler::register(SERVICE_NAME, service_handler) {
@@ -230,24 +383,8 @@ extern "system" fn ffi_service_main(_: u32, _: *mut *mut u16) {
         }
     };
 
-    // Configure service recovery
-    let recovery_config = windows_service::service::ServiceRecoveryConfig {
-        reset_period: Duration::from_secs(86400), // Reset counter after 24 hours
-        actions: vec![
-            windows_service::service::RecoveryAction::Restart {
-                delay: Duration::from_millis(RESTART_DELAY_MS as u64),
-            },
-            windows_service::service::RecoveryAction::Restart {
-                delay: Duration::from_millis(RESTART_DELAY_MS as u64),
-            },
-            windows_service::service::RecoveryAction::Restart {
-                delay: Duration::from_millis(RESTART_DELAY_MS as u64),
-            },
-        ],
-    };
-
     // Apply recovery settings
-    if let Err(e) = status_handle.set_recovery_config(&recovery_config) {
+    if let Err(e) = configure_recovery() {
         log::error!("Failed to set service recovery config: {}", e);
     } else {
         log::info!(
@@ -271,17 +408,10 @@ extern "system" fn ffi_service_main(_: u32, _: *mut *mut u16) {
         return;
     }
 
-    let _ = *START_TIME;
-    let executor_server = Arc::new(executor_server);
-    let exec_for_health = executor_server.clone();
-
-    // Run the gRPC server
-    let server_result = tokio::runtime::Runtime::new().unwrap().block_on(async {
-        Server::builder()
-            .add_service(ExecutorServiceServer::from_arc(executor_server))
-            .add_service(HealthServiceServer::from_arc(exec_for_health))
-            .serve(addr)
-            .await
+    // The control handler exits the process on stop, so the server runs until then
+    let server_result = runtime.block_on(async {
+        init_services().await;
+        serve(addr, std::future::pending()).await
     });
 
     if let Err(e) = server_result {
@@ -299,6 +429,188 @@ extern "system" fn ffi_service_main(_: u32, _: *mut *mut u16) {
     }
 }
 
+/// Restart the service after a crash, three times with a delay each
+#[cfg(target_os = "windows")]
+fn configure_recovery() -> windows_service::Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)?;
+    let service = manager.open_service(SERVICE_NAME, ServiceAccess::CHANGE_CONFIG)?;
+
+    let restart = ServiceAction {
+        action_type: ServiceActionType::Restart,
+        delay: Duration::from_millis(RESTART_DELAY_MS as u64),
+    };
+    service.update_failure_actions(ServiceFailureActions {
+        reset_period: ServiceFailureResetPeriod::After(Duration::from_secs(86400)), // Reset counter after 24 hours
+        reboot_msg: None,
+        command: None,
+        actions: Some(vec![restart; MAX_RESTART_ATTEMPTS as usize]),
+    })
+}
+
+// Service control handler
+#[cfg(target_os = "windows")]
+fn service_handler(control_event: ServiceControl) -> ServiceControlHandlerResult {
+    match control_event {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            log::info!("Service stop/shutdown requested");
+            // Initiate graceful shutdown
+            std::process::exit(0);
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    }
+}
+
+fn init_logging() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+}
+
+/// Read the listen address from EXECUTOR_ADDR, defaulting to port 50055
+fn listen_addr() -> Result<SocketAddr, Box<dyn std::error::Error>> {
+    let addr_str = env::var("EXECUTOR_ADDR").unwrap_or_else(|_| "0.0.0.0:50055".to_string());
+    let addr = addr_str.strip_prefix("http://").unwrap_or(&addr_str).parse()?;
+    Ok(addr)
+}
+
+/// Start monitoring, performance optimization, security and configuration
+///
+/// These spawn background tasks, so they must run inside the runtime.
+async fn init_services() {
+    // Initialize monitoring system
+    init_monitoring();
+    log::info!("Enhanced monitoring system initialized");
+
+    // Initialize performance optimizer
+    init_performance_optimizer(performance::PerformanceConfig::default());
+    log::info!("Performance optimization system initialized");
+
+    // Initialize security manager
+    init_security_manager(security::SecurityConfig::default());
+    log::info!("Comprehensive security system initialized");
+
+    // Initialize configuration system
+    let config = match init_config_manager().await {
+        Ok(_) => {
+            // Start configuration monitoring
+            start_config_monitoring().await;
+            config::get_config().await
+        }
+        Err(e) => {
+            log::error!("Failed to initialize configuration manager: {}", e);
+            // Fall back to the environment and built-in defaults
+            config::load_env_config()
+        }
+    };
+
+    // Create sandbox directory if it doesn't exist
+    #[cfg(target_os = "linux")]
+    let sandbox_dir = linux_sandbox::sandbox_base_dir(&config);
+    #[cfg(not(target_os = "linux"))]
+    let sandbox_dir = std::path::PathBuf::from(&config.sandbox_dir);
+    let sandbox_path = sandbox_dir.as_path();
+    if !sandbox_path.exists() {
+        match std::fs::create_dir_all(sandbox_path) {
+            Ok(()) => log::info!("Created sandbox directory at: {}", sandbox_path.display()),
+            Err(e) => log::error!(
+                "Failed to create sandbox directory {}: {}",
+                sandbox_path.display(),
+                e
+            ),
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        log::info!("Windows platform detected - Using native Job Object control");
+
+        // Perform sandbox integrity check
+        match windows_executor::check_sandbox_integrity() {
+            Ok(_) => log::info!("Sandbox integrity check passed"),
+            Err(e) => {
+                log::error!("Sandbox integrity check failed: {}", e);
+                // In production, you might want to fail startup here
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if linux_sandbox::is_supported() {
+            log::info!("Linux platform detected - Using namespace sandbox");
+        } else {
+            log::warn!("Linux platform detected - User namespaces are unavailable on this host");
+        }
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+    {
+        log::warn!("Unsupported platform detected - Limited execution capabilities");
+    }
+
+    // Log execution configuration
+    let exec_stats = get_execution_stats();
+    log::info!("Execution configuration:");
+    for (key, value) in exec_stats.iter() {
+        log::info!("  {}: {}", key, value);
+    }
+}
+
+/// Serve the executor and health services until `shutdown` completes
+async fn serve(
+    addr: SocketAddr,
+    shutdown: impl std::future::Future<Output = ()>,
+) -> Result<(), tonic::transport::Error> {
+    let _ = *START_TIME;
+    let executor_server = Arc::new(ExecutorServer);
+    let exec_for_health = executor_server.clone();
+
+    // Uploads arrive in one message, so the decoding limit follows the upload limit
+    let max_message_bytes = (config::load_env_config().max_upload_mb as usize + 1) * 1024 * 1024;
+
+    artifacts::start_cleanup_task();
+
+    log::info!("PHOENIX ORCH Executor Service starting on {}", addr);
+    println!("PHOENIX ORCH Executor Service listening on {}", addr);
+
+    Server::builder()
+        .add_service(
+            ExecutorServiceServer::from_arc(executor_server)
+                .max_decoding_message_size(max_message_bytes),
+        )
+        .add_service(HealthServiceServer::from_arc(exec_for_health))
+        .serve_with_shutdown(addr, shutdown)
+        .await
+}
+
+/// Resolve on Ctrl-C or, on Unix, SIGTERM from the container runtime
+#[cfg(not(target_os = "windows"))]
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(e) => {
+                log::error!("Failed to listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+
+    log::info!("Shutdown signal received");
+}
+
 #[tonic::async_trait]
 impl HealthService for ExecutorServer {
     async fn get_health(
@@ -332,12 +644,22 @@ impl HealthService for ExecutorServer {
             dependencies.insert("windows_job_object".to_string(), "AVAILABLE".to_string());
             dependencies.insert("process_watchdog".to_string(), "AVAILABLE".to_string());
             dependencies.insert("sandbox_directory".to_string(), "CONFIGURED".to_string());
-            dependencies.insert("configuration".to_string(), config_status.to_string());
-            dependencies.insert("monitoring".to_string(), monitoring_status.to_string());
+            dependencies.insert("input_simulation".to_string(), "AVAILABLE".to_string());
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let sandbox_status = if linux_sandbox::is_supported() {
+                "AVAILABLE"
+            } else {
+                "UNAVAILABLE"
+            };
+            dependencies.insert("linux_sandbox".to_string(), sandbox_status.to_string());
         }
 
+        dependencies.insert("configuration".to_string(), config_status.to_string());
+        dependencies.insert("monitoring".to_string(), monitoring_status.to_string());
         dependencies.insert("shell".to_string(), "AVAILABLE".to_string());
-        dependencies.insert("input_simulation".to_string(), "AVAILABLE".to_string());
 
         // Add monitoring metrics to dependencies
         dependencies.insert(
@@ -355,7 +677,7 @@ impl HealthService for ExecutorServer {
 
         Ok(Response::new(HealthResponse {
             healthy: overall_healthy,
-            service_name: "executor-service-windows-native".to_string(),
+            service_name: HEALTH_SERVICE_NAME.to_string(),
             uptime_seconds: uptime,
             status: if overall_healthy {
                 "SERVING"
@@ -366,17 +688,3 @@ impl HealthService for ExecutorServer {
         }))
     }
 }
-
-// Service control handler
-fn service_handler(control_event: ServiceControl) -> ServiceControlHandlerResult {
-    match control_event {
-        ServiceControl::Stop | ServiceControl::Shutdown => {
-            log::info!("Service stop/shutdown requested");
-            // Initiate graceful shutdown
-            std::process::exit(0);
-        }
-        _ => ServiceControlHandlerResult::NoError,
-    }
-}
-
-type ServiceControlHandlerResult = Result<(), windows_service::Error>;
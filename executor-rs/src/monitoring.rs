@@ -20,7 +20,7 @@ use tracing::{debug, error, info, warn, span, Level};
 use metrics::{counter, gauge, histogram, increment_counter, decrement_gauge};
 use once_cell::sync::Lazy;
 use serde::{Serialize, Deserialize};
-use config_management_rs::ConfigChange;
+use config_management::ConfigChange;
 
 /// Global monitoring state
 #[derive(Debug, Clone)]
@@ -58,7 +58,7 @@ pub struct ResourceMetrics {
 }
 
 /// Health status
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct HealthStatus {
     pub overall_health: HealthLevel,
     pub memory_health: HealthLevel,
@@ -70,16 +70,17 @@ pub struct HealthStatus {
 }
 
 /// Health levels
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub enum HealthLevel {
     Healthy,
     Warning,
     Critical,
+    #[default]
     Unknown,
 }
 
 /// Alert rule
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct AlertRule {
     pub name: String,
     pub metric: String,
@@ -110,7 +111,7 @@ pub enum AlertSeverity {
 }
 
 /// Execution metric
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct ExecutionMetric {
     pub command: String,
     pub start_time: Instant,
@@ -138,9 +139,7 @@ static GLOBAL_MONITORING: Lazy<Arc<MonitoringState>> = Lazy::new(|| {
         execution_stats: Arc::new(RwLock::new(ExecutionStats::default())),
         resource_metrics: Arc::new(RwLock::new(ResourceMetrics::default())),
         health_status: Arc::new(RwLock::new(HealthStatus::default())),
-        alert_rules: Arc::new(RwLock::new(vec![
-            create_default_alert_rules()
-        ])),
+        alert_rules: Arc::new(RwLock::new(create_default_alert_rules())),
     })
 });
 
@@ -364,12 +363,8 @@ async fn should_trigger_alert(
     let value = match rule.metric.as_str() {
         "memory_usage_mb" => resources.current_memory_usage_mb,
         "cpu_usage_percent" => resources.current_cpu_usage_percent,
-        "failure_rate" => {
-            if stats.total_executions > 0 {
-                stats.failed_executions as f64 / stats.total_executions as f64
-            } else {
-                0.0
-            }
+        "failure_rate" if stats.total_executions > 0 => {
+            stats.failed_executions as f64 / stats.total_executions as f64
         }
         _ => 0.0,
     };
@@ -589,11 +584,12 @@ pub async fn get_monitoring_stats() -> MonitoringStats {
 /// Get current health status
 pub async fn get_health_status() -> HealthStatus {
     let monitoring = get_monitoring();
-    monitoring.health_status.read().await.clone()
+    let health = monitoring.health_status.read().await.clone();
+    health
 }
 
 /// Monitoring statistics response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct MonitoringStats {
     pub execution_stats: ExecutionStats,
     pub resource_metrics: ResourceMetrics,
@@ -720,7 +716,7 @@ pub mod test_utils {
     }
 
     /// Simulate execution for testing
-    pub async fn simulate_execution(monitoring: &Arc<MonitoringState>, command: &str, success: bool) {
+    pub async fn simulate_execution(monitoring: &Arc<MonitoringState>, _command: &str, success: bool) {
         let mut stats = monitoring.execution_stats.write().await;
         stats.total_executions += 1;
 
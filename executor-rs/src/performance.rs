@@ -18,7 +18,7 @@ use tokio::sync::{Mutex, RwLock};
 use tracing::{debug, error, info, warn};
 use once_cell::sync::Lazy;
 use serde::{Serialize, Deserialize};
-use config_management_rs::ConfigChange;
+use config_management::ConfigChange;
 
 /// Performance optimization configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +33,12 @@ pub struct PerformanceConfig {
     pub resource_optimization_level: ResourceOptimizationLevel,
 }
 
+impl Default for PerformanceConfig {
+    fn default() -> Self {
+        examples::example_performance_config()
+    }
+}
+
 /// Resource optimization levels
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum ResourceOptimizationLevel {
@@ -42,7 +48,7 @@ pub enum ResourceOptimizationLevel {
 }
 
 /// Performance cache entry
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct CacheEntry {
     pub command: String,
     pub args: Vec<String>,
@@ -73,6 +79,7 @@ pub struct PerformanceOptimizer {
 #[derive(Debug)]
 pub struct QueryOptimizer {
     optimization_rules: Arc<RwLock<Vec<OptimizationRule>>>,
+    enabled: bool,
 }
 
 /// Batch processor
@@ -80,6 +87,7 @@ pub struct QueryOptimizer {
 pub struct BatchProcessor {
     batch_queue: Arc<Mutex<Vec<ExecutionRequest>>>,
     processing: Arc<Mutex<bool>>,
+    max_batch_size: usize,
 }
 
 /// Resource optimizer
@@ -88,13 +96,27 @@ pub struct ResourceOptimizer {
     optimization_strategies: Arc<RwLock<Vec<ResourceOptimizationStrategy>>>,
 }
 
+/// Callback receiving the result of a batched execution
+pub type ExecutionCallback = Arc<dyn Fn(Result<(String, String, i32), String>) + Send + Sync>;
+
 /// Execution request for batch processing
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone)]
 pub struct ExecutionRequest {
     pub command: String,
     pub args: Vec<String>,
     pub env_vars: HashMap<String, String>,
-    pub callback: Option<Box<dyn Fn(Result<(String, String, i32), String>) + Send + Sync>>,
+    pub callback: Option<ExecutionCallback>,
+}
+
+impl std::fmt::Debug for ExecutionRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExecutionRequest")
+            .field("command", &self.command)
+            .field("args", &self.args)
+            .field("env_vars", &self.env_vars)
+            .field("callback", &self.callback.is_some())
+            .finish()
+    }
 }
 
 /// Optimization rule
@@ -157,12 +179,12 @@ static GLOBAL_PERFORMANCE_OPTIMIZER: Lazy<Arc<PerformanceOptimizer>> = Lazy::new
 pub fn init_performance_optimizer(config: PerformanceConfig) -> Arc<PerformanceOptimizer> {
     info!("Initializing performance optimization system");
 
-    let optimizer = PerformanceOptimizer::new(config);
+    let optimizer = Arc::new(PerformanceOptimizer::new(config));
 
     // Set up performance monitoring
     setup_performance_monitoring(optimizer.clone());
 
-    Arc::new(optimizer)
+    optimizer
 }
 
 /// Get global performance optimizer
@@ -170,18 +192,20 @@ pub fn get_performance_optimizer() -> Arc<PerformanceOptimizer> {
     GLOBAL_PERFORMANCE_OPTIMIZER.clone()
 }
 
-/// Create new performance optimizer
-fn new(config: PerformanceConfig) -> PerformanceOptimizer {
-    let cache = PerformanceCache::new(config.clone());
-    let query_optimizer = QueryOptimizer::new();
-    let batch_processor = BatchProcessor::new();
-    let resource_optimizer = ResourceOptimizer::new();
+impl PerformanceOptimizer {
+    /// Create new performance optimizer
+    pub fn new(config: PerformanceConfig) -> Self {
+        let query_optimizer = QueryOptimizer::new(config.enable_query_optimization);
+        let batch_processor = BatchProcessor::new(config.max_batch_size);
+        let resource_optimizer = ResourceOptimizer::new();
+        let cache = PerformanceCache::new(config);
 
-    PerformanceOptimizer {
-        cache,
-        query_optimizer,
-        batch_processor,
-        resource_optimizer,
+        Self {
+            cache,
+            query_optimizer,
+            batch_processor,
+            resource_optimizer,
+        }
     }
 }
 
@@ -247,7 +271,7 @@ pub async fn optimize_command_execution(
     }
 
     // Optimize query
-    let optimized_command = optimizer.optimize_query(command).await;
+    let optimized_command = optimizer.optimize_command(command, args).await;
 
     // Apply resource optimization
     let resource_limits = optimizer.get_resource_limits(&optimized_command).await;
@@ -273,7 +297,7 @@ pub enum OptimizedExecution {
 }
 
 /// Resource limits
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ResourceLimits {
     pub max_memory_mb: Option<u64>,
     pub max_cpu_percent: Option<u32>,
@@ -349,6 +373,7 @@ impl PerformanceCache {
 
         // Clean up if cache is too large
         if cache.len() > self.config.max_cache_size {
+            drop(cache);
             self.cleanup_cache().await;
         }
     }
@@ -365,7 +390,6 @@ impl PerformanceCache {
     /// Clean up expired cache entries
     async fn cleanup_cache(&self) {
         let mut cache = self.cache.write().await;
-        let now = Instant::now();
 
         cache.retain(|_, entry| {
             entry.timestamp.elapsed().as_secs() < self.config.cache_ttl_seconds
@@ -377,11 +401,10 @@ impl PerformanceCache {
 
 /// Query optimizer implementation
 impl QueryOptimizer {
-    fn new() -> Self {
+    fn new(enabled: bool) -> Self {
         Self {
-            optimization_rules: Arc::new(RwLock::new(vec![
-                Self::create_default_optimization_rules()
-            ])),
+            optimization_rules: Arc::new(RwLock::new(Self::create_default_optimization_rules())),
+            enabled,
         }
     }
 
@@ -402,7 +425,7 @@ impl QueryOptimizer {
 
     /// Optimize query
     async fn optimize_query(&self, command: &str) -> String {
-        if !self.config.enable_query_optimization {
+        if !self.enabled {
             return command.to_string();
         }
 
@@ -429,10 +452,11 @@ impl QueryOptimizer {
 
 /// Batch processor implementation
 impl BatchProcessor {
-    fn new() -> Self {
+    fn new(max_batch_size: usize) -> Self {
         Self {
             batch_queue: Arc::new(Mutex::new(Vec::new())),
             processing: Arc::new(Mutex::new(false)),
+            max_batch_size,
         }
     }
 
@@ -440,8 +464,10 @@ impl BatchProcessor {
     async fn add_to_batch(&self, request: ExecutionRequest) {
         let mut queue = self.batch_queue.lock().await;
         queue.push(request);
+        let full = queue.len() >= self.max_batch_size;
+        drop(queue);
 
-        if queue.len() >= self.config.max_batch_size {
+        if full {
             self.process_batch().await;
         }
     }
@@ -456,19 +482,14 @@ impl BatchProcessor {
         *processing = true;
         drop(processing);
 
-        let mut queue = self.batch_queue.lock().await;
-        let batch = std::mem::take(&mut queue);
-
-        if batch.is_empty() {
-            return;
-        }
+        let batch = std::mem::take(&mut *self.batch_queue.lock().await);
 
         debug!("Processing batch of {} commands", batch.len());
 
         for request in batch {
             // Execute command and call callback
             let result = execute_command_with_optimization(&request).await;
-            if let Some(callback) = request.callback {
+            if let Some(callback) = &request.callback {
                 callback(result);
             }
         }
@@ -481,9 +502,7 @@ impl BatchProcessor {
 impl ResourceOptimizer {
     fn new() -> Self {
         Self {
-            optimization_strategies: Arc::new(RwLock::new(vec![
-                Self::create_default_strategies()
-            ])),
+            optimization_strategies: Arc::new(RwLock::new(Self::create_default_strategies())),
         }
     }
 
@@ -585,7 +604,7 @@ impl ResourceOptimizer {
 /// Performance optimizer implementation
 impl PerformanceOptimizer {
     /// Optimize command
-    async fn optimize_command(&self, command: &str, args: &[String]) -> String {
+    async fn optimize_command(&self, command: &str, _args: &[String]) -> String {
         self.query_optimizer.optimize_query(command).await
     }
 
@@ -629,7 +648,7 @@ impl PerformanceOptimizer {
 }
 
 /// Execute command with performance optimization
-async fn execute_command_with_optimization(request: &ExecutionRequest) -> Result<(String, String, i32), String> {
+async fn execute_command_with_optimization(_request: &ExecutionRequest) -> Result<(String, String, i32), String> {
     // This would integrate with the actual execution logic
     // For now, we'll simulate execution
     Ok(("Optimized result".to_string(), "".to_string(), 0))
@@ -670,7 +689,7 @@ async fn is_command_too_complex(command: &str, args: &[String]) -> bool {
 /// Check if command will exceed resource limits
 async fn will_exceed_resource_limits(command: &str, args: &[String]) -> bool {
     // Simple heuristic for resource usage
-    let config = get_performance_optimizer().cache.config;
+    let config = get_performance_optimizer().cache.config.clone();
 
     // Estimate memory usage based on command
     let estimated_memory = estimate_memory_usage(command, args);
@@ -784,9 +803,9 @@ pub mod test_utils {
 
     /// Test command validation
     pub async fn test_validate_command() {
-        assert!(validate_command("echo", &["hello"]).await.is_ok());
+        assert!(validate_command("echo", &["hello".to_string()]).await.is_ok());
         assert!(validate_command("", &[]).await.is_err());
-        assert!(validate_command("a".repeat(257), &[]).await.is_err());
+        assert!(validate_command(&"a".repeat(257), &[]).await.is_err());
     }
 }
 
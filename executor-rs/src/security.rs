@@ -18,7 +18,7 @@ use tokio::sync::{Mutex, RwLock};
 use tracing::{debug, error, info, warn};
 use once_cell::sync::Lazy;
 use serde::{Serialize, Deserialize};
-use config_management_rs::ConfigChange;
+use config_management::ConfigChange;
 use regex::Regex;
 
 /// Security configuration
@@ -35,6 +35,12 @@ pub struct SecurityConfig {
     pub security_level: SecurityLevel,
 }
 
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        examples::example_security_config()
+    }
+}
+
 /// Security levels
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum SecurityLevel {
@@ -45,7 +51,7 @@ pub enum SecurityLevel {
 }
 
 /// Security event
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct SecurityEvent {
     pub timestamp: Instant,
     pub event_type: SecurityEventType,
@@ -118,7 +124,7 @@ pub struct SecurityManager {
 }
 
 /// Threat pattern
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
 pub struct ThreatPattern {
     pub name: String,
     pub pattern: Regex,
@@ -135,12 +141,12 @@ static GLOBAL_SECURITY_MANAGER: Lazy<Arc<SecurityManager>> = Lazy::new(|| {
 pub fn init_security_manager(config: SecurityConfig) -> Arc<SecurityManager> {
     info!("Initializing comprehensive security system");
 
-    let manager = SecurityManager::new(config);
+    let manager = Arc::new(SecurityManager::new(config));
 
     // Set up security monitoring
     setup_security_monitoring(manager.clone());
 
-    Arc::new(manager)
+    manager
 }
 
 /// Get global security manager
@@ -148,21 +154,19 @@ pub fn get_security_manager() -> Arc<SecurityManager> {
     GLOBAL_SECURITY_MANAGER.clone()
 }
 
-/// Create new security manager
-fn new(config: SecurityConfig) -> SecurityManager {
-    let audit_log = SecurityAuditLog::new(1000);
-    let security_policies = Arc::new(RwLock::new(vec![
-        create_default_security_policies()
-    ]));
-    let threat_patterns = Arc::new(RwLock::new(vec![
-        create_default_threat_patterns()
-    ]));
-
-    SecurityManager {
-        config,
-        audit_log,
-        security_policies,
-        threat_patterns,
+impl SecurityManager {
+    /// Create new security manager
+    pub fn new(config: SecurityConfig) -> Self {
+        let audit_log = SecurityAuditLog::new(1000);
+        let security_policies = Arc::new(RwLock::new(create_default_security_policies()));
+        let threat_patterns = Arc::new(RwLock::new(create_default_threat_patterns()));
+
+        Self {
+            config,
+            audit_log,
+            security_policies,
+            threat_patterns,
+        }
     }
 }
 
@@ -231,218 +235,223 @@ fn setup_security_monitoring(manager: Arc<SecurityManager>) {
 
 /// Validate command security
 pub async fn validate_command_security(command: &str, args: &[String]) -> Result<(), String> {
-    let manager = get_security_manager();
-
-    // Check if security validation is enabled
-    if !manager.config.enable_command_validation {
-        return Ok(());
-    }
+    get_security_manager().validate_command(command, args).await
+}
 
-    // Validate command length
-    if command.len() > manager.config.max_command_length {
-        return Err(format!(
-            "Command exceeds maximum length of {} characters",
-            manager.config.max_command_length
-        ));
-    }
+impl SecurityManager {
+    /// Validate a command against the configuration, policies and threat patterns
+    pub async fn validate_command(&self, command: &str, args: &[String]) -> Result<(), String> {
+        // Check if security validation is enabled
+        if !self.config.enable_command_validation {
+            return Ok(());
+        }
 
-    // Validate argument lengths
-    for arg in args {
-        if arg.len() > manager.config.max_argument_length {
+        // Validate command length
+        if command.len() > self.config.max_command_length {
             return Err(format!(
-                "Argument exceeds maximum length of {} characters",
-                manager.config.max_argument_length
+                "Command exceeds maximum length of {} characters",
+                self.config.max_command_length
             ));
         }
-    }
 
-    // Check allowed commands
-    if !manager.config.allowed_commands.is_empty() &&
-       !manager.config.allowed_commands.contains(command) {
-        return Err(format!("Command '{}' is not in allowed list", command));
-    }
+        // Validate argument lengths
+        for arg in args {
+            if arg.len() > self.config.max_argument_length {
+                return Err(format!(
+                    "Argument exceeds maximum length of {} characters",
+                    self.config.max_argument_length
+                ));
+            }
+        }
 
-    // Check blocked commands
-    if manager.config.blocked_commands.contains(command) {
-        return Err(format!("Command '{}' is blocked", command));
-    }
+        // Check allowed commands
+        if !self.config.allowed_commands.is_empty() &&
+           !self.config.allowed_commands.contains(command) {
+            return Err(format!("Command '{}' is not in allowed list", command));
+        }
 
-    // Check security policies
-    manager.check_security_policies(command, args).await?;
+        // Check blocked commands
+        if self.config.blocked_commands.contains(command) {
+            return Err(format!("Command '{}' is blocked", command));
+        }
 
-    // Check for threat patterns
-    manager.detect_threats(command, args).await?;
+        // Check security policies
+        self.check_security_policies(command, args).await?;
 
-    Ok(())
-}
+        // Check for threat patterns
+        self.detect_threats(command, args).await?;
 
-/// Check security policies
-async fn check_security_policies(&self, command: &str, args: &[String]) -> Result<(), String> {
-    let policies = self.security_policies.read().await;
-
-    for policy in policies.iter() {
-        for rule in policy.rules.iter() {
-            if self.matches_pattern(command, &rule.pattern) {
-                match rule.action {
-                    SecurityAction::Allow => {
-                        self.log_security_event(
-                            SecurityEventType::CommandExecuted,
-                            SecuritySeverity::Info,
-                            command,
-                            format!("Allowed by policy: {}", policy.name),
-                            None,
-                            None,
-                        ).await;
-                    }
-                    SecurityAction::Block => {
-                        self.log_security_event(
-                            SecurityEventType::CommandBlocked,
-                            rule.severity.clone(),
-                            command,
-                            format!("Blocked by policy: {}", policy.name),
-                            None,
-                            None,
-                        ).await;
-                        return Err(format!("Command blocked by security policy: {}", policy.name));
-                    }
-                    SecurityAction::Audit => {
-                        self.log_security_event(
-                            SecurityEventType::CommandExecuted,
-                            SecuritySeverity::Info,
-                            command,
-                            format!("Audited by policy: {}", policy.name),
-                            None,
-                            None,
-                        ).await;
-                    }
-                    SecurityAction::Quarantine => {
-                        self.log_security_event(
-                            SecurityEventType::SecurityViolation,
-                            SecuritySeverity::Critical,
-                            command,
-                            format!("Quarantined by policy: {}", policy.name),
-                            None,
-                            None,
-                        ).await;
-                        return Err(format!("Command quarantined by security policy: {}", policy.name));
+        Ok(())
+    }
+
+    /// Check security policies
+    async fn check_security_policies(&self, command: &str, _args: &[String]) -> Result<(), String> {
+        let policies = self.security_policies.read().await;
+
+        for policy in policies.iter() {
+            for rule in policy.rules.iter() {
+                if self.matches_pattern(command, &rule.pattern) {
+                    match rule.action {
+                        SecurityAction::Allow => {
+                            self.log_security_event(
+                                SecurityEventType::CommandExecuted,
+                                SecuritySeverity::Info,
+                                command,
+                                format!("Allowed by policy: {}", policy.name),
+                                None,
+                                None,
+                            ).await;
+                        }
+                        SecurityAction::Block => {
+                            self.log_security_event(
+                                SecurityEventType::CommandBlocked,
+                                rule.severity.clone(),
+                                command,
+                                format!("Blocked by policy: {}", policy.name),
+                                None,
+                                None,
+                            ).await;
+                            return Err(format!("Command blocked by security policy: {}", policy.name));
+                        }
+                        SecurityAction::Audit => {
+                            self.log_security_event(
+                                SecurityEventType::CommandExecuted,
+                                SecuritySeverity::Info,
+                                command,
+                                format!("Audited by policy: {}", policy.name),
+                                None,
+                                None,
+                            ).await;
+                        }
+                        SecurityAction::Quarantine => {
+                            self.log_security_event(
+                                SecurityEventType::SecurityViolation,
+                                SecuritySeverity::Critical,
+                                command,
+                                format!("Quarantined by policy: {}", policy.name),
+                                None,
+                                None,
+                            ).await;
+                            return Err(format!("Command quarantined by security policy: {}", policy.name));
+                        }
                     }
                 }
             }
         }
-    }
 
-    Ok(())
-}
-
-/// Detect threats in command
-async fn detect_threats(&self, command: &str, args: &[String]) -> Result<(), String> {
-    let patterns = self.threat_patterns.read().await;
-
-    // Check command for threat patterns
-    for pattern in patterns.iter() {
-        if pattern.pattern.is_match(command) {
-            self.log_security_event(
-                SecurityEventType::ThreatDetected,
-                pattern.severity.clone(),
-                command,
-                pattern.description.clone(),
-                None,
-                None,
-            ).await;
-
-            if pattern.severity == SecuritySeverity::Critical {
-                return Err(format!("Threat detected: {}", pattern.name));
-            }
-        }
+        Ok(())
     }
 
-    // Check arguments for threat patterns
-    for arg in args {
+    /// Detect threats in command
+    async fn detect_threats(&self, command: &str, args: &[String]) -> Result<(), String> {
+        let patterns = self.threat_patterns.read().await;
+
+        // Check command for threat patterns
         for pattern in patterns.iter() {
-            if pattern.pattern.is_match(arg) {
+            if pattern.pattern.is_match(command) {
                 self.log_security_event(
                     SecurityEventType::ThreatDetected,
                     pattern.severity.clone(),
                     command,
-                    format!("Threat in argument: {} - {}", pattern.name, arg),
+                    pattern.description.clone(),
                     None,
                     None,
                 ).await;
 
                 if pattern.severity == SecuritySeverity::Critical {
-                    return Err(format!("Threat detected in argument: {}", pattern.name));
+                    return Err(format!("Threat detected: {}", pattern.name));
                 }
             }
         }
-    }
 
-    Ok(())
-}
+        // Check arguments for threat patterns
+        for arg in args {
+            for pattern in patterns.iter() {
+                if pattern.pattern.is_match(arg) {
+                    self.log_security_event(
+                        SecurityEventType::ThreatDetected,
+                        pattern.severity.clone(),
+                        command,
+                        format!("Threat in argument: {} - {}", pattern.name, arg),
+                        None,
+                        None,
+                    ).await;
+
+                    if pattern.severity == SecuritySeverity::Critical {
+                        return Err(format!("Threat detected in argument: {}", pattern.name));
+                    }
+                }
+            }
+        }
 
-/// Check if command matches pattern
-fn matches_pattern(&self, command: &str, pattern: &str) -> bool {
-    if pattern == "*" || pattern == ".*" {
-        return true;
+        Ok(())
     }
 
-    if command == pattern {
-        return true;
-    }
+    /// Check if command matches pattern
+    fn matches_pattern(&self, command: &str, pattern: &str) -> bool {
+        if pattern == "*" || pattern == ".*" {
+            return true;
+        }
 
-    if command.starts_with(pattern) {
-        return true;
+        if command == pattern {
+            return true;
+        }
+
+        if command.starts_with(pattern) {
+            return true;
+        }
+
+        false
     }
 
-    false
-}
+    /// Log security event
+    async fn log_security_event(
+        &self,
+        event_type: SecurityEventType,
+        severity: SecuritySeverity,
+        command: &str,
+        details: String,
+        user: Option<String>,
+        ip_address: Option<String>,
+    ) {
+        let event = SecurityEvent {
+            timestamp: Instant::now(),
+            event_type,
+            severity,
+            command: command.to_string(),
+            details,
+            user,
+            ip_address,
+        };
 
-/// Log security event
-async fn log_security_event(
-    &self,
-    event_type: SecurityEventType,
-    severity: SecuritySeverity,
-    command: &str,
-    details: String,
-    user: Option<String>,
-    ip_address: Option<String>,
-) {
-    let event = SecurityEvent {
-        timestamp: Instant::now(),
-        event_type,
-        severity,
-        command: command.to_string(),
-        details,
-        user,
-        ip_address,
-    };
-
-    self.audit_log.log_event(event).await;
-
-    // Log to tracing based on severity
-    match severity {
-        SecuritySeverity::Info => info!("SECURITY: {}", event.details),
-        SecuritySeverity::Warning => warn!("SECURITY: {}", event.details),
-        SecuritySeverity::Critical => error!("SECURITY: {}", event.details),
-        SecuritySeverity::Emergency => {
-            error!("SECURITY EMERGENCY: {}", event.details);
-            // In production, you might want to trigger additional alerts
+        // Log to tracing based on severity
+        match event.severity {
+            SecuritySeverity::Info => info!("SECURITY: {}", event.details),
+            SecuritySeverity::Warning => warn!("SECURITY: {}", event.details),
+            SecuritySeverity::Critical => error!("SECURITY: {}", event.details),
+            SecuritySeverity::Emergency => {
+                error!("SECURITY EMERGENCY: {}", event.details);
+                // In production, you might want to trigger additional alerts
+            }
         }
+
+        self.audit_log.log_event(event).await;
     }
-}
 
-/// Check security health
-async fn check_security_health(&self) {
-    // Check for recent security violations
-    let events = self.audit_log.get_recent_events(5).await;
+    /// Check security health
+    async fn check_security_health(&self) {
+        // Check for recent security violations
+        let events = self.audit_log.get_recent_events(5).await;
 
-    let critical_events = events.iter()
-        .filter(|e| e.severity == SecuritySeverity::Critical || e.severity == SecuritySeverity::Emergency)
-        .count();
+        let critical_events = events.iter()
+            .filter(|e| e.severity == SecuritySeverity::Critical || e.severity == SecuritySeverity::Emergency)
+            .count();
 
-    if critical_events > 0 {
-        warn!("Security health check: {} critical events detected", critical_events);
-    } else {
-        debug!("Security health check: All systems normal");
+        if critical_events > 0 {
+            warn!("Security health check: {} critical events detected", critical_events);
+        } else {
+            debug!("Security health check: All systems normal");
+        }
     }
 }
 
@@ -587,7 +596,7 @@ pub mod utils {
     /// Format security event for display
     pub fn format_security_event(event: &SecurityEvent) -> String {
         format!(
-            "[{:?}] {}: {} - {}",
+            "[{:?}] {:?}: {} - {}",
             event.severity,
             event.event_type,
             event.command,
@@ -632,8 +641,8 @@ pub mod test_utils {
     pub async fn test_validate_command_security() {
         let manager = create_test_security_manager();
 
-        assert!(manager.validate_command_security("echo", &["hello"]).await.is_ok());
-        assert!(manager.validate_command_security("rm", &["file.txt"]).await.is_err());
+        assert!(manager.validate_command("echo", &["hello".to_string()]).await.is_ok());
+        assert!(manager.validate_command("rm", &["file.txt".to_string()]).await.is_err());
     }
 }
 
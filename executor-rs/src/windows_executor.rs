@@ -897,11 +897,26 @@ pub async fn execute_with_windows_control(
     args: &[String],
     env_vars: &HashMap<String, String>,
     language: &str,
+) -> Result<(String, String, i32), String> {
+    execute_with_windows_control_in(command, args, env_vars, language, None).await
+}
+
+/// Execute command using Windows native control, with `workspace` (if any)
+/// as working directory instead of the shared sandbox directory
+pub async fn execute_with_windows_control_in(
+    command: &str,
+    args: &[String],
+    env_vars: &HashMap<String, String>,
+    language: &str,
+    workspace: Option<&Path>,
 ) -> Result<(String, String, i32), String> {
     // Enhanced security validation
     validate_command_security(command, args)?;
 
-    let job_manager = JobObjectManager::new()?;
+    let mut job_manager = JobObjectManager::new()?;
+    if let Some(workspace) = workspace {
+        job_manager.sandbox_path = workspace.to_path_buf();
+    }
     job_manager
         .execute_code(command, args, env_vars, language)
         .await
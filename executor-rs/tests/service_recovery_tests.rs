@@ -1,3 +1,6 @@
+// Job Object and watchdog recovery only exist on Windows
+#![cfg(target_os = "windows")]
+
 use anyhow::Result;
 use executor::{Executor, JobObjectManager, ResourceMonitor};
 use std::collections::HashMap;
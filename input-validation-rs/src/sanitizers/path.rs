@@ -17,7 +17,7 @@ lazy_static! {
 
     /// Regex for Unicode directory traversal attempts
     static ref UNICODE_TRAVERSAL_REGEX: Regex = Regex::new(
-        r"(?:%2e|%c0%ae|%2f|%5c|%u002e|%252e)(?:%2e|%c0%ae|%2f|%5c|%u002e|%252e)(?:/|%2f|%5c|\)"
+        r"(?i)(?:%2e|%c0%ae|%2f|%5c|%u002e|%252e)(?:%2e|%c0%ae|%2f|%5c|%u002e|%252e)(?:/|%2f|%5c|\\)"
     ).unwrap();
 
     /// Regex for dangerous path components
@@ -182,9 +182,8 @@ pub fn sanitize_filename(filename: &str) -> SanitizeResult<String> {
     let mut was_modified = false;
 
     for c in invalid_chars.chars() {
-        let original_len = result.len();
-        result = result.replace(c, "_");
-        if result.len() != original_len {
+        if result.contains(c) {
+            result = result.replace(c, "_");
             was_modified = true;
         }
     }
@@ -330,8 +329,8 @@ mod tests {
         let encoded_bad = "foo/%2e%2e/bar";
         let result = remove_path_traversal(encoded_bad);
 
-        assert!(!result.was_modified); // This is caught by the normalize function
-        assert_eq!(result.sanitized, encoded_bad);
+        assert!(result.was_modified);
+        assert_eq!(result.sanitized, "foo//bar");
     }
 
     #[test]
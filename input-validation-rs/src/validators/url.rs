@@ -5,6 +5,7 @@
 
 use crate::errors::{ValidationError, ValidationResult};
 use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use url::{Host, Url};
 
 /// Validate that a string is a valid URL
@@ -199,6 +200,85 @@ pub fn no_ip_hosts(s: &str) -> ValidationResult<()> {
     }
 }
 
+/// Check whether an IP address is publicly routable
+///
+/// Loopback, private, link-local, shared (CGNAT), documentation, benchmarking,
+/// multicast, broadcast and unspecified ranges are not, including their
+/// IPv4-mapped IPv6 forms.
+pub fn is_public_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_public_ipv4(v4),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public_ipv4(&v4),
+            None => is_public_ipv6(v6),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: &Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+        || (a == 192 && b == 0 && c == 0)
+        || (a == 198 && (18..20).contains(&b))
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: &Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || (first & 0xfe00) == 0xfc00
+        || (first & 0xffc0) == 0xfe80
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8))
+}
+
+/// Validate that a URL's host is not a private or reserved IP address or `localhost`
+///
+/// Domain names other than `localhost` pass; callers that connect to the URL
+/// should check the resolved addresses with [`is_public_ip`] as well.
+pub fn no_private_ip_hosts(s: &str) -> ValidationResult<()> {
+    match Url::parse(s) {
+        Ok(url) => {
+            let ip = match url.host() {
+                Some(Host::Domain(domain)) => {
+                    let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+                    if domain == "localhost" || domain.ends_with(".localhost") {
+                        return Err(ValidationError::SecurityThreat(
+                            "URL points to localhost".to_string(),
+                        ));
+                    }
+                    return Ok(());
+                }
+                Some(Host::Ipv4(ip)) => IpAddr::V4(ip),
+                Some(Host::Ipv6(ip)) => IpAddr::V6(ip),
+                None => {
+                    return Err(ValidationError::InvalidUrl(
+                        "URL has no host component".to_string(),
+                    ))
+                }
+            };
+            if is_public_ip(&ip) {
+                Ok(())
+            } else {
+                Err(ValidationError::SecurityThreat(format!(
+                    "URL points to private or reserved address {}",
+                    ip
+                )))
+            }
+        }
+        Err(e) => Err(ValidationError::InvalidUrl(format!("Invalid URL: {}", e))),
+    }
+}
+
 /// Validate that a URL has a file extension in the allowed list
 pub fn allowed_file_extension(s: &str, allowed: &[&str]) -> ValidationResult<()> {
     match Url::parse(s) {
@@ -313,6 +393,20 @@ mod tests {
         assert!(no_ip_hosts("https://[::1]").is_err());
     }
 
+    #[test]
+    fn test_no_private_ip_hosts() {
+        assert!(no_private_ip_hosts("https://example.com").is_ok());
+        assert!(no_private_ip_hosts("https://93.184.216.34").is_ok());
+        assert!(no_private_ip_hosts("http://localhost:8080").is_err());
+        assert!(no_private_ip_hosts("http://127.0.0.1").is_err());
+        assert!(no_private_ip_hosts("http://10.0.0.5").is_err());
+        assert!(no_private_ip_hosts("http://169.254.169.254/latest").is_err());
+        assert!(no_private_ip_hosts("http://100.64.1.1").is_err());
+        assert!(no_private_ip_hosts("http://[::1]").is_err());
+        assert!(no_private_ip_hosts("http://[fd00::1]").is_err());
+        assert!(no_private_ip_hosts("http://[::ffff:192.168.1.1]").is_err());
+    }
+
     #[test]
     fn test_no_path_traversal() {
         assert!(no_path_traversal("https://example.com/path/to/file").is_ok());
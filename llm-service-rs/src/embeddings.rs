@@ -0,0 +1,521 @@
+// llm-service-rs/src/embeddings.rs
+//
+// Text embedding backends for the EmbedText RPC
+//
+// This module provides:
+// - The `EmbeddingProvider` trait implemented by every embedding backend
+// - An OpenAI-compatible `/v1/embeddings` backend
+// - An Ollama `/api/embed` backend
+// - A deterministic hashed n-gram embedder for offline development and tests
+//
+// Providers perform exactly one HTTP attempt per batch. Batching, retries and
+// dimension checks are handled by `LLMClient::embed`.
+//
+// Configuration (.env file):
+// - LLM_EMBEDDING_PROVIDER: "openai", "ollama" or "hash" (default: "openai")
+// - LLM_EMBEDDING_API_URL: Embeddings endpoint (default depends on provider)
+// - LLM_EMBEDDING_MODEL: Embedding model (default depends on provider)
+// - LLM_EMBEDDING_BATCH_SIZE: Maximum texts per provider call (default: 64)
+// - VECTOR_SIZE: Dimension expected by Mind-KB's VectorStore (default: 1536)
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+
+use crate::llm_client::LLMError;
+use crate::providers::{classify_http_status, classify_transport_error, OpenAIProvider};
+
+// Must match DEFAULT_VECTOR_SIZE in mind-kb-rs/src/vector_store.rs
+pub const DEFAULT_EMBEDDING_DIMENSION: usize = 1536;
+
+const DEFAULT_BATCH_SIZE: usize = 64;
+
+/// Embeddings for one provider call, in input order
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddingBatch {
+    pub vectors: Vec<Vec<f32>>,
+    pub prompt_tokens: u32,
+}
+
+/// Common interface implemented by every embedding backend
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync + fmt::Debug {
+    /// Backend name reported in response metadata
+    fn name(&self) -> &str;
+
+    /// Embedding model reported in response metadata
+    fn model(&self) -> &str;
+
+    /// Label used to look up the API key in the secrets service
+    fn secret_label(&self) -> &str;
+
+    /// Whether calls to this provider need an API key
+    fn requires_api_key(&self) -> bool {
+        true
+    }
+
+    /// Maximum number of texts per call
+    fn max_batch_size(&self) -> usize {
+        DEFAULT_BATCH_SIZE
+    }
+
+    /// Embed a batch of texts in a single attempt
+    async fn embed_batch(&self, api_key: &str, texts: &[String])
+        -> Result<EmbeddingBatch, LLMError>;
+}
+
+/// Create the embedding provider selected by LLM_EMBEDDING_PROVIDER
+pub fn provider_from_env(client: Client, dimension: usize) -> Box<dyn EmbeddingProvider> {
+    provider_for(
+        &env::var("LLM_EMBEDDING_PROVIDER").unwrap_or_default(),
+        env::var("LLM_EMBEDDING_MODEL").ok(),
+        env::var("LLM_EMBEDDING_API_URL").ok(),
+        client,
+        dimension,
+    )
+}
+
+/// Create an embedding provider by name; unknown names use the OpenAI-compatible backend
+///
+/// `model` and `api_url` fall back to the provider's defaults.
+pub fn provider_for(
+    name: &str,
+    model: Option<String>,
+    api_url: Option<String>,
+    client: Client,
+    dimension: usize,
+) -> Box<dyn EmbeddingProvider> {
+    let batch_size = env::var("LLM_EMBEDDING_BATCH_SIZE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&v: &usize| v > 0)
+        .unwrap_or(DEFAULT_BATCH_SIZE);
+
+    match name.to_ascii_lowercase().as_str() {
+        "hash" | "local-hash" | "offline" => Box::new(HashingEmbedder::new(dimension)),
+        "ollama" => Box::new(OllamaEmbedder {
+            client,
+            api_url: api_url.unwrap_or_else(|| "http://localhost:11434/api/embed".to_string()),
+            model: model.unwrap_or_else(|| "nomic-embed-text".to_string()),
+            batch_size,
+        }),
+        _ => {
+            let api_url =
+                api_url.unwrap_or_else(|| "https://api.openai.com/v1/embeddings".to_string());
+            let model = model.unwrap_or_else(|| "text-embedding-3-small".to_string());
+            Box::new(OpenAIEmbedder::new(
+                client, api_url, model, dimension, batch_size,
+            ))
+        }
+    }
+}
+
+/// Whether `name` selects an embedding backend (used to validate routing targets)
+pub fn is_known_provider(name: &str) -> bool {
+    matches!(
+        name.to_ascii_lowercase().as_str(),
+        "openai" | "ollama" | "hash" | "local-hash" | "offline"
+    )
+}
+
+/// Read the dimension expected by the vector store
+pub fn expected_dimension_from_env() -> usize {
+    env::var("VECTOR_SIZE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_EMBEDDING_DIMENSION)
+}
+
+/// Reject vectors whose length differs from what the vector store expects
+pub fn check_dimensions(
+    model: &str,
+    expected: usize,
+    vectors: &[Vec<f32>],
+) -> Result<(), LLMError> {
+    match vectors.iter().find(|v| v.len() != expected) {
+        Some(vector) => Err(LLMError::DimensionMismatch(format!(
+            "model {} returned {} dimensions but VECTOR_SIZE is {}",
+            model,
+            vector.len(),
+            expected
+        ))),
+        None => Ok(()),
+    }
+}
+
+// ----------------------------------------------------------------------------
+// OpenAI-compatible embeddings
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Serialize)]
+struct OpenAIEmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimensions: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIEmbeddingResponse {
+    data: Vec<OpenAIEmbeddingData>,
+    #[serde(default)]
+    usage: Option<OpenAIEmbeddingUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIEmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIEmbeddingUsage {
+    #[serde(default)]
+    prompt_tokens: u32,
+}
+
+#[derive(Debug)]
+pub struct OpenAIEmbedder {
+    client: Client,
+    api_url: String,
+    model: String,
+    secret_label: String,
+    // Sent as `dimensions` for models that support shortened embeddings
+    requested_dimension: Option<usize>,
+    batch_size: usize,
+}
+
+impl OpenAIEmbedder {
+    pub fn new(
+        client: Client,
+        api_url: String,
+        model: String,
+        dimension: usize,
+        batch_size: usize,
+    ) -> Self {
+        let secret_label = OpenAIProvider::determine_secret_label(&api_url, &model);
+        // Only the text-embedding-3 family accepts a `dimensions` parameter
+        let requested_dimension = if model.contains("text-embedding-3") {
+            Some(dimension)
+        } else {
+            None
+        };
+
+        Self {
+            client,
+            api_url,
+            model,
+            secret_label,
+            requested_dimension,
+            batch_size,
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAIEmbedder {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn secret_label(&self) -> &str {
+        &self.secret_label
+    }
+
+    fn max_batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    async fn embed_batch(
+        &self,
+        api_key: &str,
+        texts: &[String],
+    ) -> Result<EmbeddingBatch, LLMError> {
+        let body = OpenAIEmbeddingRequest {
+            model: &self.model,
+            input: texts,
+            dimensions: self.requested_dimension,
+        };
+
+        let response = self
+            .client
+            .post(&self.api_url)
+            .header("Authorization", format!("Bearer {}", api_key))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await
+            .map_err(classify_transport_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(classify_http_status(status.as_u16(), &text));
+        }
+
+        let mut data: OpenAIEmbeddingResponse = response
+            .json()
+            .await
+            .map_err(|err| LLMError::ParseError(format!("Failed to parse response: {}", err)))?;
+
+        if data.data.len() != texts.len() {
+            return Err(LLMError::ParseError(format!(
+                "Expected {} embeddings, received {}",
+                texts.len(),
+                data.data.len()
+            )));
+        }
+
+        // The API does not guarantee ordering; `index` refers to the input position
+        data.data.sort_by_key(|d| d.index);
+
+        Ok(EmbeddingBatch {
+            vectors: data.data.into_iter().map(|d| d.embedding).collect(),
+            prompt_tokens: data.usage.map(|u| u.prompt_tokens).unwrap_or(0),
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Ollama embeddings
+// ----------------------------------------------------------------------------
+
+#[derive(Debug, Serialize)]
+struct OllamaEmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaEmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+    #[serde(default)]
+    prompt_eval_count: u32,
+}
+
+#[derive(Debug)]
+pub struct OllamaEmbedder {
+    client: Client,
+    api_url: String,
+    model: String,
+    batch_size: usize,
+}
+
+#[async_trait]
+impl EmbeddingProvider for OllamaEmbedder {
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn secret_label(&self) -> &str {
+        "local"
+    }
+
+    fn requires_api_key(&self) -> bool {
+        false
+    }
+
+    fn max_batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    async fn embed_batch(
+        &self,
+        _api_key: &str,
+        texts: &[String],
+    ) -> Result<EmbeddingBatch, LLMError> {
+        let body = OllamaEmbedRequest {
+            model: &self.model,
+            input: texts,
+        };
+
+        let response = self
+            .client
+            .post(&self.api_url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(classify_transport_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            if status.as_u16() == 404 && text.contains("model") {
+                return Err(LLMError::ModelNotAvailable(text));
+            }
+            return Err(classify_http_status(status.as_u16(), &text));
+        }
+
+        let data: OllamaEmbedResponse = response
+            .json()
+            .await
+            .map_err(|err| LLMError::ParseError(format!("Failed to parse response: {}", err)))?;
+
+        Ok(EmbeddingBatch {
+            vectors: data.embeddings,
+            prompt_tokens: data.prompt_eval_count,
+        })
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Offline hashed n-gram embedder
+// ----------------------------------------------------------------------------
+
+/// Deterministic feature-hashing embedder
+///
+/// Hashes word unigrams, word bigrams and character trigrams into a fixed number
+/// of buckets with a signed hash, then L2-normalizes. Texts sharing vocabulary get
+/// a high cosine similarity, which is enough for offline development and tests.
+/// Output is stable across processes and platforms (FNV-1a, no random seeds).
+#[derive(Debug, Clone)]
+pub struct HashingEmbedder {
+    dimension: usize,
+    model: String,
+}
+
+impl HashingEmbedder {
+    pub fn new(dimension: usize) -> Self {
+        Self {
+            dimension: dimension.max(1),
+            model: format!("hashed-ngram-{}", dimension),
+        }
+    }
+
+    fn fnv1a(bytes: &[u8]) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in bytes {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        hash
+    }
+
+    fn add_feature(&self, vector: &mut [f32], feature: &str, weight: f32) {
+        let hash = Self::fnv1a(feature.as_bytes());
+        let index = (hash % self.dimension as u64) as usize;
+        // Use a high bit for the sign so that collisions tend to cancel out
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[index] += sign * weight;
+    }
+
+    /// Embed a single text
+    pub fn embed(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimension];
+
+        let normalized: String = text
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+            .collect();
+        let words: Vec<&str> = normalized.split_whitespace().collect();
+
+        for word in &words {
+            self.add_feature(&mut vector, &format!("w:{}", word), 1.0);
+
+            // Character trigrams with boundary markers capture morphology
+            let padded: Vec<char> = format!("^{}$", word).chars().collect();
+            for gram in padded.windows(3) {
+                let gram: String = gram.iter().collect();
+                self.add_feature(&mut vector, &format!("c:{}", gram), 0.5);
+            }
+        }
+
+        for pair in words.windows(2) {
+            self.add_feature(&mut vector, &format!("b:{} {}", pair[0], pair[1]), 0.75);
+        }
+
+        let norm: f32 = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            for value in &mut vector {
+                *value /= norm;
+            }
+        }
+
+        vector
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for HashingEmbedder {
+    fn name(&self) -> &str {
+        "hash"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn secret_label(&self) -> &str {
+        "local"
+    }
+
+    fn requires_api_key(&self) -> bool {
+        false
+    }
+
+    fn max_batch_size(&self) -> usize {
+        usize::MAX
+    }
+
+    async fn embed_batch(
+        &self,
+        _api_key: &str,
+        texts: &[String],
+    ) -> Result<EmbeddingBatch, LLMError> {
+        Ok(EmbeddingBatch {
+            vectors: texts.iter().map(|text| self.embed(text)).collect(),
+            prompt_tokens: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    #[test]
+    fn test_hashing_embedder_is_deterministic_and_normalized() {
+        let embedder = HashingEmbedder::new(256);
+        let a = embedder.embed("Create the project roadmap");
+        let b = embedder.embed("Create the project roadmap");
+
+        assert_eq!(a.len(), 256);
+        assert_eq!(a, b);
+        assert!((cosine(&a, &a) - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_hashing_embedder_similarity() {
+        let embedder = HashingEmbedder::new(DEFAULT_EMBEDDING_DIMENSION);
+        let base = embedder.embed("user completed the project roadmap task");
+        let close = embedder.embed("the user completed a roadmap for the project");
+        let far = embedder.embed("weather forecast predicts heavy rain tomorrow");
+
+        assert!(cosine(&base, &close) > cosine(&base, &far));
+    }
+
+    #[test]
+    fn test_dimension_mismatch_is_reported() {
+        let vectors = vec![vec![0.0; 768]];
+        assert!(matches!(
+            check_dimensions("nomic-embed-text", 1536, &vectors),
+            Err(LLMError::DimensionMismatch(_))
+        ));
+        assert!(check_dimensions("nomic-embed-text", 768, &vectors).is_ok());
+    }
+}
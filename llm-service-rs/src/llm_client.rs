@@ -1,27 +1,31 @@
 // llm-service-rs/src/llm_client.rs
 //
-// HTTP Client for interacting with LLM providers (OpenAI-compatible API)
+// Client for interacting with LLM providers
 //
 // This module provides:
-// - Real HTTP calls to LLM API providers via reqwest
+// - Provider selection (OpenAI, Anthropic, local) via the `providers` module
+// - Model routing rules and cross-provider fallback chains via the `routing` module
 // - Exponential backoff retry mechanism for resilient operation
 // - Proper error handling with classification of retryable vs. non-retryable errors
 // - Configuration via environment variables
 //
 // Configuration (.env file):
-// - LLM_API_KEY: API key for the LLM provider
-// - LLM_API_URL: API endpoint URL (defaults to OpenAI compatible endpoint)
-// - LLM_MODEL: Model to use (e.g. "gpt-3.5-turbo", "anthropic/claude-3.5-sonnet")
+// - LLM_PROVIDER: Default backend ("openai", "anthropic", "local", "mock"); inferred from LLM_API_URL if unset
+// - LLM_API_KEY: API key for the LLM provider (LLM_<PROVIDER>_API_KEY overrides per provider)
+// - LLM_API_URL: API endpoint URL for the openai backend (defaults to OpenAI compatible endpoint)
+// - LLM_MODEL: Model for the openai backend (e.g. "gpt-3.5-turbo", "anthropic/claude-3.5-sonnet")
 // - LLM_MAX_RETRIES: Maximum number of retry attempts (default: 3)
 // - LLM_INITIAL_RETRY_DELAY_MS: Initial delay between retries in ms (default: 1000)
 // - LLM_MAX_RETRY_DELAY_MS: Maximum delay between retries in ms (default: 30000)
 
 use backoff::{backoff::Backoff, ExponentialBackoff, ExponentialBackoffBuilder};
 use rand::Rng;
-use reqwest::{Client, StatusCode};
-use serde::{Deserialize, Serialize};
+use reqwest::Client;
+use std::collections::HashMap;
 use std::env;
+use std::future::Future;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::Mutex;
@@ -29,11 +33,17 @@ use tokio::sync::Mutex;
 // Import our secrets client
 use crate::secrets_client::{SecretsClient, SecretsError};
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ChatMessage {
-    role: String,
-    content: String,
-}
+use crate::embeddings::{self, EmbeddingProvider};
+use crate::providers::{
+    AnthropicProvider, ChatMessage, Completion, CompletionRequest, DeltaSink, LlmProvider,
+    LocalProvider, MockProvider, OpenAIProvider, ProviderKind, ToolChoice, ToolSpec,
+};
+use crate::routing::{self, BudgetHint, ModelTarget, RouteReport, RouteRequest, RoutingPolicy};
+use crate::structured_output::ResponseSchema;
+use crate::tokenizer::{self, ContextReport, TokenCounter, TruncationStrategy};
+
+// Output tokens requested when the caller does not set max_tokens
+const DEFAULT_MAX_TOKENS: u32 = 1000;
 
 // Personality configuration from environment variables
 #[derive(Debug, Clone)]
@@ -189,30 +199,75 @@ impl PersonalityConfig {
     }
 }
 
-#[derive(Debug, Serialize)]
-struct ChatCompletionRequest {
-    model: String,
-    messages: Vec<ChatMessage>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    temperature: Option<f32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    max_tokens: Option<u32>,
+/// Result of an `LLMClient::embed` call
+#[derive(Debug, Clone, Default)]
+pub struct Embeddings {
+    pub vectors: Vec<Vec<f32>>,
+    pub provider: String,
+    pub model: String,
+    pub dimension: usize,
+    pub prompt_tokens: u32,
+    pub batches: u32,
+    /// Embedding route taken and any backends that failed before `provider`
+    pub route: RouteReport,
 }
 
-#[derive(Debug, Deserialize)]
-struct ChatCompletionResponse {
-    choices: Vec<ChatChoice>,
-    usage: Option<Usage>,
+/// Per-request generation options
+///
+/// Parsed from `GenerateRequest.parameters`; anything not set falls back to the
+/// provider configuration.
+#[derive(Debug, Clone, Default)]
+pub struct GenerationOptions {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    /// Overrides LLM_TRUNCATION_STRATEGY for this request
+    pub truncation: Option<TruncationStrategy>,
+    /// Schema the response must conform to; passed to providers with native support
+    pub response_schema: Option<Arc<ResponseSchema>>,
+    /// Tools offered for native tool calling
+    pub tools: Vec<ToolSpec>,
+    pub tool_choice: ToolChoice,
+    /// Earlier tool-use rounds (assistant tool calls and tool results), oldest first
+    pub tool_history: Vec<ChatMessage>,
+    /// Routing inputs: request type (e.g. "planning") and budget hint
+    pub request_type: Option<String>,
+    pub budget: Option<BudgetHint>,
+    /// Targets tried in order if `provider` / `model` fail; filled in by `LLMClient::route`
+    pub fallbacks: Vec<ModelTarget>,
+    /// Name of the routing rule that chose the targets
+    pub route: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-struct ChatChoice {
-    message: ChatMessage,
-}
+impl GenerationOptions {
+    /// Read the `provider`, `model`, `temperature`, `max_tokens`, `truncation`,
+    /// `request_type` and `budget` request parameters
+    pub fn from_parameters(parameters: &HashMap<String, String>) -> Self {
+        let non_empty = |key: &str| {
+            parameters
+                .get(key)
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+                .map(|v| v.to_string())
+        };
 
-#[derive(Debug, Deserialize)]
-struct Usage {
-    total_tokens: u32,
+        Self {
+            provider: non_empty("provider"),
+            model: non_empty("model"),
+            temperature: non_empty("temperature").and_then(|v| v.parse().ok()),
+            max_tokens: non_empty("max_tokens").and_then(|v| v.parse().ok()),
+            truncation: non_empty("truncation").and_then(|v| TruncationStrategy::parse(&v)),
+            response_schema: None,
+            tools: Vec::new(),
+            tool_choice: ToolChoice::Auto,
+            tool_history: Vec::new(),
+            request_type: non_empty("request_type").map(|v| v.to_ascii_lowercase()),
+            budget: non_empty("budget").and_then(|v| BudgetHint::parse(&v)),
+            fallbacks: Vec::new(),
+            route: None,
+        }
+    }
 }
 
 // Custom error type for LLM client operations
@@ -229,8 +284,37 @@ pub enum LLMError {
     NetworkError(String), // Connection issues, timeouts, network failures
 
     // Other errors
-    ParseError(String),   // JSON parsing errors
-    UnknownError(String), // Any other unclassified errors
+    ParseError(String),        // JSON parsing errors
+    DimensionMismatch(String), // Embedding size differs from the configured VECTOR_SIZE
+    Cancelled(String),         // The caller went away (e.g. a dropped stream)
+    UnknownError(String),      // Any other unclassified errors
+}
+
+impl LLMError {
+    /// Status and error type labels reported in response metadata
+    pub fn status_labels(&self) -> (&'static str, &'static str) {
+        match self {
+            LLMError::RateLimitExceeded(_) => ("rate_limited", "RATE_LIMITED"),
+            LLMError::InvalidRequest(_) | LLMError::ModelNotAvailable(_) => {
+                ("client_error", "CLIENT_ERROR")
+            }
+            LLMError::ServerError(_) => ("server_error", "SERVER_ERROR"),
+            LLMError::NetworkError(_) => ("network_error", "NETWORK_ERROR"),
+            LLMError::DimensionMismatch(_) => ("config_error", "CONFIG_ERROR"),
+            LLMError::Cancelled(_) => ("cancelled", "CANCELLED"),
+            LLMError::ParseError(_) | LLMError::UnknownError(_) => ("error", "UNKNOWN_ERROR"),
+        }
+    }
+
+    // Authentication failures trigger a one-off API key refresh
+    fn is_auth_error(&self) -> bool {
+        match self {
+            LLMError::InvalidRequest(msg) => {
+                msg.contains("Unauthorized") || msg.contains("Invalid API key")
+            }
+            _ => false,
+        }
+    }
 }
 
 impl std::fmt::Display for LLMError {
@@ -242,6 +326,8 @@ impl std::fmt::Display for LLMError {
             LLMError::ServerError(msg) => write!(f, "Server error: {}", msg),
             LLMError::NetworkError(msg) => write!(f, "Network error: {}", msg),
             LLMError::ParseError(msg) => write!(f, "Parse error: {}", msg),
+            LLMError::DimensionMismatch(msg) => write!(f, "Embedding dimension mismatch: {}", msg),
+            LLMError::Cancelled(msg) => write!(f, "Cancelled: {}", msg),
             LLMError::UnknownError(msg) => write!(f, "Unknown error: {}", msg),
         }
     }
@@ -251,7 +337,7 @@ impl std::error::Error for LLMError {}
 
 // Helper function to determine if an error is retryable
 // This is used by the retry mechanism to decide whether to attempt another request
-fn is_retryable(error: &LLMError) -> bool {
+pub(crate) fn is_retryable(error: &LLMError) -> bool {
     match error {
         // Server errors and network errors are always retryable
         LLMError::ServerError(_) | LLMError::NetworkError(_) => true,
@@ -266,18 +352,42 @@ fn is_retryable(error: &LLMError) -> bool {
     }
 }
 
-#[derive(Debug)]
 pub struct LLMClient {
-    client: Client,
-    api_key: Arc<Mutex<String>>,
-    api_url: String,
-    model: String,
+    providers: HashMap<ProviderKind, Arc<dyn LlmProvider>>,
+    default_provider: ProviderKind,
+    // API keys keyed by provider secret label
+    api_keys: Arc<Mutex<HashMap<String, String>>>,
     max_retries: u32,
     initial_retry_delay_ms: u64,
     max_retry_delay_ms: u64,
     personality: PersonalityConfig,
-    provider: String,
-    secrets_client: Option<SecretsClient>,
+    secrets_client: Option<Arc<SecretsClient>>,
+    embedder: Box<dyn EmbeddingProvider>,
+    // Tried in order when the embedder fails with a failover error
+    embedding_fallbacks: Vec<Box<dyn EmbeddingProvider>>,
+    // Name of the embedding routing rule, if one applies
+    embedding_route: Option<String>,
+    routing: RoutingPolicy,
+    // Dimension the vector store expects (VECTOR_SIZE)
+    embedding_dimension: usize,
+    // Default context-window truncation (LLM_TRUNCATION_STRATEGY)
+    truncation: TruncationStrategy,
+}
+
+impl std::fmt::Debug for LLMClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LLMClient")
+            .field("providers", &self.providers.keys().collect::<Vec<_>>())
+            .field("default_provider", &self.default_provider)
+            .field("max_retries", &self.max_retries)
+            .field("embedder", &self.embedder.name())
+            .field("embedding_fallbacks", &self.embedding_fallbacks.len())
+            .field("routing", &!self.routing.is_empty())
+            .field("embedding_dimension", &self.embedding_dimension)
+            .field("truncation", &self.truncation)
+            .field("secrets_client", &self.secrets_client.is_some())
+            .finish()
+    }
 }
 
 impl LLMClient {
@@ -285,21 +395,70 @@ impl LLMClient {
     /// and the secrets service
     ///
     /// Reads:
-    /// - LLM_API_URL: The API endpoint URL (defaults to OpenAI chat completions)
-    /// - LLM_MODEL: The model to use (defaults to "gpt-3.5-turbo")
+    /// - LLM_PROVIDER: Default backend ("openai", "anthropic", "local" or "mock")
     /// - LLM_MAX_RETRIES: Maximum retry attempts (default: 3)
     /// - LLM_INITIAL_RETRY_DELAY_MS: Initial backoff delay in ms (default: 1000ms)
     /// - LLM_MAX_RETRY_DELAY_MS: Maximum backoff delay in ms (default: 30000ms)
+    /// - LLM_TRUNCATION_STRATEGY: How over-long prompts are fitted (see `tokenizer`)
+    /// - LLM_ROUTING_FILE: Model routing policy (see `routing`)
+    ///
+    /// Provider-specific settings are documented in the `providers` modules.
     pub async fn new() -> Self {
-        let api_url = env::var("LLM_API_URL")
-            .unwrap_or_else(|_| "https://api.openai.com/v1/chat/completions".to_string());
-        let model = env::var("LLM_MODEL").unwrap_or_else(|_| "gpt-3.5-turbo".to_string());
+        let client = Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()
+            .unwrap_or_default();
+
+        // Register every backend; each one is cheap until it is actually called
+        let mut providers: HashMap<ProviderKind, Arc<dyn LlmProvider>> = HashMap::new();
+        providers.insert(
+            ProviderKind::OpenAI,
+            Arc::new(OpenAIProvider::from_env(client.clone())),
+        );
+        providers.insert(
+            ProviderKind::Anthropic,
+            Arc::new(AnthropicProvider::from_env(client.clone())),
+        );
+        providers.insert(
+            ProviderKind::Local,
+            Arc::new(LocalProvider::from_env(client.clone())),
+        );
+        providers.insert(ProviderKind::Mock, Arc::new(MockProvider::from_env()));
+
+        let routing = RoutingPolicy::from_env();
 
-        // Extract provider name from API URL or model
-        let provider = Self::determine_provider(&api_url, &model);
+        // Embedding routes are fixed at startup so every vector comes from the same model
+        let embedding_dimension = embeddings::expected_dimension_from_env();
+        let embedding_route = routing.select(&RouteRequest {
+            request_type: routing::EMBEDDING,
+            prompt_tokens: 0,
+            budget: None,
+        });
+        let mut embedders: Vec<Box<dyn EmbeddingProvider>> = match &embedding_route {
+            Some(route) => route
+                .targets
+                .iter()
+                .map(|target| {
+                    embeddings::provider_for(
+                        &target.provider,
+                        target.model.clone(),
+                        target.api_url.clone(),
+                        client.clone(),
+                        embedding_dimension,
+                    )
+                })
+                .collect(),
+            None => vec![embeddings::provider_from_env(client, embedding_dimension)],
+        };
+        let embedder = embedders.remove(0);
+        log::info!(
+            "Embedding provider: {} (model: {}, expected dimension: {})",
+            embedder.name(),
+            embedder.model(),
+            embedding_dimension
+        );
 
-        // Initialize with an empty API key that will be filled by the secrets service
-        let api_key = Arc::new(Mutex::new(String::new()));
+        let default_provider = Self::determine_default_provider();
 
         // Retry configuration
         let max_retries = env::var("LLM_MAX_RETRIES")
@@ -317,11 +476,6 @@ impl LLMClient {
             .and_then(|v| v.parse().ok())
             .unwrap_or(30000); // 30 seconds default
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(60))
-            .build()
-            .unwrap_or_default();
-
         // Load personality configuration from environment
         let personality = PersonalityConfig::from_env();
         log::info!(
@@ -333,7 +487,7 @@ impl LLMClient {
         let secrets_client = match SecretsClient::new().await {
             Ok(client) => {
                 log::info!("Successfully connected to secrets service");
-                Some(client)
+                Some(Arc::new(client))
             }
             Err(err) => {
                 log::warn!("Failed to initialize secrets client: {}. Falling back to environment variables.", err);
@@ -342,67 +496,205 @@ impl LLMClient {
         };
 
         // Create the LLM client
-        let mut llm_client = Self {
-            client,
-            api_key,
-            api_url,
-            model,
+        let llm_client = Self {
+            providers,
+            default_provider,
+            api_keys: Arc::new(Mutex::new(HashMap::new())),
             max_retries,
             initial_retry_delay_ms,
             max_retry_delay_ms,
             personality,
-            provider,
             secrets_client,
+            embedder,
+            embedding_fallbacks: embedders,
+            embedding_route: embedding_route.map(|route| route.name),
+            routing,
+            embedding_dimension,
+            truncation: TruncationStrategy::from_env(),
         };
 
-        // Try to load API key from secrets service or fall back to environment variable
-        match llm_client.refresh_api_key().await {
-            Ok(_) => log::info!(
-                "Successfully loaded API key for provider: {}",
-                llm_client.provider
-            ),
-            Err(err) => log::warn!(
-                "Failed to load API key from secrets: {}. API calls may fail.",
-                err
-            ),
+        // Eagerly load the key for the default provider; other providers load on first use
+        let provider = llm_client.default_provider();
+        if provider.requires_api_key() {
+            match llm_client.refresh_api_key(provider.secret_label()).await {
+                Ok(_) => log::info!(
+                    "Successfully loaded API key for provider: {}",
+                    provider.secret_label()
+                ),
+                Err(err) => log::warn!(
+                    "Failed to load API key from secrets: {}. API calls may fail.",
+                    err
+                ),
+            }
         }
 
+        log::info!(
+            "Default LLM provider: {} (model: {})",
+            provider.kind(),
+            provider.default_model()
+        );
+
         llm_client
     }
 
-    /// Determine the LLM provider based on API URL and model
-    fn determine_provider(api_url: &str, model: &str) -> String {
-        // Use the hostname from API URL or model name as provider identifier
-        if api_url.contains("openai.com") {
-            "openai".to_string()
-        } else if api_url.contains("openrouter.ai") {
-            "openrouter".to_string()
-        } else if api_url.contains("x.ai") {
-            "grok".to_string()
-        } else if api_url.contains("googleapis.com") {
-            "gemini".to_string()
-        } else if api_url.contains("localhost:11434") {
-            "ollama".to_string()
-        } else if api_url.contains("localhost:1234") {
-            "lmstudio".to_string()
-        } else if model.starts_with("anthropic/") {
-            "anthropic".to_string()
-        } else {
-            // When unable to determine, use generic provider
-            "default".to_string()
+    /// Determine the default backend from LLM_PROVIDER, falling back to the API URL
+    fn determine_default_provider() -> ProviderKind {
+        if let Ok(name) = env::var("LLM_PROVIDER") {
+            match ProviderKind::parse(&name) {
+                Some(kind) => return kind,
+                None => log::warn!("Unknown LLM_PROVIDER '{}', using openai", name),
+            }
+        }
+
+        // Backwards compatibility: only LLM_API_URL was configured before
+        match env::var("LLM_API_URL") {
+            Ok(url) if url.contains("anthropic.com") => ProviderKind::Anthropic,
+            _ => ProviderKind::OpenAI,
+        }
+    }
+
+    /// The provider used when a request does not select one
+    pub fn default_provider(&self) -> Arc<dyn LlmProvider> {
+        self.providers[&self.default_provider].clone()
+    }
+
+    /// Resolve the provider for a request
+    ///
+    /// An unknown provider name is reported as an invalid request rather than
+    /// silently falling back to the default.
+    pub fn resolve_provider(&self, name: Option<&str>) -> Result<Arc<dyn LlmProvider>, LLMError> {
+        match name {
+            None => Ok(self.default_provider()),
+            Some(name) => ProviderKind::parse(name)
+                .and_then(|kind| self.providers.get(&kind).cloned())
+                .ok_or_else(|| LLMError::InvalidRequest(format!("Unknown provider: {}", name))),
         }
     }
 
-    /// Refresh the API key from the secrets service
-    async fn refresh_api_key(&mut self) -> Result<(), String> {
+    /// Apply the routing policy to a request
+    ///
+    /// Sets `provider` and `model` to the primary target of the matching rule and
+    /// `fallbacks` to the rest of its chain. Requests that name a provider or model
+    /// explicitly are left alone. Prompt length is counted with the default
+    /// provider's tokenizer, before any truncation.
+    pub fn route(
+        &self,
+        prompt: &str,
+        system_prompt: Option<&str>,
+        options: &mut GenerationOptions,
+    ) {
+        if self.routing.is_empty() || options.provider.is_some() || options.model.is_some() {
+            return;
+        }
+
+        let default = self.default_provider();
+        let counter = TokenCounter::for_model(default.kind(), default.default_model());
+        let prompt_tokens = counter.count(prompt) + system_prompt.map_or(0, |s| counter.count(s));
+        let request = RouteRequest {
+            request_type: options.request_type.as_deref().unwrap_or(routing::GENERAL),
+            prompt_tokens,
+            budget: options.budget,
+        };
+
+        let Some(route) = self.routing.select(&request) else {
+            return;
+        };
+        log::debug!(
+            "Routing {} request ({} tokens) via rule '{}'",
+            request.request_type,
+            prompt_tokens,
+            route.name
+        );
+        let mut targets = route.targets.into_iter();
+        if let Some(primary) = targets.next() {
+            options.provider = Some(primary.provider);
+            options.model = primary.model;
+        }
+        options.fallbacks = targets.collect();
+        options.route = Some(route.name);
+    }
+
+    /// The request options for each target in order: the routed primary, then its fallbacks
+    fn failover_chain(&self, options: &GenerationOptions) -> Vec<GenerationOptions> {
+        std::iter::once(options.clone())
+            .chain(options.fallbacks.iter().map(|target| GenerationOptions {
+                provider: Some(target.provider.clone()),
+                model: target.model.clone(),
+                fallbacks: Vec::new(),
+                ..options.clone()
+            }))
+            .collect()
+    }
+
+    /// "provider:model" of the target a request resolves to, for logs and metadata
+    fn target_label(&self, options: &GenerationOptions) -> String {
+        match self.resolve_provider(options.provider.as_deref()) {
+            Ok(provider) => format!(
+                "{}:{}",
+                provider.kind(),
+                options.model.as_deref().unwrap_or(provider.default_model())
+            ),
+            Err(_) => options.provider.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Run `operation` against each target of the failover chain until one succeeds
+    ///
+    /// Moves on only for errors the routing policy lists in `fallback_on`, and only
+    /// while `may_fail_over` allows it.
+    async fn with_failover<T, F, Fut, P>(
+        &self,
+        options: &GenerationOptions,
+        mut operation: F,
+        may_fail_over: P,
+    ) -> Result<(T, RouteReport), LLMError>
+    where
+        F: FnMut(GenerationOptions, u32) -> Fut,
+        Fut: Future<Output = Result<T, LLMError>>,
+        P: Fn() -> bool,
+    {
+        let mut report = RouteReport {
+            route: options.route.clone(),
+            failed: Vec::new(),
+        };
+        let mut chain = self.failover_chain(options).into_iter().peekable();
+
+        while let Some(target) = chain.next() {
+            let has_fallback = chain.peek().is_some();
+            // Don't spend the full retry budget on a target that has fallbacks behind it
+            let max_retries = if has_fallback {
+                self.max_retries.min(self.routing.retries_before_failover())
+            } else {
+                self.max_retries
+            };
+            let label = self.target_label(&target);
+
+            match operation(target, max_retries).await {
+                Ok(value) => return Ok((value, report)),
+                Err(err) if has_fallback && self.routing.fails_over(&err) && may_fail_over() => {
+                    let category = routing::error_category(&err);
+                    log::warn!("{} failed ({}), failing over: {}", label, category, err);
+                    report.failed.push((label, category));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Err(LLMError::InvalidRequest(
+            "No model target to route to".to_string(),
+        ))
+    }
+
+    /// Refresh the API key for a provider secret label from the secrets service
+    async fn refresh_api_key(&self, label: &str) -> Result<(), String> {
+        let label = label.to_string();
+
         if let Some(secrets) = &self.secrets_client {
             // Get the API key from the secrets service
-            match secrets.get_llm_api_key(&self.provider).await {
+            match secrets.get_llm_api_key(&label).await {
                 Ok(key) => {
-                    // Update the API key
-                    let mut api_key = self.api_key.lock().await;
-                    *api_key = key;
-                    log::debug!("API key refreshed for provider: {}", self.provider);
+                    self.api_keys.lock().await.insert(label.clone(), key);
+                    log::debug!("API key refreshed for provider: {}", label);
                     return Ok(());
                 }
                 Err(err) => {
@@ -410,8 +702,7 @@ impl LLMClient {
                         // Try with the generic default key if provider-specific key is not found
                         match secrets.get_llm_api_key("default").await {
                             Ok(key) => {
-                                let mut api_key = self.api_key.lock().await;
-                                *api_key = key;
+                                self.api_keys.lock().await.insert(label, key);
                                 log::debug!(
                                     "Using default API key (provider-specific key not found)"
                                 );
@@ -426,26 +717,55 @@ impl LLMClient {
             }
         }
 
-        // Fallback to environment variable if secrets service is unavailable
-        match env::var("LLM_API_KEY") {
-            Ok(key) => {
-                if key.is_empty() {
-                    log::warn!("LLM_API_KEY environment variable is empty");
-                    Err("LLM_API_KEY environment variable is empty".to_string())
-                } else {
-                    let mut api_key = self.api_key.lock().await;
-                    *api_key = key;
-                    log::info!("Using API key from environment variable");
-                    Ok(())
-                }
+        // Fallback to environment variables if secrets service is unavailable:
+        // LLM_<LABEL>_API_KEY first, then the generic LLM_API_KEY
+        let specific_var = format!("LLM_{}_API_KEY", label.to_ascii_uppercase());
+        let key = env::var(&specific_var)
+            .ok()
+            .filter(|k| !k.is_empty())
+            .or_else(|| env::var("LLM_API_KEY").ok());
+
+        match key {
+            Some(key) if !key.is_empty() => {
+                self.api_keys.lock().await.insert(label, key);
+                log::info!("Using API key from environment variable");
+                Ok(())
             }
-            Err(_) => {
+            Some(_) => {
+                log::warn!("LLM_API_KEY environment variable is empty");
+                Err("LLM_API_KEY environment variable is empty".to_string())
+            }
+            None => {
                 log::error!("LLM_API_KEY environment variable is not set");
                 Err("LLM_API_KEY environment variable is not set".to_string())
             }
         }
     }
 
+    /// Get the API key for a secret label, loading it on first use
+    async fn api_key_for(&self, label: &str, required: bool) -> Result<String, LLMError> {
+        if !required {
+            return Ok(String::new());
+        }
+
+        if let Some(key) = self.api_keys.lock().await.get(label) {
+            if !key.is_empty() {
+                return Ok(key.clone());
+            }
+        }
+
+        self.refresh_api_key(label)
+            .await
+            .map_err(|_| LLMError::InvalidRequest("API key is not set".to_string()))?;
+
+        self.api_keys
+            .lock()
+            .await
+            .get(label)
+            .cloned()
+            .ok_or_else(|| LLMError::InvalidRequest("API key is not set".to_string()))
+    }
+
     /// Creates an exponential backoff policy with jitter
     ///
     /// The exponential backoff algorithm works as follows:
@@ -466,85 +786,280 @@ impl LLMClient {
             .build()
     }
 
-    /// Check if LLM client is properly configured
-    pub fn is_configured(&self) -> bool {
-        !self.api_key.is_empty()
+    /// Tokenizer, context window and model name for the model a request resolves to
+    pub fn token_counter(
+        &self,
+        options: &GenerationOptions,
+    ) -> Result<(TokenCounter, usize, String), LLMError> {
+        let provider = self.resolve_provider(options.provider.as_deref())?;
+        let model = options
+            .model
+            .clone()
+            .unwrap_or_else(|| provider.default_model().to_string());
+        let counter = TokenCounter::for_model(provider.kind(), &model);
+        let window = tokenizer::context_window(provider.kind(), &model);
+        Ok((counter, window, model))
     }
 
-    /// Generate text from the LLM with exponential backoff retry mechanism
+    /// Fit a prompt into the context window of every target it may be sent to
+    ///
+    /// Counts the personalized system prompt and reserves `max_tokens` for the
+    /// reply plus room for tool definitions and tool history, then applies the
+    /// request's truncation strategy (or the configured default). Fallbacks can
+    /// have smaller windows or other tokenizers than the primary target, so the
+    /// prompt is fitted to each target of the failover chain in turn. Returns
+    /// the prompt to send and a report for response metadata, in the primary
+    /// target's tokens.
+    pub fn fit_to_context(
+        &self,
+        prompt: &str,
+        system_prompt: Option<&str>,
+        options: &GenerationOptions,
+    ) -> Result<(String, ContextReport), LLMError> {
+        let (primary_fit, mut report) = self.fit_to_target(prompt, system_prompt, options)?;
+
+        let mut fitted = primary_fit.clone();
+        for target in self.failover_chain(options).iter().skip(1) {
+            fitted = self.fit_to_target(&fitted, system_prompt, target)?.0;
+        }
+        if fitted != primary_fit {
+            let (counter, _, _) = self.token_counter(options)?;
+            let removed = counter
+                .count(&primary_fit)
+                .saturating_sub(counter.count(&fitted));
+            report.removed_tokens += removed;
+            report.input_tokens = report.input_tokens.saturating_sub(removed);
+        }
+        Ok((fitted, report))
+    }
+
+    // Fit a prompt into the context window of the single target `options` resolves to
+    fn fit_to_target(
+        &self,
+        prompt: &str,
+        system_prompt: Option<&str>,
+        options: &GenerationOptions,
+    ) -> Result<(String, ContextReport), LLMError> {
+        let (counter, window, model) = self.token_counter(options)?;
+        let system = self.personality.generate_system_prompt(system_prompt);
+        let tool_tokens: usize = options
+            .tools
+            .iter()
+            .map(|tool| {
+                counter.count(&tool.description) + counter.count(&tool.parameters.to_string())
+            })
+            .chain(options.tool_history.iter().map(|message| {
+                counter.count(&message.content)
+                    + message
+                        .tool_calls
+                        .iter()
+                        .map(|call| counter.count(&call.arguments))
+                        .sum::<usize>()
+            }))
+            .sum();
+        let reserved = options.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS) as usize + tool_tokens;
+        let strategy = options.truncation.unwrap_or(self.truncation);
+
+        let (fitted, report) =
+            tokenizer::fit_prompt(&counter, &system, prompt, window, reserved, strategy).map_err(
+                |overflow| {
+                    LLMError::InvalidRequest(format!(
+                        "Prompt needs {} tokens including {} reserved for output and tools, but {} allows {}",
+                        overflow.required, reserved, model, overflow.available
+                    ))
+                },
+            )?;
+
+        if report.truncated() {
+            log::warn!(
+                "Prompt truncated to fit {} context window: removed {} tokens ({})",
+                model,
+                report.removed_tokens,
+                strategy.as_str()
+            );
+        }
+        Ok((fitted, report))
+    }
+
+    /// Build the provider-neutral request: personalized system prompt plus user prompt
+    fn build_request(
+        &self,
+        provider: &dyn LlmProvider,
+        prompt: &str,
+        system_prompt: Option<&str>,
+        options: &GenerationOptions,
+    ) -> CompletionRequest {
+        // Generate a personalized system prompt based on configured personality
+        let personalized_system_prompt = self.personality.generate_system_prompt(system_prompt);
+
+        CompletionRequest {
+            model: options
+                .model
+                .clone()
+                .unwrap_or_else(|| provider.default_model().to_string()),
+            messages: [
+                ChatMessage::system(personalized_system_prompt),
+                ChatMessage::user(prompt),
+            ]
+            .into_iter()
+            .chain(options.tool_history.iter().cloned())
+            .collect(),
+            // Use personality-configured temperature for creativity control
+            temperature: Some(options.temperature.unwrap_or(self.personality.temperature)),
+            max_tokens: Some(options.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS)),
+            response_schema: options
+                .response_schema
+                .as_ref()
+                .filter(|_| provider.supports_response_schema())
+                .map(|schema| schema.schema().clone()),
+            tools: options.tools.clone(),
+            tool_choice: options.tool_choice.clone(),
+        }
+    }
+
+    /// Generate a completion with exponential backoff retry mechanism
     ///
     /// This method:
-    /// 1. Prepares the request with user and optional system prompts
-    /// 2. Attempts to execute the request
+    /// 1. Resolves the provider from the options (or the configured default)
+    /// 2. Prepares the request with user and optional system prompts
     /// 3. Automatically retries on transient failures with exponential backoff
-    /// 4. Distinguishes between retryable and non-retryable errors
+    /// 4. Refreshes the API key once on authentication failures
+    /// 5. Fails over to the next routed target on the policy's failover errors
     ///
     /// # Arguments
     /// * `prompt` - The user's text prompt
     /// * `system_prompt` - Optional system instructions for the LLM
+    /// * `options` - Per-request provider, model and sampling overrides
     ///
     /// # Returns
-    /// * `Ok(String)` - The LLM's response text on success
-    /// * `Err(LLMError)` - Categorized error on failure
-    pub async fn generate_text(
+    /// * `Ok((Completion, RouteReport))` - The response text, model and token usage,
+    ///   plus the route taken
+    /// * `Err(LLMError)` - Categorized error from the last target tried
+    pub async fn generate(
         &self,
         prompt: &str,
         system_prompt: Option<&str>,
-    ) -> Result<String, LLMError> {
-        let mut backoff = self.create_backoff();
-        let mut attempt = 0;
-
-        // Prepare the request body outside the retry loop to avoid recreating it
-        let mut messages = Vec::new();
-
-        // Generate a personalized system prompt based on configured personality
-        let personalized_system_prompt = self.personality.generate_system_prompt(system_prompt);
-
-        // Add system message
-        messages.push(ChatMessage {
-            role: "system".to_string(),
-            content: personalized_system_prompt,
-        });
-
-        messages.push(ChatMessage {
-            role: "user".to_string(),
-            content: prompt.to_string(),
-        });
+        options: &GenerationOptions,
+    ) -> Result<(Completion, RouteReport), LLMError> {
+        self.with_failover(
+            options,
+            |target, max_retries| async move {
+                self.generate_once(prompt, system_prompt, &target, max_retries)
+                    .await
+            },
+            || true,
+        )
+        .await
+    }
 
-        // Use personality-configured temperature for creativity control
-        let request_body = ChatCompletionRequest {
-            model: self.model.clone(),
-            messages,
-            temperature: Some(self.personality.temperature),
-            max_tokens: Some(1000),
-        };
+    // Generate against a single target, including the API key refresh
+    async fn generate_once(
+        &self,
+        prompt: &str,
+        system_prompt: Option<&str>,
+        options: &GenerationOptions,
+        max_retries: u32,
+    ) -> Result<Completion, LLMError> {
+        let provider = self.resolve_provider(options.provider.as_deref())?;
+        let request = self.build_request(provider.as_ref(), prompt, system_prompt, options);
+
+        match self
+            .complete_with_retry(provider.as_ref(), &request, max_retries)
+            .await
+        {
+            Err(err) if err.is_auth_error() => {
+                log::warn!(
+                    "Authentication error: {}. Attempting to refresh API key...",
+                    err
+                );
+
+                // Try to refresh the API key and retry the request
+                match self.refresh_api_key(provider.secret_label()).await {
+                    Ok(()) => {
+                        self.complete_with_retry(provider.as_ref(), &request, max_retries)
+                            .await
+                    }
+                    Err(_) => Err(err),
+                }
+            }
+            result => result,
+        }
+    }
 
+    // Retry loop around a single provider call
+    async fn complete_with_retry(
+        &self,
+        provider: &dyn LlmProvider,
+        request: &CompletionRequest,
+        max_retries: u32,
+    ) -> Result<Completion, LLMError> {
         log::info!(
             "Preparing LLM request to {} (model: {})",
-            self.api_url,
-            self.model
+            provider.kind(),
+            request.model
         );
 
+        let retries = AtomicU32::new(0);
+        self.with_retry_when(
+            "LLM",
+            || self.execute_request(provider, request),
+            || retries.fetch_add(1, Ordering::Relaxed) < max_retries,
+        )
+        .await
+    }
+
+    /// Run an operation with exponential backoff on retryable errors
+    ///
+    /// `label` only distinguishes the log lines of different call types.
+    async fn with_retry<T, F, Fut>(&self, label: &str, operation: F) -> Result<T, LLMError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, LLMError>>,
+    {
+        self.with_retry_when(label, operation, || true).await
+    }
+
+    /// Like `with_retry`, but only retries while `may_retry` returns true
+    async fn with_retry_when<T, F, Fut, P>(
+        &self,
+        label: &str,
+        mut operation: F,
+        may_retry: P,
+    ) -> Result<T, LLMError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, LLMError>>,
+        P: Fn() -> bool,
+    {
+        let mut backoff = self.create_backoff();
+        let mut attempt = 0;
+
         // Retry loop with exponential backoff
         loop {
             attempt += 1;
 
             if attempt > 1 {
-                log::info!("Retry attempt {} for LLM request", attempt);
+                log::info!("Retry attempt {} for {} request", attempt, label);
             }
 
             // Execute the request
-            match self.execute_request(&request_body).await {
+            match operation().await {
                 // On success, return the response immediately
-                Ok(response) => return Ok(response),
+                Ok(value) => return Ok(value),
 
                 // On error, determine if we should retry
                 Err(err) => {
                     // Stop retrying if:
                     // 1. The error is not retryable (client error, etc.)
                     // 2. We've exceeded the maximum number of retry attempts
-                    if !is_retryable(&err) || attempt > self.max_retries {
-                        log::error!("LLM request failed after {} attempts: {}", attempt, err);
+                    // 3. The caller vetoes another attempt
+                    if !is_retryable(&err) || attempt > self.max_retries || !may_retry() {
+                        log::error!(
+                            "{} request failed after {} attempts: {}",
+                            label,
+                            attempt,
+                            err
+                        );
                         return Err(err);
                     }
 
@@ -576,164 +1091,255 @@ impl LLMClient {
     // Execute a single request attempt
     async fn execute_request(
         &self,
-        request_body: &ChatCompletionRequest,
-    ) -> Result<String, LLMError> {
-        // Get API key from the mutex
-        let api_key = {
-            let key = self.api_key.lock().await;
-            if key.is_empty() {
-                return Err(LLMError::InvalidRequest("API key is not set".to_string()));
-            }
-            key.clone()
-        };
+        provider: &dyn LlmProvider,
+        request: &CompletionRequest,
+    ) -> Result<Completion, LLMError> {
+        let api_key = self
+            .api_key_for(provider.secret_label(), provider.requires_api_key())
+            .await?;
 
         // If the key is about to expire, try to refresh it (but don't block the request)
         // This is handled separately to avoid deadlocks
-        if let Some(secrets) = &self.secrets_client {
+        if let (Some(secrets), true) = (&self.secrets_client, provider.requires_api_key()) {
             tokio::spawn({
-                let provider = self.provider.clone();
-                let api_key_clone = self.api_key.clone();
-                let secrets_clone = secrets.clone();
+                let label = provider.secret_label().to_string();
+                let api_keys = self.api_keys.clone();
+                let secrets = secrets.clone();
 
                 async move {
-                    if let Ok(new_key) = secrets_clone.get_llm_api_key(&provider).await {
-                        let mut api_key = api_key_clone.lock().await;
-                        if new_key != *api_key {
-                            *api_key = new_key;
-                            log::debug!("API key rotated for provider: {}", provider);
+                    if let Ok(new_key) = secrets.get_llm_api_key(&label).await {
+                        let mut keys = api_keys.lock().await;
+                        if keys.get(&label) != Some(&new_key) {
+                            keys.insert(label.clone(), new_key);
+                            log::debug!("API key rotated for provider: {}", label);
                         }
                     }
                 }
             });
         }
 
-        // Send the HTTP request
-        let response = match self
-            .client
-            .post(&self.api_url)
-            .header("Authorization", format!("Bearer {}", api_key))
-            .header("Content-Type", "application/json")
-            .json(request_body)
-            .send()
-            .await
-        {
-            Ok(resp) => resp,
-            Err(err) => {
-                // Categorize network errors
-                if err.is_timeout() {
-                    return Err(LLMError::NetworkError(format!(
-                        "Request timed out: {}",
-                        err
-                    )));
-                } else if err.is_connect() {
-                    return Err(LLMError::NetworkError(format!(
-                        "Connection failed: {}",
-                        err
-                    )));
-                } else {
-                    return Err(LLMError::NetworkError(format!("Network error: {}", err)));
-                }
-            }
-        };
+        let completion = provider.complete(&api_key, request).await?;
 
-        // Handle HTTP status codes
-        let status = response.status();
-        if !status.is_success() {
-            let text = response.text().await.unwrap_or_default();
-
-            return match status.as_u16() {
-                400 => Err(LLMError::InvalidRequest(format!("Bad request: {}", text))),
-                401 => Err(LLMError::InvalidRequest(format!("Unauthorized: {}", text))),
-                403 => Err(LLMError::InvalidRequest(format!("Forbidden: {}", text))),
-                404 => Err(LLMError::InvalidRequest(format!("Not found: {}", text))),
-                429 => Err(LLMError::RateLimitExceeded(format!(
-                    "Rate limit exceeded: {}",
-                    text
-                ))),
-                // Server errors - retryable
-                500 | 502 | 503 | 504 => Err(LLMError::ServerError(format!(
-                    "Server error ({}): {}",
-                    status, text
-                ))),
-                _ => Err(LLMError::UnknownError(format!(
-                    "Unknown error ({}): {}",
-                    status, text
-                ))),
-            };
-        }
+        log::info!(
+            "LLM request completed via {} ({}). Used {} tokens",
+            completion.provider,
+            completion.model,
+            completion.usage.total_tokens
+        );
 
-        // Parse the successful response
-        let response_data: Result<ChatCompletionResponse, _> = response.json().await;
-        match response_data {
-            Ok(data) => {
-                if let Some(choice) = data.choices.first() {
-                    let response_text = choice.message.content.clone();
+        Ok(completion)
+    }
 
-                    // Log token usage if available
-                    if let Some(usage) = &data.usage {
-                        log::info!("LLM request completed. Used {} tokens", usage.total_tokens);
+    /// Stream a completion, forwarding text deltas to `sink` as they arrive
+    ///
+    /// Retry semantics for streams:
+    /// - Failures before the first delta reaches `sink` are retried exactly like
+    ///   `generate` (backoff, one API key refresh on authentication errors, failover
+    ///   to the next routed target)
+    /// - Once any delta has been forwarded the error is returned immediately, since
+    ///   a retry would replay text the caller has already received. Callers can
+    ///   check `DeltaSink::has_emitted` to tell partial failures apart
+    /// - A dropped receiver surfaces as `LLMError::Cancelled` and is never retried
+    ///
+    /// # Returns
+    /// * `Ok((Completion, RouteReport))` - The assembled text, model, finish reason
+    ///   and token usage, plus the route taken
+    /// * `Err(LLMError)` - Categorized error on failure
+    pub async fn generate_stream(
+        &self,
+        prompt: &str,
+        system_prompt: Option<&str>,
+        options: &GenerationOptions,
+        sink: DeltaSink,
+    ) -> Result<(Completion, RouteReport), LLMError> {
+        let result = self
+            .with_failover(
+                options,
+                |target, max_retries| {
+                    let sink = sink.clone();
+                    async move {
+                        self.stream_once(prompt, system_prompt, &target, max_retries, sink)
+                            .await
                     }
-
-                    Ok(response_text)
-                } else {
-                    Err(LLMError::ParseError(
-                        "No choices returned in response".to_string(),
-                    ))
-                }
-            }
-            Err(err) => Err(LLMError::ParseError(format!(
-                "Failed to parse response: {}",
-                err
-            ))),
+                },
+                || !sink.has_emitted(),
+            )
+            .await;
+
+        if let Ok((completion, _)) = &result {
+            log::info!(
+                "Streaming LLM request completed via {} ({}). Used {} tokens",
+                completion.provider,
+                completion.model,
+                completion.usage.total_tokens
+            );
         }
+
+        result
     }
 
-    // Convert to string error for compatibility with the main.rs implementation
-    pub async fn generate_text_string(
+    // Stream from a single target, retrying only until the first delta is forwarded
+    async fn stream_once(
         &self,
         prompt: &str,
         system_prompt: Option<&str>,
-    ) -> Result<String, String> {
-        match self.generate_text(prompt, system_prompt).await {
-            Ok(text) => Ok(text),
-            Err(err) => {
-                // If we received an authentication error, try to refresh the API key
-                if let LLMError::InvalidRequest(msg) = &err {
-                    if msg.contains("Unauthorized") || msg.contains("Invalid API key") {
-                        log::warn!(
-                            "Authentication error: {}. Attempting to refresh API key...",
-                            msg
-                        );
+        options: &GenerationOptions,
+        max_retries: u32,
+        sink: DeltaSink,
+    ) -> Result<Completion, LLMError> {
+        let provider = self.resolve_provider(options.provider.as_deref())?;
+        let request = self.build_request(provider.as_ref(), prompt, system_prompt, options);
 
-                        // Try to refresh the API key and retry the request
-                        if let Ok(()) = self.refresh_api_key().await {
-                            return match self.generate_text(prompt, system_prompt).await {
-                                Ok(text) => Ok(text),
-                                Err(retry_err) => Err(format!("{}", retry_err)),
-                            };
-                        }
+        log::info!(
+            "Preparing streaming LLM request to {} (model: {})",
+            provider.kind(),
+            request.model
+        );
+
+        let attempt = || async {
+            let api_key = self
+                .api_key_for(provider.secret_label(), provider.requires_api_key())
+                .await?;
+            provider.stream(&api_key, &request, sink.clone()).await
+        };
+        let retries = AtomicU32::new(0);
+        let may_retry =
+            || retries.fetch_add(1, Ordering::Relaxed) < max_retries && !sink.has_emitted();
+
+        match self.with_retry_when("LLM stream", attempt, may_retry).await {
+            Err(err) if err.is_auth_error() && !sink.has_emitted() => {
+                log::warn!(
+                    "Authentication error: {}. Attempting to refresh API key...",
+                    err
+                );
+                match self.refresh_api_key(provider.secret_label()).await {
+                    Ok(()) => {
+                        retries.store(0, Ordering::Relaxed);
+                        self.with_retry_when("LLM stream", attempt, may_retry).await
                     }
+                    Err(_) => Err(err),
                 }
+            }
+            result => result,
+        }
+    }
 
-                Err(format!("{}", err))
+    /// Embed texts with the configured embedding provider
+    ///
+    /// Texts are split into batches of the provider's maximum batch size, each
+    /// batch is retried independently, and every returned vector is checked
+    /// against the dimension the vector store expects. If the embedding route has
+    /// fallbacks, a failover error restarts the whole request on the next backend
+    /// so all vectors come from one model.
+    pub async fn embed(&self, texts: &[String]) -> Result<Embeddings, LLMError> {
+        let mut route = RouteReport {
+            route: self.embedding_route.clone(),
+            failed: Vec::new(),
+        };
+        let embedders: Vec<&dyn EmbeddingProvider> = std::iter::once(self.embedder.as_ref())
+            .chain(
+                self.embedding_fallbacks
+                    .iter()
+                    .map(|embedder| embedder.as_ref()),
+            )
+            .collect();
+
+        for (i, embedder) in embedders.iter().enumerate() {
+            let has_fallback = i + 1 < embedders.len();
+            match self.embed_with(*embedder, texts).await {
+                Ok(mut result) => {
+                    result.route = route;
+                    return Ok(result);
+                }
+                Err(err) if has_fallback && self.routing.fails_over(&err) => {
+                    let label = format!("{}:{}", embedder.name(), embedder.model());
+                    let category = routing::error_category(&err);
+                    log::warn!(
+                        "Embedding via {} failed ({}), failing over: {}",
+                        label,
+                        category,
+                        err
+                    );
+                    route.failed.push((label, category));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Err(LLMError::InvalidRequest(
+            "No embedding provider configured".to_string(),
+        ))
+    }
+
+    // Embed every batch with one backend
+    async fn embed_with(
+        &self,
+        embedder: &dyn EmbeddingProvider,
+        texts: &[String],
+    ) -> Result<Embeddings, LLMError> {
+        let api_key = self
+            .api_key_for(embedder.secret_label(), embedder.requires_api_key())
+            .await?;
+
+        let mut result = Embeddings {
+            provider: embedder.name().to_string(),
+            model: embedder.model().to_string(),
+            dimension: self.embedding_dimension,
+            ..Default::default()
+        };
+
+        for chunk in texts.chunks(embedder.max_batch_size().max(1)) {
+            let batch = self
+                .with_retry("embedding", || embedder.embed_batch(&api_key, chunk))
+                .await?;
+
+            if batch.vectors.len() != chunk.len() {
+                return Err(LLMError::ParseError(format!(
+                    "Expected {} embeddings, received {}",
+                    chunk.len(),
+                    batch.vectors.len()
+                )));
             }
+            embeddings::check_dimensions(
+                embedder.model(),
+                self.embedding_dimension,
+                &batch.vectors,
+            )?;
+
+            result.vectors.extend(batch.vectors);
+            result.prompt_tokens += batch.prompt_tokens;
+            result.batches += 1;
         }
+
+        Ok(result)
     }
 
-    /// Check current API key status
+    /// Check API key status for the default provider
     pub async fn check_api_key(&self) -> bool {
-        let key = self.api_key.lock().await;
-        !key.is_empty()
+        let provider = self.default_provider();
+        if !provider.requires_api_key() {
+            return true;
+        }
+        let keys = self.api_keys.lock().await;
+        keys.get(provider.secret_label())
+            .map(|key| !key.is_empty())
+            .unwrap_or(false)
     }
 
-    /// Manually trigger API key rotation
+    /// Manually trigger API key rotation for the default provider
     pub async fn rotate_api_key(&self) -> Result<(), String> {
+        let provider = self.default_provider();
         if let Some(secrets) = &self.secrets_client {
-            match secrets.get_llm_api_key(&self.provider).await {
+            match secrets.get_llm_api_key(provider.secret_label()).await {
                 Ok(key) => {
-                    let mut api_key = self.api_key.lock().await;
-                    *api_key = key;
-                    log::info!("API key manually rotated for provider: {}", self.provider);
+                    self.api_keys
+                        .lock()
+                        .await
+                        .insert(provider.secret_label().to_string(), key);
+                    log::info!(
+                        "API key manually rotated for provider: {}",
+                        provider.secret_label()
+                    );
                     Ok(())
                 }
                 Err(err) => Err(format!("Failed to rotate API key: {}", err)),
@@ -743,3 +1349,68 @@ impl LLMClient {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generation_options_from_parameters() {
+        let mut params = HashMap::new();
+        params.insert("provider".to_string(), "anthropic".to_string());
+        params.insert("temperature".to_string(), "0.2".to_string());
+        params.insert("max_tokens".to_string(), "not-a-number".to_string());
+        params.insert("model".to_string(), "  ".to_string());
+
+        let options = GenerationOptions::from_parameters(&params);
+        assert_eq!(options.provider.as_deref(), Some("anthropic"));
+        assert_eq!(options.temperature, Some(0.2));
+        assert_eq!(options.max_tokens, None);
+        assert_eq!(options.model, None);
+    }
+
+    #[tokio::test]
+    async fn test_fit_to_context_covers_fallback_windows() {
+        let client = LLMClient::new().await;
+        let options = GenerationOptions {
+            provider: Some("openai".to_string()),
+            model: Some("gpt-4o".to_string()),
+            fallbacks: vec![ModelTarget {
+                provider: "openai".to_string(),
+                model: Some("gpt-4".to_string()),
+                api_url: None,
+            }],
+            max_tokens: Some(512),
+            truncation: Some(TruncationStrategy::MiddleOut),
+            ..GenerationOptions::default()
+        };
+        let prompt = "lorem ipsum dolor sit amet ".repeat(4000);
+
+        let (fitted, report) = client.fit_to_context(&prompt, None, &options).unwrap();
+        assert!(report.truncated());
+        assert_eq!(report.context_window, 128_000);
+
+        // The prompt fits the 8k window of the fallback, not just the primary's
+        let fallback = GenerationOptions {
+            provider: Some("openai".to_string()),
+            model: Some("gpt-4".to_string()),
+            ..GenerationOptions::default()
+        };
+        let (counter, window, _) = client.token_counter(&fallback).unwrap();
+        assert_eq!(window, 8192);
+        assert!(counter.count(&fitted) + 512 < window);
+    }
+
+    #[test]
+    fn test_error_status_labels() {
+        assert_eq!(
+            LLMError::RateLimitExceeded(String::new()).status_labels(),
+            ("rate_limited", "RATE_LIMITED")
+        );
+        assert_eq!(
+            LLMError::ModelNotAvailable(String::new()).status_labels(),
+            ("client_error", "CLIENT_ERROR")
+        );
+        assert!(LLMError::InvalidRequest("Unauthorized: bad key".to_string()).is_auth_error());
+    }
+}
@@ -8,13 +8,32 @@ use once_cell::sync::Lazy;
 use std::collections::HashMap;
 use std::env;
 use std::net::SocketAddr;
+use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Instant;
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, Stream};
 use tonic::{transport::Server, Request, Response, Status};
 
+mod embeddings;
 mod llm_client;
+mod processing;
+mod prompt_manager;
+mod providers;
+mod response_cache;
+mod routing;
 mod secrets_client; // Add secrets client module
-use llm_client::LLMClient;
+mod structured_output;
+mod tokenizer;
+mod tool_calling;
+use llm_client::{GenerationOptions, LLMClient, LLMError};
+use prompt_manager::{PromptError, PromptManager, RenderedPrompt};
+use providers::{Completion, DeltaSink, TokenUsage, ToolChoice};
+use response_cache::{CacheConfig, CacheKey, CacheMode, ResponseCache};
+use structured_output::{ResponseSchema, StructuredReport};
+
+// Chunks buffered per stream before the provider is back-pressured
+const STREAM_BUFFER: usize = 32;
 
 // Track service start time for uptime reporting
 static START_TIME: Lazy<Instant> = Lazy::new(Instant::now);
@@ -27,104 +46,433 @@ pub mod agi_core {
 use agi_core::{
     health_service_server::{HealthService, HealthServiceServer},
     llm_service_server::{LlmService, LlmServiceServer},
-    CompileContextRequest, CompiledContextResponse, ContextSummarySchema, GenerateRequest,
-    GenerateResponse, HealthRequest, HealthResponse, LlmProcessRequest, LlmProcessResponse,
-    RawContextData,
+    CompileContextRequest, CompiledContextResponse, ContextSummarySchema, CountTokensRequest,
+    CountTokensResponse, GenerateFromTemplateRequest, GenerateRequest, GenerateResponse,
+    GenerateStreamChunk, HealthRequest, HealthResponse, LlmProcessRequest, LlmProcessResponse,
+    RawContextData, RenderPromptRequest, RenderPromptResponse,
 };
 
 // Define the LLM Server Structure
 #[derive(Debug)]
 pub struct LlmServer {
-    client: LLMClient,
+    // Shared so streaming generations can outlive the RPC handler
+    client: Arc<LLMClient>,
+    prompts: Arc<PromptManager>,
+    // None unless LLM_CACHE_ENABLED is set
+    cache: Option<Arc<ResponseCache>>,
 }
 
 // The client initialization is now async, so we can't use Default
 impl LlmServer {
     async fn new() -> Self {
         // Initialize LLM client with secrets support
-        let client = LLMClient::new().await;
-        Self { client }
+        let client = Arc::new(LLMClient::new().await);
+
+        // Load prompt templates and watch the template directory for edits
+        let prompts = Arc::new(PromptManager::from_env());
+        prompts.spawn_hot_reload();
+
+        let cache = CacheConfig::from_env().map(|config| {
+            log::info!(
+                "LLM response cache enabled (ttl={}s, semantic={})",
+                config.ttl.as_secs(),
+                config.semantic
+            );
+            let cache = Arc::new(ResponseCache::new(config));
+            cache.spawn_persistence();
+            cache
+        });
+
+        Self {
+            client,
+            prompts,
+            cache,
+        }
     }
-}
 
-// Implement the LlmService Trait
-#[tonic::async_trait]
-impl LlmService for LlmServer {
-    async fn generate_text(
+    // Wrap the prompt in the `role_<role>` template when a known role is requested.
+    // Roles without a template are sent as-is with no system prompt.
+    fn role_prompt(
         &self,
-        request: Request<GenerateRequest>,
-    ) -> Result<Response<GenerateResponse>, Status> {
-        let req_data = request.into_inner();
+        prompt: &str,
+        parameters: &HashMap<String, String>,
+    ) -> Option<RenderedPrompt> {
+        let role = parameters.get("role").filter(|role| !role.is_empty())?;
 
-        log::info!(
-            "Received GenerateText request: prompt length={}",
-            req_data.prompt.len()
+        let mut variables = HashMap::new();
+        variables.insert("prompt".to_string(), prompt.to_string());
+        match self.prompts.render(
+            &format!("role_{}", role),
+            parameters.get("prompt_version").map(String::as_str),
+            parameters.get("user_id").map(String::as_str),
+            &variables,
+        ) {
+            Ok(rendered) => Some(rendered),
+            Err(PromptError::TemplateNotFound(_)) => None,
+            Err(e) => {
+                log::warn!("Role prompt for '{}' unavailable: {}", role, e);
+                None
+            }
+        }
+    }
+
+    // Generate through the response cache when it is enabled
+    //
+    // `cacheable` decides whether a fresh completion may be stored, so callers can
+    // keep output that failed their own validation out of the cache.
+    async fn generate_cached(
+        &self,
+        prompt: &str,
+        system_prompt: Option<&str>,
+        options: &GenerationOptions,
+        mode: CacheMode,
+        cacheable: &(dyn Fn(&Completion) -> bool + Sync),
+        meta: &mut HashMap<String, String>,
+    ) -> Result<Completion, LLMError> {
+        // Enforce the context window first so the cache keys what is actually sent
+        let (prompt, context) = self.client.fit_to_context(prompt, system_prompt, options)?;
+        context.write_metadata(meta);
+        let prompt = prompt.as_str();
+
+        let Some(cache) = &self.cache else {
+            return self
+                .generate_routed(prompt, system_prompt, options, meta)
+                .await;
+        };
+        if mode == CacheMode::Bypass {
+            meta.insert("cache".to_string(), "bypass".to_string());
+            return self
+                .generate_routed(prompt, system_prompt, options, meta)
+                .await;
+        }
+
+        let provider = self.client.resolve_provider(options.provider.as_deref())?;
+        let model = options
+            .model
+            .clone()
+            .unwrap_or_else(|| provider.default_model().to_string());
+        let key = CacheKey::new(
+            prompt,
+            system_prompt,
+            provider.kind().as_str(),
+            &model,
+            options,
         );
 
-        // Determine system prompt based on parameters or context
-        // In a real system, this would be more sophisticated
-        let system_prompt = if let Some(role) = req_data.parameters.get("role") {
-            match role.as_str() {
-                "blue_team" => Some("You are the Blue Team Agent, responsible for defensive security operations. Analyze the situation and recommend defensive actions."),
-                "red_team" => Some("You are the Red Team Agent, responsible for adversarial simulation. Identify vulnerabilities and propose attack vectors."),
-                "master" => Some("You are the Master Orchestrator, responsible for high-level planning and coordination of the Digital Twin system."),
-                _ => None,
+        if mode == CacheMode::Use {
+            if let Some(hit) = cache.get_exact(&key) {
+                cache.write_metadata(Some(&hit), meta);
+                return Ok(hit.completion);
+            }
+        }
+
+        // The embedding is needed for the semantic lookup and to store the new entry
+        let embedding = if cache.semantic_enabled() {
+            match self.client.embed(&[key.prompt().to_string()]).await {
+                Ok(mut embeddings) => embeddings.vectors.pop(),
+                Err(e) => {
+                    log::warn!("Semantic cache lookup skipped, embedding failed: {}", e);
+                    None
+                }
             }
         } else {
             None
         };
 
-        // Call LLM Client with improved error handling
-        match self
+        if mode == CacheMode::Use {
+            if let Some(hit) = embedding
+                .as_deref()
+                .and_then(|embedding| cache.get_semantic(&key, embedding))
+            {
+                cache.write_metadata(Some(&hit), meta);
+                return Ok(hit.completion);
+            }
+        }
+
+        let completion = self
+            .generate_routed(prompt, system_prompt, options, meta)
+            .await?;
+        if cacheable(&completion) {
+            cache.insert(&key, &completion, embedding);
+        }
+        cache.write_metadata(None, meta);
+        if mode == CacheMode::Refresh {
+            meta.insert("cache".to_string(), "refresh".to_string());
+        }
+        Ok(completion)
+    }
+
+    // Generate without the cache, recording the route taken
+    async fn generate_routed(
+        &self,
+        prompt: &str,
+        system_prompt: Option<&str>,
+        options: &GenerationOptions,
+        meta: &mut HashMap<String, String>,
+    ) -> Result<Completion, LLMError> {
+        let (completion, route) = self.client.generate(prompt, system_prompt, options).await?;
+        route.write_metadata(meta);
+        Ok(completion)
+    }
+
+    // Generate until the output conforms to `options.response_schema` or attempts run out
+    //
+    // Each re-ask carries the validation errors and the rejected output. Only the
+    // first attempt may be served from the cache, and only conforming output is
+    // stored. Token usage is summed across attempts.
+    async fn generate_structured(
+        &self,
+        prompt: &str,
+        system_prompt: Option<&str>,
+        options: &GenerationOptions,
+        mode: CacheMode,
+        meta: &mut HashMap<String, String>,
+    ) -> Result<(Completion, StructuredReport), LLMError> {
+        let schema = options
+            .response_schema
+            .clone()
+            .ok_or_else(|| LLMError::InvalidRequest("No response schema".to_string()))?;
+        let native = self
             .client
-            .generate_text_string(&req_data.prompt, system_prompt)
+            .resolve_provider(options.provider.as_deref())?
+            .supports_response_schema();
+        let system_prompt = schema.system_prompt(system_prompt);
+        let max_attempts = structured_output::max_attempts();
+        let conforms = |completion: &Completion| schema.validate(&completion.text).is_ok();
+
+        let mut attempt_prompt = prompt.to_string();
+        let mut usage = TokenUsage::default();
+        let mut attempt = 1;
+        loop {
+            let attempt_mode = if attempt == 1 {
+                mode
+            } else {
+                CacheMode::Bypass
+            };
+            let mut completion = self
+                .generate_cached(
+                    &attempt_prompt,
+                    Some(&system_prompt),
+                    options,
+                    attempt_mode,
+                    &conforms,
+                    meta,
+                )
+                .await?;
+            usage += completion.usage;
+
+            let outcome = schema.validate(&completion.text);
+            if let Err(reason) = &outcome {
+                log::warn!(
+                    "Structured output failed validation (attempt {}/{}): {}",
+                    attempt,
+                    max_attempts,
+                    reason
+                );
+                if attempt < max_attempts {
+                    attempt_prompt = schema.retry_prompt(prompt, reason, &completion.text);
+                    attempt += 1;
+                    continue;
+                }
+            }
+
+            completion.usage = usage;
+            let report = StructuredReport {
+                attempts: attempt,
+                native,
+                outcome,
+            };
+            return Ok((completion, report));
+        }
+    }
+
+    // Run a generation and build the GenerateResponse, recording the template if one was used
+    async fn generate_reply(
+        &self,
+        prompt: &str,
+        system_prompt: Option<&str>,
+        options: &GenerationOptions,
+        cache_mode: CacheMode,
+        template: Option<&RenderedPrompt>,
+    ) -> GenerateResponse {
+        let mut meta = HashMap::new();
+        if let Some(template) = template {
+            template.write_metadata(&mut meta);
+        }
+
+        // Call LLM Client with improved error handling
+        let result = if options.response_schema.is_some() {
+            self.generate_structured(prompt, system_prompt, options, cache_mode, &mut meta)
+                .await
+                .map(|(completion, report)| (completion, Some(report)))
+        } else {
+            self.generate_cached(
+                prompt,
+                system_prompt,
+                options,
+                cache_mode,
+                &|_| true,
+                &mut meta,
+            )
             .await
-        {
-            Ok(text) => {
-                let reply = GenerateResponse {
-                    text,
-                    metadata: {
-                        let mut meta = std::collections::HashMap::new();
+            .map(|completion| (completion, None))
+        };
+        match result {
+            Ok((completion, report)) => {
+                match &report {
+                    Some(report) if report.outcome.is_err() => {
+                        meta.insert("status".to_string(), "format_error".to_string());
+                        meta.insert("error_type".to_string(), "INVALID_OUTPUT".to_string());
+                    }
+                    _ => {
                         meta.insert("status".to_string(), "success".to_string());
-                        meta
-                    },
-                };
-                Ok(Response::new(reply))
+                    }
+                }
+                if let Some(report) = &report {
+                    report.write_metadata(&mut meta);
+                }
+                meta.insert("provider".to_string(), completion.provider);
+                meta.insert("model".to_string(), completion.model);
+                if let Some(finish_reason) = completion.finish_reason {
+                    meta.insert("finish_reason".to_string(), finish_reason);
+                }
+                completion.usage.write_metadata(&mut meta);
+                if !completion.tool_calls.is_empty() {
+                    meta.insert(
+                        "tool_calls".to_string(),
+                        completion.tool_calls.len().to_string(),
+                    );
+                }
+                GenerateResponse {
+                    text: completion.text,
+                    metadata: meta,
+                    tool_calls: tool_calling::calls_to_proto(completion.tool_calls),
+                }
             }
             Err(e) => {
                 log::error!("LLM generation failed: {}", e);
 
                 // Classify error type for better client feedback
-                let (status, error_type) = if e.contains("Rate limit exceeded") {
-                    ("rate_limited", "RATE_LIMITED")
-                } else if e.contains("Invalid request")
-                    || e.contains("Unauthorized")
-                    || e.contains("Forbidden")
-                {
-                    ("client_error", "CLIENT_ERROR")
-                } else if e.contains("Server error") {
-                    ("server_error", "SERVER_ERROR")
-                } else if e.contains("Network error") {
-                    ("network_error", "NETWORK_ERROR")
-                } else {
-                    ("error", "UNKNOWN_ERROR")
-                };
+                let (status, error_type) = e.status_labels();
 
                 // Return a failure response
-                let reply = GenerateResponse {
+                meta.insert("status".to_string(), status.to_string());
+                meta.insert("error".to_string(), e.to_string());
+                meta.insert("error_type".to_string(), error_type.to_string());
+                GenerateResponse {
                     text: format!("Error generating text: {}", e),
-                    metadata: {
-                        let mut meta = std::collections::HashMap::new();
-                        meta.insert("status".to_string(), status.to_string());
-                        meta.insert("error".to_string(), e.clone());
-                        meta.insert("error_type".to_string(), error_type.to_string());
-                        meta
-                    },
-                };
-                Ok(Response::new(reply))
+                    metadata: meta,
+                    tool_calls: Vec::new(),
+                }
             }
         }
     }
+}
+
+fn prompt_error_status(e: PromptError) -> Status {
+    match e {
+        PromptError::TemplateNotFound(_) | PromptError::VersionNotFound { .. } => {
+            Status::not_found(e.to_string())
+        }
+        PromptError::MissingVariable { .. } => Status::invalid_argument(e.to_string()),
+        PromptError::InvalidTemplate(_) => Status::internal(e.to_string()),
+    }
+}
+
+// Empty proto strings mean "not set"
+fn non_empty(value: &str) -> Option<&str> {
+    Some(value).filter(|v| !v.is_empty())
+}
+
+// Compile the optional `json_schema` field of a generation request
+fn response_schema(text: &str) -> Result<Option<Arc<ResponseSchema>>, Status> {
+    non_empty(text.trim())
+        .map(|text| {
+            ResponseSchema::parse(text)
+                .map(Arc::new)
+                .map_err(Status::invalid_argument)
+        })
+        .transpose()
+}
+
+// Apply the tool definitions, tool choice and earlier tool rounds of a GenerateRequest
+fn apply_tools(request: &GenerateRequest, options: &mut GenerationOptions) -> Result<(), Status> {
+    options.tools =
+        tool_calling::tools_from_proto(&request.tools).map_err(Status::invalid_argument)?;
+    options.tool_choice = ToolChoice::parse(&request.tool_choice);
+    options.tool_history = tool_calling::history_from_proto(&request.tool_turns);
+
+    if let ToolChoice::Tool(name) = &options.tool_choice {
+        if !options.tools.iter().any(|tool| &tool.name == name) {
+            return Err(Status::invalid_argument(format!(
+                "tool_choice names unknown tool '{}'",
+                name
+            )));
+        }
+    }
+    if options.tool_choice == ToolChoice::Required && options.tools.is_empty() {
+        return Err(Status::invalid_argument(
+            "tool_choice 'required' needs at least one tool",
+        ));
+    }
+    if !options.tools.is_empty() && options.response_schema.is_some() {
+        return Err(Status::invalid_argument(
+            "json_schema and tools cannot be combined",
+        ));
+    }
+    Ok(())
+}
+
+fn delta_chunk(delta: String) -> GenerateStreamChunk {
+    GenerateStreamChunk {
+        delta,
+        done: false,
+        finish_reason: String::new(),
+        metadata: HashMap::new(),
+    }
+}
+
+// Implement the LlmService Trait
+#[tonic::async_trait]
+impl LlmService for LlmServer {
+    async fn generate_text(
+        &self,
+        request: Request<GenerateRequest>,
+    ) -> Result<Response<GenerateResponse>, Status> {
+        let req_data = request.into_inner();
+
+        log::info!(
+            "Received GenerateText request: prompt length={}",
+            req_data.prompt.len()
+        );
+
+        let role_prompt = self.role_prompt(&req_data.prompt, &req_data.parameters);
+        let (prompt, system_prompt) = match &role_prompt {
+            Some(rendered) => (rendered.prompt.as_str(), rendered.system_prompt.as_deref()),
+            None => (req_data.prompt.as_str(), None),
+        };
+        let mut options = GenerationOptions::from_parameters(&req_data.parameters);
+        options.response_schema = response_schema(&req_data.json_schema)?;
+        apply_tools(&req_data, &mut options)?;
+        self.client.route(prompt, system_prompt, &mut options);
+        // Cached entries do not keep tool calls
+        let cache_mode = if options.tools.is_empty() && options.tool_history.is_empty() {
+            CacheMode::from_parameters(&req_data.parameters)
+        } else {
+            CacheMode::Bypass
+        };
+
+        let reply = self
+            .generate_reply(
+                prompt,
+                system_prompt,
+                &options,
+                cache_mode,
+                role_prompt.as_ref(),
+            )
+            .await;
+        Ok(Response::new(reply))
+    }
 
     async fn generate(
         &self,
@@ -134,6 +482,266 @@ impl LlmService for LlmServer {
         self.generate_text(request).await
     }
 
+    type GenerateTextStreamStream =
+        Pin<Box<dyn Stream<Item = Result<GenerateStreamChunk, Status>> + Send + 'static>>;
+
+    async fn generate_text_stream(
+        &self,
+        request: Request<GenerateRequest>,
+    ) -> Result<Response<Self::GenerateTextStreamStream>, Status> {
+        let req_data = request.into_inner();
+
+        log::info!(
+            "Received GenerateTextStream request: prompt length={}",
+            req_data.prompt.len()
+        );
+
+        if !req_data.tools.is_empty() || !req_data.tool_turns.is_empty() {
+            return Err(Status::invalid_argument(
+                "GenerateTextStream does not support tools; use GenerateText",
+            ));
+        }
+        let mut options = GenerationOptions::from_parameters(&req_data.parameters);
+        options.response_schema = response_schema(&req_data.json_schema)?;
+        let role_prompt = self.role_prompt(&req_data.prompt, &req_data.parameters);
+        let (prompt, mut system_prompt) = match &role_prompt {
+            Some(rendered) => (rendered.prompt.clone(), rendered.system_prompt.clone()),
+            None => (req_data.prompt, None),
+        };
+        // Streamed output cannot be re-asked, so a schema is enforced natively where
+        // possible and the final chunk reports whether the output conforms
+        if let Some(schema) = &options.response_schema {
+            system_prompt = Some(schema.system_prompt(system_prompt.as_deref()));
+        }
+        self.client.route(&prompt, system_prompt.as_deref(), &mut options);
+        let native_schema = self
+            .client
+            .resolve_provider(options.provider.as_deref())
+            .map(|provider| provider.supports_response_schema())
+            .unwrap_or(false);
+        let client = self.client.clone();
+
+        let (delta_tx, mut delta_rx) = mpsc::channel::<String>(STREAM_BUFFER);
+        let (chunk_tx, chunk_rx) = mpsc::channel(STREAM_BUFFER);
+        let sink = DeltaSink::new(delta_tx);
+
+        tokio::spawn(async move {
+            let mut meta = HashMap::new();
+            if let Some(template) = &role_prompt {
+                template.write_metadata(&mut meta);
+            }
+
+            let result = match client.fit_to_context(&prompt, system_prompt.as_deref(), &options) {
+                Err(e) => Err(e),
+                Ok((prompt, context)) => {
+                    context.write_metadata(&mut meta);
+                    let generation = client.generate_stream(
+                        &prompt,
+                        system_prompt.as_deref(),
+                        &options,
+                        sink.clone(),
+                    );
+                    tokio::pin!(generation);
+
+                    // Relay deltas until the provider finishes. If the client disconnects,
+                    // returning drops the generation future, which closes the provider connection.
+                    loop {
+                        tokio::select! {
+                            result = &mut generation => break result,
+                            Some(delta) = delta_rx.recv() => {
+                                if chunk_tx.send(Ok(delta_chunk(delta))).await.is_err() {
+                                    log::info!("GenerateTextStream client disconnected; cancelling generation");
+                                    return;
+                                }
+                            }
+                            _ = chunk_tx.closed() => {
+                                log::info!("GenerateTextStream client disconnected; cancelling generation");
+                                return;
+                            }
+                        }
+                    }
+                }
+            };
+
+            // Flush deltas the provider produced just before finishing
+            while let Ok(delta) = delta_rx.try_recv() {
+                if chunk_tx.send(Ok(delta_chunk(delta))).await.is_err() {
+                    return;
+                }
+            }
+
+            let finish_reason = match result {
+                Ok((completion, route)) => {
+                    meta.insert("status".to_string(), "success".to_string());
+                    route.write_metadata(&mut meta);
+                    if let Some(schema) = &options.response_schema {
+                        let report = StructuredReport {
+                            attempts: 1,
+                            native: native_schema,
+                            outcome: schema.validate(&completion.text),
+                        };
+                        if report.outcome.is_err() {
+                            meta.insert("status".to_string(), "format_error".to_string());
+                            meta.insert("error_type".to_string(), "INVALID_OUTPUT".to_string());
+                        }
+                        report.write_metadata(&mut meta);
+                    }
+                    meta.insert("provider".to_string(), completion.provider);
+                    meta.insert("model".to_string(), completion.model);
+                    completion.usage.write_metadata(&mut meta);
+                    completion
+                        .finish_reason
+                        .unwrap_or_else(|| "stop".to_string())
+                }
+                Err(e) => {
+                    log::error!("Streaming LLM generation failed: {}", e);
+                    let (status, error_type) = e.status_labels();
+                    let partial = sink.has_emitted();
+                    meta.insert("status".to_string(), status.to_string());
+                    meta.insert("error".to_string(), e.to_string());
+                    meta.insert("error_type".to_string(), error_type.to_string());
+                    // Partial output is never retried server-side; tell the caller
+                    // whether re-issuing the whole request is worthwhile
+                    meta.insert("partial".to_string(), partial.to_string());
+                    meta.insert(
+                        "retryable".to_string(),
+                        llm_client::is_retryable(&e).to_string(),
+                    );
+                    "error".to_string()
+                }
+            };
+
+            let _ = chunk_tx
+                .send(Ok(GenerateStreamChunk {
+                    delta: String::new(),
+                    done: true,
+                    finish_reason,
+                    metadata: meta,
+                }))
+                .await;
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(chunk_rx))))
+    }
+
+    async fn render_prompt(
+        &self,
+        request: Request<RenderPromptRequest>,
+    ) -> Result<Response<RenderPromptResponse>, Status> {
+        let req_data = request.into_inner();
+
+        log::info!(
+            "Received RenderPrompt request: template={}, version={}",
+            req_data.template_name,
+            req_data.version
+        );
+
+        let rendered = self
+            .prompts
+            .render(
+                &req_data.template_name,
+                non_empty(&req_data.version),
+                non_empty(&req_data.selection_key),
+                &req_data.variables,
+            )
+            .map_err(prompt_error_status)?;
+
+        let mut meta = rendered.metadata.clone();
+        rendered.write_metadata(&mut meta);
+
+        Ok(Response::new(RenderPromptResponse {
+            system_prompt: rendered.system_prompt.unwrap_or_default(),
+            prompt: rendered.prompt,
+            template_name: rendered.name,
+            version: rendered.version,
+            metadata: meta,
+        }))
+    }
+
+    async fn generate_from_template(
+        &self,
+        request: Request<GenerateFromTemplateRequest>,
+    ) -> Result<Response<GenerateResponse>, Status> {
+        let req_data = request.into_inner();
+
+        log::info!(
+            "Received GenerateFromTemplate request: template={}, version={}",
+            req_data.template_name,
+            req_data.version
+        );
+
+        let rendered = self
+            .prompts
+            .render(
+                &req_data.template_name,
+                non_empty(&req_data.version),
+                non_empty(&req_data.selection_key),
+                &req_data.variables,
+            )
+            .map_err(prompt_error_status)?;
+
+        // Request parameters override the template's defaults
+        let mut parameters = rendered.parameters.clone();
+        parameters.extend(req_data.parameters);
+        let mut options = GenerationOptions::from_parameters(&parameters);
+        options.response_schema = response_schema(&req_data.json_schema)?;
+        self.client.route(
+            &rendered.prompt,
+            rendered.system_prompt.as_deref(),
+            &mut options,
+        );
+        let cache_mode = CacheMode::from_parameters(&parameters);
+
+        let reply = self
+            .generate_reply(
+                &rendered.prompt,
+                rendered.system_prompt.as_deref(),
+                &options,
+                cache_mode,
+                Some(&rendered),
+            )
+            .await;
+        Ok(Response::new(reply))
+    }
+
+    async fn count_tokens(
+        &self,
+        request: Request<CountTokensRequest>,
+    ) -> Result<Response<CountTokensResponse>, Status> {
+        let req_data = request.into_inner();
+
+        log::debug!(
+            "Received CountTokens request: texts={}, model={}",
+            req_data.texts.len(),
+            req_data.model
+        );
+
+        let options = GenerationOptions {
+            provider: non_empty(&req_data.provider).map(str::to_string),
+            model: non_empty(&req_data.model).map(str::to_string),
+            ..Default::default()
+        };
+        let (counter, window, model) = self
+            .client
+            .token_counter(&options)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        let token_counts: Vec<u32> = req_data
+            .texts
+            .iter()
+            .map(|text| counter.count(text) as u32)
+            .collect();
+
+        Ok(Response::new(CountTokensResponse {
+            total_tokens: token_counts.iter().sum(),
+            token_counts,
+            tokenizer: counter.encoding().to_string(),
+            model,
+            context_window: window as u32,
+            approximate: counter.is_approximate(),
+        }))
+    }
+
     async fn process(
         &self,
         request: Request<LlmProcessRequest>,
@@ -146,29 +754,47 @@ impl LlmService for LlmServer {
             req_data.text.len()
         );
 
-        // --- LLM PROCESSING STUB ---
-        // In a real scenario, this would involve:
-        // 1. Processing text based on the operation type
-        // 2. Applying NLP operations (summarization, translation, etc.)
-        // 3. Returning the processed result
-        // For now, we return a stub response
+        let operation = processing::Operation::parse(&req_data.operation, &req_data.parameters)
+            .map_err(Status::invalid_argument)?;
+        if req_data.text.trim().is_empty() {
+            return Err(Status::invalid_argument("Cannot process empty text"));
+        }
+        let options = GenerationOptions::from_parameters(&req_data.parameters);
 
-        let result = format!(
-            "LLM processed text (operation: {}): \"{}\"",
-            req_data.operation, req_data.text
-        );
+        match processing::run(&self.client, &operation, &req_data.text, &options).await {
+            Ok(output) => {
+                let mut meta = output.metadata;
+                meta.insert("status".to_string(), "success".to_string());
+                Ok(Response::new(LlmProcessResponse {
+                    result: output.result,
+                    metadata: meta,
+                }))
+            }
+            Err(e) => {
+                log::error!("Process operation {} failed: {}", operation.name(), e);
 
-        let reply = LlmProcessResponse {
-            result,
-            metadata: {
                 let mut meta = std::collections::HashMap::new();
-                meta.insert("operation".to_string(), req_data.operation);
-                meta.insert("status".to_string(), "success".to_string());
-                meta
-            },
-        };
+                meta.insert("operation".to_string(), operation.name().to_string());
+                meta.insert("error".to_string(), e.to_string());
+                match &e {
+                    processing::ProcessError::Llm(llm_error) => {
+                        let (status, error_type) = llm_error.status_labels();
+                        meta.insert("status".to_string(), status.to_string());
+                        meta.insert("error_type".to_string(), error_type.to_string());
+                    }
+                    processing::ProcessError::InvalidOutput { attempts, .. } => {
+                        meta.insert("status".to_string(), "format_error".to_string());
+                        meta.insert("error_type".to_string(), "INVALID_OUTPUT".to_string());
+                        meta.insert("attempts".to_string(), attempts.to_string());
+                    }
+                }
 
-        Ok(Response::new(reply))
+                Ok(Response::new(LlmProcessResponse {
+                    result: String::new(),
+                    metadata: meta,
+                }))
+            }
+        }
     }
 
     async fn embed_text(
@@ -182,26 +808,56 @@ impl LlmService for LlmServer {
             req_data.text.len()
         );
 
-        // --- LLM EMBEDDING STUB ---
-        // In a real scenario, this would involve:
-        // 1. Calling an embedding model (OpenAI, sentence-transformers, etc.)
-        // 2. Converting text to a vector representation
-        // 3. Returning the embedding vector
-        // For now, we return a stub response with a string representation
+        // "embed_batch" takes a JSON array of strings and returns an array of vectors;
+        // any other operation embeds `text` as a single input
+        let batch_mode = req_data.operation == "embed_batch";
+        let texts: Vec<String> = if batch_mode {
+            serde_json::from_str(&req_data.text).map_err(|e| {
+                Status::invalid_argument(format!(
+                    "embed_batch expects a JSON array of strings: {}",
+                    e
+                ))
+            })?
+        } else {
+            vec![req_data.text]
+        };
+
+        if texts.is_empty() || texts.iter().any(|t| t.trim().is_empty()) {
+            return Err(Status::invalid_argument("Cannot embed empty text"));
+        }
 
-        // Stub embedding vector representation (1536 dimensions, all 0.0 for now)
-        let embedding_vec = vec![0.0f32; 1536];
-        let embedding_vector = serde_json::to_string(&embedding_vec).unwrap_or_default();
+        let embeddings = self.client.embed(&texts).await.map_err(|e| {
+            log::error!("Embedding failed: {}", e);
+            match e {
+                LLMError::DimensionMismatch(_) => Status::failed_precondition(e.to_string()),
+                LLMError::InvalidRequest(_) | LLMError::ModelNotAvailable(_) => {
+                    Status::invalid_argument(e.to_string())
+                }
+                _ => Status::unavailable(e.to_string()),
+            }
+        })?;
+
+        let result = if batch_mode {
+            serde_json::to_string(&embeddings.vectors)
+        } else {
+            serde_json::to_string(&embeddings.vectors[0])
+        }
+        .map_err(|e| Status::internal(format!("Failed to serialize embedding: {}", e)))?;
 
         let reply = LlmProcessResponse {
-            result: embedding_vector,
+            result,
             metadata: {
                 let mut meta = std::collections::HashMap::new();
+                meta.insert("embedding_model".to_string(), embeddings.model);
+                meta.insert("embedding_provider".to_string(), embeddings.provider);
+                meta.insert("dimensions".to_string(), embeddings.dimension.to_string());
+                meta.insert("count".to_string(), embeddings.vectors.len().to_string());
+                meta.insert("batches".to_string(), embeddings.batches.to_string());
+                embeddings.route.write_metadata(&mut meta);
                 meta.insert(
-                    "embedding_model".to_string(),
-                    "stub-embedding-model".to_string(),
+                    "prompt_tokens".to_string(),
+                    embeddings.prompt_tokens.to_string(),
                 );
-                meta.insert("dimensions".to_string(), "1536".to_string());
                 meta.insert("status".to_string(), "success".to_string());
                 meta
             },
@@ -215,50 +871,23 @@ impl LlmService for LlmServer {
         request: Request<CompileContextRequest>,
     ) -> Result<Response<CompiledContextResponse>, Status> {
         let req_data = request.into_inner();
+        let schema_spec = req_data.schema.unwrap_or_default();
+        let raw_data = req_data.raw_data.unwrap_or_default();
 
         log::info!(
             "Received CompileContext request: request_id={}, schema_id={}",
             req_data.request_id,
-            req_data.schema.schema_id
-        );
-
-        // Create the system prompt for context compilation
-        let system_prompt = Some(
-            format!(
-                "You are a Context Compiler responsible for structuring and condensing raw context data.
-
-Your task is to analyze the provided raw context data and compile it into a concise, structured JSON format
-that strictly adheres to the provided schema specification.
-
-SCHEMA SPECIFICATION:
-ID: {}
-Fields: {}
-Description: {}
-
-Your goals are to:
-1. Strictly follow the schema field definitions
-2. Extract only the most relevant and high-signal information from the raw data
-3. Condense the information into the smallest possible representation while preserving meaning
-4. Return a valid, well-formed JSON object that matches the schema
-5. Ensure all required fields are present in the output
-6. Omit any redundant or low-value information
-
-The quality of your compilation directly impacts the LLM's working memory efficiency.
-Be selective and precise in what you include.",
-                req_data.schema.schema_id,
-                req_data.schema.field_definitions.join(", "),
-                req_data.schema.schema_description
-            )
+            schema_spec.schema_id
         );
 
         // Prepare the prompt content from raw data
         // Convert raw context entries to a readable format
         let mut raw_content = format!(
             "User ID: {}\nQuery: {}\n\nContext Entries:\n",
-            req_data.raw_data.user_id, req_data.raw_data.query
+            raw_data.user_id, raw_data.query
         );
 
-        for (i, entry) in req_data.raw_data.entries.iter().enumerate() {
+        for (i, entry) in raw_data.entries.iter().enumerate() {
             raw_content.push_str(&format!(
                 "Entry {}:\n  Source: {}\n  Content: {}\n  Relevance: {}\n  Timestamp: {}\n\n",
                 i + 1,
@@ -270,65 +899,111 @@ Be selective and precise in what you include.",
         }
 
         // Add any metadata as additional context
-        if !req_data.raw_data.metadata.is_empty() {
+        if !raw_data.metadata.is_empty() {
             raw_content.push_str("Additional Metadata:\n");
-            for (key, value) in &req_data.raw_data.metadata {
+            for (key, value) in &raw_data.metadata {
                 raw_content.push_str(&format!("  {}: {}\n", key, value));
             }
         }
 
-        // Call LLM Client with the prepared prompt and system prompt
-        match self
-            .client
-            .generate_text_string(&raw_content, system_prompt)
+        // Render the context compiler prompt with the schema specification
+        let mut variables = HashMap::new();
+        variables.insert("schema_id".to_string(), schema_spec.schema_id.clone());
+        variables.insert(
+            "field_definitions".to_string(),
+            schema_spec.field_definitions.join(", "),
+        );
+        variables.insert(
+            "schema_description".to_string(),
+            schema_spec.schema_description.clone(),
+        );
+        variables.insert("raw_context".to_string(), raw_content);
+        let rendered = self
+            .prompts
+            .render(
+                "compile_context",
+                None,
+                Some(&req_data.request_id),
+                &variables,
+            )
+            .map_err(prompt_error_status)?;
+
+        // Generate against a schema built from the field definitions; non-conforming
+        // output is re-asked with the validation errors and never cached
+        let schema = ResponseSchema::from_field_definitions(&schema_spec.field_definitions)
+            .map_err(Status::invalid_argument)?;
+        let mut options = GenerationOptions {
+            response_schema: Some(Arc::new(schema)),
+            request_type: Some("compile_context".to_string()),
+            ..Default::default()
+        };
+        self.client.route(
+            &rendered.prompt,
+            rendered.system_prompt.as_deref(),
+            &mut options,
+        );
+        let mut cache_meta = HashMap::new();
+        let result = self
+            .generate_structured(
+                &rendered.prompt,
+                rendered.system_prompt.as_deref(),
+                &options,
+                CacheMode::Use,
+                &mut cache_meta,
+            )
             .await
-        {
-            Ok(json_text) => {
-                // Try to validate the response is proper JSON
-                match serde_json::from_str::<serde_json::Value>(&json_text) {
-                    Ok(_) => {
-                        // Successfully parsed as JSON
-                        let tokens_used = json_text.split_whitespace().count() as i32;
-
-                        let reply = CompiledContextResponse {
-                            request_id: req_data.request_id,
-                            compiled_json: json_text,
-                            tokens_used,
-                            metadata: {
-                                let mut meta = std::collections::HashMap::new();
-                                meta.insert("status".to_string(), "success".to_string());
-                                meta.insert(
-                                    "schema_id".to_string(),
-                                    req_data.schema.schema_id.clone(),
-                                );
-                                meta
-                            },
-                        };
-                        Ok(Response::new(reply))
-                    }
-                    Err(e) => {
-                        // The response wasn't valid JSON, return an error
-                        log::error!("Generated text is not valid JSON: {}", e);
-
-                        // Return a failure response
-                        let reply = CompiledContextResponse {
-                            request_id: req_data.request_id,
-                            compiled_json: "{}".to_string(), // Empty JSON object
-                            tokens_used: 0,
-                            metadata: {
-                                let mut meta = std::collections::HashMap::new();
-                                meta.insert("status".to_string(), "format_error".to_string());
-                                meta.insert(
-                                    "error".to_string(),
-                                    format!("Invalid JSON format: {}", e),
-                                );
-                                meta
-                            },
-                        };
-                        Ok(Response::new(reply))
-                    }
+            .map_err(|e| e.to_string());
+
+        match result {
+            Ok((completion, report)) => match &report.outcome {
+                Ok(value) => {
+                    let json_text = value.to_string();
+                    let tokens_used = completion.usage.completion_tokens as i32;
+
+                    let reply = CompiledContextResponse {
+                        request_id: req_data.request_id,
+                        compiled_json: json_text,
+                        tokens_used,
+                        metadata: {
+                            let mut meta = std::collections::HashMap::new();
+                            meta.insert("status".to_string(), "success".to_string());
+                            meta.insert("schema_id".to_string(), schema_spec.schema_id.clone());
+                            meta.insert("provider".to_string(), completion.provider.clone());
+                            meta.insert("model".to_string(), completion.model.clone());
+                            rendered.write_metadata(&mut meta);
+                            report.write_metadata(&mut meta);
+                            meta.extend(cache_meta);
+                            meta
+                        },
+                    };
+                    Ok(Response::new(reply))
                 }
-            }
+                Err(reason) => {
+                    // The response never matched the schema, return an error
+                    log::error!(
+                        "Generated text does not match the context schema: {}",
+                        reason
+                    );
+
+                    // Return a failure response
+                    let reply = CompiledContextResponse {
+                        request_id: req_data.request_id,
+                        compiled_json: "{}".to_string(), // Empty JSON object
+                        tokens_used: 0,
+                        metadata: {
+                            let mut meta = std::collections::HashMap::new();
+                            meta.insert("status".to_string(), "format_error".to_string());
+                            meta.insert(
+                                "error".to_string(),
+                                format!("Invalid JSON format: {}", reason),
+                            );
+                            report.write_metadata(&mut meta);
+                            meta
+                        },
+                    };
+                    Ok(Response::new(reply))
+                }
+            },
             Err(e) => {
                 log::error!("Context compilation failed: {}", e);
 
@@ -0,0 +1,577 @@
+// llm-service-rs/src/processing.rs
+//
+// Text processing operations behind the Process RPC
+//
+// This module provides:
+// - summarize: condense text to roughly `target_length` words
+// - translate: translate text into `target_language`
+// - classify:  pick one of the caller-supplied `labels`
+// - extract:   pull the caller-supplied `fields` out of text as a JSON object
+// - sentiment: positive / negative / neutral / mixed with a score
+//
+// Each operation has its own prompt template and validates the model output.
+// When validation fails the model is asked again with the validation error
+// appended, up to MAX_ATTEMPTS times.
+//
+// Operation arguments are read from `LLMProcessRequest.parameters`. List
+// arguments (`labels`, `fields`) accept a JSON array or a comma-separated list.
+
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+use crate::llm_client::{GenerationOptions, LLMClient, LLMError};
+use crate::tokenizer::TruncationStrategy;
+
+// Total generations per request, including re-asks after invalid output
+const MAX_ATTEMPTS: u32 = 2;
+
+// Summaries may overshoot the requested length by this factor before being rejected
+const SUMMARY_LENGTH_TOLERANCE: f64 = 1.5;
+
+const DEFAULT_SUMMARY_WORDS: usize = 100;
+
+const SENTIMENT_LABELS: [&str; 4] = ["positive", "negative", "neutral", "mixed"];
+
+const SUMMARIZE_SYSTEM_PROMPT: &str =
+    "You are a precise summarizer. Preserve key facts, names and numbers. \
+Do not add information that is not in the source text. Reply with the summary only.";
+
+const TRANSLATE_SYSTEM_PROMPT: &str =
+    "You are a professional translator. Preserve meaning, tone and formatting. \
+Reply with the translation only, without notes or quotation marks.";
+
+const CLASSIFY_SYSTEM_PROMPT: &str =
+    "You are a text classifier. Choose exactly one label from the allowed set. \
+Reply with a single JSON object and nothing else.";
+
+const EXTRACT_SYSTEM_PROMPT: &str =
+    "You are an information extraction engine. Extract only what the text states. \
+Reply with a single JSON object and nothing else.";
+
+const SENTIMENT_SYSTEM_PROMPT: &str =
+    "You are a sentiment analyzer. Judge the overall sentiment expressed by the author. \
+Reply with a single JSON object and nothing else.";
+
+/// A validated Process operation with its arguments
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    Summarize {
+        target_words: usize,
+    },
+    Translate {
+        target_language: String,
+        source_language: Option<String>,
+    },
+    Classify {
+        labels: Vec<String>,
+    },
+    Extract {
+        fields: Vec<String>,
+    },
+    Sentiment,
+}
+
+/// Structured result of a successful operation
+#[derive(Debug, Clone)]
+pub struct ProcessOutput {
+    pub result: String,
+    pub metadata: HashMap<String, String>,
+}
+
+/// Errors surfaced by `run`
+#[derive(Debug)]
+pub enum ProcessError {
+    /// The model call itself failed
+    Llm(LLMError),
+    /// The model kept returning output that failed validation
+    InvalidOutput { attempts: u32, reason: String },
+}
+
+impl std::fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessError::Llm(e) => write!(f, "{}", e),
+            ProcessError::InvalidOutput { attempts, reason } => write!(
+                f,
+                "Model output failed validation after {} attempt(s): {}",
+                attempts, reason
+            ),
+        }
+    }
+}
+
+impl Operation {
+    /// Parse the operation name and its arguments from a Process request
+    pub fn parse(name: &str, parameters: &HashMap<String, String>) -> Result<Self, String> {
+        let param = |key: &str| {
+            parameters
+                .get(key)
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+        };
+
+        match name.trim().to_ascii_lowercase().as_str() {
+            "summarize" | "summarise" | "summary" => {
+                let target_words = match param("target_length") {
+                    Some(value) => value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|n| *n > 0)
+                        .ok_or_else(|| {
+                            format!("target_length must be a positive integer, got '{}'", value)
+                        })?,
+                    None => DEFAULT_SUMMARY_WORDS,
+                };
+                Ok(Operation::Summarize { target_words })
+            }
+            "translate" => {
+                let target_language = param("target_language")
+                    .ok_or("translate requires the 'target_language' parameter")?
+                    .to_string();
+                Ok(Operation::Translate {
+                    target_language,
+                    source_language: param("source_language").map(|v| v.to_string()),
+                })
+            }
+            "classify" => {
+                let labels = parse_list(param("labels").unwrap_or_default())?;
+                if labels.len() < 2 {
+                    return Err("classify requires at least two 'labels'".to_string());
+                }
+                Ok(Operation::Classify { labels })
+            }
+            "extract" => {
+                let fields = parse_list(param("fields").unwrap_or_default())?;
+                if fields.is_empty() {
+                    return Err("extract requires a non-empty 'fields' list".to_string());
+                }
+                Ok(Operation::Extract { fields })
+            }
+            "sentiment" => Ok(Operation::Sentiment),
+            other => Err(format!(
+                "Unsupported operation '{}'; expected one of summarize, translate, classify, extract, sentiment",
+                other
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Summarize { .. } => "summarize",
+            Operation::Translate { .. } => "translate",
+            Operation::Classify { .. } => "classify",
+            Operation::Extract { .. } => "extract",
+            Operation::Sentiment => "sentiment",
+        }
+    }
+
+    fn system_prompt(&self) -> &'static str {
+        match self {
+            Operation::Summarize { .. } => SUMMARIZE_SYSTEM_PROMPT,
+            Operation::Translate { .. } => TRANSLATE_SYSTEM_PROMPT,
+            Operation::Classify { .. } => CLASSIFY_SYSTEM_PROMPT,
+            Operation::Extract { .. } => EXTRACT_SYSTEM_PROMPT,
+            Operation::Sentiment => SENTIMENT_SYSTEM_PROMPT,
+        }
+    }
+
+    /// Render the operation's user prompt around the input text
+    fn render_prompt(&self, text: &str) -> String {
+        match self {
+            Operation::Summarize { target_words } => format!(
+                "Summarize the following text in at most {} words.\n\nTEXT:\n{}",
+                target_words, text
+            ),
+            Operation::Translate {
+                target_language,
+                source_language,
+            } => {
+                let from = source_language
+                    .as_deref()
+                    .map(|lang| format!(" from {}", lang))
+                    .unwrap_or_default();
+                format!(
+                    "Translate the following text{} into {}.\n\nTEXT:\n{}",
+                    from, target_language, text
+                )
+            }
+            Operation::Classify { labels } => format!(
+                "Classify the following text into exactly one of these labels: {}.\n\
+                 Respond as {{\"label\": \"<one of the labels>\", \"confidence\": <number between 0 and 1>}}.\n\nTEXT:\n{}",
+                serde_json::to_string(labels).unwrap_or_default(),
+                text
+            ),
+            Operation::Extract { fields } => format!(
+                "Extract the following fields from the text: {}.\n\
+                 Respond with a JSON object containing exactly these keys. \
+                 Use null for any field the text does not mention.\n\nTEXT:\n{}",
+                serde_json::to_string(fields).unwrap_or_default(),
+                text
+            ),
+            Operation::Sentiment => format!(
+                "Analyze the sentiment of the following text.\n\
+                 Respond as {{\"sentiment\": \"positive|negative|neutral|mixed\", \
+                 \"score\": <number from -1 (most negative) to 1 (most positive)>, \
+                 \"confidence\": <number between 0 and 1>}}.\n\nTEXT:\n{}",
+                text
+            ),
+        }
+    }
+
+    /// Check the raw model output and turn it into a structured result
+    fn validate(&self, output: &str) -> Result<ProcessOutput, String> {
+        let mut metadata = HashMap::new();
+
+        match self {
+            Operation::Summarize { target_words } => {
+                let summary = output.trim();
+                if summary.is_empty() {
+                    return Err("the summary is empty".to_string());
+                }
+                let words = summary.split_whitespace().count();
+                let limit = (*target_words as f64 * SUMMARY_LENGTH_TOLERANCE).ceil() as usize;
+                if words > limit {
+                    return Err(format!(
+                        "the summary has {} words but must have at most {}",
+                        words, target_words
+                    ));
+                }
+                metadata.insert("target_length".to_string(), target_words.to_string());
+                metadata.insert("word_count".to_string(), words.to_string());
+                Ok(ProcessOutput {
+                    result: summary.to_string(),
+                    metadata,
+                })
+            }
+            Operation::Translate {
+                target_language,
+                source_language,
+            } => {
+                let translation = strip_quotes(output.trim());
+                if translation.is_empty() {
+                    return Err("the translation is empty".to_string());
+                }
+                metadata.insert("target_language".to_string(), target_language.clone());
+                if let Some(source) = source_language {
+                    metadata.insert("source_language".to_string(), source.clone());
+                }
+                Ok(ProcessOutput {
+                    result: translation.to_string(),
+                    metadata,
+                })
+            }
+            Operation::Classify { labels } => {
+                let object = parse_json_object(output)?;
+                let raw_label = object
+                    .get("label")
+                    .and_then(Value::as_str)
+                    .ok_or("the response has no string 'label' field")?;
+                let label = labels
+                    .iter()
+                    .find(|l| l.eq_ignore_ascii_case(raw_label.trim()))
+                    .ok_or_else(|| format!("'{}' is not one of the allowed labels", raw_label))?;
+                let confidence = unit_number(&object, "confidence", 0.0, 1.0)?;
+
+                metadata.insert("label".to_string(), label.clone());
+                if let Some(confidence) = confidence {
+                    metadata.insert("confidence".to_string(), format!("{:.3}", confidence));
+                }
+                metadata.insert("labels".to_string(), labels.join(","));
+                Ok(ProcessOutput {
+                    result: label.clone(),
+                    metadata,
+                })
+            }
+            Operation::Extract { fields } => {
+                let object = parse_json_object(output)?;
+                let missing_keys: Vec<&str> = fields
+                    .iter()
+                    .filter(|f| !object.contains_key(f.as_str()))
+                    .map(|f| f.as_str())
+                    .collect();
+                if !missing_keys.is_empty() {
+                    return Err(format!(
+                        "the object is missing the keys {}",
+                        missing_keys.join(", ")
+                    ));
+                }
+
+                // Keep only the requested fields; unknown keys are dropped
+                let mut extracted = Map::new();
+                let mut not_found = Vec::new();
+                for field in fields {
+                    let value = object.get(field).cloned().unwrap_or(Value::Null);
+                    if value.is_null() {
+                        not_found.push(field.as_str());
+                    }
+                    extracted.insert(field.clone(), value);
+                }
+
+                metadata.insert("fields".to_string(), fields.join(","));
+                metadata.insert(
+                    "fields_found".to_string(),
+                    (fields.len() - not_found.len()).to_string(),
+                );
+                if !not_found.is_empty() {
+                    metadata.insert("fields_missing".to_string(), not_found.join(","));
+                }
+                Ok(ProcessOutput {
+                    result: Value::Object(extracted).to_string(),
+                    metadata,
+                })
+            }
+            Operation::Sentiment => {
+                let object = parse_json_object(output)?;
+                let sentiment = object
+                    .get("sentiment")
+                    .and_then(Value::as_str)
+                    .map(|s| s.trim().to_ascii_lowercase())
+                    .filter(|s| SENTIMENT_LABELS.contains(&s.as_str()))
+                    .ok_or_else(|| {
+                        format!("'sentiment' must be one of {}", SENTIMENT_LABELS.join(", "))
+                    })?;
+                let score = unit_number(&object, "score", -1.0, 1.0)?
+                    .ok_or("the response has no numeric 'score' field")?;
+                let confidence = unit_number(&object, "confidence", 0.0, 1.0)?;
+
+                metadata.insert("sentiment".to_string(), sentiment.clone());
+                metadata.insert("score".to_string(), format!("{:.3}", score));
+                if let Some(confidence) = confidence {
+                    metadata.insert("confidence".to_string(), format!("{:.3}", confidence));
+                }
+                Ok(ProcessOutput {
+                    result: sentiment,
+                    metadata,
+                })
+            }
+        }
+    }
+}
+
+/// Run an operation against the model, re-asking when the output fails validation
+///
+/// Temperature defaults to 0 so results are as repeatable as the provider allows;
+/// callers may still override it through the request parameters.
+pub async fn run(
+    client: &LLMClient,
+    operation: &Operation,
+    text: &str,
+    options: &GenerationOptions,
+) -> Result<ProcessOutput, ProcessError> {
+    let mut options = options.clone();
+    options.temperature.get_or_insert(0.0);
+
+    // Operation instructions lead the prompt, so by default long inputs lose
+    // their middle rather than the instructions
+    options
+        .truncation
+        .get_or_insert(TruncationStrategy::MiddleOut);
+    options
+        .request_type
+        .get_or_insert_with(|| "process".to_string());
+    let rendered = operation.render_prompt(text);
+    client.route(&rendered, Some(operation.system_prompt()), &mut options);
+    let (base_prompt, context) = client
+        .fit_to_context(&rendered, Some(operation.system_prompt()), &options)
+        .map_err(ProcessError::Llm)?;
+    let mut prompt = base_prompt.clone();
+    let mut last_reason = String::new();
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let (completion, route) = client
+            .generate(&prompt, Some(operation.system_prompt()), &options)
+            .await
+            .map_err(ProcessError::Llm)?;
+
+        match operation.validate(&completion.text) {
+            Ok(mut output) => {
+                output
+                    .metadata
+                    .insert("operation".to_string(), operation.name().to_string());
+                output
+                    .metadata
+                    .insert("attempts".to_string(), attempt.to_string());
+                output
+                    .metadata
+                    .insert("provider".to_string(), completion.provider);
+                output
+                    .metadata
+                    .insert("model".to_string(), completion.model);
+                completion.usage.write_metadata(&mut output.metadata);
+                context.write_metadata(&mut output.metadata);
+                route.write_metadata(&mut output.metadata);
+                return Ok(output);
+            }
+            Err(reason) => {
+                log::warn!(
+                    "{} output failed validation (attempt {}/{}): {}",
+                    operation.name(),
+                    attempt,
+                    MAX_ATTEMPTS,
+                    reason
+                );
+                prompt = format!(
+                    "{}\n\nYour previous response was rejected because {}. \
+                     Follow the response format exactly.\n\nPREVIOUS RESPONSE:\n{}",
+                    base_prompt, reason, completion.text
+                );
+                last_reason = reason;
+            }
+        }
+    }
+
+    Err(ProcessError::InvalidOutput {
+        attempts: MAX_ATTEMPTS,
+        reason: last_reason,
+    })
+}
+
+/// Parse a JSON array of strings or a comma-separated list
+fn parse_list(value: &str) -> Result<Vec<String>, String> {
+    let items: Vec<String> = if value.trim_start().starts_with('[') {
+        serde_json::from_str::<Vec<String>>(value)
+            .map_err(|e| format!("Invalid JSON list '{}': {}", value, e))?
+    } else {
+        value.split(',').map(|s| s.to_string()).collect()
+    };
+
+    let mut unique: Vec<String> = Vec::new();
+    for item in items.iter().map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        if !unique.iter().any(|u| u == item) {
+            unique.push(item.to_string());
+        }
+    }
+    Ok(unique)
+}
+
+/// Find the JSON object in a model response, tolerating code fences and surrounding prose
+fn parse_json_object(output: &str) -> Result<Map<String, Value>, String> {
+    let start = output.find('{');
+    let end = output.rfind('}');
+    let candidate = match (start, end) {
+        (Some(start), Some(end)) if start < end => &output[start..=end],
+        _ => return Err("the response does not contain a JSON object".to_string()),
+    };
+
+    match serde_json::from_str::<Value>(candidate) {
+        Ok(Value::Object(object)) => Ok(object),
+        Ok(_) => Err("the response is not a JSON object".to_string()),
+        Err(e) => Err(format!("the response is not valid JSON ({})", e)),
+    }
+}
+
+/// Read an optional number field and check it lies within [min, max]
+fn unit_number(
+    object: &Map<String, Value>,
+    key: &str,
+    min: f64,
+    max: f64,
+) -> Result<Option<f64>, String> {
+    match object.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => {
+            let number = value
+                .as_f64()
+                .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+                .ok_or_else(|| format!("'{}' must be a number", key))?;
+            if number < min || number > max {
+                return Err(format!("'{}' must be between {} and {}", key, min, max));
+            }
+            Ok(Some(number))
+        }
+    }
+}
+
+fn strip_quotes(text: &str) -> &str {
+    for (open, close) in [('"', '"'), ('“', '”'), ('\'', '\'')] {
+        if let Some(inner) = text
+            .strip_prefix(open)
+            .and_then(|rest| rest.strip_suffix(close))
+        {
+            return inner.trim();
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_requires_operation_arguments() {
+        assert!(Operation::parse("translate", &HashMap::new()).is_err());
+        assert!(Operation::parse("classify", &params(&[("labels", "spam")])).is_err());
+        assert!(Operation::parse("tokenize", &HashMap::new()).is_err());
+
+        let op = Operation::parse(
+            "classify",
+            &params(&[("labels", r#"["spam", "ham", "spam"]"#)]),
+        )
+        .unwrap();
+        assert_eq!(
+            op,
+            Operation::Classify {
+                labels: vec!["spam".to_string(), "ham".to_string()]
+            }
+        );
+        assert_eq!(
+            Operation::parse("summarize", &HashMap::new()).unwrap(),
+            Operation::Summarize {
+                target_words: DEFAULT_SUMMARY_WORDS
+            }
+        );
+    }
+
+    #[test]
+    fn test_classify_and_sentiment_validation() {
+        let classify = Operation::Classify {
+            labels: vec!["Billing".to_string(), "Support".to_string()],
+        };
+        let output = classify
+            .validate("```json\n{\"label\": \"billing\", \"confidence\": 0.9}\n```")
+            .unwrap();
+        assert_eq!(output.result, "Billing");
+        assert_eq!(output.metadata["confidence"], "0.900");
+        assert!(classify.validate(r#"{"label": "Sales"}"#).is_err());
+
+        let sentiment = Operation::Sentiment;
+        let output = sentiment
+            .validate(r#"{"sentiment": "Negative", "score": -0.7}"#)
+            .unwrap();
+        assert_eq!(output.result, "negative");
+        assert!(sentiment
+            .validate(r#"{"sentiment": "negative", "score": -3}"#)
+            .is_err());
+    }
+
+    #[test]
+    fn test_extract_keeps_requested_fields_only() {
+        let extract = Operation::Extract {
+            fields: vec!["name".to_string(), "email".to_string()],
+        };
+        let output = extract
+            .validate(r#"{"name": "Ada", "email": null, "phone": "555"}"#)
+            .unwrap();
+        let value: Value = serde_json::from_str(&output.result).unwrap();
+        assert_eq!(value, serde_json::json!({"name": "Ada", "email": null}));
+        assert_eq!(output.metadata["fields_missing"], "email");
+        assert!(extract.validate(r#"{"name": "Ada"}"#).is_err());
+    }
+
+    #[test]
+    fn test_summary_length_is_enforced() {
+        let summarize = Operation::Summarize { target_words: 4 };
+        assert!(summarize.validate("one two three four five").is_ok());
+        assert!(summarize
+            .validate("one two three four five six seven")
+            .is_err());
+    }
+}
@@ -0,0 +1,596 @@
+// llm-service-rs/src/prompt_manager.rs
+//
+// Versioned prompt template registry
+//
+// This module provides:
+// - Loading prompt templates from TOML files (one template version per file)
+// - `{{variable}}` substitution with required variables and defaults
+// - Version selection: exact pin, weighted A/B split, or latest version
+// - Hot reload by polling the template directory for changes
+//
+// Templates bundled in `llm-service-rs/prompts/` are compiled into the binary so
+// the service always has a working set. Files in LLM_PROMPT_DIR are loaded on top
+// of them; a file with the same name and version replaces the bundled one.
+//
+// Template file format:
+//
+//   name = "compile_context"
+//   version = "1.1.0"
+//   description = "..."
+//   weight = 50                    # A/B share among versions with weight > 0
+//   system = "..."                 # Optional system prompt
+//   template = "..."               # User prompt
+//
+//   [metadata]                     # Free-form, returned with every render
+//   [parameters]                   # Default generation parameters (model, temperature, ...)
+//   [[variables]]                  # name, description, required, default
+//
+// Configuration (.env file):
+// - LLM_PROMPT_DIR: Directory of *.toml templates (default: "prompts")
+// - LLM_PROMPT_RELOAD_SECS: Poll interval for hot reload, 0 disables (default: 10)
+
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+
+// Templates shipped with the service
+const BUNDLED_TEMPLATES: &[(&str, &str)] = &[
+    (
+        "compile_context.toml",
+        include_str!("../prompts/compile_context.toml"),
+    ),
+    (
+        "orchestrator_planning.toml",
+        include_str!("../prompts/orchestrator_planning.toml"),
+    ),
+    (
+        "role_blue_team.toml",
+        include_str!("../prompts/role_blue_team.toml"),
+    ),
+    (
+        "role_master.toml",
+        include_str!("../prompts/role_master.toml"),
+    ),
+    (
+        "role_red_team.toml",
+        include_str!("../prompts/role_red_team.toml"),
+    ),
+];
+
+/// A declared template variable
+#[derive(Debug, Clone, Deserialize)]
+pub struct VariableSpec {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+/// One version of a prompt template
+#[derive(Debug, Clone, Deserialize)]
+pub struct PromptTemplate {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub weight: u32,
+    #[serde(default)]
+    pub system: Option<String>,
+    pub template: String,
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+    #[serde(default)]
+    pub parameters: HashMap<String, String>,
+    #[serde(default)]
+    pub variables: Vec<VariableSpec>,
+}
+
+/// How the rendered version was chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    Pinned,
+    AbTest,
+    Latest,
+}
+
+impl Selection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Selection::Pinned => "pinned",
+            Selection::AbTest => "ab_test",
+            Selection::Latest => "latest",
+        }
+    }
+}
+
+/// A rendered template ready to send to a provider
+#[derive(Debug, Clone)]
+pub struct RenderedPrompt {
+    pub name: String,
+    pub version: String,
+    pub selection: Selection,
+    pub system_prompt: Option<String>,
+    pub prompt: String,
+    /// Template default generation parameters
+    pub parameters: HashMap<String, String>,
+    /// Template metadata from the file
+    pub metadata: HashMap<String, String>,
+}
+
+impl RenderedPrompt {
+    /// Record which template version produced a response
+    pub fn write_metadata(&self, meta: &mut HashMap<String, String>) {
+        meta.insert("prompt_template".to_string(), self.name.clone());
+        meta.insert("prompt_version".to_string(), self.version.clone());
+        meta.insert(
+            "prompt_selection".to_string(),
+            self.selection.as_str().to_string(),
+        );
+    }
+}
+
+#[derive(Debug)]
+pub enum PromptError {
+    TemplateNotFound(String),
+    VersionNotFound { name: String, version: String },
+    MissingVariable { name: String, variable: String },
+    InvalidTemplate(String),
+}
+
+impl fmt::Display for PromptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PromptError::TemplateNotFound(name) => write!(f, "Unknown prompt template '{}'", name),
+            PromptError::VersionNotFound { name, version } => {
+                write!(f, "Prompt template '{}' has no version '{}'", name, version)
+            }
+            PromptError::MissingVariable { name, variable } => write!(
+                f,
+                "Prompt template '{}' requires variable '{}'",
+                name, variable
+            ),
+            PromptError::InvalidTemplate(msg) => write!(f, "Invalid prompt template: {}", msg),
+        }
+    }
+}
+
+// All versions of every template, each list sorted oldest to newest
+type Library = HashMap<String, Vec<Arc<PromptTemplate>>>;
+
+// (path, modified, len) of every template file, used to detect changes
+type Fingerprint = Vec<(PathBuf, Option<SystemTime>, u64)>;
+
+#[derive(Debug)]
+pub struct PromptManager {
+    dir: Option<PathBuf>,
+    library: RwLock<Library>,
+    fingerprint: Mutex<Fingerprint>,
+}
+
+impl PromptManager {
+    /// Create the manager from LLM_PROMPT_DIR, falling back to bundled templates only
+    pub fn from_env() -> Self {
+        let dir = PathBuf::from(env::var("LLM_PROMPT_DIR").unwrap_or_else(|_| "prompts".to_string()));
+        let manager = Self::new(Some(dir));
+        if let Err(e) = manager.reload() {
+            log::error!("Failed to load prompt templates: {}; using bundled templates", e);
+        }
+        manager
+    }
+
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        let mut library = Library::new();
+        for (file, source) in BUNDLED_TEMPLATES {
+            // Bundled templates are covered by tests, so a failure here is a build defect
+            let template = parse_template(file, source)
+                .unwrap_or_else(|e| panic!("bundled prompt template {} is invalid: {}", file, e));
+            insert_template(&mut library, template);
+        }
+
+        Self {
+            dir,
+            library: RwLock::new(library),
+            fingerprint: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Reload bundled templates plus the template directory
+    ///
+    /// The new set is swapped in only if every file parses, so a bad edit never
+    /// takes down templates that are already serving.
+    pub fn reload(&self) -> Result<usize, PromptError> {
+        let mut library = Self::new(None).library.into_inner().unwrap_or_default();
+
+        let fingerprint = match &self.dir {
+            Some(dir) if dir.is_dir() => {
+                let fingerprint = fingerprint(dir);
+                for (path, _, _) in &fingerprint {
+                    let source = std::fs::read_to_string(path).map_err(|e| {
+                        PromptError::InvalidTemplate(format!("{}: {}", path.display(), e))
+                    })?;
+                    let template = parse_template(&path.display().to_string(), &source)?;
+                    insert_template(&mut library, template);
+                }
+                fingerprint
+            }
+            Some(dir) => {
+                log::info!(
+                    "Prompt directory {} not found; using bundled templates",
+                    dir.display()
+                );
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+
+        let count = library.values().map(Vec::len).sum();
+        *self.library.write().unwrap() = library;
+        *self.fingerprint.lock().unwrap() = fingerprint;
+        Ok(count)
+    }
+
+    /// Reload if any file in the template directory was added, removed or modified
+    fn reload_if_changed(&self) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        let current = fingerprint(dir);
+        if *self.fingerprint.lock().unwrap() == current {
+            return;
+        }
+
+        match self.reload() {
+            Ok(count) => log::info!("Reloaded {} prompt template versions", count),
+            Err(e) => {
+                log::error!("Prompt template reload failed, keeping previous set: {}", e);
+                // Don't retry the same broken files on every tick
+                *self.fingerprint.lock().unwrap() = current;
+            }
+        }
+    }
+
+    /// Poll the template directory every LLM_PROMPT_RELOAD_SECS seconds
+    pub fn spawn_hot_reload(self: &Arc<Self>) {
+        let interval_secs: u64 = env::var("LLM_PROMPT_RELOAD_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10);
+        if interval_secs == 0 || self.dir.is_none() {
+            return;
+        }
+
+        let manager = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+            loop {
+                ticker.tick().await;
+                manager.reload_if_changed();
+            }
+        });
+    }
+
+    /// Render a template
+    ///
+    /// `version` pins an exact version. Otherwise versions with a non-zero weight
+    /// split traffic between them (sticky per `selection_key` when one is given),
+    /// and without weights the latest version is used.
+    pub fn render(
+        &self,
+        name: &str,
+        version: Option<&str>,
+        selection_key: Option<&str>,
+        variables: &HashMap<String, String>,
+    ) -> Result<RenderedPrompt, PromptError> {
+        let (template, selection) = self.select(name, version, selection_key)?;
+
+        let mut values: HashMap<&str, &str> = HashMap::new();
+        for spec in &template.variables {
+            match variables
+                .get(&spec.name)
+                .map(String::as_str)
+                .or(spec.default.as_deref())
+            {
+                Some(value) => {
+                    values.insert(&spec.name, value);
+                }
+                None if spec.required => {
+                    let variable = if spec.description.is_empty() {
+                        spec.name.clone()
+                    } else {
+                        format!("{}: {}", spec.name, spec.description)
+                    };
+                    return Err(PromptError::MissingVariable {
+                        name: template.name.clone(),
+                        variable,
+                    });
+                }
+                None => {
+                    values.insert(&spec.name, "");
+                }
+            }
+        }
+
+        Ok(RenderedPrompt {
+            name: template.name.clone(),
+            version: template.version.clone(),
+            selection,
+            system_prompt: template
+                .system
+                .as_deref()
+                .map(|system| substitute(system, &values)),
+            prompt: substitute(&template.template, &values),
+            parameters: template.parameters.clone(),
+            metadata: {
+                let mut metadata = template.metadata.clone();
+                if !template.description.is_empty() {
+                    metadata.insert("description".to_string(), template.description.clone());
+                }
+                metadata
+            },
+        })
+    }
+
+    fn select(
+        &self,
+        name: &str,
+        version: Option<&str>,
+        selection_key: Option<&str>,
+    ) -> Result<(Arc<PromptTemplate>, Selection), PromptError> {
+        let library = self.library.read().unwrap();
+        let versions = library
+            .get(name)
+            .ok_or_else(|| PromptError::TemplateNotFound(name.to_string()))?;
+
+        if let Some(version) = version.filter(|v| !v.is_empty()) {
+            return versions
+                .iter()
+                .find(|t| t.version == version)
+                .map(|t| (Arc::clone(t), Selection::Pinned))
+                .ok_or_else(|| PromptError::VersionNotFound {
+                    name: name.to_string(),
+                    version: version.to_string(),
+                });
+        }
+
+        let weighted: Vec<&Arc<PromptTemplate>> =
+            versions.iter().filter(|t| t.weight > 0).collect();
+        if !weighted.is_empty() {
+            let total: u64 = weighted.iter().map(|t| t.weight as u64).sum();
+            let ticket = match selection_key.filter(|k| !k.is_empty()) {
+                Some(key) => {
+                    let mut hasher = DefaultHasher::new();
+                    (name, key).hash(&mut hasher);
+                    hasher.finish() % total
+                }
+                None => rand::random::<u64>() % total,
+            };
+
+            let mut cumulative = 0;
+            for template in &weighted {
+                cumulative += template.weight as u64;
+                if ticket < cumulative {
+                    return Ok((Arc::clone(template), Selection::AbTest));
+                }
+            }
+        }
+
+        // Lists are non-empty and sorted by version
+        Ok((Arc::clone(versions.last().unwrap()), Selection::Latest))
+    }
+}
+
+fn parse_template(origin: &str, source: &str) -> Result<PromptTemplate, PromptError> {
+    let template: PromptTemplate = toml::from_str(source)
+        .map_err(|e| PromptError::InvalidTemplate(format!("{}: {}", origin, e)))?;
+
+    if template.name.trim().is_empty() || template.version.trim().is_empty() {
+        return Err(PromptError::InvalidTemplate(format!(
+            "{}: name and version are required",
+            origin
+        )));
+    }
+
+    // Every placeholder must be declared so typos fail at load time, not per request
+    let declared: Vec<&str> = template.variables.iter().map(|v| v.name.as_str()).collect();
+    let parts = template.system.iter().chain(std::iter::once(&template.template));
+    for text in parts {
+        for placeholder in placeholders(text) {
+            if !declared.contains(&placeholder) {
+                return Err(PromptError::InvalidTemplate(format!(
+                    "{}: placeholder '{{{{{}}}}}' is not declared in [[variables]]",
+                    origin, placeholder
+                )));
+            }
+        }
+    }
+
+    Ok(template)
+}
+
+fn insert_template(library: &mut Library, template: PromptTemplate) {
+    let versions = library.entry(template.name.clone()).or_default();
+    versions.retain(|t| t.version != template.version);
+    versions.push(Arc::new(template));
+    versions.sort_by(|a, b| compare_versions(&a.version, &b.version));
+}
+
+fn fingerprint(dir: &Path) -> Fingerprint {
+    let mut entries: Fingerprint = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+                .map(|path| {
+                    let meta = std::fs::metadata(&path).ok();
+                    let modified = meta.as_ref().and_then(|m| m.modified().ok());
+                    let len = meta.map(|m| m.len()).unwrap_or(0);
+                    (path, modified, len)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
+
+/// Compare dotted versions numerically where possible ("1.10" > "1.9")
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let mut left = a.split(['.', '-']);
+    let mut right = b.split(['.', '-']);
+    loop {
+        match (left.next(), right.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) => {
+                let ordering = match (l.parse::<u64>(), r.parse::<u64>()) {
+                    (Ok(l), Ok(r)) => l.cmp(&r),
+                    _ => l.cmp(r),
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+        }
+    }
+}
+
+/// Names of the `{{name}}` placeholders in a template
+fn placeholders(text: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        names.push(after[..end].trim());
+        rest = &after[end + 2..];
+    }
+    names
+}
+
+fn substitute(text: &str, values: &HashMap<&str, &str>) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        output.push_str(values.get(after[..end].trim()).copied().unwrap_or_default());
+        rest = &after[end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1: &str = r#"
+name = "greeting"
+version = "1.9.0"
+template = "Hello {{ who }}{{punctuation}}"
+
+[[variables]]
+name = "who"
+required = true
+
+[[variables]]
+name = "punctuation"
+default = "!"
+"#;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn manager_with(sources: &[&str]) -> PromptManager {
+        let manager = PromptManager::new(None);
+        {
+            let mut library = manager.library.write().unwrap();
+            for source in sources {
+                insert_template(&mut library, parse_template("test", source).unwrap());
+            }
+        }
+        manager
+    }
+
+    #[test]
+    fn test_bundled_templates_parse() {
+        let manager = PromptManager::new(None);
+        let rendered = manager
+            .render("role_master", None, None, &vars(&[("prompt", "plan")]))
+            .unwrap();
+        assert_eq!(rendered.prompt, "plan");
+        assert!(rendered.system_prompt.unwrap().contains("Master Orchestrator"));
+    }
+
+    #[test]
+    fn test_render_variables_and_latest_version() {
+        let v2 = V1.replace("1.9.0", "1.10.0").replace("Hello", "Hi");
+        let manager = manager_with(&[V1, &v2]);
+
+        let rendered = manager
+            .render("greeting", None, None, &vars(&[("who", "Ada")]))
+            .unwrap();
+        assert_eq!(rendered.version, "1.10.0");
+        assert_eq!(rendered.selection, Selection::Latest);
+        assert_eq!(rendered.prompt, "Hi Ada!");
+
+        let pinned = manager
+            .render("greeting", Some("1.9.0"), None, &vars(&[("who", "Ada")]))
+            .unwrap();
+        assert_eq!(pinned.prompt, "Hello Ada!");
+
+        assert!(matches!(
+            manager.render("greeting", None, None, &HashMap::new()),
+            Err(PromptError::MissingVariable { .. })
+        ));
+    }
+
+    #[test]
+    fn test_ab_selection_is_sticky_per_key() {
+        let a = V1.replace("version = \"1.9.0\"", "version = \"a\"\nweight = 50");
+        let b = V1.replace("version = \"1.9.0\"", "version = \"b\"\nweight = 50");
+        let manager = manager_with(&[&a, &b]);
+
+        let first = manager
+            .render("greeting", None, Some("user-42"), &vars(&[("who", "x")]))
+            .unwrap();
+        assert_eq!(first.selection, Selection::AbTest);
+        for _ in 0..10 {
+            let again = manager
+                .render("greeting", None, Some("user-42"), &vars(&[("who", "x")]))
+                .unwrap();
+            assert_eq!(again.version, first.version);
+        }
+    }
+
+    #[test]
+    fn test_undeclared_placeholder_is_rejected() {
+        let source = V1.replace("{{punctuation}}", "{{punctuaton}}");
+        assert!(matches!(
+            parse_template("test", &source),
+            Err(PromptError::InvalidTemplate(_))
+        ));
+    }
+}
@@ -0,0 +1,551 @@
+// llm-service-rs/src/providers/anthropic.rs
+//
+// Anthropic Messages API backend
+//
+// Differences from the OpenAI wire format handled here:
+// - The system prompt is a top-level `system` field, not a message
+// - `max_tokens` is mandatory
+// - Consecutive messages with the same role must be merged
+// - Authentication uses the `x-api-key` header plus `anthropic-version`
+// - Overload is reported as HTTP 529 / `overloaded_error`
+// - Tool calls and results are `tool_use` / `tool_result` content blocks
+//
+// Configuration (.env file):
+// - LLM_ANTHROPIC_API_URL: Messages endpoint (default: https://api.anthropic.com/v1/messages)
+// - LLM_ANTHROPIC_MODEL: Default model (default: "claude-3-5-sonnet-latest")
+// - LLM_ANTHROPIC_VERSION: API version header (default: "2023-06-01")
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::env;
+
+use super::{
+    classify_http_status, classify_transport_error, sse_data, Completion, CompletionRequest,
+    DeltaSink, LineReader, LlmProvider, ProviderKind, TokenUsage, ToolCall, ToolChoice,
+};
+use crate::llm_client::LLMError;
+
+// The Messages API rejects requests without max_tokens
+const DEFAULT_MAX_TOKENS: u32 = 1024;
+
+#[derive(Debug, Serialize)]
+struct MessagesRequest<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<WireMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<WireTool<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct WireMessage {
+    role: &'static str,
+    content: Vec<WireBlock>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WireBlock {
+    Text {
+        text: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    ToolResult {
+        tool_use_id: String,
+        content: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct WireTool<'a> {
+    name: &'a str,
+    description: &'a str,
+    input_schema: &'a serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesResponse {
+    model: String,
+    content: Vec<ContentBlock>,
+    #[serde(default)]
+    stop_reason: Option<String>,
+    usage: Usage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentBlock {
+    #[serde(rename = "type")]
+    block_type: String,
+    #[serde(default)]
+    text: Option<String>,
+    // tool_use blocks
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    input: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Usage {
+    #[serde(default)]
+    input_tokens: u32,
+    #[serde(default)]
+    output_tokens: u32,
+}
+
+// Server-sent event payloads of a streaming Messages call
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent {
+    MessageStart {
+        message: StreamMessage,
+    },
+    ContentBlockDelta {
+        delta: StreamDelta,
+    },
+    MessageDelta {
+        delta: StreamStop,
+        #[serde(default)]
+        usage: Option<Usage>,
+    },
+    MessageStop,
+    Error {
+        error: ErrorBody,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamMessage {
+    model: String,
+    usage: Usage,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamDelta {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StreamStop {
+    #[serde(default)]
+    stop_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorEnvelope {
+    error: ErrorBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    #[serde(rename = "type")]
+    error_type: String,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Debug)]
+pub struct AnthropicProvider {
+    client: Client,
+    api_url: String,
+    model: String,
+    api_version: String,
+}
+
+impl AnthropicProvider {
+    pub fn new(client: Client, api_url: String, model: String, api_version: String) -> Self {
+        Self {
+            client,
+            api_url,
+            model,
+            api_version,
+        }
+    }
+
+    /// Create the provider from LLM_ANTHROPIC_* environment variables
+    pub fn from_env(client: Client) -> Self {
+        let api_url = env::var("LLM_ANTHROPIC_API_URL")
+            .unwrap_or_else(|_| "https://api.anthropic.com/v1/messages".to_string());
+        let model = env::var("LLM_ANTHROPIC_MODEL")
+            .unwrap_or_else(|_| "claude-3-5-sonnet-latest".to_string());
+        let api_version =
+            env::var("LLM_ANTHROPIC_VERSION").unwrap_or_else(|_| "2023-06-01".to_string());
+        Self::new(client, api_url, model, api_version)
+    }
+
+    /// Convert the neutral request into the Messages API shape
+    ///
+    /// Tool results travel in user turns, so `tool` messages become user content.
+    fn build_messages(request: &CompletionRequest) -> Vec<WireMessage> {
+        let mut messages: Vec<WireMessage> = Vec::new();
+
+        for message in request.conversation() {
+            let role = if message.role == "assistant" {
+                "assistant"
+            } else {
+                "user"
+            };
+
+            let mut blocks = Vec::new();
+            match &message.tool_call_id {
+                Some(call_id) => blocks.push(WireBlock::ToolResult {
+                    tool_use_id: call_id.clone(),
+                    content: message.content.clone(),
+                }),
+                None if !message.content.is_empty() => blocks.push(WireBlock::Text {
+                    text: message.content.clone(),
+                }),
+                None => {}
+            }
+            for call in &message.tool_calls {
+                blocks.push(WireBlock::ToolUse {
+                    id: call.id.clone(),
+                    name: call.name.clone(),
+                    input: serde_json::from_str(&call.arguments)
+                        .unwrap_or_else(|_| serde_json::json!({})),
+                });
+            }
+
+            match messages.last_mut() {
+                // The API requires strictly alternating roles
+                Some(last) if last.role == role => {
+                    let mut blocks = blocks.into_iter();
+                    if let (Some(WireBlock::Text { text }), Some(WireBlock::Text { text: next })) =
+                        (last.content.last_mut(), blocks.as_slice().first())
+                    {
+                        text.push_str("\n\n");
+                        text.push_str(next);
+                        blocks.next();
+                    }
+                    last.content.extend(blocks);
+                }
+                _ => messages.push(WireMessage {
+                    role,
+                    content: blocks,
+                }),
+            }
+        }
+
+        messages
+    }
+
+    /// Map an Anthropic error response onto `LLMError`
+    fn classify_error(status: u16, body: &str) -> LLMError {
+        if let Ok(envelope) = serde_json::from_str::<ErrorEnvelope>(body) {
+            let message = envelope.error.message;
+            match envelope.error.error_type.as_str() {
+                "overloaded_error" | "api_error" => {
+                    return LLMError::ServerError(format!("Server error ({}): {}", status, message));
+                }
+                "rate_limit_error" => {
+                    return LLMError::RateLimitExceeded(format!("Rate limit exceeded: {}", message));
+                }
+                "not_found_error" if message.contains("model") => {
+                    return LLMError::ModelNotAvailable(message);
+                }
+                "authentication_error" => {
+                    return LLMError::InvalidRequest(format!("Unauthorized: {}", message));
+                }
+                "permission_error" => {
+                    return LLMError::InvalidRequest(format!("Forbidden: {}", message));
+                }
+                _ => {}
+            }
+        }
+
+        match status {
+            // Anthropic-specific "overloaded" status
+            529 => LLMError::ServerError(format!("Server error ({}): {}", status, body)),
+            _ => classify_http_status(status, body),
+        }
+    }
+
+    fn build_body<'a>(request: &'a CompletionRequest, stream: bool) -> MessagesRequest<'a> {
+        MessagesRequest {
+            model: &request.model,
+            max_tokens: request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS),
+            system: request.system_prompt(),
+            messages: Self::build_messages(request),
+            // Anthropic accepts temperatures in [0, 1]
+            temperature: request.temperature.map(|t| t.clamp(0.0, 1.0)),
+            stream,
+            tools: request
+                .tools
+                .iter()
+                .map(|tool| WireTool {
+                    name: &tool.name,
+                    description: &tool.description,
+                    input_schema: &tool.parameters,
+                })
+                .collect(),
+            tool_choice: (!request.tools.is_empty()).then(|| match &request.tool_choice {
+                ToolChoice::Auto => serde_json::json!({"type": "auto"}),
+                ToolChoice::None => serde_json::json!({"type": "none"}),
+                ToolChoice::Required => serde_json::json!({"type": "any"}),
+                ToolChoice::Tool(name) => serde_json::json!({"type": "tool", "name": name}),
+            }),
+        }
+    }
+
+    async fn send(
+        &self,
+        api_key: &str,
+        body: &MessagesRequest<'_>,
+    ) -> Result<reqwest::Response, LLMError> {
+        let response = self
+            .client
+            .post(&self.api_url)
+            .header("x-api-key", api_key)
+            .header("anthropic-version", &self.api_version)
+            .header("Content-Type", "application/json")
+            .json(body)
+            .send()
+            .await
+            .map_err(classify_transport_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(Self::classify_error(status.as_u16(), &text));
+        }
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl LlmProvider for AnthropicProvider {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Anthropic
+    }
+
+    fn secret_label(&self) -> &str {
+        "anthropic"
+    }
+
+    fn default_model(&self) -> &str {
+        &self.model
+    }
+
+    async fn complete(
+        &self,
+        api_key: &str,
+        request: &CompletionRequest,
+    ) -> Result<Completion, LLMError> {
+        let body = Self::build_body(request, false);
+        let response = self.send(api_key, &body).await?;
+
+        let data: MessagesResponse = response
+            .json()
+            .await
+            .map_err(|err| LLMError::ParseError(format!("Failed to parse response: {}", err)))?;
+
+        let text: String = data
+            .content
+            .iter()
+            .filter(|block| block.block_type == "text")
+            .filter_map(|block| block.text.as_deref())
+            .collect();
+
+        let tool_calls: Vec<ToolCall> = data
+            .content
+            .into_iter()
+            .filter(|block| block.block_type == "tool_use")
+            .map(|block| ToolCall {
+                id: block.id.unwrap_or_default(),
+                name: block.name.unwrap_or_default(),
+                arguments: block
+                    .input
+                    .unwrap_or_else(|| serde_json::json!({}))
+                    .to_string(),
+            })
+            .collect();
+
+        if text.is_empty() && tool_calls.is_empty() {
+            return Err(LLMError::ParseError(
+                "No text content returned in response".to_string(),
+            ));
+        }
+
+        Ok(Completion {
+            text,
+            provider: self.kind().to_string(),
+            model: data.model,
+            finish_reason: data.stop_reason,
+            usage: TokenUsage::new(data.usage.input_tokens, data.usage.output_tokens),
+            tool_calls,
+        })
+    }
+
+    async fn stream(
+        &self,
+        api_key: &str,
+        request: &CompletionRequest,
+        sink: DeltaSink,
+    ) -> Result<Completion, LLMError> {
+        let body = Self::build_body(request, true);
+        let mut lines = LineReader::new(self.send(api_key, &body).await?);
+
+        let mut completion = Completion {
+            text: String::new(),
+            provider: self.kind().to_string(),
+            model: request.model.clone(),
+            finish_reason: None,
+            usage: TokenUsage::default(),
+            tool_calls: Vec::new(),
+        };
+        let mut input_tokens = 0;
+
+        while let Some(line) = lines.next_line().await? {
+            // Only `data:` lines matter; every payload carries its own `type`
+            let Some(data) = sse_data(&line) else {
+                continue;
+            };
+
+            let event: StreamEvent = serde_json::from_str(data).map_err(|err| {
+                LLMError::ParseError(format!("Failed to parse stream event: {}", err))
+            })?;
+
+            match event {
+                StreamEvent::MessageStart { message } => {
+                    completion.model = message.model;
+                    input_tokens = message.usage.input_tokens;
+                }
+                StreamEvent::ContentBlockDelta { delta } => {
+                    if let Some(text) = delta.text {
+                        completion.text.push_str(&text);
+                        sink.send(text).await?;
+                    }
+                }
+                StreamEvent::MessageDelta { delta, usage } => {
+                    completion.finish_reason = delta.stop_reason;
+                    if let Some(usage) = usage {
+                        completion.usage = TokenUsage::new(input_tokens, usage.output_tokens);
+                    }
+                }
+                StreamEvent::MessageStop => break,
+                StreamEvent::Error { error } => {
+                    // Errors after the 200 response arrive in-band
+                    let body = serde_json::json!({ "error": {
+                        "type": error.error_type,
+                        "message": error.message,
+                    }});
+                    return Err(Self::classify_error(200, &body.to_string()));
+                }
+                StreamEvent::Other => {}
+            }
+        }
+
+        Ok(completion)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::ChatMessage;
+
+    #[test]
+    fn test_build_messages_merges_roles_and_drops_system() {
+        let request = CompletionRequest {
+            model: "claude".to_string(),
+            messages: vec![
+                ChatMessage::system("be brief"),
+                ChatMessage::user("first"),
+                ChatMessage::user("second"),
+                ChatMessage::assistant("answer"),
+            ],
+            temperature: None,
+            max_tokens: None,
+            response_schema: None,
+            tools: Vec::new(),
+            tool_choice: ToolChoice::Auto,
+        };
+
+        let messages = AnthropicProvider::build_messages(&request);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[0].content,
+            vec![WireBlock::Text {
+                text: "first\n\nsecond".to_string()
+            }]
+        );
+        assert_eq!(messages[1].role, "assistant");
+    }
+
+    #[test]
+    fn test_tool_turns_become_content_blocks() {
+        let request = CompletionRequest {
+            model: "claude".to_string(),
+            messages: vec![
+                ChatMessage::user("weather?"),
+                ChatMessage::tool_calls(vec![ToolCall {
+                    id: "toolu_1".to_string(),
+                    name: "get_weather".to_string(),
+                    arguments: r#"{"city":"Oslo"}"#.to_string(),
+                }]),
+                ChatMessage::tool_result("toolu_1", "sunny"),
+            ],
+            temperature: None,
+            max_tokens: None,
+            response_schema: None,
+            tools: Vec::new(),
+            tool_choice: ToolChoice::Auto,
+        };
+
+        let messages = AnthropicProvider::build_messages(&request);
+        assert_eq!(messages.len(), 3);
+        assert!(matches!(
+            &messages[1].content[0],
+            WireBlock::ToolUse { input, .. } if input["city"] == "Oslo"
+        ));
+        assert_eq!(messages[2].role, "user");
+        assert!(matches!(
+            &messages[2].content[0],
+            WireBlock::ToolResult { tool_use_id, .. } if tool_use_id == "toolu_1"
+        ));
+    }
+
+    #[test]
+    fn test_stream_events_parse() {
+        let delta: StreamEvent = serde_json::from_str(
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}"#,
+        )
+        .unwrap();
+        assert!(matches!(delta, StreamEvent::ContentBlockDelta { delta } if delta.text.as_deref() == Some("Hi")));
+
+        let ping: StreamEvent = serde_json::from_str(r#"{"type":"ping"}"#).unwrap();
+        assert!(matches!(ping, StreamEvent::Other));
+    }
+
+    #[test]
+    fn test_overloaded_is_retryable_server_error() {
+        let body = r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        assert!(matches!(
+            AnthropicProvider::classify_error(529, body),
+            LLMError::ServerError(_)
+        ));
+        assert!(matches!(
+            AnthropicProvider::classify_error(529, ""),
+            LLMError::ServerError(_)
+        ));
+    }
+}
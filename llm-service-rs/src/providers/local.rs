@@ -0,0 +1,501 @@
+// llm-service-rs/src/providers/local.rs
+//
+// Local model backend for Ollama and llama.cpp HTTP servers
+//
+// Uses each server's native API rather than its OpenAI-compatible shim so that
+// token counts and stop reasons are reported accurately:
+// - Ollama:    POST {base}/api/chat
+// - llama.cpp: POST {base}/completion (messages are flattened into a single prompt)
+//
+// No API key is required. Tool calling is supported on Ollama only; llama.cpp's
+// /completion endpoint has no tool format.
+//
+// Configuration (.env file):
+// - LLM_LOCAL_API_URL: Server base URL (default: http://localhost:11434)
+// - LLM_LOCAL_MODEL: Default model (default: "llama3")
+// - LLM_LOCAL_FLAVOR: "ollama" or "llamacpp" (default: "ollama")
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::env;
+
+use super::{
+    classify_http_status, classify_transport_error, sse_data, ChatMessage, Completion,
+    CompletionRequest, DeltaSink, LineReader, LlmProvider, ProviderKind, TokenUsage, ToolCall,
+    ToolChoice,
+};
+use crate::llm_client::LLMError;
+
+/// Which local server implementation is listening on the configured URL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalFlavor {
+    Ollama,
+    LlamaCpp,
+}
+
+impl LocalFlavor {
+    fn parse(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "llamacpp" | "llama.cpp" | "llama-cpp" => LocalFlavor::LlamaCpp,
+            _ => LocalFlavor::Ollama,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<OllamaMessage<'a>>,
+    stream: bool,
+    options: OllamaOptions,
+    // JSON Schema for structured outputs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'a serde_json::Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<OllamaTool<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<OllamaToolCall>,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaTool<'a> {
+    #[serde(rename = "type")]
+    tool_type: &'static str,
+    function: OllamaFunctionDef<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaFunctionDef<'a> {
+    name: &'a str,
+    description: &'a str,
+    parameters: &'a serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaToolCall {
+    function: OllamaFunctionCall,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaFunctionCall {
+    name: String,
+    // Ollama sends arguments as an object rather than JSON text
+    #[serde(default)]
+    arguments: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaResponseMessage {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    tool_calls: Vec<OllamaToolCall>,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaChatResponse {
+    model: String,
+    #[serde(default)]
+    done: bool,
+    message: OllamaResponseMessage,
+    #[serde(default)]
+    done_reason: Option<String>,
+    #[serde(default)]
+    prompt_eval_count: u32,
+    #[serde(default)]
+    eval_count: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct LlamaCppRequest<'a> {
+    prompt: String,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n_predict: Option<u32>,
+    // Converted to a grammar by the server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    json_schema: Option<&'a serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LlamaCppResponse {
+    content: String,
+    #[serde(default)]
+    stop: bool,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    tokens_evaluated: u32,
+    #[serde(default)]
+    tokens_predicted: u32,
+    #[serde(default)]
+    stop_type: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct LocalProvider {
+    client: Client,
+    base_url: String,
+    model: String,
+    flavor: LocalFlavor,
+}
+
+impl LocalProvider {
+    pub fn new(client: Client, base_url: String, model: String, flavor: LocalFlavor) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model,
+            flavor,
+        }
+    }
+
+    /// Create the provider from LLM_LOCAL_* environment variables
+    pub fn from_env(client: Client) -> Self {
+        let base_url =
+            env::var("LLM_LOCAL_API_URL").unwrap_or_else(|_| "http://localhost:11434".to_string());
+        let model = env::var("LLM_LOCAL_MODEL").unwrap_or_else(|_| "llama3".to_string());
+        let flavor = LocalFlavor::parse(&env::var("LLM_LOCAL_FLAVOR").unwrap_or_default());
+        Self::new(client, base_url, model, flavor)
+    }
+
+    /// Flatten chat messages into a plain prompt for llama.cpp's /completion endpoint
+    fn render_prompt(messages: &[ChatMessage]) -> String {
+        let mut prompt = String::new();
+        for message in messages {
+            let label = match message.role.as_str() {
+                "system" => "System",
+                "assistant" => "Assistant",
+                "tool" => "Tool",
+                _ => "User",
+            };
+            prompt.push_str(&format!("{}: {}\n\n", label, message.content));
+        }
+        prompt.push_str("Assistant:");
+        prompt
+    }
+
+    /// Map a local server error onto `LLMError`
+    fn classify_error(status: u16, body: &str) -> LLMError {
+        // Both servers report a missing model with a 404 mentioning the model
+        if status == 404 && body.contains("model") {
+            return LLMError::ModelNotAvailable(body.to_string());
+        }
+        classify_http_status(status, body)
+    }
+
+    async fn post<T: Serialize + ?Sized>(
+        &self,
+        path: &str,
+        body: &T,
+    ) -> Result<reqwest::Response, LLMError> {
+        let response = self
+            .client
+            .post(format!("{}{}", self.base_url, path))
+            .header("Content-Type", "application/json")
+            .json(body)
+            .send()
+            .await
+            .map_err(classify_transport_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(Self::classify_error(status.as_u16(), &text));
+        }
+
+        Ok(response)
+    }
+
+    fn ollama_body(request: &CompletionRequest, stream: bool) -> OllamaChatRequest<'_> {
+        let messages = request
+            .messages
+            .iter()
+            .map(|message| OllamaMessage {
+                role: &message.role,
+                content: &message.content,
+                tool_calls: message
+                    .tool_calls
+                    .iter()
+                    .map(|call| OllamaToolCall {
+                        function: OllamaFunctionCall {
+                            name: call.name.clone(),
+                            arguments: serde_json::from_str(&call.arguments)
+                                .unwrap_or_else(|_| serde_json::json!({})),
+                        },
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        // Ollama has no tool_choice; narrow or drop the offered tools instead
+        let tools = request
+            .tools
+            .iter()
+            .filter(|tool| match &request.tool_choice {
+                ToolChoice::None => false,
+                ToolChoice::Tool(name) => &tool.name == name,
+                ToolChoice::Auto | ToolChoice::Required => true,
+            })
+            .map(|tool| OllamaTool {
+                tool_type: "function",
+                function: OllamaFunctionDef {
+                    name: &tool.name,
+                    description: &tool.description,
+                    parameters: &tool.parameters,
+                },
+            })
+            .collect();
+
+        OllamaChatRequest {
+            model: &request.model,
+            messages,
+            stream,
+            options: OllamaOptions {
+                temperature: request.temperature,
+                num_predict: request.max_tokens,
+            },
+            format: request.response_schema.as_ref(),
+            tools,
+        }
+    }
+
+    /// Ollama does not assign call ids, so number the calls in order
+    fn ollama_tool_calls(calls: Vec<OllamaToolCall>) -> Vec<ToolCall> {
+        calls
+            .into_iter()
+            .enumerate()
+            .map(|(index, call)| ToolCall {
+                id: format!("call_{}", index),
+                name: call.function.name,
+                arguments: call.function.arguments.to_string(),
+            })
+            .collect()
+    }
+
+    fn llamacpp_body(request: &CompletionRequest, stream: bool) -> LlamaCppRequest<'_> {
+        LlamaCppRequest {
+            prompt: Self::render_prompt(&request.messages),
+            stream,
+            temperature: request.temperature,
+            n_predict: request.max_tokens,
+            json_schema: request.response_schema.as_ref(),
+        }
+    }
+
+    async fn complete_ollama(&self, request: &CompletionRequest) -> Result<Completion, LLMError> {
+        let body = Self::ollama_body(request, false);
+
+        let data: OllamaChatResponse = self
+            .post("/api/chat", &body)
+            .await?
+            .json()
+            .await
+            .map_err(|err| LLMError::ParseError(format!("Failed to parse response: {}", err)))?;
+
+        Ok(Completion {
+            text: data.message.content,
+            provider: self.kind().to_string(),
+            model: data.model,
+            finish_reason: data.done_reason,
+            usage: TokenUsage::new(data.prompt_eval_count, data.eval_count),
+            tool_calls: Self::ollama_tool_calls(data.message.tool_calls),
+        })
+    }
+
+    async fn complete_llamacpp(&self, request: &CompletionRequest) -> Result<Completion, LLMError> {
+        if !request.tools.is_empty() {
+            return Err(LLMError::InvalidRequest(
+                "llama.cpp /completion does not support tool calling".to_string(),
+            ));
+        }
+        let body = Self::llamacpp_body(request, false);
+
+        let data: LlamaCppResponse = self
+            .post("/completion", &body)
+            .await?
+            .json()
+            .await
+            .map_err(|err| LLMError::ParseError(format!("Failed to parse response: {}", err)))?;
+
+        Ok(Completion {
+            text: data.content.trim().to_string(),
+            provider: self.kind().to_string(),
+            model: data.model.unwrap_or_else(|| request.model.clone()),
+            finish_reason: data.stop_type,
+            usage: TokenUsage::new(data.tokens_evaluated, data.tokens_predicted),
+            tool_calls: Vec::new(),
+        })
+    }
+
+    /// Ollama streams newline-delimited JSON objects, the last one with `done: true`
+    async fn stream_ollama(
+        &self,
+        request: &CompletionRequest,
+        sink: DeltaSink,
+    ) -> Result<Completion, LLMError> {
+        let body = Self::ollama_body(request, true);
+        let mut lines = LineReader::new(self.post("/api/chat", &body).await?);
+        let mut completion = self.empty_completion(request);
+
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let chunk: OllamaChatResponse = serde_json::from_str(&line).map_err(|err| {
+                LLMError::ParseError(format!("Failed to parse stream chunk: {}", err))
+            })?;
+
+            completion.text.push_str(&chunk.message.content);
+            sink.send(chunk.message.content).await?;
+
+            if chunk.done {
+                completion.model = chunk.model;
+                completion.finish_reason = chunk.done_reason;
+                completion.usage = TokenUsage::new(chunk.prompt_eval_count, chunk.eval_count);
+                break;
+            }
+        }
+
+        Ok(completion)
+    }
+
+    /// llama.cpp streams SSE `data:` lines, the last one with `stop: true`
+    async fn stream_llamacpp(
+        &self,
+        request: &CompletionRequest,
+        sink: DeltaSink,
+    ) -> Result<Completion, LLMError> {
+        let body = Self::llamacpp_body(request, true);
+        let mut lines = LineReader::new(self.post("/completion", &body).await?);
+        let mut completion = self.empty_completion(request);
+
+        while let Some(line) = lines.next_line().await? {
+            let Some(data) = sse_data(&line) else {
+                continue;
+            };
+            let chunk: LlamaCppResponse = serde_json::from_str(data).map_err(|err| {
+                LLMError::ParseError(format!("Failed to parse stream chunk: {}", err))
+            })?;
+
+            completion.text.push_str(&chunk.content);
+            sink.send(chunk.content).await?;
+
+            if chunk.stop {
+                if let Some(model) = chunk.model {
+                    completion.model = model;
+                }
+                completion.finish_reason = chunk.stop_type;
+                completion.usage = TokenUsage::new(chunk.tokens_evaluated, chunk.tokens_predicted);
+                break;
+            }
+        }
+
+        Ok(completion)
+    }
+
+    fn empty_completion(&self, request: &CompletionRequest) -> Completion {
+        Completion {
+            text: String::new(),
+            provider: self.kind().to_string(),
+            model: request.model.clone(),
+            finish_reason: None,
+            usage: TokenUsage::default(),
+            tool_calls: Vec::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for LocalProvider {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Local
+    }
+
+    fn secret_label(&self) -> &str {
+        "local"
+    }
+
+    fn default_model(&self) -> &str {
+        &self.model
+    }
+
+    fn requires_api_key(&self) -> bool {
+        false
+    }
+
+    fn supports_response_schema(&self) -> bool {
+        // Ollama `format` and llama.cpp `json_schema` both take a schema
+        true
+    }
+
+    async fn complete(
+        &self,
+        _api_key: &str,
+        request: &CompletionRequest,
+    ) -> Result<Completion, LLMError> {
+        match self.flavor {
+            LocalFlavor::Ollama => self.complete_ollama(request).await,
+            LocalFlavor::LlamaCpp => self.complete_llamacpp(request).await,
+        }
+    }
+
+    async fn stream(
+        &self,
+        _api_key: &str,
+        request: &CompletionRequest,
+        sink: DeltaSink,
+    ) -> Result<Completion, LLMError> {
+        match self.flavor {
+            LocalFlavor::Ollama => self.stream_ollama(request, sink).await,
+            LocalFlavor::LlamaCpp => self.stream_llamacpp(request, sink).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flavor_parsing_defaults_to_ollama() {
+        assert_eq!(LocalFlavor::parse("llama.cpp"), LocalFlavor::LlamaCpp);
+        assert_eq!(LocalFlavor::parse(""), LocalFlavor::Ollama);
+    }
+
+    #[test]
+    fn test_render_prompt_ends_with_assistant_turn() {
+        let prompt = LocalProvider::render_prompt(&[
+            ChatMessage::system("sys"),
+            ChatMessage::user("hello"),
+        ]);
+        assert!(prompt.starts_with("System: sys"));
+        assert!(prompt.ends_with("User: hello\n\nAssistant:"));
+    }
+
+    #[test]
+    fn test_missing_model_is_classified() {
+        let body = r#"{"error":"model 'llama9' not found, try pulling it first"}"#;
+        assert!(matches!(
+            LocalProvider::classify_error(404, body),
+            LLMError::ModelNotAvailable(_)
+        ));
+    }
+}
@@ -0,0 +1,619 @@
+// llm-service-rs/src/providers/mock.rs
+//
+// Scriptable mock backend for offline and CI runs
+//
+// Replies come from a TOML fixture file of pattern → response rules instead of
+// a model server, so the whole plan-execute-reflect loop can run without
+// network access or API keys. Fixtures support:
+// - Matching on prompt substrings, a regex, system prompt substrings and model
+// - Deterministic sequences: each match of a rule takes its next step, then
+//   repeats the last step, cycles, or falls through to later rules
+// - Simulated latency per fixture, rule or step
+// - Error injection with any `LLMError` category (e.g. a rate limit on the first call)
+// - Tool calls, and schema-conforming JSON when a request carries a response schema
+//   and no rule matches
+//
+// See fixtures/mock_llm.toml (the built-in fixtures) for the format.
+//
+// Configuration (.env file):
+// - LLM_MOCK_FIXTURES: Fixture file (default: the built-in fixtures/mock_llm.toml)
+// - LLM_MOCK_MODEL: Model name reported when a request names none (default: "mock")
+
+use async_trait::async_trait;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::env;
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use super::{
+    Completion, CompletionRequest, DeltaSink, LlmProvider, ProviderKind, TokenUsage, ToolCall,
+};
+use crate::llm_client::LLMError;
+
+const BUILTIN_FIXTURES: &str = include_str!("../../fixtures/mock_llm.toml");
+
+const DEFAULT_STREAM_CHUNK_CHARS: usize = 16;
+
+// Rough characters-per-token ratio for the reported usage
+const CHARS_PER_TOKEN: usize = 4;
+
+// Fixture file format
+
+#[derive(Debug, Deserialize)]
+struct FixtureFile {
+    #[serde(default)]
+    default_response: Option<String>,
+    #[serde(default)]
+    latency_ms: u64,
+    #[serde(default)]
+    stream_chunk_chars: Option<usize>,
+    #[serde(default)]
+    rules: Vec<RuleFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RuleFile {
+    name: String,
+    #[serde(default)]
+    contains: Vec<String>,
+    #[serde(default)]
+    pattern: Option<String>,
+    #[serde(default)]
+    system_contains: Vec<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    latency_ms: Option<u64>,
+    #[serde(default)]
+    then: Option<String>,
+    #[serde(default)]
+    response: Option<String>,
+    #[serde(default)]
+    steps: Vec<StepSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum StepSpec {
+    Text(String),
+    Full(MockStep),
+}
+
+/// One scripted reply
+#[derive(Debug, Clone, Default, Deserialize)]
+struct MockStep {
+    #[serde(default)]
+    text: String,
+    /// Error category to fail with instead of replying
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    latency_ms: Option<u64>,
+    #[serde(default)]
+    tool_calls: Vec<MockToolCall>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MockToolCall {
+    name: String,
+    #[serde(default)]
+    arguments: Option<String>,
+}
+
+/// What a rule does once its steps are used up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SequenceEnd {
+    RepeatLast,
+    Cycle,
+    Fallthrough,
+}
+
+#[derive(Debug)]
+struct MockRule {
+    name: String,
+    contains: Vec<String>,
+    pattern: Option<Regex>,
+    system_contains: Vec<String>,
+    model: Option<String>,
+    latency_ms: Option<u64>,
+    then: SequenceEnd,
+    steps: Vec<MockStep>,
+    // Matches so far; selects the next step
+    calls: AtomicUsize,
+}
+
+impl MockRule {
+    fn matches(&self, prompt: &str, system: &str, model: &str) -> bool {
+        self.contains.iter().all(|s| prompt.contains(s.as_str()))
+            && self
+                .system_contains
+                .iter()
+                .all(|s| system.contains(s.as_str()))
+            && self.pattern.as_ref().is_none_or(|re| re.is_match(prompt))
+            && self.model.as_deref().is_none_or(|m| m == model)
+    }
+
+    /// Take the next step of the sequence; None once a fall-through rule is used up
+    fn next_step(&self) -> Option<&MockStep> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
+        match self.then {
+            _ if call < self.steps.len() => self.steps.get(call),
+            SequenceEnd::RepeatLast => self.steps.last(),
+            SequenceEnd::Cycle => self.steps.get(call % self.steps.len()),
+            SequenceEnd::Fallthrough => None,
+        }
+    }
+}
+
+/// Parsed fixtures
+#[derive(Debug)]
+pub struct MockFixtures {
+    default_response: String,
+    latency_ms: u64,
+    stream_chunk_chars: usize,
+    rules: Vec<MockRule>,
+}
+
+impl MockFixtures {
+    /// Parse and validate a TOML fixture file
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let file: FixtureFile = toml::from_str(source).map_err(|e| e.to_string())?;
+
+        let mut rules = Vec::with_capacity(file.rules.len());
+        for rule in file.rules {
+            let then = match rule.then.as_deref().unwrap_or("repeat_last") {
+                "repeat_last" => SequenceEnd::RepeatLast,
+                "cycle" => SequenceEnd::Cycle,
+                "fallthrough" => SequenceEnd::Fallthrough,
+                other => {
+                    return Err(format!(
+                        "Rule '{}': unknown then '{}' (expected repeat_last, cycle or fallthrough)",
+                        rule.name, other
+                    ))
+                }
+            };
+            let pattern = rule
+                .pattern
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .map_err(|e| format!("Rule '{}': invalid pattern: {}", rule.name, e))?;
+
+            let mut steps: Vec<MockStep> = rule
+                .steps
+                .into_iter()
+                .map(|step| match step {
+                    StepSpec::Text(text) => MockStep {
+                        text,
+                        ..Default::default()
+                    },
+                    StepSpec::Full(step) => step,
+                })
+                .collect();
+            if let Some(response) = rule.response {
+                steps.push(MockStep {
+                    text: response,
+                    ..Default::default()
+                });
+            }
+            if steps.is_empty() {
+                return Err(format!("Rule '{}' has no response or steps", rule.name));
+            }
+            if let Some(error) = steps
+                .iter()
+                .filter_map(|s| s.error.as_deref())
+                .find(|e| injected_error(e, String::new()).is_none())
+            {
+                return Err(format!("Rule '{}': unknown error '{}'", rule.name, error));
+            }
+
+            rules.push(MockRule {
+                name: rule.name,
+                contains: rule.contains,
+                pattern,
+                system_contains: rule.system_contains,
+                model: rule.model,
+                latency_ms: rule.latency_ms,
+                then,
+                steps,
+                calls: AtomicUsize::new(0),
+            });
+        }
+
+        Ok(Self {
+            default_response: file
+                .default_response
+                .unwrap_or_else(|| "This is a mock response.".to_string()),
+            latency_ms: file.latency_ms,
+            stream_chunk_chars: file
+                .stream_chunk_chars
+                .filter(|n| *n > 0)
+                .unwrap_or(DEFAULT_STREAM_CHUNK_CHARS),
+            rules,
+        })
+    }
+
+    fn builtin() -> Self {
+        Self::parse(BUILTIN_FIXTURES).expect("built-in mock fixtures are valid")
+    }
+}
+
+/// The `LLMError` an injected error category stands for
+fn injected_error(category: &str, message: String) -> Option<LLMError> {
+    Some(match category {
+        "rate_limited" => LLMError::RateLimitExceeded(message),
+        "server_error" => LLMError::ServerError(message),
+        "network_error" => LLMError::NetworkError(message),
+        "model_unavailable" => LLMError::ModelNotAvailable(message),
+        "invalid_request" => LLMError::InvalidRequest(message),
+        "parse_error" => LLMError::ParseError(message),
+        "unknown" => LLMError::UnknownError(message),
+        _ => return None,
+    })
+}
+
+/// A minimal value that conforms to `schema`
+///
+/// Covers the keywords generated schemas use (type, properties, items, enum,
+/// const, default, anyOf/oneOf, minItems, minimum, minLength).
+fn schema_example(schema: &Value) -> Value {
+    for keyword in ["const", "default"] {
+        if let Some(value) = schema.get(keyword) {
+            return value.clone();
+        }
+    }
+    if let Some(first) = schema.get("enum").and_then(|v| v.as_array()?.first()) {
+        return first.clone();
+    }
+    for keyword in ["anyOf", "oneOf"] {
+        if let Some(first) = schema.get(keyword).and_then(|v| v.as_array()?.first()) {
+            return schema_example(first);
+        }
+    }
+
+    let schema_type = match schema.get("type") {
+        Some(Value::String(t)) => t.as_str(),
+        Some(Value::Array(types)) => types
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|t| *t != "null")
+            .unwrap_or("null"),
+        _ if schema.get("properties").is_some() => "object",
+        _ => "string",
+    };
+
+    match schema_type {
+        "object" => {
+            let mut object = Map::new();
+            if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                for (name, property) in properties {
+                    object.insert(name.clone(), schema_example(property));
+                }
+            }
+            Value::Object(object)
+        }
+        "array" => {
+            let count = schema.get("minItems").and_then(Value::as_u64).unwrap_or(1);
+            let item = schema
+                .get("items")
+                .map(schema_example)
+                .unwrap_or(json!("mock"));
+            Value::Array(vec![item; count as usize])
+        }
+        "integer" => json!(schema.get("minimum").and_then(Value::as_i64).unwrap_or(0)),
+        "number" => json!(schema.get("minimum").and_then(Value::as_f64).unwrap_or(0.0)),
+        "boolean" => json!(false),
+        "null" => Value::Null,
+        _ => {
+            let min_length = schema.get("minLength").and_then(Value::as_u64).unwrap_or(0);
+            let mut text = "mock".to_string();
+            while (text.len() as u64) < min_length {
+                text.push('x');
+            }
+            json!(text)
+        }
+    }
+}
+
+fn approximate_tokens(text: &str) -> u32 {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN) as u32
+}
+
+/// Fixture-driven provider; never touches the network
+#[derive(Debug)]
+pub struct MockProvider {
+    model: String,
+    fixtures: MockFixtures,
+}
+
+impl MockProvider {
+    pub fn new(model: impl Into<String>, fixtures: MockFixtures) -> Self {
+        Self {
+            model: model.into(),
+            fixtures,
+        }
+    }
+
+    /// Load LLM_MOCK_FIXTURES, falling back to the built-in fixtures
+    pub fn from_env() -> Self {
+        let model = env::var("LLM_MOCK_MODEL").unwrap_or_else(|_| "mock".to_string());
+
+        let fixtures = match env::var("LLM_MOCK_FIXTURES") {
+            Ok(path) if !path.trim().is_empty() => match fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|source| MockFixtures::parse(&source))
+            {
+                Ok(fixtures) => {
+                    log::info!(
+                        "Loaded {} mock LLM rules from {}",
+                        fixtures.rules.len(),
+                        path
+                    );
+                    fixtures
+                }
+                Err(e) => {
+                    log::error!(
+                        "Failed to load mock fixtures {}: {}; using built-in fixtures",
+                        path,
+                        e
+                    );
+                    MockFixtures::builtin()
+                }
+            },
+            _ => MockFixtures::builtin(),
+        };
+
+        Self::new(model, fixtures)
+    }
+
+    /// Pick the scripted reply for a request, after its simulated latency
+    async fn reply(&self, request: &CompletionRequest) -> Result<Completion, LLMError> {
+        let prompt = request
+            .conversation()
+            .map(|m| m.content.as_str())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let system = request.system_prompt().unwrap_or_default();
+
+        let matched = self.fixtures.rules.iter().find_map(|rule| {
+            if !rule.matches(&prompt, &system, &request.model) {
+                return None;
+            }
+            rule.next_step().map(|step| (rule, step))
+        });
+
+        let (text, tool_calls, finish_reason, latency_ms) = match matched {
+            Some((rule, step)) => {
+                log::debug!("Mock LLM rule '{}' matched", rule.name);
+                let latency_ms = step
+                    .latency_ms
+                    .or(rule.latency_ms)
+                    .unwrap_or(self.fixtures.latency_ms);
+                if let Some(category) = &step.error {
+                    tokio::time::sleep(Duration::from_millis(latency_ms)).await;
+                    let message = step.message.clone().unwrap_or_else(|| {
+                        format!("simulated {} (rule '{}')", category, rule.name)
+                    });
+                    return Err(injected_error(category, message)
+                        .unwrap_or_else(|| LLMError::UnknownError(category.clone())));
+                }
+                let last_user = request
+                    .conversation()
+                    .filter(|m| m.role == "user")
+                    .last()
+                    .map(|m| m.content.as_str())
+                    .unwrap_or_default();
+                let tool_calls: Vec<ToolCall> = step
+                    .tool_calls
+                    .iter()
+                    .enumerate()
+                    .map(|(index, call)| ToolCall {
+                        id: format!("call_{}", index),
+                        name: call.name.clone(),
+                        arguments: call.arguments.clone().unwrap_or_else(|| "{}".to_string()),
+                    })
+                    .collect();
+                let finish_reason = step.finish_reason.clone().unwrap_or_else(|| {
+                    if tool_calls.is_empty() {
+                        "stop"
+                    } else {
+                        "tool_calls"
+                    }
+                    .to_string()
+                });
+                (
+                    step.text.replace("{{prompt}}", last_user),
+                    tool_calls,
+                    finish_reason,
+                    latency_ms,
+                )
+            }
+            None => {
+                // Unscripted structured requests still get output that validates
+                let text = match &request.response_schema {
+                    Some(schema) => schema_example(schema).to_string(),
+                    None => self.fixtures.default_response.clone(),
+                };
+                (
+                    text,
+                    Vec::new(),
+                    "stop".to_string(),
+                    self.fixtures.latency_ms,
+                )
+            }
+        };
+
+        tokio::time::sleep(Duration::from_millis(latency_ms)).await;
+
+        Ok(Completion {
+            usage: TokenUsage::new(
+                approximate_tokens(&system) + approximate_tokens(&prompt),
+                approximate_tokens(&text),
+            ),
+            text,
+            provider: self.kind().to_string(),
+            model: request.model.clone(),
+            finish_reason: Some(finish_reason),
+            tool_calls,
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MockProvider {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Mock
+    }
+
+    fn secret_label(&self) -> &str {
+        "mock"
+    }
+
+    fn default_model(&self) -> &str {
+        &self.model
+    }
+
+    fn requires_api_key(&self) -> bool {
+        false
+    }
+
+    fn supports_response_schema(&self) -> bool {
+        true
+    }
+
+    async fn complete(
+        &self,
+        _api_key: &str,
+        request: &CompletionRequest,
+    ) -> Result<Completion, LLMError> {
+        self.reply(request).await
+    }
+
+    async fn stream(
+        &self,
+        _api_key: &str,
+        request: &CompletionRequest,
+        sink: DeltaSink,
+    ) -> Result<Completion, LLMError> {
+        let completion = self.reply(request).await?;
+
+        let chars: Vec<char> = completion.text.chars().collect();
+        for chunk in chars.chunks(self.fixtures.stream_chunk_chars) {
+            sink.send(chunk.iter().collect()).await?;
+        }
+        Ok(completion)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{ChatMessage, ToolChoice};
+
+    const FIXTURES: &str = r#"
+        default_response = "fallback"
+
+        [[rules]]
+        name = "flaky-plan"
+        contains = ["Break down this request"]
+        steps = [
+            { error = "rate_limited" },
+            '{"steps": []}',
+        ]
+
+        [[rules]]
+        name = "search"
+        pattern = "(?i)search for \\w+"
+        then = "fallthrough"
+        steps = [{ tool_calls = [{ name = "web_search", arguments = '{"q":"rust"}' }] }]
+
+        [[rules]]
+        name = "echo"
+        system_contains = ["echo"]
+        response = "You said: {{prompt}}"
+    "#;
+
+    fn request(system: &str, prompt: &str) -> CompletionRequest {
+        CompletionRequest {
+            model: "mock".to_string(),
+            messages: vec![ChatMessage::system(system), ChatMessage::user(prompt)],
+            temperature: None,
+            max_tokens: None,
+            response_schema: None,
+            tools: Vec::new(),
+            tool_choice: ToolChoice::Auto,
+        }
+    }
+
+    fn provider() -> MockProvider {
+        MockProvider::new("mock", MockFixtures::parse(FIXTURES).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_sequences_and_error_injection() {
+        let provider = provider();
+        let plan = request("", "Task: Break down this request into steps");
+
+        let first = provider.complete("", &plan).await.unwrap_err();
+        assert!(matches!(first, LLMError::RateLimitExceeded(_)));
+        // Later matches repeat the last step
+        for _ in 0..2 {
+            let completion = provider.complete("", &plan).await.unwrap();
+            assert_eq!(completion.text, r#"{"steps": []}"#);
+        }
+
+        let search = request("", "Please Search for crates");
+        let completion = provider.complete("", &search).await.unwrap();
+        assert_eq!(completion.tool_calls[0].name, "web_search");
+        assert_eq!(completion.finish_reason.as_deref(), Some("tool_calls"));
+        // The fall-through rule is used up, so the default response applies
+        let completion = provider.complete("", &search).await.unwrap();
+        assert_eq!(completion.text, "fallback");
+
+        let completion = provider.complete("", &request("echo", "hi")).await.unwrap();
+        assert_eq!(completion.text, "You said: hi");
+    }
+
+    #[tokio::test]
+    async fn test_unscripted_schema_requests_get_conforming_json() {
+        let provider = provider();
+        let mut structured = request("", "Summarize the context");
+        structured.response_schema = Some(json!({
+            "type": "object",
+            "properties": {
+                "mood": {"type": "string", "enum": ["calm", "tense"]},
+                "score": {"type": "number", "minimum": 0.5},
+                "tags": {"type": "array", "items": {"type": "string"}, "minItems": 2}
+            },
+            "required": ["mood", "score", "tags"]
+        }));
+
+        let completion = provider.complete("", &structured).await.unwrap();
+        let value: Value = serde_json::from_str(&completion.text).unwrap();
+        assert_eq!(
+            value,
+            json!({"mood": "calm", "score": 0.5, "tags": ["mock", "mock"]})
+        );
+    }
+
+    #[test]
+    fn test_fixture_validation() {
+        assert!(MockFixtures::parse("[[rules]]\nname = \"empty\"").is_err());
+        assert!(
+            MockFixtures::parse("[[rules]]\nname = \"x\"\nsteps = [{ error = \"teapot\" }]")
+                .is_err()
+        );
+        assert!(
+            MockFixtures::parse("[[rules]]\nname = \"x\"\nthen = \"loop\"\nresponse = \"y\"")
+                .is_err()
+        );
+        assert!(!MockFixtures::builtin().rules.is_empty());
+    }
+}
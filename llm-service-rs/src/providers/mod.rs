@@ -0,0 +1,516 @@
+// llm-service-rs/src/providers/mod.rs
+//
+// Pluggable LLM provider backends
+//
+// This module provides:
+// - The `LlmProvider` trait implemented by every backend
+// - Provider-neutral request/response types (`CompletionRequest`, `Completion`, `TokenUsage`)
+// - Provider-neutral tool calling types (`ToolSpec`, `ToolCall`, `ToolChoice`)
+// - Shared HTTP error classification into `LLMError`
+//
+// Each provider performs exactly one HTTP attempt per call. Retries, backoff and
+// API key management stay in `LLMClient` so every backend gets the same resilience
+// behavior.
+//
+// Streaming providers push text deltas into a `DeltaSink` as they arrive and
+// return the assembled `Completion` (with usage) once the provider finishes.
+//
+// Available backends:
+// - `openai`: OpenAI chat-completions wire format (also OpenRouter, xAI, Gemini, LM Studio)
+// - `anthropic`: Anthropic Messages API
+// - `local`: Local Ollama or llama.cpp HTTP server
+// - `mock`: Scripted fixture replies for offline and CI runs
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+use crate::llm_client::LLMError;
+
+pub mod anthropic;
+pub mod local;
+pub mod mock;
+pub mod openai;
+
+pub use anthropic::AnthropicProvider;
+pub use local::LocalProvider;
+pub use mock::MockProvider;
+pub use openai::OpenAIProvider;
+
+/// A single chat message in provider-neutral form
+///
+/// Tool fields are not part of the serialized form; providers map them onto
+/// their own wire formats.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+    /// Tool calls made by an assistant message
+    #[serde(skip)]
+    pub tool_calls: Vec<ToolCall>,
+    /// The call a `tool` message answers
+    #[serde(skip)]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    fn new(role: &str, content: impl Into<String>) -> Self {
+        Self {
+            role: role.to_string(),
+            content: content.into(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }
+    }
+
+    pub fn system(content: impl Into<String>) -> Self {
+        Self::new("system", content)
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self::new("user", content)
+    }
+
+    #[cfg(test)]
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self::new("assistant", content)
+    }
+
+    /// Assistant turn that called tools
+    pub fn tool_calls(calls: Vec<ToolCall>) -> Self {
+        Self {
+            tool_calls: calls,
+            ..Self::new("assistant", "")
+        }
+    }
+
+    /// Result of a tool call, sent back to the model
+    pub fn tool_result(call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            tool_call_id: Some(call_id.into()),
+            ..Self::new("tool", content)
+        }
+    }
+}
+
+/// A tool the model may call, with a JSON Schema for its arguments
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolSpec {
+    pub name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+}
+
+/// A tool invocation requested by the model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolCall {
+    /// Provider call id, echoed back with the result
+    pub id: String,
+    pub name: String,
+    /// Arguments as JSON object text
+    pub arguments: String,
+}
+
+/// How the model may use the offered tools
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ToolChoice {
+    /// The model decides whether to call tools
+    #[default]
+    Auto,
+    /// Tools are described but must not be called
+    None,
+    /// The model must call at least one tool
+    Required,
+    /// The model must call this tool
+    Tool(String),
+}
+
+impl ToolChoice {
+    /// Parse "auto", "none", "required" (alias "any") or a tool name
+    pub fn parse(value: &str) -> Self {
+        match value.trim() {
+            "" | "auto" => ToolChoice::Auto,
+            "none" => ToolChoice::None,
+            "required" | "any" => ToolChoice::Required,
+            name => ToolChoice::Tool(name.to_string()),
+        }
+    }
+}
+
+/// Provider-neutral completion request built by `LLMClient`
+#[derive(Debug, Clone)]
+pub struct CompletionRequest {
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    /// JSON Schema for native structured output; only set for providers that support it
+    pub response_schema: Option<serde_json::Value>,
+    pub tools: Vec<ToolSpec>,
+    pub tool_choice: ToolChoice,
+}
+
+impl CompletionRequest {
+    /// Concatenated content of all system messages
+    pub fn system_prompt(&self) -> Option<String> {
+        let system: Vec<&str> = self
+            .messages
+            .iter()
+            .filter(|m| m.role == "system")
+            .map(|m| m.content.as_str())
+            .collect();
+
+        if system.is_empty() {
+            None
+        } else {
+            Some(system.join("\n\n"))
+        }
+    }
+
+    /// All messages except system messages, in order
+    pub fn conversation(&self) -> impl Iterator<Item = &ChatMessage> {
+        self.messages.iter().filter(|m| m.role != "system")
+    }
+}
+
+/// Token usage reported by the provider for a single call
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+impl TokenUsage {
+    pub fn new(prompt_tokens: u32, completion_tokens: u32) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        }
+    }
+
+    /// Write usage counters into a response metadata map
+    pub fn write_metadata(&self, meta: &mut HashMap<String, String>) {
+        meta.insert("prompt_tokens".to_string(), self.prompt_tokens.to_string());
+        meta.insert(
+            "completion_tokens".to_string(),
+            self.completion_tokens.to_string(),
+        );
+        meta.insert("total_tokens".to_string(), self.total_tokens.to_string());
+    }
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+/// Result of a successful completion call
+#[derive(Debug, Clone)]
+pub struct Completion {
+    pub text: String,
+    pub provider: String,
+    pub model: String,
+    pub finish_reason: Option<String>,
+    pub usage: TokenUsage,
+    /// Tool calls requested by the model; empty for plain text replies
+    pub tool_calls: Vec<ToolCall>,
+}
+
+/// Backend kinds selectable via `LLM_PROVIDER` or the `provider` request parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProviderKind {
+    OpenAI,
+    Anthropic,
+    Local,
+    Mock,
+}
+
+impl ProviderKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProviderKind::OpenAI => "openai",
+            ProviderKind::Anthropic => "anthropic",
+            ProviderKind::Local => "local",
+            ProviderKind::Mock => "mock",
+        }
+    }
+
+    /// Parse a provider name, accepting common aliases
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "openai" | "openrouter" | "grok" | "gemini" | "lmstudio" | "default" => {
+                Some(ProviderKind::OpenAI)
+            }
+            "anthropic" | "claude" => Some(ProviderKind::Anthropic),
+            "local" | "ollama" | "llamacpp" | "llama.cpp" => Some(ProviderKind::Local),
+            "mock" | "fake" => Some(ProviderKind::Mock),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ProviderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Common interface implemented by every LLM backend
+#[async_trait]
+pub trait LlmProvider: Send + Sync + fmt::Debug {
+    /// Backend kind of this provider
+    fn kind(&self) -> ProviderKind;
+
+    /// Label used to look up the API key in the secrets service
+    /// (e.g. "openai", "openrouter", "anthropic")
+    fn secret_label(&self) -> &str;
+
+    /// Model used when the request does not name one
+    fn default_model(&self) -> &str;
+
+    /// Whether calls to this provider need an API key
+    fn requires_api_key(&self) -> bool {
+        true
+    }
+
+    /// Whether the backend can constrain output to a JSON Schema natively
+    ///
+    /// Schemas are always described in the system prompt as well, so backends
+    /// without support still receive the contract.
+    fn supports_response_schema(&self) -> bool {
+        false
+    }
+
+    /// Execute a single completion attempt
+    ///
+    /// Implementations must classify failures into `LLMError` so that the
+    /// retry loop in `LLMClient` can decide whether to try again.
+    async fn complete(
+        &self,
+        api_key: &str,
+        request: &CompletionRequest,
+    ) -> Result<Completion, LLMError>;
+
+    /// Execute a single streaming attempt, forwarding deltas to `sink`
+    ///
+    /// The default implementation falls back to `complete` and emits the whole
+    /// text as one delta, for backends without native streaming.
+    async fn stream(
+        &self,
+        api_key: &str,
+        request: &CompletionRequest,
+        sink: DeltaSink,
+    ) -> Result<Completion, LLMError> {
+        let completion = self.complete(api_key, request).await?;
+        sink.send(completion.text.clone()).await?;
+        Ok(completion)
+    }
+}
+
+/// Destination for streamed text deltas
+///
+/// Cloning shares the "emitted" flag, which lets the retry loop know whether the
+/// client has already seen partial output from a failed attempt.
+#[derive(Debug, Clone)]
+pub struct DeltaSink {
+    tx: mpsc::Sender<String>,
+    emitted: Arc<AtomicBool>,
+}
+
+impl DeltaSink {
+    pub fn new(tx: mpsc::Sender<String>) -> Self {
+        Self {
+            tx,
+            emitted: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Forward a delta; fails with `LLMError::Cancelled` once the receiver is gone
+    pub async fn send(&self, delta: String) -> Result<(), LLMError> {
+        if delta.is_empty() {
+            return Ok(());
+        }
+        self.tx
+            .send(delta)
+            .await
+            .map_err(|_| LLMError::Cancelled("stream receiver dropped".to_string()))?;
+        self.emitted.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Whether any delta has reached the receiver
+    pub fn has_emitted(&self) -> bool {
+        self.emitted.load(Ordering::SeqCst)
+    }
+}
+
+/// Incremental line reader over a streaming HTTP response body
+///
+/// Used for both Server-Sent Events (OpenAI, Anthropic, llama.cpp) and
+/// newline-delimited JSON (Ollama).
+pub struct LineReader {
+    response: reqwest::Response,
+    buffer: Vec<u8>,
+    finished: bool,
+}
+
+impl LineReader {
+    pub fn new(response: reqwest::Response) -> Self {
+        Self {
+            response,
+            buffer: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// Next complete line without its terminator, or `None` at end of body
+    pub async fn next_line(&mut self) -> Result<Option<String>, LLMError> {
+        loop {
+            if let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+                let mut line: Vec<u8> = self.buffer.drain(..=pos).collect();
+                line.pop();
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                return Ok(Some(String::from_utf8_lossy(&line).into_owned()));
+            }
+
+            if self.finished {
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                }
+                let rest = std::mem::take(&mut self.buffer);
+                return Ok(Some(String::from_utf8_lossy(&rest).into_owned()));
+            }
+
+            match self.response.chunk().await {
+                Ok(Some(bytes)) => self.buffer.extend_from_slice(&bytes),
+                Ok(None) => self.finished = true,
+                Err(err) => {
+                    return Err(LLMError::NetworkError(format!(
+                        "Stream interrupted: {}",
+                        err
+                    )))
+                }
+            }
+        }
+    }
+}
+
+/// Payload of an SSE `data:` line, if the line is one
+pub fn sse_data(line: &str) -> Option<&str> {
+    line.strip_prefix("data:").map(|data| data.trim_start())
+}
+
+/// Classify a transport-level reqwest failure
+pub fn classify_transport_error(err: reqwest::Error) -> LLMError {
+    if err.is_timeout() {
+        LLMError::NetworkError(format!("Request timed out: {}", err))
+    } else if err.is_connect() {
+        LLMError::NetworkError(format!("Connection failed: {}", err))
+    } else {
+        LLMError::NetworkError(format!("Network error: {}", err))
+    }
+}
+
+/// Classify a non-success HTTP status into an `LLMError`
+///
+/// Providers call this after checking for their own error codes, so only the
+/// generic status mapping lives here.
+pub fn classify_http_status(status: u16, body: &str) -> LLMError {
+    match status {
+        400 => LLMError::InvalidRequest(format!("Bad request: {}", body)),
+        401 => LLMError::InvalidRequest(format!("Unauthorized: {}", body)),
+        403 => LLMError::InvalidRequest(format!("Forbidden: {}", body)),
+        404 => LLMError::InvalidRequest(format!("Not found: {}", body)),
+        429 => LLMError::RateLimitExceeded(format!("Rate limit exceeded: {}", body)),
+        // Server errors - retryable
+        500 | 502 | 503 | 504 => {
+            LLMError::ServerError(format!("Server error ({}): {}", status, body))
+        }
+        _ => LLMError::UnknownError(format!("Unknown error ({}): {}", status, body)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_kind_aliases() {
+        assert_eq!(ProviderKind::parse("OpenRouter"), Some(ProviderKind::OpenAI));
+        assert_eq!(ProviderKind::parse("claude"), Some(ProviderKind::Anthropic));
+        assert_eq!(ProviderKind::parse("ollama"), Some(ProviderKind::Local));
+        assert_eq!(ProviderKind::parse("mock"), Some(ProviderKind::Mock));
+        assert_eq!(ProviderKind::parse("unknown"), None);
+    }
+
+    #[test]
+    fn test_classify_http_status() {
+        assert!(matches!(
+            classify_http_status(429, ""),
+            LLMError::RateLimitExceeded(_)
+        ));
+        assert!(matches!(
+            classify_http_status(503, ""),
+            LLMError::ServerError(_)
+        ));
+        assert!(matches!(
+            classify_http_status(401, ""),
+            LLMError::InvalidRequest(_)
+        ));
+    }
+
+    #[test]
+    fn test_tool_choice_parsing() {
+        assert_eq!(ToolChoice::parse(""), ToolChoice::Auto);
+        assert_eq!(ToolChoice::parse("any"), ToolChoice::Required);
+        assert_eq!(
+            ToolChoice::parse("web_search"),
+            ToolChoice::Tool("web_search".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delta_sink_tracks_emission_and_cancellation() {
+        let (tx, mut rx) = mpsc::channel(4);
+        let sink = DeltaSink::new(tx);
+
+        sink.send(String::new()).await.unwrap();
+        assert!(!sink.has_emitted());
+
+        sink.send("hello".to_string()).await.unwrap();
+        assert!(sink.clone().has_emitted());
+        assert_eq!(rx.recv().await.as_deref(), Some("hello"));
+
+        drop(rx);
+        assert!(matches!(
+            sink.send("more".to_string()).await,
+            Err(LLMError::Cancelled(_))
+        ));
+    }
+
+    #[test]
+    fn test_system_prompt_is_split_from_conversation() {
+        let request = CompletionRequest {
+            model: "m".to_string(),
+            messages: vec![ChatMessage::system("sys"), ChatMessage::user("hi")],
+            temperature: None,
+            max_tokens: None,
+            response_schema: None,
+            tools: Vec::new(),
+            tool_choice: ToolChoice::Auto,
+        };
+        assert_eq!(request.system_prompt().as_deref(), Some("sys"));
+        assert_eq!(request.conversation().count(), 1);
+    }
+}
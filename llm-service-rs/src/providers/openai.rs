@@ -0,0 +1,583 @@
+// llm-service-rs/src/providers/openai.rs
+//
+// OpenAI chat-completions backend
+//
+// Speaks the `/v1/chat/completions` wire format, which is also served by
+// OpenRouter, xAI, Gemini's OpenAI-compatible endpoint and LM Studio.
+//
+// Configuration (.env file):
+// - LLM_API_URL: Chat completions endpoint (default: OpenAI)
+// - LLM_MODEL: Default model (default: "gpt-3.5-turbo")
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::env;
+
+use super::{
+    classify_http_status, classify_transport_error, sse_data, ChatMessage, Completion,
+    CompletionRequest, DeltaSink, LineReader, LlmProvider, ProviderKind, TokenUsage, ToolCall,
+    ToolChoice,
+};
+use crate::llm_client::LLMError;
+
+#[derive(Debug, Serialize)]
+struct ChatCompletionRequest<'a> {
+    model: &'a str,
+    messages: Vec<WireMessage<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<WireTool<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct WireMessage<'a> {
+    role: &'a str,
+    // Null for assistant turns that only call tools
+    content: Option<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<WireToolCall>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<&'a str>,
+}
+
+impl<'a> From<&'a ChatMessage> for WireMessage<'a> {
+    fn from(message: &'a ChatMessage) -> Self {
+        WireMessage {
+            role: &message.role,
+            content: Some(message.content.as_str())
+                .filter(|content| !content.is_empty() || message.tool_calls.is_empty()),
+            tool_calls: message.tool_calls.iter().map(WireToolCall::from).collect(),
+            tool_call_id: message.tool_call_id.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct WireTool<'a> {
+    #[serde(rename = "type")]
+    tool_type: &'static str,
+    function: WireFunctionDef<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct WireFunctionDef<'a> {
+    name: &'a str,
+    description: &'a str,
+    parameters: &'a serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WireToolCall {
+    #[serde(default)]
+    id: String,
+    #[serde(rename = "type", default = "function_type")]
+    call_type: String,
+    function: WireFunctionCall,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WireFunctionCall {
+    #[serde(default)]
+    name: String,
+    // JSON-encoded arguments object
+    #[serde(default)]
+    arguments: String,
+}
+
+fn function_type() -> String {
+    "function".to_string()
+}
+
+impl From<&ToolCall> for WireToolCall {
+    fn from(call: &ToolCall) -> Self {
+        WireToolCall {
+            id: call.id.clone(),
+            call_type: function_type(),
+            function: WireFunctionCall {
+                name: call.name.clone(),
+                arguments: call.arguments.clone(),
+            },
+        }
+    }
+}
+
+impl From<WireToolCall> for ToolCall {
+    fn from(call: WireToolCall) -> Self {
+        ToolCall {
+            id: call.id,
+            name: call.function.name,
+            arguments: call.function.arguments,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct StreamOptions {
+    include_usage: bool,
+}
+
+// `{"type": "json_schema", "json_schema": {...}}` structured output
+#[derive(Debug, Serialize)]
+struct ResponseFormat<'a> {
+    #[serde(rename = "type")]
+    format_type: &'static str,
+    json_schema: JsonSchemaFormat<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonSchemaFormat<'a> {
+    name: &'static str,
+    schema: &'a serde_json::Value,
+    // Strict mode requires additionalProperties: false on every object, which
+    // arbitrary caller schemas don't guarantee; the service validates instead
+    strict: bool,
+}
+
+impl<'a> ResponseFormat<'a> {
+    fn from_request(request: &'a CompletionRequest) -> Option<Self> {
+        request
+            .response_schema
+            .as_ref()
+            .map(|schema| ResponseFormat {
+                format_type: "json_schema",
+                json_schema: JsonSchemaFormat {
+                    name: "response",
+                    schema,
+                    strict: false,
+                },
+            })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionResponse {
+    #[serde(default)]
+    model: Option<String>,
+    choices: Vec<ChatChoice>,
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatChoice {
+    message: ResponseMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseMessage {
+    // Null when the model only calls tools
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<WireToolCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Usage {
+    #[serde(default)]
+    prompt_tokens: u32,
+    #[serde(default)]
+    completion_tokens: u32,
+    #[serde(default)]
+    total_tokens: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChunk {
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    #[serde(default)]
+    delta: ChunkDelta,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ChunkDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorEnvelope {
+    error: ErrorBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    code: Option<serde_json::Value>,
+}
+
+#[derive(Debug)]
+pub struct OpenAIProvider {
+    client: Client,
+    api_url: String,
+    model: String,
+    secret_label: String,
+}
+
+impl OpenAIProvider {
+    pub fn new(client: Client, api_url: String, model: String) -> Self {
+        let secret_label = Self::determine_secret_label(&api_url, &model);
+        Self {
+            client,
+            api_url,
+            model,
+            secret_label,
+        }
+    }
+
+    /// Create the provider from LLM_API_URL / LLM_MODEL
+    pub fn from_env(client: Client) -> Self {
+        let api_url = env::var("LLM_API_URL")
+            .unwrap_or_else(|_| "https://api.openai.com/v1/chat/completions".to_string());
+        let model = env::var("LLM_MODEL").unwrap_or_else(|_| "gpt-3.5-turbo".to_string());
+        Self::new(client, api_url, model)
+    }
+
+    /// Determine the secrets label based on API URL and model
+    ///
+    /// Several vendors serve the OpenAI wire format, each with its own key.
+    pub(crate) fn determine_secret_label(api_url: &str, model: &str) -> String {
+        if api_url.contains("openai.com") {
+            "openai".to_string()
+        } else if api_url.contains("openrouter.ai") {
+            "openrouter".to_string()
+        } else if api_url.contains("x.ai") {
+            "grok".to_string()
+        } else if api_url.contains("googleapis.com") {
+            "gemini".to_string()
+        } else if api_url.contains("localhost:11434") {
+            "ollama".to_string()
+        } else if api_url.contains("localhost:1234") {
+            "lmstudio".to_string()
+        } else if model.starts_with("anthropic/") {
+            "anthropic".to_string()
+        } else {
+            // When unable to determine, use generic provider
+            "default".to_string()
+        }
+    }
+
+    /// Map an OpenAI error response onto `LLMError`
+    fn classify_error(status: u16, body: &str) -> LLMError {
+        if let Ok(envelope) = serde_json::from_str::<ErrorEnvelope>(body) {
+            let code = envelope
+                .error
+                .code
+                .as_ref()
+                .and_then(|c| c.as_str())
+                .unwrap_or_default();
+
+            match code {
+                "model_not_found" => {
+                    return LLMError::ModelNotAvailable(envelope.error.message);
+                }
+                "content_policy_violation" => {
+                    return LLMError::ModelNotAvailable(format!(
+                        "Content policy violation: {}",
+                        envelope.error.message
+                    ));
+                }
+                "insufficient_quota" => {
+                    // Quota exhaustion is a 429 but will not clear by waiting
+                    return LLMError::InvalidRequest(format!(
+                        "Insufficient quota: {}",
+                        envelope.error.message
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        classify_http_status(status, body)
+    }
+
+    fn build_body(request: &CompletionRequest, stream: bool) -> ChatCompletionRequest<'_> {
+        let tools: Vec<WireTool> = request
+            .tools
+            .iter()
+            .map(|tool| WireTool {
+                tool_type: "function",
+                function: WireFunctionDef {
+                    name: &tool.name,
+                    description: &tool.description,
+                    parameters: &tool.parameters,
+                },
+            })
+            .collect();
+        let tool_choice = (!tools.is_empty()).then(|| match &request.tool_choice {
+            ToolChoice::Auto => serde_json::json!("auto"),
+            ToolChoice::None => serde_json::json!("none"),
+            ToolChoice::Required => serde_json::json!("required"),
+            ToolChoice::Tool(name) => {
+                serde_json::json!({"type": "function", "function": {"name": name}})
+            }
+        });
+
+        ChatCompletionRequest {
+            model: &request.model,
+            messages: request.messages.iter().map(WireMessage::from).collect(),
+            temperature: request.temperature,
+            max_tokens: request.max_tokens,
+            stream,
+            // Ask for a trailing usage chunk; servers that don't know it ignore it
+            stream_options: stream.then_some(StreamOptions {
+                include_usage: true,
+            }),
+            response_format: ResponseFormat::from_request(request),
+            tools,
+            tool_choice,
+        }
+    }
+
+    async fn send(
+        &self,
+        api_key: &str,
+        body: &ChatCompletionRequest<'_>,
+    ) -> Result<reqwest::Response, LLMError> {
+        let mut http_request = self
+            .client
+            .post(&self.api_url)
+            .header("Content-Type", "application/json");
+        if !api_key.is_empty() {
+            http_request = http_request.header("Authorization", format!("Bearer {}", api_key));
+        }
+
+        let response = http_request
+            .json(body)
+            .send()
+            .await
+            .map_err(classify_transport_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(Self::classify_error(status.as_u16(), &text));
+        }
+
+        Ok(response)
+    }
+}
+
+impl From<Usage> for TokenUsage {
+    fn from(u: Usage) -> Self {
+        TokenUsage {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+            total_tokens: u.total_tokens.max(u.prompt_tokens + u.completion_tokens),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAIProvider {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::OpenAI
+    }
+
+    fn secret_label(&self) -> &str {
+        &self.secret_label
+    }
+
+    fn default_model(&self) -> &str {
+        &self.model
+    }
+
+    fn requires_api_key(&self) -> bool {
+        // Local OpenAI-compatible servers accept any (or no) key
+        !matches!(self.secret_label.as_str(), "ollama" | "lmstudio")
+    }
+
+    fn supports_response_schema(&self) -> bool {
+        // Every known vendor accepts `response_format: json_schema`; unknown
+        // OpenAI-compatible servers may reject the field outright
+        self.secret_label != "default"
+    }
+
+    async fn complete(
+        &self,
+        api_key: &str,
+        request: &CompletionRequest,
+    ) -> Result<Completion, LLMError> {
+        let body = Self::build_body(request, false);
+
+        let response = self.send(api_key, &body).await?;
+
+        let data: ChatCompletionResponse = response
+            .json()
+            .await
+            .map_err(|err| LLMError::ParseError(format!("Failed to parse response: {}", err)))?;
+
+        let choice = data.choices.into_iter().next().ok_or_else(|| {
+            LLMError::ParseError("No choices returned in response".to_string())
+        })?;
+
+        Ok(Completion {
+            text: choice.message.content.unwrap_or_default(),
+            provider: self.kind().to_string(),
+            model: data.model.unwrap_or_else(|| request.model.clone()),
+            finish_reason: choice.finish_reason,
+            usage: data.usage.map(TokenUsage::from).unwrap_or_default(),
+            tool_calls: choice
+                .message
+                .tool_calls
+                .into_iter()
+                .map(ToolCall::from)
+                .collect(),
+        })
+    }
+
+    async fn stream(
+        &self,
+        api_key: &str,
+        request: &CompletionRequest,
+        sink: DeltaSink,
+    ) -> Result<Completion, LLMError> {
+        let body = Self::build_body(request, true);
+
+        let mut lines = LineReader::new(self.send(api_key, &body).await?);
+        let mut completion = Completion {
+            text: String::new(),
+            provider: self.kind().to_string(),
+            model: request.model.clone(),
+            finish_reason: None,
+            usage: TokenUsage::default(),
+            tool_calls: Vec::new(),
+        };
+
+        while let Some(line) = lines.next_line().await? {
+            let Some(data) = sse_data(&line) else {
+                continue;
+            };
+            if data == "[DONE]" {
+                break;
+            }
+
+            let chunk: ChatCompletionChunk = serde_json::from_str(data).map_err(|err| {
+                LLMError::ParseError(format!("Failed to parse stream chunk: {}", err))
+            })?;
+
+            if let Some(model) = chunk.model {
+                completion.model = model;
+            }
+            if let Some(usage) = chunk.usage {
+                completion.usage = usage.into();
+            }
+            for choice in chunk.choices {
+                if let Some(reason) = choice.finish_reason {
+                    completion.finish_reason = Some(reason);
+                }
+                if let Some(content) = choice.delta.content {
+                    completion.text.push_str(&content);
+                    sink.send(content).await?;
+                }
+            }
+        }
+
+        Ok(completion)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::ToolSpec;
+
+    #[test]
+    fn test_secret_label_from_url() {
+        assert_eq!(
+            OpenAIProvider::determine_secret_label(
+                "https://openrouter.ai/api/v1/chat/completions",
+                "anthropic/claude-3.5-sonnet"
+            ),
+            "openrouter"
+        );
+        assert_eq!(
+            OpenAIProvider::determine_secret_label("https://example.com/v1", "gpt-4"),
+            "default"
+        );
+    }
+
+    #[test]
+    fn test_tool_calls_round_trip_wire_format() {
+        let request = CompletionRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![
+                ChatMessage::user("weather?"),
+                ChatMessage::tool_calls(vec![ToolCall {
+                    id: "call_1".to_string(),
+                    name: "get_weather".to_string(),
+                    arguments: r#"{"city":"Oslo"}"#.to_string(),
+                }]),
+                ChatMessage::tool_result("call_1", "sunny"),
+            ],
+            temperature: None,
+            max_tokens: None,
+            response_schema: None,
+            tools: vec![ToolSpec {
+                name: "get_weather".to_string(),
+                description: "Current weather".to_string(),
+                parameters: serde_json::json!({"type": "object"}),
+            }],
+            tool_choice: ToolChoice::Tool("get_weather".to_string()),
+        };
+
+        let body = serde_json::to_value(OpenAIProvider::build_body(&request, false)).unwrap();
+        assert_eq!(body["messages"][1]["content"], serde_json::Value::Null);
+        assert_eq!(
+            body["messages"][1]["tool_calls"][0]["function"]["name"],
+            "get_weather"
+        );
+        assert_eq!(body["messages"][2]["tool_call_id"], "call_1");
+        assert_eq!(body["tools"][0]["type"], "function");
+        assert_eq!(body["tool_choice"]["function"]["name"], "get_weather");
+
+        let response: ChatCompletionResponse = serde_json::from_str(
+            r#"{"choices":[{"message":{"role":"assistant","content":null,"tool_calls":[
+                {"id":"call_2","type":"function","function":{"name":"get_weather","arguments":"{}"}}
+            ]},"finish_reason":"tool_calls"}]}"#,
+        )
+        .unwrap();
+        let message = &response.choices[0].message;
+        assert!(message.content.is_none());
+        assert_eq!(message.tool_calls[0].id, "call_2");
+    }
+
+    #[test]
+    fn test_model_not_found_is_classified() {
+        let body = r#"{"error":{"message":"The model `gpt-9` does not exist","code":"model_not_found"}}"#;
+        assert!(matches!(
+            OpenAIProvider::classify_error(404, body),
+            LLMError::ModelNotAvailable(_)
+        ));
+        assert!(matches!(
+            OpenAIProvider::classify_error(502, "bad gateway"),
+            LLMError::ServerError(_)
+        ));
+    }
+}
@@ -0,0 +1,537 @@
+// llm-service-rs/src/response_cache.rs
+//
+// Optional cache for generated responses
+//
+// This module provides:
+// - Exact tier: keyed by the normalized prompt, system prompt, provider, model
+//   and sampling parameters
+// - Semantic tier (optional): reuses a response whose prompt embedding is at
+//   least LLM_CACHE_SIMILARITY_THRESHOLD cosine-similar, within the same
+//   system prompt / model / parameter scope
+// - Per-entry TTL and a bounded entry count (oldest entries are evicted first)
+// - Persistence to a JSON file so the cache survives restarts
+// - Hit-rate counters reported in response metadata
+//
+// Requests can opt out with the `cache` parameter:
+// - "bypass":  neither read nor write the cache
+// - "refresh": skip the lookup but store the new response
+//
+// Configuration (.env file):
+// - LLM_CACHE_ENABLED: Enable the cache (default: false)
+// - LLM_CACHE_TTL_SECS: Entry lifetime in seconds (default: 3600)
+// - LLM_CACHE_MAX_ENTRIES: Maximum cached responses (default: 1000)
+// - LLM_CACHE_SEMANTIC: Enable the semantic tier (default: false)
+// - LLM_CACHE_SIMILARITY_THRESHOLD: Minimum cosine similarity for a semantic hit (default: 0.95)
+// - LLM_CACHE_PATH: JSON file for persistence; unset keeps the cache in memory only
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::llm_client::GenerationOptions;
+use crate::providers::{Completion, TokenUsage};
+
+// How often dirty cache contents are written to LLM_CACHE_PATH
+const PERSIST_INTERVAL: Duration = Duration::from_secs(30);
+
+// Separates the scope from the prompt in exact-match keys
+const KEY_SEPARATOR: char = '\u{1f}';
+
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    pub ttl: Duration,
+    pub max_entries: usize,
+    pub semantic: bool,
+    pub similarity_threshold: f32,
+    pub path: Option<PathBuf>,
+}
+
+impl CacheConfig {
+    /// Read LLM_CACHE_* variables; `None` when the cache is disabled
+    pub fn from_env() -> Option<Self> {
+        let flag = |key: &str| {
+            env::var(key)
+                .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+                .unwrap_or(false)
+        };
+        if !flag("LLM_CACHE_ENABLED") {
+            return None;
+        }
+
+        Some(Self {
+            ttl: Duration::from_secs(
+                env::var("LLM_CACHE_TTL_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(3600),
+            ),
+            max_entries: env::var("LLM_CACHE_MAX_ENTRIES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1000),
+            semantic: flag("LLM_CACHE_SEMANTIC"),
+            similarity_threshold: env::var("LLM_CACHE_SIMILARITY_THRESHOLD")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0.95),
+            path: env::var("LLM_CACHE_PATH")
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .map(PathBuf::from),
+        })
+    }
+}
+
+/// Per-request cache behaviour, from the `cache` request parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheMode {
+    Use,
+    Bypass,
+    Refresh,
+}
+
+impl CacheMode {
+    pub fn from_parameters(parameters: &HashMap<String, String>) -> Self {
+        match parameters
+            .get("cache")
+            .map(|v| v.trim().to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("bypass") | Some("off") | Some("false") => CacheMode::Bypass,
+            Some("refresh") => CacheMode::Refresh,
+            _ => CacheMode::Use,
+        }
+    }
+}
+
+/// Cache key for one generation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    /// Everything except the prompt: system prompt, provider, model, parameters
+    scope: String,
+    /// Whitespace-normalized prompt
+    prompt: String,
+}
+
+impl CacheKey {
+    pub fn new(
+        prompt: &str,
+        system_prompt: Option<&str>,
+        provider: &str,
+        model: &str,
+        options: &GenerationOptions,
+    ) -> Self {
+        let scope = format!(
+            "{}|{}|{}|{}|{}|{}",
+            provider,
+            model,
+            options
+                .temperature
+                .map(|t| format!("{:.3}", t))
+                .unwrap_or_default(),
+            options.max_tokens.map(|t| t.to_string()).unwrap_or_default(),
+            options
+                .response_schema
+                .as_ref()
+                .map(|schema| schema.schema().to_string())
+                .unwrap_or_default(),
+            normalize(system_prompt.unwrap_or_default()),
+        );
+        Self {
+            scope,
+            prompt: normalize(prompt),
+        }
+    }
+
+    /// The text embedded for the semantic tier
+    pub fn prompt(&self) -> &str {
+        &self.prompt
+    }
+
+    fn exact(&self) -> String {
+        format!("{}{}{}", self.scope, KEY_SEPARATOR, self.prompt)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    scope: String,
+    text: String,
+    provider: String,
+    model: String,
+    finish_reason: Option<String>,
+    prompt_tokens: u32,
+    completion_tokens: u32,
+    created_at: u64,
+    #[serde(default)]
+    embedding: Option<Vec<f32>>,
+}
+
+impl CacheEntry {
+    fn age(&self, now: u64) -> u64 {
+        now.saturating_sub(self.created_at)
+    }
+}
+
+/// Which tier answered a lookup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitKind {
+    Exact,
+    Semantic,
+}
+
+/// A cached response returned instead of calling the provider
+#[derive(Debug, Clone)]
+pub struct CacheHit {
+    pub completion: Completion,
+    pub kind: HitKind,
+    pub similarity: f32,
+    pub age_secs: u64,
+    /// Tokens the original generation consumed and this request did not
+    pub saved_tokens: u32,
+}
+
+#[derive(Debug, Default)]
+struct CacheStats {
+    lookups: AtomicU64,
+    exact_hits: AtomicU64,
+    semantic_hits: AtomicU64,
+}
+
+#[derive(Debug)]
+pub struct ResponseCache {
+    config: CacheConfig,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    stats: CacheStats,
+    dirty: AtomicBool,
+}
+
+impl ResponseCache {
+    pub fn new(config: CacheConfig) -> Self {
+        let cache = Self {
+            config,
+            entries: Mutex::new(HashMap::new()),
+            stats: CacheStats::default(),
+            dirty: AtomicBool::new(false),
+        };
+        cache.load();
+        cache
+    }
+
+    pub fn semantic_enabled(&self) -> bool {
+        self.config.semantic
+    }
+
+    /// Exact-match lookup; counts towards the hit rate
+    pub fn get_exact(&self, key: &CacheKey) -> Option<CacheHit> {
+        self.stats.lookups.fetch_add(1, Ordering::Relaxed);
+        let now = unix_now();
+        let mut entries = self.entries.lock().unwrap();
+
+        let exact = key.exact();
+        match entries.get(&exact) {
+            Some(entry) if self.is_fresh(entry, now) => {
+                self.stats.exact_hits.fetch_add(1, Ordering::Relaxed);
+                Some(Self::hit(entry, HitKind::Exact, 1.0, now))
+            }
+            Some(_) => {
+                entries.remove(&exact);
+                self.dirty.store(true, Ordering::Relaxed);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Best semantic match in the same scope, if it clears the similarity threshold
+    ///
+    /// Call only after `get_exact` missed, so each request counts as one lookup.
+    pub fn get_semantic(&self, key: &CacheKey, embedding: &[f32]) -> Option<CacheHit> {
+        let now = unix_now();
+        let entries = self.entries.lock().unwrap();
+
+        let best = entries
+            .values()
+            .filter(|entry| entry.scope == key.scope && self.is_fresh(entry, now))
+            .filter_map(|entry| {
+                let candidate = entry.embedding.as_deref()?;
+                Some((entry, cosine_similarity(embedding, candidate)))
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+        if best.1 < self.config.similarity_threshold {
+            return None;
+        }
+        self.stats.semantic_hits.fetch_add(1, Ordering::Relaxed);
+        Some(Self::hit(best.0, HitKind::Semantic, best.1, now))
+    }
+
+    /// Store a successful completion
+    pub fn insert(&self, key: &CacheKey, completion: &Completion, embedding: Option<Vec<f32>>) {
+        let now = unix_now();
+        let mut entries = self.entries.lock().unwrap();
+
+        entries.insert(
+            key.exact(),
+            CacheEntry {
+                scope: key.scope.clone(),
+                text: completion.text.clone(),
+                provider: completion.provider.clone(),
+                model: completion.model.clone(),
+                finish_reason: completion.finish_reason.clone(),
+                prompt_tokens: completion.usage.prompt_tokens,
+                completion_tokens: completion.usage.completion_tokens,
+                created_at: now,
+                embedding,
+            },
+        );
+
+        entries.retain(|_, entry| self.is_fresh(entry, now));
+        while entries.len() > self.config.max_entries {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.created_at)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(oldest) => entries.remove(&oldest),
+                None => break,
+            };
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Report the cache outcome and running hit rate in response metadata
+    pub fn write_metadata(&self, hit: Option<&CacheHit>, meta: &mut HashMap<String, String>) {
+        let outcome = match hit.map(|h| h.kind) {
+            Some(HitKind::Exact) => "hit_exact",
+            Some(HitKind::Semantic) => "hit_semantic",
+            None => "miss",
+        };
+        meta.insert("cache".to_string(), outcome.to_string());
+
+        if let Some(hit) = hit {
+            meta.insert("cache_age_secs".to_string(), hit.age_secs.to_string());
+            meta.insert("cache_saved_tokens".to_string(), hit.saved_tokens.to_string());
+            if hit.kind == HitKind::Semantic {
+                meta.insert("cache_similarity".to_string(), format!("{:.4}", hit.similarity));
+            }
+        }
+
+        let lookups = self.stats.lookups.load(Ordering::Relaxed);
+        let exact = self.stats.exact_hits.load(Ordering::Relaxed);
+        let semantic = self.stats.semantic_hits.load(Ordering::Relaxed);
+        let rate = if lookups == 0 {
+            0.0
+        } else {
+            (exact + semantic) as f64 / lookups as f64
+        };
+        meta.insert("cache_lookups".to_string(), lookups.to_string());
+        meta.insert("cache_exact_hits".to_string(), exact.to_string());
+        meta.insert("cache_semantic_hits".to_string(), semantic.to_string());
+        meta.insert("cache_hit_rate".to_string(), format!("{:.4}", rate));
+        meta.insert(
+            "cache_entries".to_string(),
+            self.entries.lock().unwrap().len().to_string(),
+        );
+    }
+
+    /// Periodically write the cache to LLM_CACHE_PATH when it has changed
+    pub fn spawn_persistence(self: &Arc<Self>) {
+        if self.config.path.is_none() {
+            return;
+        }
+
+        let cache = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(PERSIST_INTERVAL);
+            loop {
+                ticker.tick().await;
+                if cache.dirty.swap(false, Ordering::Relaxed) {
+                    if let Err(e) = cache.save() {
+                        log::warn!("Failed to persist LLM response cache: {}", e);
+                        cache.dirty.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+        });
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.config.path else {
+            return Ok(());
+        };
+        let json = {
+            let entries = self.entries.lock().unwrap();
+            serde_json::to_vec(&*entries)?
+        };
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Write then rename so a crash never leaves a truncated cache file
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, path)
+    }
+
+    fn load(&self) {
+        let Some(path) = &self.config.path else {
+            return;
+        };
+        let Ok(data) = std::fs::read(path) else {
+            return;
+        };
+
+        match serde_json::from_slice::<HashMap<String, CacheEntry>>(&data) {
+            Ok(mut loaded) => {
+                let now = unix_now();
+                loaded.retain(|_, entry| self.is_fresh(entry, now));
+                log::info!(
+                    "Loaded {} cached LLM responses from {}",
+                    loaded.len(),
+                    path.display()
+                );
+                *self.entries.lock().unwrap() = loaded;
+            }
+            Err(e) => log::warn!(
+                "Ignoring unreadable LLM cache file {}: {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+
+    fn is_fresh(&self, entry: &CacheEntry, now: u64) -> bool {
+        entry.age(now) < self.config.ttl.as_secs()
+    }
+
+    fn hit(entry: &CacheEntry, kind: HitKind, similarity: f32, now: u64) -> CacheHit {
+        let usage = TokenUsage::new(entry.prompt_tokens, entry.completion_tokens);
+        CacheHit {
+            completion: Completion {
+                text: entry.text.clone(),
+                provider: entry.provider.clone(),
+                model: entry.model.clone(),
+                finish_reason: entry.finish_reason.clone(),
+                // Nothing was spent on this request
+                usage: TokenUsage::default(),
+                tool_calls: Vec::new(),
+            },
+            kind,
+            similarity,
+            age_secs: entry.age(now),
+            saved_tokens: usage.total_tokens,
+        }
+    }
+}
+
+/// Collapse runs of whitespace so formatting-only differences share an entry
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> CacheConfig {
+        CacheConfig {
+            ttl: Duration::from_secs(60),
+            max_entries: 2,
+            semantic: true,
+            similarity_threshold: 0.9,
+            path: None,
+        }
+    }
+
+    fn completion(text: &str) -> Completion {
+        Completion {
+            text: text.to_string(),
+            provider: "openai".to_string(),
+            model: "gpt-4o".to_string(),
+            finish_reason: Some("stop".to_string()),
+            usage: TokenUsage::new(10, 5),
+            tool_calls: Vec::new(),
+        }
+    }
+
+    fn key(prompt: &str) -> CacheKey {
+        CacheKey::new(prompt, None, "openai", "gpt-4o", &GenerationOptions::default())
+    }
+
+    #[test]
+    fn test_exact_hit_ignores_whitespace_and_reports_metadata() {
+        let cache = ResponseCache::new(config());
+        cache.insert(&key("plan   the\nrelease"), &completion("steps"), None);
+
+        let hit = cache.get_exact(&key("plan the release")).unwrap();
+        assert_eq!(hit.kind, HitKind::Exact);
+        assert_eq!(hit.completion.text, "steps");
+        assert_eq!(hit.saved_tokens, 15);
+        assert!(cache.get_exact(&key("plan the launch")).is_none());
+
+        let mut meta = HashMap::new();
+        cache.write_metadata(Some(&hit), &mut meta);
+        assert_eq!(meta["cache"], "hit_exact");
+        assert_eq!(meta["cache_hit_rate"], "0.5000");
+    }
+
+    #[test]
+    fn test_scope_separates_models_and_semantic_threshold_applies() {
+        let cache = ResponseCache::new(config());
+        cache.insert(&key("summarize"), &completion("a"), Some(vec![1.0, 0.0]));
+
+        let other_model = CacheKey::new(
+            "summarize",
+            None,
+            "openai",
+            "gpt-4o-mini",
+            &GenerationOptions::default(),
+        );
+        assert!(cache.get_exact(&other_model).is_none());
+        assert!(cache.get_semantic(&other_model, &[1.0, 0.0]).is_none());
+
+        assert!(cache.get_semantic(&key("sum up"), &[0.99, 0.1]).is_some());
+        assert!(cache.get_semantic(&key("translate"), &[0.0, 1.0]).is_none());
+    }
+
+    #[test]
+    fn test_oldest_entries_are_evicted() {
+        let cache = ResponseCache::new(config());
+        for prompt in ["one", "two", "three"] {
+            cache.insert(&key(prompt), &completion(prompt), None);
+        }
+        assert_eq!(cache.entries.lock().unwrap().len(), 2);
+        assert_eq!(
+            CacheMode::from_parameters(&HashMap::from([(
+                "cache".to_string(),
+                "bypass".to_string()
+            )])),
+            CacheMode::Bypass
+        );
+    }
+}
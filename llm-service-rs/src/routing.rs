@@ -0,0 +1,543 @@
+// llm-service-rs/src/routing.rs
+//
+// Declarative model routing and cross-provider failover
+//
+// This module provides:
+// - `RoutingPolicy`: ordered routing rules loaded from a TOML file
+// - Rule matching on request type, prompt length (tokens) and budget hint
+// - Fallback chains: the targets after the first one are tried in order when a
+//   target fails with one of the `fallback_on` error categories
+// - `RouteReport`: the rule taken and the targets that failed, for response metadata
+//
+// Callers set the request type with the `request_type` parameter (e.g. "planning",
+// "synthesis"); CompileContext, Process and EmbedText requests use "compile_context",
+// "process" and "embedding". Explicit `provider` / `model` parameters bypass routing.
+//
+// Embedding rules are resolved once at startup, since vectors from different
+// models cannot share an index; their fallbacks should serve the same model.
+//
+// Configuration (.env file):
+// - LLM_ROUTING_FILE: Path to the routing policy (default: none, every request uses LLM_PROVIDER)
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+
+use crate::embeddings;
+use crate::llm_client::LLMError;
+use crate::providers::ProviderKind;
+
+/// Request type used when the caller does not set one
+pub const GENERAL: &str = "general";
+
+/// Request type of EmbedText; only matched by rules that name it explicitly
+pub const EMBEDDING: &str = "embedding";
+
+// Error categories that fail over when the policy does not list its own
+const DEFAULT_FALLBACK_ON: &[&str] = &["rate_limited", "server_error", "network_error"];
+
+const ERROR_CATEGORIES: &[&str] = &[
+    "rate_limited",
+    "server_error",
+    "network_error",
+    "model_unavailable",
+    "invalid_request",
+    "parse_error",
+    "other",
+];
+
+// Retries on a target that still has fallbacks behind it
+const DEFAULT_RETRIES_BEFORE_FAILOVER: u32 = 1;
+
+/// Failover category of an error, as named in `fallback_on`
+pub fn error_category(error: &LLMError) -> &'static str {
+    match error {
+        LLMError::RateLimitExceeded(_) => "rate_limited",
+        LLMError::ServerError(_) => "server_error",
+        LLMError::NetworkError(_) => "network_error",
+        LLMError::ModelNotAvailable(_) => "model_unavailable",
+        LLMError::InvalidRequest(_) => "invalid_request",
+        LLMError::ParseError(_) => "parse_error",
+        LLMError::DimensionMismatch(_) | LLMError::Cancelled(_) | LLMError::UnknownError(_) => {
+            "other"
+        }
+    }
+}
+
+/// Cost/quality hint from the `budget` request parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetHint {
+    Low,
+    Standard,
+    High,
+}
+
+impl BudgetHint {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "low" | "cheap" | "economy" => Some(BudgetHint::Low),
+            "standard" | "normal" | "default" => Some(BudgetHint::Standard),
+            "high" | "premium" | "quality" => Some(BudgetHint::High),
+            _ => None,
+        }
+    }
+}
+
+/// A provider and model a request can be sent to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelTarget {
+    pub provider: String,
+    /// None uses the provider's configured model
+    pub model: Option<String>,
+    /// Endpoint override; only used by embedding targets
+    pub api_url: Option<String>,
+}
+
+impl ModelTarget {
+    /// Parse the "provider:model" shorthand; the model may itself contain ':'
+    fn parse(spec: &str) -> Self {
+        let (provider, model) = match spec.split_once(':') {
+            Some((provider, model)) => (provider, Some(model.trim().to_string())),
+            None => (spec, None),
+        };
+        Self {
+            provider: provider.trim().to_ascii_lowercase(),
+            model: model.filter(|m| !m.is_empty()),
+            api_url: None,
+        }
+    }
+}
+
+/// What a route matches against
+#[derive(Debug, Clone, Copy)]
+pub struct RouteRequest<'a> {
+    pub request_type: &'a str,
+    pub prompt_tokens: usize,
+    pub budget: Option<BudgetHint>,
+}
+
+#[derive(Debug, Clone)]
+struct RouteRule {
+    name: String,
+    request_types: Vec<String>,
+    min_prompt_tokens: Option<usize>,
+    max_prompt_tokens: Option<usize>,
+    budgets: Vec<BudgetHint>,
+    targets: Vec<ModelTarget>,
+}
+
+impl RouteRule {
+    fn matches(&self, request: &RouteRequest) -> bool {
+        // Rules without request types cover every generation request, never embeddings
+        let type_matches = if self.request_types.is_empty() {
+            request.request_type != EMBEDDING
+        } else {
+            self.request_types.iter().any(|t| t == request.request_type)
+        };
+        let budget = request.budget.unwrap_or(BudgetHint::Standard);
+
+        type_matches
+            && self
+                .min_prompt_tokens
+                .is_none_or(|min| request.prompt_tokens >= min)
+            && self
+                .max_prompt_tokens
+                .is_none_or(|max| request.prompt_tokens <= max)
+            && (self.budgets.is_empty() || self.budgets.contains(&budget))
+    }
+}
+
+/// The rule a request matched and its ordered targets (primary first)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub name: String,
+    pub targets: Vec<ModelTarget>,
+}
+
+// On-disk policy format
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TargetSpec {
+    Short(String),
+    Full {
+        provider: String,
+        #[serde(default)]
+        model: Option<String>,
+        #[serde(default)]
+        api_url: Option<String>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct RuleFile {
+    name: String,
+    #[serde(default)]
+    request_types: Vec<String>,
+    #[serde(default)]
+    min_prompt_tokens: Option<usize>,
+    #[serde(default)]
+    max_prompt_tokens: Option<usize>,
+    #[serde(default)]
+    budgets: Vec<String>,
+    targets: Vec<TargetSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PolicyFile {
+    #[serde(default)]
+    fallback_on: Option<Vec<String>>,
+    #[serde(default)]
+    retries_before_failover: Option<u32>,
+    #[serde(default)]
+    default: Vec<TargetSpec>,
+    #[serde(default)]
+    rules: Vec<RuleFile>,
+}
+
+/// Routing rules and failover settings
+#[derive(Debug, Clone)]
+pub struct RoutingPolicy {
+    rules: Vec<RouteRule>,
+    // Chain for generation requests no rule matches
+    default: Vec<ModelTarget>,
+    fallback_on: Vec<String>,
+    retries_before_failover: u32,
+}
+
+impl Default for RoutingPolicy {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            default: Vec::new(),
+            fallback_on: DEFAULT_FALLBACK_ON.iter().map(|c| c.to_string()).collect(),
+            retries_before_failover: DEFAULT_RETRIES_BEFORE_FAILOVER,
+        }
+    }
+}
+
+impl RoutingPolicy {
+    /// Load the policy named by LLM_ROUTING_FILE; an unreadable or invalid file disables routing
+    pub fn from_env() -> Self {
+        let Ok(path) = env::var("LLM_ROUTING_FILE") else {
+            return Self::default();
+        };
+        if path.trim().is_empty() {
+            return Self::default();
+        }
+
+        match fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|source| Self::parse(&source))
+        {
+            Ok(policy) => {
+                log::info!(
+                    "Loaded routing policy from {} ({} rules, {} default targets)",
+                    path,
+                    policy.rules.len(),
+                    policy.default.len()
+                );
+                policy
+            }
+            Err(e) => {
+                log::error!(
+                    "Failed to load routing policy {}: {}; routing disabled",
+                    path,
+                    e
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// Parse and validate a TOML policy
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let file: PolicyFile = toml::from_str(source).map_err(|e| e.to_string())?;
+        let mut policy = Self::default();
+
+        if let Some(fallback_on) = file.fallback_on {
+            if let Some(unknown) = fallback_on
+                .iter()
+                .find(|c| !ERROR_CATEGORIES.contains(&c.as_str()))
+            {
+                return Err(format!(
+                    "Unknown fallback_on category '{}' (expected one of: {})",
+                    unknown,
+                    ERROR_CATEGORIES.join(", ")
+                ));
+            }
+            policy.fallback_on = fallback_on;
+        }
+        if let Some(retries) = file.retries_before_failover {
+            policy.retries_before_failover = retries;
+        }
+        policy.default = targets_from_specs("default", file.default, false)?;
+
+        for rule in file.rules {
+            let request_types: Vec<String> = rule
+                .request_types
+                .iter()
+                .map(|t| t.trim().to_ascii_lowercase())
+                .collect();
+            let embedding = request_types.iter().any(|t| t == EMBEDDING);
+            if embedding && request_types.len() > 1 {
+                return Err(format!(
+                    "Rule '{}' mixes '{}' with generation request types",
+                    rule.name, EMBEDDING
+                ));
+            }
+            let budgets = rule
+                .budgets
+                .iter()
+                .map(|b| {
+                    BudgetHint::parse(b)
+                        .ok_or_else(|| format!("Rule '{}' has unknown budget '{}'", rule.name, b))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let targets = targets_from_specs(&rule.name, rule.targets, embedding)?;
+            if targets.is_empty() {
+                return Err(format!("Rule '{}' has no targets", rule.name));
+            }
+
+            policy.rules.push(RouteRule {
+                name: rule.name,
+                request_types,
+                min_prompt_tokens: rule.min_prompt_tokens,
+                max_prompt_tokens: rule.max_prompt_tokens,
+                budgets,
+                targets,
+            });
+        }
+
+        Ok(policy)
+    }
+
+    /// Whether any routing is configured
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty() && self.default.is_empty()
+    }
+
+    /// First matching rule, else the default chain (generation requests only)
+    pub fn select(&self, request: &RouteRequest) -> Option<Route> {
+        if let Some(rule) = self.rules.iter().find(|rule| rule.matches(request)) {
+            return Some(Route {
+                name: rule.name.clone(),
+                targets: rule.targets.clone(),
+            });
+        }
+        if request.request_type == EMBEDDING || self.default.is_empty() {
+            return None;
+        }
+        Some(Route {
+            name: "default".to_string(),
+            targets: self.default.clone(),
+        })
+    }
+
+    /// Whether an error moves the request on to the next target
+    pub fn fails_over(&self, error: &LLMError) -> bool {
+        let category = error_category(error);
+        self.fallback_on.iter().any(|c| c == category)
+    }
+
+    /// Retries spent on a target before failing over to the next one
+    pub fn retries_before_failover(&self) -> u32 {
+        self.retries_before_failover
+    }
+}
+
+fn targets_from_specs(
+    rule: &str,
+    specs: Vec<TargetSpec>,
+    embedding: bool,
+) -> Result<Vec<ModelTarget>, String> {
+    specs
+        .into_iter()
+        .map(|spec| {
+            let target = match spec {
+                TargetSpec::Short(spec) => ModelTarget::parse(&spec),
+                TargetSpec::Full {
+                    provider,
+                    model,
+                    api_url,
+                } => ModelTarget {
+                    provider: provider.trim().to_ascii_lowercase(),
+                    model: model.filter(|m| !m.trim().is_empty()),
+                    api_url: api_url.filter(|u| !u.trim().is_empty()),
+                },
+            };
+
+            let known = if embedding {
+                embeddings::is_known_provider(&target.provider)
+            } else {
+                ProviderKind::parse(&target.provider).is_some()
+            };
+            if !known {
+                return Err(format!(
+                    "Rule '{}' targets unknown {}provider '{}'",
+                    rule,
+                    if embedding { "embedding " } else { "" },
+                    target.provider
+                ));
+            }
+            if target.api_url.is_some() && !embedding {
+                return Err(format!(
+                    "Rule '{}': api_url is only supported for embedding targets",
+                    rule
+                ));
+            }
+            Ok(target)
+        })
+        .collect()
+}
+
+/// The route a generation took, for response metadata
+#[derive(Debug, Clone, Default)]
+pub struct RouteReport {
+    /// Matched rule; None when routing did not apply
+    pub route: Option<String>,
+    /// Targets that failed before the one that answered, with their error category
+    pub failed: Vec<(String, &'static str)>,
+}
+
+impl RouteReport {
+    pub fn write_metadata(&self, meta: &mut HashMap<String, String>) {
+        if let Some(route) = &self.route {
+            meta.insert("route".to_string(), route.clone());
+        }
+        if !self.failed.is_empty() {
+            meta.insert("fallback_count".to_string(), self.failed.len().to_string());
+            meta.insert(
+                "failed_targets".to_string(),
+                self.failed
+                    .iter()
+                    .map(|(target, category)| format!("{} ({})", target, category))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: &str = r#"
+        fallback_on = ["rate_limited", "server_error"]
+        default = ["openai:gpt-4o-mini", "local:llama3:8b"]
+
+        [[rules]]
+        name = "planning"
+        request_types = ["planning"]
+        budgets = ["standard", "high"]
+        targets = ["anthropic:claude-3-5-sonnet-latest", "openai:gpt-4o"]
+
+        [[rules]]
+        name = "long-context"
+        min_prompt_tokens = 30000
+        targets = ["anthropic"]
+
+        [[rules]]
+        name = "embeddings"
+        request_types = ["embedding"]
+        targets = [{ provider = "ollama", model = "nomic-embed-text", api_url = "http://gpu:11434/api/embed" }]
+    "#;
+
+    fn request(
+        request_type: &str,
+        prompt_tokens: usize,
+        budget: Option<BudgetHint>,
+    ) -> RouteRequest<'_> {
+        RouteRequest {
+            request_type,
+            prompt_tokens,
+            budget,
+        }
+    }
+
+    #[test]
+    fn test_rules_match_in_order() {
+        let policy = RoutingPolicy::parse(POLICY).unwrap();
+
+        let route = policy.select(&request("planning", 100, None)).unwrap();
+        assert_eq!(route.name, "planning");
+        assert_eq!(route.targets[0].provider, "anthropic");
+        assert_eq!(route.targets[1].model.as_deref(), Some("gpt-4o"));
+
+        // A low budget skips the planning rule and falls through to the default chain
+        let route = policy
+            .select(&request("planning", 100, Some(BudgetHint::Low)))
+            .unwrap();
+        assert_eq!(route.name, "default");
+        assert_eq!(route.targets[1].model.as_deref(), Some("llama3:8b"));
+
+        let route = policy.select(&request("synthesis", 40000, None)).unwrap();
+        assert_eq!(route.name, "long-context");
+        assert_eq!(route.targets[0].model, None);
+
+        let route = policy.select(&request(EMBEDDING, 0, None)).unwrap();
+        assert_eq!(route.name, "embeddings");
+        assert_eq!(
+            route.targets[0].api_url.as_deref(),
+            Some("http://gpu:11434/api/embed")
+        );
+    }
+
+    #[test]
+    fn test_fallback_categories() {
+        let policy = RoutingPolicy::parse(POLICY).unwrap();
+        assert!(policy.fails_over(&LLMError::RateLimitExceeded(String::new())));
+        assert!(!policy.fails_over(&LLMError::NetworkError(String::new())));
+        assert!(!policy.fails_over(&LLMError::InvalidRequest(String::new())));
+
+        let default = RoutingPolicy::default();
+        assert!(default.is_empty());
+        assert!(default.fails_over(&LLMError::NetworkError(String::new())));
+        assert!(default.select(&request(GENERAL, 10, None)).is_none());
+    }
+
+    #[test]
+    fn test_invalid_policies_are_rejected() {
+        assert!(RoutingPolicy::parse("fallback_on = [\"teapot\"]").is_err());
+        assert!(RoutingPolicy::parse(
+            "[[rules]]\nname = \"x\"\ntargets = [\"nosuchprovider:model\"]"
+        )
+        .is_err());
+        assert!(RoutingPolicy::parse(
+            "[[rules]]\nname = \"x\"\nbudgets = [\"huge\"]\ntargets = [\"openai\"]"
+        )
+        .is_err());
+        assert!(RoutingPolicy::parse(
+            "[[rules]]\nname = \"x\"\nrequest_types = [\"embedding\", \"planning\"]\ntargets = [\"openai\"]"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_example_policy_parses() {
+        let policy = RoutingPolicy::parse(include_str!("../routing.example.toml")).unwrap();
+        let route = policy.select(&request(EMBEDDING, 0, None)).unwrap();
+        assert_eq!(route.targets.len(), 2);
+    }
+
+    #[test]
+    fn test_report_metadata() {
+        let mut meta = HashMap::new();
+        RouteReport {
+            route: Some("planning".to_string()),
+            failed: vec![(
+                "anthropic:claude-3-5-sonnet-latest".to_string(),
+                "rate_limited",
+            )],
+        }
+        .write_metadata(&mut meta);
+        assert_eq!(meta["route"], "planning");
+        assert_eq!(meta["fallback_count"], "1");
+        assert_eq!(
+            meta["failed_targets"],
+            "anthropic:claude-3-5-sonnet-latest (rate_limited)"
+        );
+    }
+}
@@ -0,0 +1,295 @@
+// llm-service-rs/src/structured_output.rs
+//
+// Schema-constrained (JSON mode) generation
+//
+// This module provides:
+// - `ResponseSchema`: a compiled JSON Schema supplied with a GenerateRequest
+// - Extraction of the JSON value from a model response (code fences and prose tolerated)
+// - Validation with error messages suitable for feeding back to the model
+// - `StructuredReport`: attempt count and outcome written into response metadata
+//
+// The schema is always described in the system prompt. Providers that support
+// native structured output (OpenAI-compatible `response_format`, Ollama `format`,
+// llama.cpp `json_schema`) additionally receive it in their own request field.
+//
+// Configuration (.env file):
+// - LLM_STRUCTURED_MAX_ATTEMPTS: Generations per request, including re-asks (default: 3)
+
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+// Validation errors listed in feedback to the model; the rest are summarized
+const MAX_REPORTED_ERRORS: usize = 5;
+
+/// Generations allowed per structured request, from LLM_STRUCTURED_MAX_ATTEMPTS
+pub fn max_attempts() -> u32 {
+    env::var("LLM_STRUCTURED_MAX_ATTEMPTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_MAX_ATTEMPTS)
+}
+
+/// A JSON Schema the response must conform to
+pub struct ResponseSchema {
+    schema: Value,
+    validator: jsonschema::Validator,
+}
+
+impl fmt::Debug for ResponseSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseSchema")
+            .field("schema", &self.schema)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ResponseSchema {
+    /// Parse and compile a schema sent as JSON text
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let schema: Value = serde_json::from_str(text)
+            .map_err(|e| format!("json_schema is not valid JSON: {}", e))?;
+        Self::new(schema)
+    }
+
+    pub fn new(schema: Value) -> Result<Self, String> {
+        let validator = jsonschema::validator_for(&schema)
+            .map_err(|e| format!("json_schema is not a valid JSON Schema: {}", e))?;
+        Ok(Self { schema, validator })
+    }
+
+    /// Object schema from CompileContext field definitions such as "last_action: string"
+    ///
+    /// Every named field is required; unknown type names leave the field unconstrained.
+    pub fn from_field_definitions(definitions: &[String]) -> Result<Self, String> {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for definition in definitions {
+            let (name, type_name) = match definition.split_once(':') {
+                Some((name, type_name)) => (name.trim(), type_name.trim()),
+                None => (definition.trim(), ""),
+            };
+            if name.is_empty() {
+                continue;
+            }
+            let json_type = match type_name.to_ascii_lowercase().as_str() {
+                "string" | "str" | "text" => Some("string"),
+                "number" | "float" | "double" => Some("number"),
+                "integer" | "int" => Some("integer"),
+                "boolean" | "bool" => Some("boolean"),
+                "array" | "list" => Some("array"),
+                "object" | "map" => Some("object"),
+                _ => None,
+            };
+            let property = match json_type {
+                Some(json_type) => json!({ "type": json_type }),
+                None => json!({}),
+            };
+            properties.insert(name.to_string(), property);
+            required.push(Value::String(name.to_string()));
+        }
+
+        Self::new(json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        }))
+    }
+
+    pub fn schema(&self) -> &Value {
+        &self.schema
+    }
+
+    /// The caller's system prompt extended with the output contract
+    pub fn system_prompt(&self, system_prompt: Option<&str>) -> String {
+        let contract = format!(
+            "Respond only with a single JSON value that conforms to this JSON Schema. \
+             Do not add explanations or code fences.\n\nJSON SCHEMA:\n{}",
+            self.schema
+        );
+        match system_prompt {
+            Some(system) if !system.trim().is_empty() => format!("{}\n\n{}", system, contract),
+            _ => contract,
+        }
+    }
+
+    /// Extract the JSON value from a response and validate it against the schema
+    pub fn validate(&self, output: &str) -> Result<Value, String> {
+        let value = extract_json(output)?;
+
+        let errors: Vec<String> = self
+            .validator
+            .iter_errors(&value)
+            .map(|error| {
+                let path = error.instance_path.to_string();
+                if path.is_empty() {
+                    error.to_string()
+                } else {
+                    format!("{}: {}", path, error)
+                }
+            })
+            .collect();
+
+        if errors.is_empty() {
+            return Ok(value);
+        }
+
+        let mut reason = errors
+            .iter()
+            .take(MAX_REPORTED_ERRORS)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("; ");
+        if errors.len() > MAX_REPORTED_ERRORS {
+            reason.push_str(&format!(
+                " (and {} more)",
+                errors.len() - MAX_REPORTED_ERRORS
+            ));
+        }
+        Err(format!("the JSON does not match the schema: {}", reason))
+    }
+
+    /// Prompt for a re-ask after `previous` failed validation
+    pub fn retry_prompt(&self, prompt: &str, reason: &str, previous: &str) -> String {
+        format!(
+            "{}\n\nYour previous response was rejected because {}. \
+             Respond again with JSON that conforms to the schema exactly.\n\nPREVIOUS RESPONSE:\n{}",
+            prompt, reason, previous
+        )
+    }
+}
+
+/// Outcome of a structured generation, for response metadata
+#[derive(Debug, Clone)]
+pub struct StructuredReport {
+    pub attempts: u32,
+    /// Whether the provider enforced the schema natively
+    pub native: bool,
+    pub outcome: Result<Value, String>,
+}
+
+impl StructuredReport {
+    pub fn write_metadata(&self, meta: &mut HashMap<String, String>) {
+        meta.insert("structured_attempts".to_string(), self.attempts.to_string());
+        meta.insert("structured_native".to_string(), self.native.to_string());
+        match &self.outcome {
+            Ok(value) => {
+                meta.insert("structured_output".to_string(), "valid".to_string());
+                meta.insert("validated_json".to_string(), value.to_string());
+            }
+            Err(reason) => {
+                meta.insert("structured_output".to_string(), "invalid".to_string());
+                meta.insert("validation_error".to_string(), reason.clone());
+            }
+        }
+    }
+}
+
+/// Find the JSON value in a model response, tolerating code fences and surrounding prose
+fn extract_json(output: &str) -> Result<Value, String> {
+    let trimmed = output.trim();
+    if let Ok(value) = serde_json::from_str::<Value>(trimmed) {
+        return Ok(value);
+    }
+
+    // Take the outermost object or array, whichever starts first
+    let start = trimmed.find(['{', '[']);
+    let candidate = start.and_then(|start| {
+        let close = if trimmed[start..].starts_with('{') {
+            '}'
+        } else {
+            ']'
+        };
+        trimmed
+            .rfind(close)
+            .filter(|end| *end > start)
+            .map(|end| &trimmed[start..=end])
+    });
+
+    match candidate {
+        Some(candidate) => serde_json::from_str::<Value>(candidate)
+            .map_err(|e| format!("the response is not valid JSON ({})", e)),
+        None => Err("the response does not contain JSON".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn person_schema() -> ResponseSchema {
+        ResponseSchema::new(json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "age": {"type": "integer", "minimum": 0}
+            },
+            "required": ["name", "age"]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_validates_fenced_json() {
+        let schema = person_schema();
+        let value = schema
+            .validate("Sure:\n```json\n{\"name\": \"Ada\", \"age\": 36}\n```")
+            .unwrap();
+        assert_eq!(value, json!({"name": "Ada", "age": 36}));
+    }
+
+    #[test]
+    fn test_reports_schema_violations_with_paths() {
+        let schema = person_schema();
+        let reason = schema
+            .validate(r#"{"name": "Ada", "age": -1}"#)
+            .unwrap_err();
+        assert!(reason.contains("/age"), "{}", reason);
+
+        let reason = schema.validate(r#"{"name": "Ada"}"#).unwrap_err();
+        assert!(reason.contains("age"), "{}", reason);
+
+        assert!(schema.validate("no json here").is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_schema() {
+        assert!(ResponseSchema::parse("{not json").is_err());
+        assert!(ResponseSchema::parse(r#"{"type": "no-such-type"}"#).is_err());
+    }
+
+    #[test]
+    fn test_schema_from_field_definitions() {
+        let schema = ResponseSchema::from_field_definitions(&[
+            "last_action: string".to_string(),
+            "mood_score: number".to_string(),
+            "notes".to_string(),
+        ])
+        .unwrap();
+        assert!(schema
+            .validate(r#"{"last_action": "login", "mood_score": 0.4, "notes": [1]}"#)
+            .is_ok());
+        assert!(schema
+            .validate(r#"{"last_action": 3, "mood_score": 0.4, "notes": null}"#)
+            .is_err());
+        assert!(schema.validate(r#"{"last_action": "login"}"#).is_err());
+    }
+
+    #[test]
+    fn test_report_metadata() {
+        let mut meta = HashMap::new();
+        StructuredReport {
+            attempts: 2,
+            native: true,
+            outcome: Ok(json!({"ok": true})),
+        }
+        .write_metadata(&mut meta);
+        assert_eq!(meta["structured_attempts"], "2");
+        assert_eq!(meta["structured_output"], "valid");
+        assert_eq!(meta["validated_json"], r#"{"ok":true}"#);
+    }
+}
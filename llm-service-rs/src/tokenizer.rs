@@ -0,0 +1,446 @@
+// llm-service-rs/src/tokenizer.rs
+//
+// Token counting and context-window enforcement
+//
+// This module provides:
+// - BPE token counts using the tiktoken encoding of the target model family
+//   (o200k_base for GPT-4o / o-series, cl100k_base otherwise)
+// - Context window sizes per provider and model
+// - Prompt truncation strategies applied before a request reaches the provider
+//
+// Only OpenAI models publish their tokenizer. Anthropic and local models are
+// counted with cl100k_base and reported as approximate; their budget keeps an
+// extra APPROXIMATION_MARGIN of headroom to absorb the difference.
+//
+// Configuration (.env file):
+// - LLM_CONTEXT_WINDOW: Override the context window for every model (tokens)
+// - LLM_TRUNCATION_STRATEGY: "drop_oldest" (default), "middle_out" or "reject"
+
+use std::collections::HashMap;
+use std::env;
+use tiktoken_rs::CoreBPE;
+
+use crate::providers::ProviderKind;
+
+// Per-message framing tokens added by chat formats (role markers, separators)
+const MESSAGE_OVERHEAD: usize = 4;
+
+// Tokens priming the assistant reply
+const REPLY_OVERHEAD: usize = 3;
+
+// Fraction of the window kept free when counts are approximate
+const APPROXIMATION_MARGIN: f64 = 0.10;
+
+// Separates paragraphs when dropping the oldest context
+const PARAGRAPH_SEPARATOR: &str = "\n\n";
+
+/// How an over-long prompt is made to fit the context window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TruncationStrategy {
+    /// Drop leading paragraphs, keeping the end of the prompt (usually the task)
+    DropOldest,
+    /// Keep the beginning and end of the prompt and cut the middle
+    MiddleOut,
+    /// Fail the request instead of truncating
+    Reject,
+}
+
+impl TruncationStrategy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "drop_oldest" | "oldest" => Some(TruncationStrategy::DropOldest),
+            "middle_out" | "middle" => Some(TruncationStrategy::MiddleOut),
+            "reject" | "none" | "error" => Some(TruncationStrategy::Reject),
+            _ => None,
+        }
+    }
+
+    /// LLM_TRUNCATION_STRATEGY, defaulting to drop_oldest
+    pub fn from_env() -> Self {
+        env::var("LLM_TRUNCATION_STRATEGY")
+            .ok()
+            .and_then(|v| Self::parse(&v))
+            .unwrap_or(TruncationStrategy::DropOldest)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TruncationStrategy::DropOldest => "drop_oldest",
+            TruncationStrategy::MiddleOut => "middle_out",
+            TruncationStrategy::Reject => "reject",
+        }
+    }
+}
+
+/// A BPE tokenizer matched to a model family
+#[derive(Clone)]
+pub struct TokenCounter {
+    encoding: &'static str,
+    approximate: bool,
+    bpe: &'static CoreBPE,
+}
+
+impl std::fmt::Debug for TokenCounter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenCounter")
+            .field("encoding", &self.encoding)
+            .field("approximate", &self.approximate)
+            .finish()
+    }
+}
+
+impl TokenCounter {
+    pub fn for_model(provider: ProviderKind, model: &str) -> Self {
+        // OpenAI-compatible gateways prefix models with a vendor ("openai/gpt-4o")
+        let bare = model.rsplit('/').next().unwrap_or(model);
+
+        if provider == ProviderKind::OpenAI || model.starts_with("openai/") {
+            if let Some(tiktoken_rs::tokenizer::Tokenizer::O200kBase) =
+                tiktoken_rs::tokenizer::get_tokenizer(bare)
+            {
+                return Self {
+                    encoding: "o200k_base",
+                    approximate: false,
+                    bpe: tiktoken_rs::o200k_base_singleton(),
+                };
+            }
+            let known = tiktoken_rs::tokenizer::get_tokenizer(bare).is_some();
+            return Self {
+                encoding: "cl100k_base",
+                approximate: !known,
+                bpe: tiktoken_rs::cl100k_base_singleton(),
+            };
+        }
+
+        Self {
+            encoding: "cl100k_base",
+            approximate: true,
+            bpe: tiktoken_rs::cl100k_base_singleton(),
+        }
+    }
+
+    pub fn encoding(&self) -> &'static str {
+        self.encoding
+    }
+
+    /// True when the model's real tokenizer is not available
+    pub fn is_approximate(&self) -> bool {
+        self.approximate
+    }
+
+    pub fn count(&self, text: &str) -> usize {
+        self.bpe.encode_ordinary(text).len()
+    }
+
+    /// The first `keep` tokens of `text`
+    fn head(&self, text: &str, keep: usize) -> String {
+        let tokens = self.bpe.encode_ordinary(text);
+        let mut end = keep.min(tokens.len());
+        // A cut inside a multi-byte character does not decode; back off a token
+        while end > 0 {
+            if let Ok(decoded) = self.bpe.decode(tokens[..end].to_vec()) {
+                return decoded;
+            }
+            end -= 1;
+        }
+        String::new()
+    }
+
+    /// The last `keep` tokens of `text`
+    fn tail(&self, text: &str, keep: usize) -> String {
+        let tokens = self.bpe.encode_ordinary(text);
+        let mut start = tokens.len().saturating_sub(keep);
+        while start < tokens.len() {
+            if let Ok(decoded) = self.bpe.decode(tokens[start..].to_vec()) {
+                return decoded;
+            }
+            start += 1;
+        }
+        String::new()
+    }
+}
+
+/// Context window of a model in tokens (LLM_CONTEXT_WINDOW overrides)
+pub fn context_window(provider: ProviderKind, model: &str) -> usize {
+    if let Some(window) = env::var("LLM_CONTEXT_WINDOW")
+        .ok()
+        .and_then(|v| v.parse().ok())
+    {
+        return window;
+    }
+
+    let bare = model
+        .rsplit('/')
+        .next()
+        .unwrap_or(model)
+        .to_ascii_lowercase();
+    if bare.starts_with("claude") {
+        return 200_000;
+    }
+    if bare.starts_with("gemini") {
+        return 1_000_000;
+    }
+    match provider {
+        ProviderKind::Anthropic => 200_000,
+        ProviderKind::Local => local_context_window(&bare),
+        ProviderKind::Mock => 128_000,
+        ProviderKind::OpenAI if bare.starts_with("gpt") || bare.starts_with('o') => {
+            tiktoken_rs::model::get_context_size(&bare)
+        }
+        ProviderKind::OpenAI => local_context_window(&bare),
+    }
+}
+
+// Defaults for common open-weight model families served by Ollama / llama.cpp / LM Studio
+fn local_context_window(model: &str) -> usize {
+    if model.starts_with("llama3.1")
+        || model.starts_with("llama3.2")
+        || model.starts_with("llama3.3")
+    {
+        128_000
+    } else if model.starts_with("mistral")
+        || model.starts_with("mixtral")
+        || model.starts_with("qwen")
+    {
+        32_768
+    } else {
+        8_192
+    }
+}
+
+/// Outcome of fitting a prompt into the context window
+#[derive(Debug, Clone)]
+pub struct ContextReport {
+    pub encoding: &'static str,
+    pub approximate: bool,
+    pub context_window: usize,
+    /// Prompt + system tokens actually sent
+    pub input_tokens: usize,
+    pub strategy: TruncationStrategy,
+    /// Tokens removed from the prompt; 0 when nothing was truncated
+    pub removed_tokens: usize,
+}
+
+impl ContextReport {
+    pub fn truncated(&self) -> bool {
+        self.removed_tokens > 0
+    }
+
+    pub fn write_metadata(&self, meta: &mut HashMap<String, String>) {
+        meta.insert("tokenizer".to_string(), self.encoding.to_string());
+        meta.insert(
+            "tokenizer_approximate".to_string(),
+            self.approximate.to_string(),
+        );
+        meta.insert(
+            "context_window".to_string(),
+            self.context_window.to_string(),
+        );
+        meta.insert(
+            "input_tokens_estimated".to_string(),
+            self.input_tokens.to_string(),
+        );
+        meta.insert("truncated".to_string(), self.truncated().to_string());
+        if self.truncated() {
+            meta.insert(
+                "truncation_strategy".to_string(),
+                self.strategy.as_str().to_string(),
+            );
+            meta.insert(
+                "truncated_tokens".to_string(),
+                self.removed_tokens.to_string(),
+            );
+        }
+    }
+}
+
+/// Why a prompt could not be fitted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContextOverflow {
+    pub required: usize,
+    pub available: usize,
+}
+
+/// Shrink `prompt` so that system prompt, prompt and reserved output fit the window
+///
+/// The system prompt is never truncated; if it alone exceeds the budget the
+/// request cannot be served and `ContextOverflow` is returned.
+pub fn fit_prompt(
+    counter: &TokenCounter,
+    system_prompt: &str,
+    prompt: &str,
+    context_window: usize,
+    reserved_output: usize,
+    strategy: TruncationStrategy,
+) -> Result<(String, ContextReport), ContextOverflow> {
+    let usable = if counter.approximate {
+        (context_window as f64 * (1.0 - APPROXIMATION_MARGIN)) as usize
+    } else {
+        context_window
+    };
+    let fixed = counter.count(system_prompt) + 2 * MESSAGE_OVERHEAD + REPLY_OVERHEAD;
+    let prompt_tokens = counter.count(prompt);
+    let required = fixed + prompt_tokens + reserved_output;
+
+    let mut report = ContextReport {
+        encoding: counter.encoding,
+        approximate: counter.approximate,
+        context_window,
+        input_tokens: fixed + prompt_tokens,
+        strategy,
+        removed_tokens: 0,
+    };
+
+    if required <= usable {
+        return Ok((prompt.to_string(), report));
+    }
+
+    let overflow = ContextOverflow {
+        required,
+        available: usable,
+    };
+    let budget = usable.saturating_sub(fixed + reserved_output);
+    if budget == 0 || strategy == TruncationStrategy::Reject {
+        return Err(overflow);
+    }
+
+    // Re-encoding the joined pieces can merge tokens differently at the seams,
+    // so shrink the target until the result is within budget
+    let mut target = budget;
+    let (fitted, fitted_tokens) = loop {
+        let fitted = match strategy {
+            TruncationStrategy::DropOldest => drop_oldest(counter, prompt, target),
+            TruncationStrategy::MiddleOut => middle_out(counter, prompt, prompt_tokens, target),
+            TruncationStrategy::Reject => unreachable!(),
+        };
+        let tokens = counter.count(&fitted);
+        if tokens <= budget || target == 0 {
+            break (fitted, tokens);
+        }
+        target = target.saturating_sub(tokens - budget);
+    };
+
+    report.input_tokens = fixed + fitted_tokens;
+    report.removed_tokens = prompt_tokens.saturating_sub(fitted_tokens);
+    Ok((fitted, report))
+}
+
+// Remove whole paragraphs from the front, then cut the remaining head by tokens
+fn drop_oldest(counter: &TokenCounter, prompt: &str, budget: usize) -> String {
+    let paragraphs: Vec<&str> = prompt.split(PARAGRAPH_SEPARATOR).collect();
+    let separator_tokens = counter.count(PARAGRAPH_SEPARATOR);
+
+    let mut kept: Vec<&str> = Vec::new();
+    let mut used = 0;
+    for paragraph in paragraphs.iter().rev() {
+        let cost = counter.count(paragraph) + if kept.is_empty() { 0 } else { separator_tokens };
+        if used + cost > budget {
+            if kept.is_empty() {
+                // Even the final paragraph is too long; keep its end
+                return counter.tail(paragraph, budget);
+            }
+            break;
+        }
+        used += cost;
+        kept.push(paragraph);
+    }
+
+    kept.reverse();
+    kept.join(PARAGRAPH_SEPARATOR)
+}
+
+// Keep equal shares of the beginning and end with a marker where text was cut
+fn middle_out(counter: &TokenCounter, prompt: &str, prompt_tokens: usize, budget: usize) -> String {
+    let marker_for = |removed: usize| format!("\n\n[... {} tokens omitted ...]\n\n", removed);
+    let marker_tokens = counter.count(&marker_for(prompt_tokens));
+    let keep = budget.saturating_sub(marker_tokens);
+    if keep == 0 {
+        return counter.tail(prompt, budget);
+    }
+
+    let head = counter.head(prompt, keep / 2);
+    let tail = counter.tail(prompt, keep - keep / 2);
+    format!("{}{}{}", head, marker_for(prompt_tokens - keep), tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counter() -> TokenCounter {
+        TokenCounter::for_model(ProviderKind::OpenAI, "gpt-4o")
+    }
+
+    #[test]
+    fn test_model_family_selects_encoding() {
+        assert_eq!(counter().encoding(), "o200k_base");
+        assert!(!counter().is_approximate());
+
+        let gpt4 = TokenCounter::for_model(ProviderKind::OpenAI, "gpt-4");
+        assert_eq!(gpt4.encoding(), "cl100k_base");
+        assert!(
+            TokenCounter::for_model(ProviderKind::Anthropic, "claude-3-5-sonnet-latest")
+                .is_approximate()
+        );
+
+        assert_eq!(
+            context_window(ProviderKind::Anthropic, "claude-3-opus"),
+            200_000
+        );
+        assert_eq!(context_window(ProviderKind::Local, "llama3"), 8_192);
+    }
+
+    #[test]
+    fn test_prompt_within_window_is_untouched() {
+        let (prompt, report) = fit_prompt(
+            &counter(),
+            "system",
+            "hello world",
+            1000,
+            100,
+            TruncationStrategy::DropOldest,
+        )
+        .unwrap();
+        assert_eq!(prompt, "hello world");
+        assert!(!report.truncated());
+    }
+
+    #[test]
+    fn test_drop_oldest_keeps_the_final_paragraph() {
+        let context = "old context sentence. ".repeat(50);
+        let prompt = format!("{}\n\n{}\n\nQuestion: what now?", context, context);
+        let (fitted, report) = fit_prompt(
+            &counter(),
+            "",
+            &prompt,
+            400,
+            100,
+            TruncationStrategy::DropOldest,
+        )
+        .unwrap();
+
+        assert!(report.truncated());
+        assert!(fitted.ends_with("Question: what now?"));
+        assert!(report.input_tokens + 100 <= 400);
+    }
+
+    #[test]
+    fn test_middle_out_and_reject() {
+        let prompt = format!("BEGIN {} END", "filler words here ".repeat(200));
+        let (fitted, report) = fit_prompt(
+            &counter(),
+            "",
+            &prompt,
+            300,
+            50,
+            TruncationStrategy::MiddleOut,
+        )
+        .unwrap();
+        assert!(fitted.starts_with("BEGIN"));
+        assert!(fitted.ends_with("END"));
+        assert!(fitted.contains("tokens omitted"));
+        assert!(report.input_tokens + 50 <= 300);
+
+        assert!(fit_prompt(&counter(), "", &prompt, 300, 50, TruncationStrategy::Reject).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+// llm-service-rs/src/tool_calling.rs
+//
+// Native tool calling for GenerateText
+//
+// This module provides:
+// - Validation of `ToolDefinition`s into provider-neutral `ToolSpec`s
+// - Conversion of earlier tool-use rounds (`ToolTurn`) into chat history
+// - Conversion of provider tool calls back into the proto `ToolCall`
+//
+// Each provider maps `ToolSpec` / `ToolCall` onto its own wire format (OpenAI
+// `tools` + `tool_calls`, Anthropic `tool_use` / `tool_result` blocks, Ollama
+// `tools`). Callers run the loop: execute the returned calls (e.g. through
+// tools-service), then send them back as a `ToolTurn` with the results.
+
+use serde_json::{json, Value};
+
+use crate::agi_core;
+use crate::providers::{ChatMessage, ToolCall, ToolSpec};
+
+// Name limit shared by the OpenAI and Anthropic tool APIs
+const MAX_TOOL_NAME_LEN: usize = 64;
+
+/// Validate tool definitions and parse their parameter schemas
+pub fn tools_from_proto(definitions: &[agi_core::ToolDefinition]) -> Result<Vec<ToolSpec>, String> {
+    let mut tools: Vec<ToolSpec> = Vec::with_capacity(definitions.len());
+
+    for definition in definitions {
+        let name = definition.name.trim();
+        let valid_name = !name.is_empty()
+            && name.len() <= MAX_TOOL_NAME_LEN
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid_name {
+            return Err(format!(
+                "Invalid tool name '{}': use 1-{} letters, digits, '_' or '-'",
+                definition.name, MAX_TOOL_NAME_LEN
+            ));
+        }
+        if tools.iter().any(|tool| tool.name == name) {
+            return Err(format!("Duplicate tool name '{}'", name));
+        }
+
+        let parameters = if definition.parameters_schema.trim().is_empty() {
+            json!({"type": "object", "properties": {}})
+        } else {
+            match serde_json::from_str::<Value>(&definition.parameters_schema) {
+                Ok(schema @ Value::Object(_)) => schema,
+                Ok(_) => {
+                    return Err(format!(
+                        "Parameters schema of tool '{}' must be a JSON object",
+                        name
+                    ))
+                }
+                Err(e) => {
+                    return Err(format!(
+                        "Parameters schema of tool '{}' is not valid JSON: {}",
+                        name, e
+                    ))
+                }
+            }
+        };
+
+        tools.push(ToolSpec {
+            name: name.to_string(),
+            description: definition.description.clone(),
+            parameters,
+        });
+    }
+
+    Ok(tools)
+}
+
+/// Chat history for earlier rounds: each turn's calls followed by their results
+pub fn history_from_proto(turns: &[agi_core::ToolTurn]) -> Vec<ChatMessage> {
+    let mut history = Vec::new();
+
+    for turn in turns {
+        history.push(ChatMessage::tool_calls(
+            turn.calls
+                .iter()
+                .map(|call| ToolCall {
+                    id: call.id.clone(),
+                    name: call.name.clone(),
+                    arguments: call.arguments.clone(),
+                })
+                .collect(),
+        ));
+        for result in &turn.results {
+            let content = if result.is_error {
+                format!("Error: {}", result.content)
+            } else {
+                result.content.clone()
+            };
+            history.push(ChatMessage::tool_result(result.call_id.clone(), content));
+        }
+    }
+
+    history
+}
+
+/// Provider tool calls in proto form; malformed arguments are passed through as-is
+pub fn calls_to_proto(calls: Vec<ToolCall>) -> Vec<agi_core::ToolCall> {
+    calls
+        .into_iter()
+        .map(|call| agi_core::ToolCall {
+            id: call.id,
+            name: call.name,
+            arguments: if call.arguments.trim().is_empty() {
+                "{}".to_string()
+            } else {
+                call.arguments
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(name: &str, schema: &str) -> agi_core::ToolDefinition {
+        agi_core::ToolDefinition {
+            name: name.to_string(),
+            description: "test tool".to_string(),
+            parameters_schema: schema.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_tool_definitions_are_validated() {
+        let tools = tools_from_proto(&[
+            definition(
+                "web_search",
+                r#"{"type":"object","properties":{"q":{"type":"string"}}}"#,
+            ),
+            definition("clock", ""),
+        ])
+        .unwrap();
+        assert_eq!(tools.len(), 2);
+        assert_eq!(tools[1].parameters["type"], "object");
+
+        assert!(tools_from_proto(&[definition("file.read", "")]).is_err());
+        assert!(tools_from_proto(&[definition("a", "[]")]).is_err());
+        assert!(tools_from_proto(&[definition("a", ""), definition("a", "")]).is_err());
+    }
+
+    #[test]
+    fn test_history_pairs_calls_with_results() {
+        let history = history_from_proto(&[agi_core::ToolTurn {
+            calls: vec![agi_core::ToolCall {
+                id: "call_1".to_string(),
+                name: "web_search".to_string(),
+                arguments: r#"{"q":"rust"}"#.to_string(),
+            }],
+            results: vec![agi_core::ToolCallResult {
+                call_id: "call_1".to_string(),
+                content: "timeout".to_string(),
+                is_error: true,
+            }],
+        }]);
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].role, "assistant");
+        assert_eq!(history[0].tool_calls[0].name, "web_search");
+        assert_eq!(history[1].tool_call_id.as_deref(), Some("call_1"));
+        assert_eq!(history[1].content, "Error: timeout");
+    }
+}
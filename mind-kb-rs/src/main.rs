@@ -95,6 +95,7 @@ impl MindKBServer {
                     let request = LlmProcessRequest {
                         operation: "embed".to_string(),
                         text: text.to_string(),
+                        parameters: HashMap::new(),
                     };
 
                     match client.embed_text(request)
@@ -31,9 +31,13 @@ pub mod agi_core {
 use agi_core::{
     AgiResponse, // Added for unified response format
     ContextRequest,
+    DescribeToolsRequest,
+    DescribeToolsResponse,
     EthicsCheckRequest,
     EthicsCheckResponse,
+    GenerateFromTemplateRequest,
     GenerateRequest,
+    GenerateResponse,
     GetAgentRequest,
     HealthRequest,
     HealthResponse,
@@ -56,6 +60,29 @@ use agi_core::{
 
 // 3. Orchestration planning and error types
 
+// JSON Schema for `Plan`; the LLM Service re-asks until the plan conforms
+const PLAN_SCHEMA: &str = r#"{
+  "type": "object",
+  "properties": {
+    "steps": {
+      "type": "array",
+      "items": {
+        "type": "object",
+        "properties": {
+          "id": {"type": "string"},
+          "action": {"type": "string", "enum": ["llm", "kb", "tools", "safety", "final"]},
+          "description": {"type": "string"},
+          "target_service": {"type": "string"},
+          "tool_name": {"type": "string"},
+          "tool_parameters": {"type": "object", "additionalProperties": {"type": "string"}}
+        },
+        "required": ["id", "action", "description"]
+      }
+    }
+  },
+  "required": ["steps"]
+}"#;
+
 #[derive(Debug, serde::Deserialize)]
 struct Plan {
     steps: Vec<PlanStep>,
@@ -414,6 +441,65 @@ impl OrchestratorServer {
             .ok_or_else(|| Status::unavailable("Data Router Service client not initialized"))
     }
 
+    /// Fetch the Tools Service catalog via the Data Router and render it for the planning prompt
+    /// Returns an empty string if the catalog is unavailable so planning proceeds without it
+    async fn fetch_tool_catalog(
+        &self,
+        router_client: &mut DataRouterServiceClient<tonic::transport::Channel>,
+        request_id: &str,
+    ) -> String {
+        let describe_req = DescribeToolsRequest {
+            category: String::new(),
+            tool_ids: Vec::new(),
+        };
+
+        let route_request = RouteRequest {
+            target_service: "tools-service".to_string(),
+            request: Some(ProtoRequest {
+                id: format!("{}-tools", request_id),
+                service: "tools-service".to_string(),
+                method: "describe_tools".to_string(),
+                payload: describe_req.encode_to_vec(),
+                metadata: std::collections::HashMap::new(),
+            }),
+        };
+
+        let response = match router_client.route(tonic::Request::new(route_request)).await {
+            Ok(resp) => resp.into_inner().response,
+            Err(e) => {
+                log::warn!("Tool catalog unavailable (planning without it): {}", e);
+                return String::new();
+            }
+        };
+
+        let described = match response
+            .map(|r| DescribeToolsResponse::decode(r.payload.as_slice()))
+        {
+            Some(Ok(described)) => described,
+            _ => {
+                log::warn!("Tools Service returned no tool catalog");
+                return String::new();
+            }
+        };
+
+        if described.tools.is_empty() {
+            return String::new();
+        }
+
+        let mut catalog = String::from(
+            "Available tools (for 'tools' steps, set 'tool_name' to a tool id, optionally pinned as id@version, and 'tool_parameters' to string values matching its parameters schema):\n",
+        );
+        for tool in &described.tools {
+            catalog.push_str(&format!(
+                "- {} (v{}, {}): {}\n  parameters: {}\n",
+                tool.id, tool.version, tool.category, tool.description, tool.parameters_schema
+            ));
+        }
+
+        log::info!("Including {} tool(s) in planning prompt", described.tools.len());
+        catalog
+    }
+
     /// Initialize the Reflection Service client
     pub async fn init_reflection_client(
         &self,
@@ -685,22 +771,43 @@ impl OrchestratorService for OrchestratorServer {
         }
 
         // Step 1: Call LLM Service via Data Router to generate a plan
-        // Create a request to LLM Service for planning
+        // The planning prompt is the "orchestrator_planning" template managed by the LLM Service
+        // and lists the registered tools so plans only reference real tools and parameters
+        let tool_catalog = match req_data.metadata.get("tool_preference") {
+            Some(pref) if pref.eq_ignore_ascii_case("disable") => String::new(),
+            _ => self.fetch_tool_catalog(&mut router_client, &req_data.id).await,
+        };
         let planning_request = ProtoRequest {
             id: format!("{}-plan", req_data.id),
             service: "llm-service".to_string(),
-            method: "generate_text".to_string(),
+            method: "generate_from_template".to_string(),
             payload: {
-                let generate_req = GenerateRequest {
-                    prompt: format!(
-                        "Context: {}\n\nTask: Break down this request into actionable steps: {}. Return a JSON list of steps, each with 'action' (llm, tools, kb, safety) and 'description'.",
-                        enriched_prompt, user_query
-                    ),
-                    parameters: std::collections::HashMap::new(),
+                let template_req = GenerateFromTemplateRequest {
+                    template_name: "orchestrator_planning".to_string(),
+                    version: String::new(),
+                    variables: {
+                        let mut vars = std::collections::HashMap::new();
+                        vars.insert("context".to_string(), enriched_prompt.clone());
+                        vars.insert("query".to_string(), user_query.to_string());
+                        vars.insert("tools".to_string(), tool_catalog.clone());
+                        vars
+                    },
+                    // Keep a user on the same template version across requests
+                    selection_key: req_data
+                        .metadata
+                        .get("user_id")
+                        .cloned()
+                        .unwrap_or_default(),
+                    // Lets the LLM Service routing policy pick the planning model
+                    parameters: std::collections::HashMap::from([(
+                        "request_type".to_string(),
+                        "planning".to_string(),
+                    )]),
+                    json_schema: PLAN_SCHEMA.to_string(),
                 };
                 let mut buf = Vec::new();
-                generate_req.encode(&mut buf).map_err(|e| {
-                    Status::internal(format!("Failed to encode GenerateRequest: {}", e))
+                template_req.encode(&mut buf).map_err(|e| {
+                    Status::internal(format!("Failed to encode GenerateFromTemplateRequest: {}", e))
                 })?;
                 buf
             },
@@ -733,7 +840,15 @@ impl OrchestratorService for OrchestratorServer {
 
         let planning_data = planning_response.into_inner();
         let plan_text = if let Some(plan_resp) = planning_data.response {
-            String::from_utf8_lossy(&plan_resp.payload).to_string()
+            // Prefer the schema-validated plan over the raw generated text
+            match GenerateResponse::decode(plan_resp.payload.as_slice()) {
+                Ok(generated) => generated
+                    .metadata
+                    .get("validated_json")
+                    .cloned()
+                    .unwrap_or(generated.text),
+                Err(_) => String::from_utf8_lossy(&plan_resp.payload).to_string(),
+            }
         } else {
             log::warn!("LLM Service returned empty planning response, using direct execution");
             "Direct execution".to_string()
@@ -747,12 +862,14 @@ impl OrchestratorService for OrchestratorServer {
             kb_notes: Vec<String>,
             tool_results: Vec<String>,
             llm_intermediate_answers: Vec<String>,
+            artifact_urls: Vec<String>,
         }
 
         let mut exec_ctx = ExecutionContext {
             kb_notes: Vec::new(),
             tool_results: Vec::new(),
             llm_intermediate_answers: Vec::new(),
+            artifact_urls: Vec::new(),
         };
 
         let tool_preference = req_data
@@ -928,6 +1045,11 @@ impl OrchestratorService for OrchestratorServer {
                     let tool_request = ToolRequest {
                         tool_name: tool_name.clone(),
                         parameters,
+                        session_id: req_data
+                            .metadata
+                            .get("session_id")
+                            .cloned()
+                            .unwrap_or_default(),
                     };
 
                     let mut tool_payload = Vec::new();
@@ -1051,6 +1173,17 @@ impl OrchestratorService for OrchestratorServer {
                             .unwrap_or("generated result")
                     ));
 
+                    // Tools that ran code through the executor report collected artifacts
+                    // as a comma-separated list of artifact:// urls
+                    if let Some(urls) = tool_response.metadata.get("artifact_urls") {
+                        exec_ctx.artifact_urls.extend(
+                            urls.split(',')
+                                .map(str::trim)
+                                .filter(|url| !url.is_empty())
+                                .map(String::from),
+                        );
+                    }
+
                     // Also keep full tool result for potential future synthesis usage
                     exec_ctx.tool_results.push(tool_response.result);
                 }
@@ -1118,8 +1251,16 @@ impl OrchestratorService for OrchestratorServer {
                 "orchestration_mode".to_string(),
                 "plan_and_execute".to_string(),
             );
-
-            let generate_req = GenerateRequest { prompt, parameters };
+            parameters.insert("request_type".to_string(), "synthesis".to_string());
+
+            let generate_req = GenerateRequest {
+                prompt,
+                parameters,
+                json_schema: String::new(),
+                tools: Vec::new(),
+                tool_choice: String::new(),
+                tool_turns: Vec::new(),
+            };
 
             let mut buf = Vec::new();
             generate_req.encode(&mut buf).map_err(|e| {
@@ -1164,7 +1305,7 @@ impl OrchestratorService for OrchestratorServer {
         let execution_data = execution_response.into_inner();
 
         // Phase 5: Response Aggregation - Build AgiResponse
-        let mut output_artifacts = Vec::new();
+        let output_artifacts = std::mem::take(&mut exec_ctx.artifact_urls);
         let final_answer;
         let execution_plan_details;
         let routed_service = execution_data.routed_to.clone();
@@ -0,0 +1,194 @@
+//! Auth Service Client
+//!
+//! Checks that callers issuing capability grants hold the matching RBAC
+//! permission in auth-service-rs, and forwards capability audit events to the
+//! auth service audit log. Pushing audit events authenticates with the service
+//! token stored in secrets-service under TOOLS_AUTH_TOKEN_SECRET (default
+//! `tools-service/auth-token`); without it events are only written to the local
+//! `audit` log target.
+
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::{mpsc, OnceCell};
+use tonic::transport::Channel;
+use tonic::Request;
+
+use crate::secrets_client::SecretsClient;
+use crate::tool_manager::{AuditEvent, Capability};
+
+pub mod auth_service {
+    tonic::include_proto!("phoenix_orch.auth_service");
+}
+
+use auth_service::auth_service_client::AuthServiceClient;
+use auth_service::{CheckPermissionRequest, PushAuditLogRequest, ValidateTokenRequest};
+
+/// Service id the RBAC permissions are scoped to
+const SERVICE_ID: &str = "tools-service";
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("Unauthenticated: {0}")]
+    Unauthenticated(String),
+
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
+    #[error("Connection error: {0}")]
+    ConnectionError(String),
+
+    #[error("GRPC error: {0}")]
+    GrpcError(#[from] tonic::Status),
+}
+
+/// Client for auth-service-rs
+pub struct AuthClient {
+    client: AuthServiceClient<Channel>,
+}
+
+static SHARED: OnceCell<Arc<AuthClient>> = OnceCell::const_new();
+
+/// RBAC resource guarding grants of one capability
+pub fn capability_resource(capability: Capability) -> String {
+    format!("{}/capabilities/{:?}", SERVICE_ID, capability)
+}
+
+impl AuthClient {
+    /// Connect using AUTH_SERVICE_ADDR/PORT
+    pub async fn new() -> Result<Self, AuthError> {
+        let auth_addr = config_rs::get_client_address("AUTH", 50090, None);
+
+        log::debug!("Connecting to auth service at: {}", auth_addr);
+        let client = AuthServiceClient::connect(auth_addr)
+            .await
+            .map_err(|e| AuthError::ConnectionError(e.to_string()))?;
+
+        Ok(Self { client })
+    }
+
+    /// Shared client, connected on first successful call
+    pub async fn shared() -> Result<Arc<AuthClient>, AuthError> {
+        SHARED
+            .get_or_try_init(|| async { Self::new().await.map(Arc::new) })
+            .await
+            .cloned()
+    }
+
+    /// Subject (user or service id) of a valid token
+    pub async fn token_subject(&self, token: &str) -> Result<String, AuthError> {
+        let response = self
+            .client
+            .clone()
+            .validate_token(Request::new(ValidateTokenRequest {
+                token: token.to_string(),
+            }))
+            .await?
+            .into_inner();
+
+        match response.token_data {
+            Some(data) if response.is_valid => Ok(data.subject),
+            _ => Err(AuthError::Unauthenticated("invalid token".to_string())),
+        }
+    }
+
+    /// Require `permission` on each capability's resource; returns the issuer's subject
+    pub async fn authorize_capabilities(
+        &self,
+        token: &str,
+        permission: &str,
+        capabilities: &[Capability],
+    ) -> Result<String, AuthError> {
+        let subject = self.token_subject(token).await?;
+
+        for capability in capabilities {
+            let resource = capability_resource(*capability);
+            let allowed = self
+                .client
+                .clone()
+                .check_permission(Request::new(CheckPermissionRequest {
+                    token: token.to_string(),
+                    permission: permission.to_string(),
+                    service_id: SERVICE_ID.to_string(),
+                    resource: Some(resource.clone()),
+                }))
+                .await?
+                .into_inner()
+                .has_permission;
+            if !allowed {
+                return Err(AuthError::PermissionDenied(format!(
+                    "{} may not {} on {}",
+                    subject, permission, resource
+                )));
+            }
+        }
+
+        Ok(subject)
+    }
+
+    async fn push_audit_event(&self, token: &str, event: &AuditEvent) -> Result<(), AuthError> {
+        let mut metadata = HashMap::new();
+        metadata.insert("capabilities".to_string(), event.capability_list());
+        metadata.insert("message".to_string(), event.detail.clone());
+        metadata.insert("timestamp".to_string(), event.timestamp.to_string());
+        if let Some(tool_id) = &event.tool_id {
+            metadata.insert("tool_id".to_string(), tool_id.clone());
+        }
+
+        let mut request = Request::new(PushAuditLogRequest {
+            event_type: event.event_type.as_str().to_string(),
+            actor: event.actor.clone(),
+            action: event.event_type.as_str().to_string(),
+            resource_type: "session_capability".to_string(),
+            resource_id: event.session_id.clone(),
+            service_id: SERVICE_ID.to_string(),
+            metadata,
+            status: if event.allowed { "SUCCESS" } else { "FAILURE" }.to_string(),
+            client_ip: None,
+            session_id: Some(event.session_id.clone()),
+            request_id: None,
+        });
+        let value = format!("Bearer {}", token)
+            .parse()
+            .map_err(|_| AuthError::Unauthenticated("invalid service token".to_string()))?;
+        request.metadata_mut().insert("authorization", value);
+
+        self.client.clone().push_audit_log(request).await?;
+        Ok(())
+    }
+}
+
+/// Forward audit events to the auth service until the channel closes
+pub fn spawn_audit_forwarder(mut events: mpsc::UnboundedReceiver<AuditEvent>) {
+    let token_secret = env::var("TOOLS_AUTH_TOKEN_SECRET")
+        .unwrap_or_else(|_| "tools-service/auth-token".to_string());
+
+    tokio::spawn(async move {
+        let mut warned = false;
+        while let Some(event) = events.recv().await {
+            let result = async {
+                let token = SecretsClient::shared()
+                    .await
+                    .map_err(|e| AuthError::ConnectionError(e.to_string()))?
+                    .get_secret(&token_secret)
+                    .await
+                    .map_err(|e| AuthError::Unauthenticated(e.to_string()))?;
+                AuthClient::shared()
+                    .await?
+                    .push_audit_event(&token, &event)
+                    .await
+            }
+            .await;
+
+            match result {
+                Ok(()) => warned = false,
+                Err(e) if !warned => {
+                    log::warn!("Audit events are only logged locally: {}", e);
+                    warned = true;
+                }
+                Err(_) => {}
+            }
+        }
+    });
+}
@@ -0,0 +1,93 @@
+//! Executor Service Client
+//!
+//! Runs code through executor-rs when EXECUTOR_ADDR is set. The executor
+//! runs each command in a fresh sandboxed workspace and keeps the files
+//! matching the requested output globs as artifacts.
+
+use std::env;
+use thiserror::Error;
+use tonic::transport::Channel;
+use tonic::Request;
+
+use crate::agi_core::executor_service_client::ExecutorServiceClient;
+use crate::agi_core::{CommandRequest, CommandResponse};
+
+#[derive(Debug, Error)]
+pub enum ExecutorError {
+    #[error("Configuration error: {0}")]
+    ConfigurationError(String),
+
+    #[error("GRPC error: {0}")]
+    GrpcError(#[from] tonic::Status),
+}
+
+/// Client for executor-rs
+#[derive(Clone)]
+pub struct ExecutorClient {
+    client: ExecutorServiceClient<Channel>,
+}
+
+impl ExecutorClient {
+    /// Client for EXECUTOR_ADDR, or None when no executor is configured
+    pub fn from_env() -> Option<Self> {
+        let addr = env::var("EXECUTOR_ADDR").ok()?;
+        if addr.trim().is_empty() {
+            return None;
+        }
+
+        match Self::new(&addr) {
+            Ok(client) => Some(client),
+            Err(e) => {
+                log::error!("Ignoring EXECUTOR_ADDR: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Client for `addr`, connected on first use
+    pub fn new(addr: &str) -> Result<Self, ExecutorError> {
+        let uri = if addr.contains("://") {
+            addr.to_string()
+        } else {
+            format!("http://{}", addr)
+        };
+        let channel = Channel::from_shared(uri)
+            .map_err(|e| ExecutorError::ConfigurationError(format!("{}: {}", addr, e)))?
+            .connect_lazy();
+
+        Ok(Self {
+            client: ExecutorServiceClient::new(channel),
+        })
+    }
+
+    /// Run a command and wait for its output and artifacts
+    pub async fn execute_command(
+        &self,
+        request: CommandRequest,
+    ) -> Result<CommandResponse, ExecutorError> {
+        let response = self
+            .client
+            .clone()
+            .execute_command(Request::new(request))
+            .await?
+            .into_inner();
+        Ok(response)
+    }
+}
+
+/// Comma-separated `artifact://` urls of a response's artifacts, if any
+///
+/// Tools report them as `artifact_urls` result metadata, which the
+/// orchestrator collects into the task's output artifacts.
+pub fn artifact_urls(response: &CommandResponse) -> Option<String> {
+    if response.artifacts.is_empty() {
+        return None;
+    }
+
+    let urls: Vec<&str> = response
+        .artifacts
+        .iter()
+        .map(|artifact| artifact.url.as_str())
+        .collect();
+    Some(urls.join(","))
+}
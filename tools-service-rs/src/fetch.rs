@@ -0,0 +1,740 @@
+//! URL Fetch Tool
+//!
+//! `fetch_url` downloads a web page and returns it as readable text, so the
+//! agent can read the pages `web_search` finds. Requests are restricted by
+//! FetchConfig:
+//! - only http/https URLs without credentials
+//! - optional domain allowlist and denylist (`example.com` matches exactly,
+//!   `.example.com` matches subdomains), compared case-insensitively and
+//!   ignoring the trailing dot of a fully qualified host
+//! - hosts resolving to private or reserved addresses are refused, checked
+//!   again on every redirect and pinned for the connection so DNS rebinding
+//!   cannot bypass the check
+//! - response size and overall time limits
+//!
+//! HTML responses are reduced to text; the page title and links are returned
+//! in the result metadata.
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use input_validation_rs::validators::url as url_validators;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::Url;
+
+use crate::tool_manager::{
+    Capability, ParameterDefinition, Tool, ToolContext, ToolManagerError, ToolMetadata, ToolResult,
+};
+use crate::validation::ToolValidationError;
+
+/// Redirects followed before giving up
+const MAX_REDIRECTS: usize = 5;
+
+/// Links returned in the result metadata
+const MAX_LINKS: usize = 100;
+
+/// Limits and domain policy for `fetch_url`
+#[derive(Debug, Clone)]
+pub struct FetchConfig {
+    /// Domains that may be fetched; empty allows any domain
+    pub allowed_domains: Vec<String>,
+    /// Domains that may never be fetched
+    pub denied_domains: Vec<String>,
+    /// Response bytes read; the rest is dropped and the result marked truncated
+    pub max_bytes: usize,
+    /// Time limit for the whole fetch including redirects
+    pub timeout: Duration,
+    /// Allow private and reserved addresses (local development only)
+    pub allow_private_ips: bool,
+}
+
+impl Default for FetchConfig {
+    fn default() -> Self {
+        Self {
+            allowed_domains: Vec::new(),
+            denied_domains: Vec::new(),
+            max_bytes: 2 * 1024 * 1024,
+            timeout: Duration::from_secs(20),
+            allow_private_ips: false,
+        }
+    }
+}
+
+impl FetchConfig {
+    /// Read TOOLS_FETCH_* variables, falling back to the defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let domains = |name: &str| -> Vec<String> {
+            env::var(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(|d| d.trim().to_ascii_lowercase())
+                .filter(|d| !d.is_empty())
+                .collect()
+        };
+
+        Self {
+            allowed_domains: domains("TOOLS_FETCH_ALLOWED_DOMAINS"),
+            denied_domains: domains("TOOLS_FETCH_DENIED_DOMAINS"),
+            max_bytes: env::var("TOOLS_FETCH_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_bytes),
+            timeout: env::var("TOOLS_FETCH_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.timeout),
+            allow_private_ips: env::var("TOOLS_FETCH_ALLOW_PRIVATE_IPS")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+        }
+    }
+
+    /// Check a URL against the protocol, credential, domain and address rules
+    fn check_url(&self, url: &str) -> Result<(), ToolValidationError> {
+        let url = normalize_host(url);
+        let url = url.as_str();
+        url_validators::allowed_protocol(url, &["http", "https"])?;
+        url_validators::no_credentials(url)?;
+        if !self.allowed_domains.is_empty() {
+            let allowed = normalize_domains(&self.allowed_domains);
+            let allowed: Vec<&str> = allowed.iter().map(String::as_str).collect();
+            url_validators::allowed_domain(url, &allowed)?;
+        }
+        if !self.denied_domains.is_empty() {
+            let denied = normalize_domains(&self.denied_domains);
+            let denied: Vec<&str> = denied.iter().map(String::as_str).collect();
+            url_validators::denied_domain(url, &denied)?;
+        }
+        if !self.allow_private_ips {
+            url_validators::no_private_ip_hosts(url)?;
+        }
+        Ok(())
+    }
+}
+
+/// `url` with its host lowercased and without the trailing dot of a fully
+/// qualified name, so `blocked.example.` matches a `blocked.example` entry
+fn normalize_host(url: &str) -> String {
+    let mut parsed = match Url::parse(url) {
+        Ok(parsed) => parsed,
+        Err(_) => return url.to_string(),
+    };
+    let host = match parsed.host_str() {
+        Some(host) => host.trim_end_matches('.').to_ascii_lowercase(),
+        None => return url.to_string(),
+    };
+    if host.is_empty() || parsed.host_str() == Some(host.as_str()) {
+        return url.to_string();
+    }
+    match parsed.set_host(Some(&host)) {
+        Ok(()) => parsed.into(),
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Domain list entries in the form `normalize_host` produces
+fn normalize_domains(domains: &[String]) -> Vec<String> {
+    domains
+        .iter()
+        .map(|d| d.trim_end_matches('.').to_ascii_lowercase())
+        .collect()
+}
+
+/// A downloaded response body
+struct FetchedPage {
+    url: Url,
+    status: u16,
+    content_type: String,
+    body: Vec<u8>,
+    truncated: bool,
+}
+
+/// Text extracted from an HTML page
+#[derive(Debug, Default, PartialEq)]
+pub struct ExtractedPage {
+    pub title: Option<String>,
+    pub text: String,
+    /// Absolute http(s) links as (text, url), deduplicated by url
+    pub links: Vec<(String, String)>,
+}
+
+/// Fetch URL Tool
+pub struct FetchUrlTool {
+    metadata: ToolMetadata,
+    config: FetchConfig,
+}
+
+impl FetchUrlTool {
+    pub fn new() -> Self {
+        Self::with_config(FetchConfig::from_env())
+    }
+
+    pub fn with_config(config: FetchConfig) -> Self {
+        let mut capabilities = HashSet::new();
+        capabilities.insert(Capability::Network);
+
+        let parameters = vec![
+            ParameterDefinition {
+                name: "url".to_string(),
+                description: "http or https URL to fetch".to_string(),
+                required: true,
+                param_type: "string".to_string(),
+                default: None,
+                validation: None,
+            },
+            ParameterDefinition {
+                name: "raw".to_string(),
+                description: "Return the response body as-is instead of extracted text".to_string(),
+                required: false,
+                param_type: "boolean".to_string(),
+                default: Some("false".to_string()),
+                validation: None,
+            },
+        ];
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        Self {
+            metadata: ToolMetadata {
+                id: "fetch_url".to_string(),
+                name: "Fetch URL".to_string(),
+                description: "Fetches a web page and returns its readable text, title and links"
+                    .to_string(),
+                version: "1.0.0".to_string(),
+                author: "System".to_string(),
+                category: "external".to_string(),
+                parameters,
+                capabilities,
+                enabled: true,
+                created_at: now,
+                updated_at: now,
+                deprecated: None,
+            },
+            config,
+        }
+    }
+
+    /// Addresses to connect to for `url`, all of which must pass the address check
+    async fn resolve(&self, url: &Url) -> Result<Vec<SocketAddr>, ToolManagerError> {
+        let host = url
+            .host_str()
+            .ok_or_else(|| ToolManagerError::ExecutionError("URL has no host".to_string()))?;
+        let port = url.port_or_known_default().unwrap_or(80);
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|e| {
+                ToolManagerError::ExecutionError(format!("Failed to resolve {}: {}", host, e))
+            })?
+            .collect();
+        if addrs.is_empty() {
+            return Err(ToolManagerError::ExecutionError(format!(
+                "{} did not resolve to any address",
+                host
+            )));
+        }
+        if !self.config.allow_private_ips {
+            if let Some(addr) = addrs
+                .iter()
+                .find(|a| !url_validators::is_public_ip(&a.ip()))
+            {
+                return Err(ToolManagerError::ValidationError(
+                    ToolValidationError::SecurityThreat(format!(
+                        "{} resolves to private or reserved address {}",
+                        host,
+                        addr.ip()
+                    )),
+                ));
+            }
+        }
+        Ok(addrs)
+    }
+
+    /// Download `url`, following redirects and re-checking every hop
+    async fn fetch(&self, url: &str) -> Result<FetchedPage, ToolManagerError> {
+        let mut current = Url::parse(url)
+            .map_err(|e| ToolManagerError::ExecutionError(format!("Invalid URL: {}", e)))?;
+
+        for _ in 0..=MAX_REDIRECTS {
+            self.config
+                .check_url(current.as_str())
+                .map_err(ToolManagerError::ValidationError)?;
+            let addrs = self.resolve(&current).await?;
+
+            let mut builder = reqwest::Client::builder()
+                .redirect(reqwest::redirect::Policy::none())
+                .user_agent("phoenix-tools-service/1.0 (fetch_url)");
+            if let Some(domain) = current.domain() {
+                builder = builder.resolve_to_addrs(domain, &addrs);
+            }
+            let client = builder.build().map_err(|e| {
+                ToolManagerError::ExecutionError(format!("Failed to build HTTP client: {}", e))
+            })?;
+
+            let mut response = client.get(current.clone()).send().await.map_err(|e| {
+                ToolManagerError::ExecutionError(format!("Request to {} failed: {}", current, e))
+            })?;
+
+            if response.status().is_redirection() {
+                let location = response
+                    .headers()
+                    .get(reqwest::header::LOCATION)
+                    .and_then(|v| v.to_str().ok())
+                    .ok_or_else(|| {
+                        ToolManagerError::ExecutionError(format!(
+                            "Redirect from {} without a Location header",
+                            current
+                        ))
+                    })?;
+                current = current.join(location).map_err(|e| {
+                    ToolManagerError::ExecutionError(format!("Invalid redirect location: {}", e))
+                })?;
+                continue;
+            }
+
+            let status = response.status().as_u16();
+            let content_type = response
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+
+            let mut body = Vec::new();
+            let mut truncated = false;
+            while let Some(chunk) = response.chunk().await.map_err(|e| {
+                ToolManagerError::ExecutionError(format!("Failed to read response: {}", e))
+            })? {
+                let remaining = self.config.max_bytes - body.len();
+                if chunk.len() > remaining {
+                    body.extend_from_slice(&chunk[..remaining]);
+                    truncated = true;
+                    break;
+                }
+                body.extend_from_slice(&chunk);
+            }
+
+            return Ok(FetchedPage {
+                url: current,
+                status,
+                content_type,
+                body,
+                truncated,
+            });
+        }
+
+        Err(ToolManagerError::ExecutionError(format!(
+            "Too many redirects (more than {})",
+            MAX_REDIRECTS
+        )))
+    }
+}
+
+#[async_trait]
+impl Tool for FetchUrlTool {
+    fn metadata(&self) -> &ToolMetadata {
+        &self.metadata
+    }
+
+    fn validate_parameters(
+        &self,
+        parameters: &HashMap<String, String>,
+    ) -> Result<(), ToolManagerError> {
+        let url = parameters.get("url").ok_or_else(|| {
+            ToolManagerError::ValidationError(ToolValidationError::Other(
+                "Missing required parameter: url".to_string(),
+            ))
+        })?;
+        self.config
+            .check_url(url)
+            .map_err(ToolManagerError::ValidationError)?;
+
+        if let Some(raw) = parameters.get("raw") {
+            if raw != "true" && raw != "false" {
+                return Err(ToolManagerError::ValidationError(
+                    ToolValidationError::Other("raw must be 'true' or 'false'".to_string()),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn execute(&self, context: ToolContext) -> Result<ToolResult, ToolManagerError> {
+        let start_time = Instant::now();
+
+        let url = context.parameters.get("url").unwrap().clone();
+        let raw = context.parameters.get("raw").is_some_and(|v| v == "true");
+
+        let page = tokio::time::timeout(self.config.timeout, self.fetch(&url))
+            .await
+            .map_err(|_| {
+                ToolManagerError::ExecutionError(format!(
+                    "Fetching {} timed out after {}s",
+                    url,
+                    self.config.timeout.as_secs()
+                ))
+            })??;
+
+        let is_html = page.content_type.contains("html")
+            || (page.content_type.is_empty() && looks_like_html(&page.body));
+        let is_text = is_html
+            || page.content_type.starts_with("text/")
+            || page.content_type.contains("json")
+            || page.content_type.contains("xml")
+            || page.content_type.is_empty();
+        if !is_text {
+            return Err(ToolManagerError::ExecutionError(format!(
+                "Unsupported content type: {}",
+                page.content_type
+            )));
+        }
+
+        let body = String::from_utf8_lossy(&page.body).to_string();
+        let mut metadata = HashMap::new();
+        let data = if is_html && !raw {
+            let extracted = extract_html(&body, &page.url);
+            if let Some(title) = &extracted.title {
+                metadata.insert("title".to_string(), title.clone());
+            }
+            let links: Vec<serde_json::Value> = extracted
+                .links
+                .iter()
+                .map(|(text, href)| serde_json::json!({"text": text, "url": href}))
+                .collect();
+            metadata.insert("link_count".to_string(), links.len().to_string());
+            metadata.insert(
+                "links".to_string(),
+                serde_json::Value::Array(links).to_string(),
+            );
+            extracted.text
+        } else {
+            body
+        };
+
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        metadata.insert("url".to_string(), page.url.to_string());
+        metadata.insert("status".to_string(), page.status.to_string());
+        metadata.insert("content_type".to_string(), page.content_type);
+        metadata.insert("bytes".to_string(), page.body.len().to_string());
+        metadata.insert("truncated".to_string(), page.truncated.to_string());
+        metadata.insert("execution_time_ms".to_string(), duration_ms.to_string());
+
+        let success = page.status < 400;
+        Ok(ToolResult {
+            success,
+            error: if success {
+                String::new()
+            } else {
+                format!("HTTP status {}", page.status)
+            },
+            data,
+            metadata,
+            duration_ms,
+        })
+    }
+}
+
+fn looks_like_html(body: &[u8]) -> bool {
+    let start = String::from_utf8_lossy(&body[..body.len().min(512)]).to_ascii_lowercase();
+    start.contains("<!doctype html") || start.contains("<html")
+}
+
+static COMMENT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<!--.*?-->").unwrap());
+static TITLE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title\s*>").unwrap());
+static HIDDEN_RES: Lazy<Vec<Regex>> = Lazy::new(|| {
+    ["head", "script", "style", "noscript", "template", "svg"]
+        .iter()
+        .map(|tag| Regex::new(&format!(r"(?is)<{0}\b[^>]*>.*?</{0}\s*>", tag)).unwrap())
+        .collect()
+});
+static LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<a\b[^>]*?\bhref\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))[^>]*>(.*?)</a\s*>"#)
+        .unwrap()
+});
+static LIST_ITEM_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<li\b[^>]*>").unwrap());
+static BLOCK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)</?(br|p|div|h[1-6]|ul|ol|li|tr|table|section|article|header|footer|nav|main|aside|blockquote|pre|hr|dt|dd|figure|form)\b[^>]*>",
+    )
+    .unwrap()
+});
+static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+static ENTITY_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").unwrap());
+static SPACE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[ \t\r\f\v\u{a0}]+").unwrap());
+
+/// Reduce an HTML document to readable text, its title and its links
+pub fn extract_html(html: &str, base: &Url) -> ExtractedPage {
+    let html = COMMENT_RE.replace_all(html, "");
+
+    let title = TITLE_RE
+        .captures(&html)
+        .map(|c| collapse_whitespace(&decode_entities(&c[1])))
+        .filter(|t| !t.is_empty());
+
+    let mut body = html.to_string();
+    for re in HIDDEN_RES.iter() {
+        body = re.replace_all(&body, " ").to_string();
+    }
+
+    let mut links = Vec::new();
+    let mut seen = HashSet::new();
+    for captures in LINK_RE.captures_iter(&body) {
+        let href = captures
+            .get(1)
+            .or_else(|| captures.get(2))
+            .or_else(|| captures.get(3))
+            .map(|m| decode_entities(m.as_str().trim()))
+            .unwrap_or_default();
+        let Ok(mut resolved) = base.join(&href) else {
+            continue;
+        };
+        if !matches!(resolved.scheme(), "http" | "https") {
+            continue;
+        }
+        resolved.set_fragment(None);
+        if links.len() < MAX_LINKS && seen.insert(resolved.to_string()) {
+            let text =
+                collapse_whitespace(&decode_entities(&TAG_RE.replace_all(&captures[4], " ")));
+            links.push((text, resolved.to_string()));
+        }
+    }
+
+    let body = LIST_ITEM_RE.replace_all(&body, "\n- ");
+    let body = BLOCK_RE.replace_all(&body, "\n");
+    let body = TAG_RE.replace_all(&body, " ");
+    let body = decode_entities(&body);
+
+    let mut text = String::new();
+    let mut blank = true;
+    for line in body.lines() {
+        let line = collapse_whitespace(line);
+        if line.is_empty() || line == "-" {
+            if !blank {
+                text.push('\n');
+                blank = true;
+            }
+            continue;
+        }
+        text.push_str(&line);
+        text.push('\n');
+        blank = false;
+    }
+
+    ExtractedPage {
+        title,
+        text: text.trim_end().to_string(),
+        links,
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    SPACE_RE.replace_all(text, " ").trim().to_string()
+}
+
+fn decode_entities(text: &str) -> String {
+    ENTITY_RE
+        .replace_all(text, |caps: &regex::Captures| {
+            let entity = &caps[1];
+            let decoded = if let Some(hex) = entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+            {
+                u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+            } else if let Some(dec) = entity.strip_prefix('#') {
+                dec.parse().ok().and_then(char::from_u32)
+            } else {
+                match entity {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some(' '),
+                    "mdash" => Some('—'),
+                    "ndash" => Some('–'),
+                    "hellip" => Some('…'),
+                    "copy" => Some('©'),
+                    _ => None,
+                }
+            };
+            decoded
+                .map(String::from)
+                .unwrap_or_else(|| caps[0].to_string())
+        })
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn context(url: &str) -> ToolContext {
+        let mut parameters = HashMap::new();
+        parameters.insert("url".to_string(), url.to_string());
+        ToolContext {
+            parameters,
+            user_id: None,
+            session_id: None,
+            request_id: "test".to_string(),
+            context_data: HashMap::new(),
+        }
+    }
+
+    /// HTTP server redirecting `/` to `/page`, which serves `page`
+    async fn serve(page: String) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                let response = if request.starts_with("GET /page ") {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        page.len(),
+                        page
+                    )
+                } else {
+                    "HTTP/1.1 302 Found\r\nLocation: /page\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        base
+    }
+
+    #[test]
+    fn test_extract_html() {
+        let html = r#"<!DOCTYPE html><html><head><title> Rust &amp; You </title>
+            <style>body { color: red }</style><script>var x = "<p>hidden</p>";</script></head>
+            <body><h1>Welcome</h1><!-- comment --><p>First&nbsp;paragraph with <b>bold</b> text.</p>
+            <ul><li><a href="/docs#intro">Docs</a></li><li><a href='https://other.org/x'>Other</a></li>
+            <li><a href="mailto:a@b.c">Mail</a></li><li><a href="/docs">Docs again</a></li></ul></body></html>"#;
+        let base = Url::parse("https://example.com/start").unwrap();
+        let page = extract_html(html, &base);
+
+        assert_eq!(page.title.as_deref(), Some("Rust & You"));
+        assert_eq!(
+            page.text,
+            "Welcome\n\nFirst paragraph with bold text.\n\n- Docs\n\n- Other\n\n- Mail\n\n- Docs again"
+        );
+        assert!(!page.text.contains("hidden"));
+        assert_eq!(
+            page.links,
+            vec![
+                ("Docs".to_string(), "https://example.com/docs".to_string()),
+                ("Other".to_string(), "https://other.org/x".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_url_policy_blocks_private_and_denied_hosts() {
+        let tool = FetchUrlTool::with_config(FetchConfig {
+            allowed_domains: vec!["example.com".to_string(), ".example.org".to_string()],
+            denied_domains: vec!["blocked.example.org".to_string()],
+            ..FetchConfig::default()
+        });
+        let check = |url: &str| {
+            tool.validate_parameters(&context(url).parameters)
+                .map_err(|e| e.to_string())
+        };
+
+        assert!(check("https://example.com/page").is_ok());
+        assert!(check("https://docs.example.org/").is_ok());
+        assert!(check("https://blocked.example.org/").is_err());
+        assert!(check("https://example.com./page").is_ok());
+        assert!(check("https://elsewhere.net./").is_err());
+
+        // Trailing-dot and mixed-case hosts don't slip past the denylist
+        let deny_only = FetchUrlTool::with_config(FetchConfig {
+            denied_domains: vec!["blocked.example".to_string(), ".tracker.net".to_string()],
+            ..FetchConfig::default()
+        });
+        for url in [
+            "https://blocked.example/",
+            "https://blocked.example./",
+            "https://BLOCKED.Example../x",
+            "https://ads.tracker.net./",
+        ] {
+            assert!(
+                deny_only
+                    .validate_parameters(&context(url).parameters)
+                    .is_err(),
+                "{} should be denied",
+                url
+            );
+        }
+        assert!(deny_only
+            .validate_parameters(&context("https://open.example./").parameters)
+            .is_ok());
+        assert!(check("https://elsewhere.net/").is_err());
+        assert!(check("ftp://example.com/file").is_err());
+        assert!(check("https://user:pw@example.com/").is_err());
+
+        let open = FetchUrlTool::with_config(FetchConfig::default());
+        for url in [
+            "http://127.0.0.1:8080/",
+            "http://localhost/",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/",
+        ] {
+            assert!(
+                open.validate_parameters(&context(url).parameters).is_err(),
+                "{} should be blocked",
+                url
+            );
+        }
+
+        // Domains are checked again after resolution
+        let resolved = Url::parse("http://localhost:9/").unwrap();
+        assert!(matches!(
+            open.resolve(&resolved).await,
+            Err(ToolManagerError::ValidationError(
+                ToolValidationError::SecurityThreat(_)
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_follows_redirects_and_truncates() {
+        let page = format!(
+            "<html><head><title>Local</title></head><body><p>{}</p></body></html>",
+            "word ".repeat(200)
+        );
+        let base = serve(page).await;
+
+        // Private addresses are refused by default
+        let strict = FetchUrlTool::with_config(FetchConfig::default());
+        assert!(strict.execute(context(&base)).await.is_err());
+
+        let tool = FetchUrlTool::with_config(FetchConfig {
+            allow_private_ips: true,
+            max_bytes: 256,
+            ..FetchConfig::default()
+        });
+        let result = tool.execute(context(&base)).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.metadata["url"], format!("{}/page", base));
+        assert_eq!(result.metadata["title"], "Local");
+        assert_eq!(result.metadata["truncated"], "true");
+        assert_eq!(result.metadata["bytes"], "256");
+        assert!(result.data.starts_with("word word"));
+    }
+}
@@ -0,0 +1,958 @@
+//! Declarative HTTP Tools
+//!
+//! REST-backed tools defined by manifest files instead of Rust code. Manifests
+//! live in the tool manifest directory next to WebAssembly plugins and are
+//! picked up by `ToolManager::load_tools_from_directory`. TOML manifests are
+//! recognised by their `[http]` or `[openapi]` table; `*.yaml` / `*.yml`
+//! manifests are always HTTP tools and use the same fields:
+//!
+//! ```toml
+//! id = "github_issue"
+//! name = "GitHub Issue"
+//! description = "Fetches a GitHub issue"
+//! version = "1.0.0"
+//! category = "web"
+//!
+//! [http]
+//! method = "GET"
+//! url = "https://api.github.com/repos/{owner}/{repo}/issues/{number}"
+//! headers = { Accept = "application/vnd.github+json" }
+//! extract = "$.title"                    # Optional JSONPath applied to the response
+//! timeout_secs = 30
+//! max_response_bytes = 1048576
+//! auth = { type = "bearer", secret = "http-tools/github-token" }
+//!
+//! [[parameters]]
+//! name = "owner"
+//! description = "Repository owner"
+//! required = true
+//! param_type = "string"
+//! in = "path"                            # path | query | header | body
+//! field = "owner"                        # Upstream name, dotted for nested body fields
+//! ```
+//!
+//! Auth types are `bearer`, `basic` (`username` + secret password), `header`
+//! (`name`, optional `prefix`) and `query` (`name`); `secret` is a key resolved
+//! through secrets-service-rs at call time and never stored in the manifest.
+//!
+//! Instead of `http.method` / `http.url` / `parameters`, a tool can be generated
+//! from an OpenAPI 3 operation; manifest parameters with the same name override
+//! the generated ones:
+//!
+//! ```toml
+//! [openapi]
+//! spec = "petstore.yaml"                 # Relative to the manifest, YAML or JSON
+//! operation_id = "getPetById"
+//! server = "https://petstore.example.com/v3"  # Optional, default: first server in the spec
+//! ```
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use serde_json_path::JsonPath;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::secrets_client::SecretsClient;
+use crate::tool_manager::{
+    validate_parameter_definitions, Capability, ParameterDefinition, Tool, ToolContext,
+    ToolManagerError, ToolMetadata, ToolResult,
+};
+
+// Upstream error bodies are cut to this many characters in tool errors
+const MAX_ERROR_BODY_CHARS: usize = 500;
+
+const OPENAPI_METHODS: [&str; 7] = ["get", "put", "post", "delete", "patch", "head", "options"];
+
+/// Where a parameter goes in the upstream request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamLocation {
+    Path,
+    Query,
+    Header,
+    Body,
+}
+
+/// Tool parameter plus its mapping into the request
+#[derive(Debug, Clone, Deserialize)]
+pub struct HttpParameter {
+    #[serde(flatten)]
+    pub definition: ParameterDefinition,
+    /// Default: query for GET/DELETE/HEAD, body otherwise
+    #[serde(rename = "in", default)]
+    pub location: Option<ParamLocation>,
+    /// Upstream name (default: the parameter name)
+    #[serde(default)]
+    pub field: Option<String>,
+}
+
+/// Credentials attached to each request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum HttpAuth {
+    Bearer {
+        secret: String,
+    },
+    Basic {
+        username: String,
+        secret: String,
+    },
+    Header {
+        name: String,
+        secret: String,
+        #[serde(default)]
+        prefix: String,
+    },
+    Query {
+        name: String,
+        secret: String,
+    },
+}
+
+impl HttpAuth {
+    fn secret(&self) -> &str {
+        match self {
+            HttpAuth::Bearer { secret }
+            | HttpAuth::Basic { secret, .. }
+            | HttpAuth::Header { secret, .. }
+            | HttpAuth::Query { secret, .. } => secret,
+        }
+    }
+
+    /// Attach the resolved secret to a request
+    fn apply(&self, request: reqwest::RequestBuilder, secret: &str) -> reqwest::RequestBuilder {
+        match self {
+            HttpAuth::Bearer { .. } => request.bearer_auth(secret),
+            HttpAuth::Basic { username, .. } => request.basic_auth(username, Some(secret)),
+            HttpAuth::Header { name, prefix, .. } => {
+                request.header(name.as_str(), format!("{}{}", prefix, secret))
+            }
+            HttpAuth::Query { name, .. } => request.query(&[(name.as_str(), secret)]),
+        }
+    }
+}
+
+/// `[http]` table of a manifest
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct HttpSpec {
+    #[serde(default)]
+    pub method: Option<String>,
+    /// URL with `{parameter}` placeholders for path parameters
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub auth: Option<HttpAuth>,
+    /// JSONPath (RFC 9535) selecting the result from a JSON response
+    #[serde(default)]
+    pub extract: Option<String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default = "default_max_response_bytes")]
+    pub max_response_bytes: usize,
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_max_response_bytes() -> usize {
+    1024 * 1024
+}
+
+/// `[openapi]` table of a manifest
+#[derive(Debug, Clone, Deserialize)]
+pub struct OpenApiSource {
+    pub spec: PathBuf,
+    pub operation_id: String,
+    #[serde(default)]
+    pub server: Option<String>,
+}
+
+/// HTTP tool manifest file
+#[derive(Debug, Clone, Deserialize)]
+pub struct HttpToolManifest {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default = "default_version")]
+    pub version: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default = "default_category")]
+    pub category: String,
+    /// Capabilities beyond Network, which every HTTP tool requires
+    #[serde(default)]
+    pub capabilities: HashSet<Capability>,
+    #[serde(default)]
+    pub parameters: Vec<HttpParameter>,
+    #[serde(default)]
+    pub http: HttpSpec,
+    #[serde(default)]
+    pub openapi: Option<OpenApiSource>,
+    /// Deprecation notice shown when this version runs
+    #[serde(default)]
+    pub deprecated: Option<String>,
+}
+
+fn default_version() -> String {
+    "1.0.0".to_string()
+}
+
+fn default_category() -> String {
+    "http".to_string()
+}
+
+fn is_yaml(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "yaml" || ext == "yml")
+}
+
+/// Whether a manifest file describes an HTTP tool rather than a WebAssembly plugin
+pub fn is_http_manifest(path: &Path) -> bool {
+    if is_yaml(path) {
+        return true;
+    }
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|source| source.parse::<toml::Table>().ok())
+        .is_some_and(|table| table.contains_key("http") || table.contains_key("openapi"))
+}
+
+impl HttpToolManifest {
+    /// Parse a manifest; `yaml` selects YAML instead of TOML
+    pub fn parse(source: &str, yaml: bool) -> Result<Self, ToolManagerError> {
+        let parsed = if yaml {
+            serde_yaml::from_str(source).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(source).map_err(|e| e.to_string())
+        };
+        parsed.map_err(|e| {
+            ToolManagerError::InvalidMetadata(format!("Invalid HTTP tool manifest: {}", e))
+        })
+    }
+}
+
+/// Request shape taken from an OpenAPI operation
+struct OpenApiOperation {
+    method: String,
+    url: String,
+    summary: Option<String>,
+    description: Option<String>,
+    parameters: Vec<HttpParameter>,
+}
+
+/// Follow local `$ref`s (`#/components/...`)
+fn resolve_ref<'a>(spec: &'a Value, mut value: &'a Value) -> &'a Value {
+    for _ in 0..8 {
+        match value
+            .get("$ref")
+            .and_then(Value::as_str)
+            .and_then(|r| r.strip_prefix('#'))
+            .and_then(|pointer| spec.pointer(pointer))
+        {
+            Some(target) => value = target,
+            None => break,
+        }
+    }
+    value
+}
+
+/// Parameter definition from an OpenAPI schema object
+fn definition_from_schema(
+    name: &str,
+    description: Option<&str>,
+    required: bool,
+    schema: &Value,
+) -> ParameterDefinition {
+    ParameterDefinition {
+        name: name.to_string(),
+        description: description
+            .or_else(|| schema.get("description").and_then(Value::as_str))
+            .unwrap_or_default()
+            .to_string(),
+        required,
+        param_type: schema
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or("string")
+            .to_string(),
+        default: schema.get("default").map(|v| match v {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }),
+        validation: schema
+            .get("pattern")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+    }
+}
+
+fn load_openapi_operation(
+    spec_path: &Path,
+    source: &OpenApiSource,
+) -> Result<OpenApiOperation, ToolManagerError> {
+    let text = std::fs::read_to_string(spec_path)
+        .map_err(|e| ToolManagerError::LoadingError(format!("{}: {}", spec_path.display(), e)))?;
+    // YAML is a superset of JSON, so one parser handles both spec formats
+    let spec: Value = serde_yaml::from_str(&text)
+        .map_err(|e| ToolManagerError::LoadingError(format!("{}: {}", spec_path.display(), e)))?;
+
+    let not_found = || {
+        ToolManagerError::LoadingError(format!(
+            "Operation {} not found in {}",
+            source.operation_id,
+            spec_path.display()
+        ))
+    };
+    let paths = spec
+        .get("paths")
+        .and_then(Value::as_object)
+        .ok_or_else(not_found)?;
+    let (path, item, method, operation) = paths
+        .iter()
+        .flat_map(|(path, item)| {
+            OPENAPI_METHODS
+                .iter()
+                .filter_map(move |method| item.get(*method).map(|op| (path, item, *method, op)))
+        })
+        .find(|(_, _, _, op)| {
+            op.get("operationId").and_then(Value::as_str) == Some(source.operation_id.as_str())
+        })
+        .ok_or_else(not_found)?;
+
+    let server = match &source.server {
+        Some(server) => server.clone(),
+        None => spec
+            .pointer("/servers/0/url")
+            .and_then(Value::as_str)
+            .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+            .ok_or_else(|| {
+                ToolManagerError::LoadingError(format!(
+                    "{} has no absolute server URL; set openapi.server",
+                    spec_path.display()
+                ))
+            })?
+            .to_string(),
+    };
+
+    let mut parameters = Vec::new();
+    let declared = item
+        .get("parameters")
+        .and_then(Value::as_array)
+        .into_iter()
+        .chain(operation.get("parameters").and_then(Value::as_array))
+        .flatten();
+    for param in declared {
+        let param = resolve_ref(&spec, param);
+        let Some(name) = param.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        let location = match param.get("in").and_then(Value::as_str) {
+            Some("path") => ParamLocation::Path,
+            Some("query") => ParamLocation::Query,
+            Some("header") => ParamLocation::Header,
+            _ => continue,
+        };
+        let required = location == ParamLocation::Path
+            || param
+                .get("required")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+        let schema = resolve_ref(&spec, param.get("schema").unwrap_or(&Value::Null));
+        // Operation-level parameters override path-level ones with the same name
+        parameters.retain(|p: &HttpParameter| p.definition.name != name);
+        parameters.push(HttpParameter {
+            definition: definition_from_schema(
+                name,
+                param.get("description").and_then(Value::as_str),
+                required,
+                schema,
+            ),
+            location: Some(location),
+            field: None,
+        });
+    }
+
+    if let Some(body) = operation.get("requestBody") {
+        let body = resolve_ref(&spec, body);
+        let schema = resolve_ref(
+            &spec,
+            body.pointer("/content/application~1json/schema")
+                .unwrap_or(&Value::Null),
+        );
+        let required: HashSet<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|names| names.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (name, property) in properties {
+                let property = resolve_ref(&spec, property);
+                parameters.push(HttpParameter {
+                    definition: definition_from_schema(
+                        name,
+                        None,
+                        required.contains(name.as_str()),
+                        property,
+                    ),
+                    location: Some(ParamLocation::Body),
+                    field: None,
+                });
+            }
+        }
+    }
+
+    Ok(OpenApiOperation {
+        method: method.to_uppercase(),
+        url: format!("{}{}", server.trim_end_matches('/'), path),
+        summary: operation
+            .get("summary")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        description: operation
+            .get("description")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        parameters,
+    })
+}
+
+/// Resolved parameter mapping
+struct ParamMapping {
+    name: String,
+    location: ParamLocation,
+    field: String,
+    param_type: String,
+}
+
+/// A tool that calls an HTTP endpoint described by a manifest
+pub struct HttpTool {
+    metadata: ToolMetadata,
+    method: reqwest::Method,
+    url: String,
+    mappings: Vec<ParamMapping>,
+    headers: HashMap<String, String>,
+    auth: Option<HttpAuth>,
+    extract: Option<JsonPath>,
+    timeout: Duration,
+    max_response_bytes: usize,
+    spec_path: Option<PathBuf>,
+    client: reqwest::Client,
+}
+
+impl HttpTool {
+    /// Load a tool from its manifest, resolving an OpenAPI operation if one is referenced
+    pub fn load(manifest_path: &Path) -> Result<Self, ToolManagerError> {
+        let source = std::fs::read_to_string(manifest_path).map_err(|e| {
+            ToolManagerError::LoadingError(format!("{}: {}", manifest_path.display(), e))
+        })?;
+        let manifest = HttpToolManifest::parse(&source, is_yaml(manifest_path))?;
+        let manifest_dir = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+        Self::from_manifest(manifest, manifest_dir)
+    }
+
+    pub fn from_manifest(
+        manifest: HttpToolManifest,
+        manifest_dir: &Path,
+    ) -> Result<Self, ToolManagerError> {
+        let http = manifest.http;
+        let mut name = manifest.name;
+        let mut description = manifest.description;
+        let mut parameters = manifest.parameters;
+        let mut method = http.method;
+        let mut url = http.url;
+        let mut spec_path = None;
+
+        if let Some(openapi) = &manifest.openapi {
+            let path = manifest_dir.join(&openapi.spec);
+            let operation = load_openapi_operation(&path, openapi)?;
+            method = method.or(Some(operation.method));
+            url = url.or(Some(operation.url));
+            name = name.or_else(|| operation.summary.clone());
+            description = description.or(operation.description).or(operation.summary);
+
+            let mut generated = operation.parameters;
+            generated.retain(|p| {
+                !parameters
+                    .iter()
+                    .any(|own| own.definition.name == p.definition.name)
+            });
+            parameters.extend(generated);
+            spec_path = Some(path);
+        }
+
+        let url = url.ok_or_else(|| {
+            ToolManagerError::InvalidMetadata(format!(
+                "HTTP tool {} needs http.url or an openapi operation",
+                manifest.id
+            ))
+        })?;
+        let method = method.unwrap_or_else(|| "GET".to_string());
+        let method =
+            reqwest::Method::from_bytes(method.to_uppercase().as_bytes()).map_err(|_| {
+                ToolManagerError::InvalidMetadata(format!("Invalid HTTP method: {}", method))
+            })?;
+        let default_location = if matches!(
+            method,
+            reqwest::Method::GET | reqwest::Method::DELETE | reqwest::Method::HEAD
+        ) {
+            ParamLocation::Query
+        } else {
+            ParamLocation::Body
+        };
+
+        let mappings: Vec<ParamMapping> = parameters
+            .iter()
+            .map(|p| ParamMapping {
+                name: p.definition.name.clone(),
+                location: p.location.unwrap_or(default_location),
+                field: p.field.clone().unwrap_or_else(|| p.definition.name.clone()),
+                param_type: p.definition.param_type.clone(),
+            })
+            .collect();
+
+        // Every placeholder in the URL must be filled by a path parameter
+        for placeholder in url_placeholders(&url) {
+            let mapped = mappings
+                .iter()
+                .any(|m| m.location == ParamLocation::Path && m.field == placeholder);
+            if !mapped {
+                return Err(ToolManagerError::InvalidMetadata(format!(
+                    "URL placeholder {{{}}} of tool {} has no path parameter",
+                    placeholder, manifest.id
+                )));
+            }
+        }
+
+        let extract = http
+            .extract
+            .as_deref()
+            .map(JsonPath::parse)
+            .transpose()
+            .map_err(|e| ToolManagerError::InvalidMetadata(format!("Invalid JSONPath: {}", e)))?;
+
+        let client = reqwest::Client::builder()
+            .user_agent("phoenix-tools-service")
+            .build()
+            .map_err(|e| ToolManagerError::LoadingError(e.to_string()))?;
+
+        let mut capabilities = manifest.capabilities;
+        capabilities.insert(Capability::Network);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        Ok(Self {
+            metadata: ToolMetadata {
+                name: name.unwrap_or_else(|| manifest.id.clone()),
+                id: manifest.id,
+                description: description.unwrap_or_default(),
+                version: manifest.version,
+                author: manifest.author,
+                category: manifest.category,
+                parameters: parameters.into_iter().map(|p| p.definition).collect(),
+                capabilities,
+                enabled: true,
+                created_at: now,
+                updated_at: now,
+                deprecated: manifest.deprecated,
+            },
+            method,
+            url,
+            mappings,
+            headers: http.headers,
+            auth: http.auth,
+            extract,
+            timeout: Duration::from_secs(http.timeout_secs),
+            max_response_bytes: http.max_response_bytes,
+            spec_path,
+            client,
+        })
+    }
+
+    /// OpenAPI spec the tool was generated from, watched for hot reload
+    pub fn spec_path(&self) -> Option<&Path> {
+        self.spec_path.as_deref()
+    }
+
+    /// Build the upstream request from call parameters (without auth)
+    fn build_request(&self, parameters: &HashMap<String, String>) -> reqwest::RequestBuilder {
+        let mut url = self.url.clone();
+        let mut query: Vec<(&str, &str)> = Vec::new();
+        let mut headers: Vec<(&str, &str)> = Vec::new();
+        let mut body = Map::new();
+
+        for (mapping, definition) in self.mappings.iter().zip(&self.metadata.parameters) {
+            let Some(value) = parameters
+                .get(&mapping.name)
+                .or(definition.default.as_ref())
+            else {
+                continue;
+            };
+            match mapping.location {
+                ParamLocation::Path => {
+                    url = url.replace(
+                        &format!("{{{}}}", mapping.field),
+                        &encode_path_segment(value),
+                    )
+                }
+                ParamLocation::Query => query.push((&mapping.field, value)),
+                ParamLocation::Header => headers.push((&mapping.field, value)),
+                ParamLocation::Body => insert_body_field(
+                    &mut body,
+                    &mapping.field,
+                    typed_value(&mapping.param_type, value),
+                ),
+            }
+        }
+
+        let mut request = self
+            .client
+            .request(self.method.clone(), url)
+            .timeout(self.timeout)
+            .query(&query);
+        for (name, value) in self
+            .headers
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .chain(headers)
+        {
+            request = request.header(name, value);
+        }
+        if !body.is_empty() {
+            request = request.json(&Value::Object(body));
+        }
+        request
+    }
+
+    /// Result text: the JSONPath selection, or the raw body
+    fn extract_result(&self, body: &[u8]) -> Result<String, String> {
+        let Some(path) = &self.extract else {
+            return Ok(String::from_utf8_lossy(body).into_owned());
+        };
+        let json: Value = serde_json::from_slice(body)
+            .map_err(|e| format!("Response is not JSON, cannot apply {}: {}", path, e))?;
+        let matches = path.query(&json).all();
+        match matches.as_slice() {
+            [] => Err(format!("JSONPath {} matched nothing in the response", path)),
+            [Value::String(s)] => Ok(s.clone()),
+            [single] => Ok(single.to_string()),
+            many => Ok(Value::Array(many.iter().map(|v| (*v).clone()).collect()).to_string()),
+        }
+    }
+}
+
+/// `{name}` placeholders in a URL template
+fn url_placeholders(url: &str) -> Vec<&str> {
+    url.split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+        .collect()
+}
+
+/// Percent-encode everything except RFC 3986 unreserved characters
+fn encode_path_segment(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Convert a string parameter to JSON according to its declared type
+pub fn typed_value(param_type: &str, value: &str) -> Value {
+    let parsed = match param_type {
+        "integer" | "number" | "boolean" | "object" | "array" => serde_json::from_str(value).ok(),
+        _ => None,
+    };
+    parsed.unwrap_or_else(|| Value::String(value.to_string()))
+}
+
+/// Insert `value` at a dotted path (`filter.status`) in a JSON object
+fn insert_body_field(body: &mut Map<String, Value>, field: &str, value: Value) {
+    match field.split_once('.') {
+        Some((head, rest)) => {
+            let entry = body
+                .entry(head.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !entry.is_object() {
+                *entry = Value::Object(Map::new());
+            }
+            if let Value::Object(nested) = entry {
+                insert_body_field(nested, rest, value);
+            }
+        }
+        None => {
+            body.insert(field.to_string(), value);
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for HttpTool {
+    fn metadata(&self) -> &ToolMetadata {
+        &self.metadata
+    }
+
+    fn validate_parameters(
+        &self,
+        parameters: &HashMap<String, String>,
+    ) -> Result<(), ToolManagerError> {
+        validate_parameter_definitions(&self.metadata.parameters, parameters)
+    }
+
+    async fn execute(&self, context: ToolContext) -> Result<ToolResult, ToolManagerError> {
+        let start_time = Instant::now();
+        let mut request = self.build_request(&context.parameters);
+
+        if let Some(auth) = &self.auth {
+            let secrets = SecretsClient::shared().await.map_err(|e| {
+                ToolManagerError::ExecutionError(format!("Secrets service unavailable: {}", e))
+            })?;
+            let secret = secrets.get_secret(auth.secret()).await.map_err(|e| {
+                ToolManagerError::ExecutionError(format!(
+                    "Failed to resolve secret {}: {}",
+                    auth.secret(),
+                    e
+                ))
+            })?;
+            request = auth.apply(request, &secret);
+        }
+
+        let mut response = request
+            .send()
+            .await
+            .map_err(|e| ToolManagerError::ExecutionError(format!("HTTP request failed: {}", e)))?;
+        let status = response.status();
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|e| {
+            ToolManagerError::ExecutionError(format!("Failed to read response: {}", e))
+        })? {
+            if body.len() + chunk.len() > self.max_response_bytes {
+                return Err(ToolManagerError::ExecutionError(format!(
+                    "Response exceeds {} bytes",
+                    self.max_response_bytes
+                )));
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        let (success, data, error) = if !status.is_success() {
+            let text: String = String::from_utf8_lossy(&body)
+                .chars()
+                .take(MAX_ERROR_BODY_CHARS)
+                .collect();
+            (false, String::new(), format!("HTTP {}: {}", status, text))
+        } else {
+            match self.extract_result(&body) {
+                Ok(data) => (true, data, String::new()),
+                Err(e) => (false, String::new(), e),
+            }
+        };
+
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        let mut metadata = HashMap::new();
+        metadata.insert("status_code".to_string(), status.as_u16().to_string());
+        metadata.insert("response_bytes".to_string(), body.len().to_string());
+        metadata.insert("execution_time_ms".to_string(), duration_ms.to_string());
+
+        Ok(ToolResult {
+            success,
+            data,
+            error,
+            metadata,
+            duration_ms,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const PETSTORE: &str = r#"
+openapi: 3.0.0
+servers:
+  - url: https://petstore.example.com/v3
+paths:
+  /pet/{petId}:
+    parameters:
+      - $ref: '#/components/parameters/PetId'
+    get:
+      operationId: getPetById
+      summary: Find pet by ID
+      parameters:
+        - name: fields
+          in: query
+          schema: { type: string, default: "name" }
+  /pet:
+    post:
+      operationId: addPet
+      requestBody:
+        content:
+          application/json:
+            schema: { $ref: '#/components/schemas/Pet' }
+components:
+  parameters:
+    PetId:
+      name: petId
+      in: path
+      description: ID of pet
+      schema: { type: integer }
+  schemas:
+    Pet:
+      type: object
+      required: [name]
+      properties:
+        name: { type: string }
+        age: { type: integer }
+"#;
+
+    fn tool(source: &str, yaml: bool, dir: &Path) -> HttpTool {
+        HttpTool::from_manifest(HttpToolManifest::parse(source, yaml).unwrap(), dir).unwrap()
+    }
+
+    /// Serve one canned JSON response and hand back the raw request head
+    async fn serve_once(body: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 8192];
+            let n = socket.read(&mut buf).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+        (addr, handle)
+    }
+
+    #[tokio::test]
+    async fn test_parameters_are_mapped_and_result_extracted() {
+        let (addr, request) = serve_once(r#"{"items":[{"name":"a"},{"name":"b"}]}"#).await;
+        let source = format!(
+            r#"
+id: list_items
+http:
+  url: "{}/lists/{{list}}/items"
+  headers: {{ Accept: application/json }}
+  extract: "$.items[*].name"
+parameters:
+  - {{ name: list, description: List, required: true, param_type: string, in: path }}
+  - {{ name: limit, description: Limit, required: false, param_type: integer, default: "10" }}
+  - {{ name: trace, description: Trace id, required: false, param_type: string, in: header, field: X-Trace }}
+"#,
+            addr
+        );
+        let tool = tool(&source, true, Path::new("."));
+        assert!(tool.metadata().capabilities.contains(&Capability::Network));
+
+        let context = ToolContext {
+            parameters: HashMap::from([
+                ("list".to_string(), "my list".to_string()),
+                ("trace".to_string(), "t-1".to_string()),
+            ]),
+            user_id: None,
+            session_id: None,
+            request_id: "test".to_string(),
+            context_data: HashMap::new(),
+        };
+        let result = tool.execute(context).await.unwrap();
+        assert!(result.success, "{}", result.error);
+        assert_eq!(result.data, r#"["a","b"]"#);
+
+        let head = request.await.unwrap().to_lowercase();
+        assert!(head.starts_with("get /lists/my%20list/items?limit=10 http/1.1"));
+        assert!(head.contains("x-trace: t-1"));
+    }
+
+    #[test]
+    fn test_auth_and_body_mapping() {
+        let source = r#"
+id = "create_ticket"
+[http]
+method = "POST"
+url = "https://tickets.example.com/api"
+auth = { type = "header", name = "X-Api-Key", secret = "http-tools/tickets" }
+
+[[parameters]]
+name = "priority"
+description = "Priority"
+required = true
+param_type = "integer"
+field = "meta.priority"
+"#;
+        let tool = tool(source, false, Path::new("."));
+        let parameters = HashMap::from([("priority".to_string(), "2".to_string())]);
+        let request = tool
+            .auth
+            .as_ref()
+            .unwrap()
+            .apply(tool.build_request(&parameters), "s3cret")
+            .build()
+            .unwrap();
+
+        assert_eq!(request.method(), reqwest::Method::POST);
+        assert_eq!(request.headers()["x-api-key"], "s3cret");
+        let body: Value =
+            serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert_eq!(body, serde_json::json!({"meta": {"priority": 2}}));
+    }
+
+    #[test]
+    fn test_tool_generated_from_openapi_operation() {
+        let dir = std::env::temp_dir().join(format!("http-tools-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("petstore.yaml"), PETSTORE).unwrap();
+
+        let get = tool(
+            "id = \"get_pet\"\n[openapi]\nspec = \"petstore.yaml\"\noperation_id = \"getPetById\"\n",
+            false,
+            &dir,
+        );
+        assert_eq!(get.metadata().name, "Find pet by ID");
+        assert_eq!(get.url, "https://petstore.example.com/v3/pet/{petId}");
+        let pet_id = &get.metadata().parameters[0];
+        assert_eq!((pet_id.name.as_str(), pet_id.required), ("petId", true));
+        assert_eq!(pet_id.param_type, "integer");
+        assert_eq!(get.spec_path(), Some(dir.join("petstore.yaml").as_path()));
+
+        let add = tool(
+            "id = \"add_pet\"\n[openapi]\nspec = \"petstore.yaml\"\noperation_id = \"addPet\"\n",
+            false,
+            &dir,
+        );
+        assert_eq!(add.method, reqwest::Method::POST);
+        let required: Vec<_> = add
+            .metadata()
+            .parameters
+            .iter()
+            .map(|p| (p.name.as_str(), p.required))
+            .collect();
+        assert!(required.contains(&("name", true)) && required.contains(&("age", false)));
+
+        let missing = HttpToolManifest::parse(
+            "id = \"x\"\n[openapi]\nspec = \"petstore.yaml\"\noperation_id = \"nope\"\n",
+            false,
+        )
+        .unwrap();
+        assert!(HttpTool::from_manifest(missing, &dir).is_err());
+    }
+}
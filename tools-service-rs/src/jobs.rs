@@ -0,0 +1,610 @@
+//! Asynchronous Tool Jobs
+//!
+//! Runs tools in the background for callers that cannot hold a unary call open
+//! for the whole execution. A job moves from `running` to one of the terminal
+//! states `succeeded`, `failed`, `cancelled` or `timed_out`; finished jobs are
+//! kept for a retention period so their results can still be fetched.
+//!
+//! Tools append partial output with [`append_output`] (a no-op outside a job)
+//! and report progress through the handle from [`current_reporter`].
+//!
+//! Configuration:
+//! - TOOLS_JOB_TIMEOUT_SECS: Default job timeout (default: 600)
+//! - TOOLS_JOB_TOOL_TIMEOUTS: Per-tool timeouts, e.g. "execute_code=1800,web_search=60"
+//! - TOOLS_JOB_MAX_OUTPUT_BYTES: Cap on partial and final output (default: 1 MiB)
+//! - TOOLS_JOB_RETENTION_SECS: How long finished jobs are kept (default: 3600)
+
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tokio::sync::watch;
+use tokio::task::AbortHandle;
+
+use crate::tool_manager::{ToolContext, ToolManager, ToolManagerError, TOOL_MANAGER};
+
+/// Job lifecycle states
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+    TimedOut,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+            JobStatus::TimedOut => "timed_out",
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        *self != JobStatus::Running
+    }
+}
+
+/// Point-in-time view of a job
+#[derive(Debug, Clone)]
+pub struct JobSnapshot {
+    pub job_id: String,
+    pub tool_id: String,
+    pub status: JobStatus,
+    /// Fraction complete (0.0 - 1.0) as reported by the tool
+    pub progress: f32,
+    pub progress_message: String,
+    /// Partial output while running, the tool result once succeeded
+    pub output: String,
+    pub output_truncated: bool,
+    pub error: String,
+    pub metadata: HashMap<String, String>,
+    /// Unix seconds
+    pub created_at: u64,
+    /// Unix seconds; 0 while running
+    pub finished_at: u64,
+}
+
+#[derive(Debug, Error)]
+pub enum JobError {
+    #[error("Job not found: {0}")]
+    NotFound(String),
+
+    #[error(transparent)]
+    Tool(#[from] ToolManagerError),
+}
+
+/// Job limits
+#[derive(Debug, Clone)]
+pub struct JobConfig {
+    pub default_timeout: Duration,
+    pub tool_timeouts: HashMap<String, Duration>,
+    pub max_output_bytes: usize,
+    pub retention: Duration,
+}
+
+impl Default for JobConfig {
+    fn default() -> Self {
+        Self {
+            default_timeout: Duration::from_secs(600),
+            tool_timeouts: HashMap::new(),
+            max_output_bytes: 1024 * 1024,
+            retention: Duration::from_secs(3600),
+        }
+    }
+}
+
+impl JobConfig {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let env_u64 = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+
+        let tool_timeouts = std::env::var("TOOLS_JOB_TOOL_TIMEOUTS")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|entry| {
+                let (tool, secs) = entry.split_once('=')?;
+                let secs = secs.trim().parse::<u64>().ok()?;
+                Some((tool.trim().to_string(), Duration::from_secs(secs)))
+            })
+            .collect();
+
+        Self {
+            default_timeout: env_u64("TOOLS_JOB_TIMEOUT_SECS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.default_timeout),
+            tool_timeouts,
+            max_output_bytes: env_u64("TOOLS_JOB_MAX_OUTPUT_BYTES")
+                .map(|v| v as usize)
+                .unwrap_or(defaults.max_output_bytes),
+            retention: env_u64("TOOLS_JOB_RETENTION_SECS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.retention),
+        }
+    }
+
+    /// Timeout configured for a tool
+    pub fn timeout_for(&self, tool_id: &str) -> Duration {
+        self.tool_timeouts
+            .get(tool_id)
+            .copied()
+            .unwrap_or(self.default_timeout)
+    }
+}
+
+/// A background job; its state lives in the watch channel so watchers see every update
+struct Job {
+    state: watch::Sender<JobSnapshot>,
+    abort: Mutex<Option<AbortHandle>>,
+    finished: Mutex<Option<Instant>>,
+    max_output_bytes: usize,
+}
+
+impl Job {
+    fn append_output(&self, text: &str) {
+        let cap = self.max_output_bytes;
+        self.state.send_if_modified(|snapshot| {
+            if snapshot.status.is_finished() || snapshot.output_truncated {
+                return false;
+            }
+            let (kept, truncated) = truncate_to(text, cap.saturating_sub(snapshot.output.len()));
+            snapshot.output.push_str(kept);
+            snapshot.output_truncated = truncated;
+            !kept.is_empty() || truncated
+        });
+    }
+
+    fn report_progress(&self, progress: f32, message: String) {
+        self.state.send_if_modified(|snapshot| {
+            if snapshot.status.is_finished() {
+                return false;
+            }
+            snapshot.progress = progress.clamp(0.0, 1.0);
+            snapshot.progress_message = message;
+            true
+        });
+    }
+
+    /// Move to a terminal state; returns false if the job had already finished
+    fn finish(&self, update: impl FnOnce(&mut JobSnapshot)) -> bool {
+        let finished = self.state.send_if_modified(|snapshot| {
+            if snapshot.status.is_finished() {
+                return false;
+            }
+            update(snapshot);
+            snapshot.finished_at = now_secs();
+            true
+        });
+        if finished {
+            *self.finished.lock().unwrap() = Some(Instant::now());
+        }
+        finished
+    }
+}
+
+tokio::task_local! {
+    static CURRENT_JOB: Arc<Job>;
+}
+
+/// Append partial output to the job the calling tool runs in
+pub fn append_output(text: &str) {
+    let _ = CURRENT_JOB.try_with(|job| job.append_output(text));
+}
+
+/// Handle to the calling tool's job; can be moved to other tasks
+pub fn current_reporter() -> Option<JobReporter> {
+    CURRENT_JOB
+        .try_with(|job| JobReporter(Arc::clone(job)))
+        .ok()
+}
+
+/// Reports progress of one job
+#[derive(Clone)]
+pub struct JobReporter(Arc<Job>);
+
+impl JobReporter {
+    pub fn job_id(&self) -> String {
+        self.0.state.borrow().job_id.clone()
+    }
+
+    /// Fraction complete (0.0 - 1.0) and a short status message
+    pub fn report_progress(&self, progress: f32, message: impl Into<String>) {
+        self.0.report_progress(progress, message.into());
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Longest prefix of `text` within `max_bytes` on a char boundary, and whether it was cut
+fn truncate_to(text: &str, max_bytes: usize) -> (&str, bool) {
+    if text.len() <= max_bytes {
+        return (text, false);
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    (&text[..end], true)
+}
+
+/// Registry of background tool jobs
+pub struct JobManager {
+    tools: Arc<ToolManager>,
+    config: JobConfig,
+    jobs: RwLock<HashMap<String, Arc<Job>>>,
+    next_id: AtomicU64,
+}
+
+/// Global instance of JobManager
+pub static JOB_MANAGER: Lazy<Arc<JobManager>> =
+    Lazy::new(|| Arc::new(JobManager::new(TOOL_MANAGER.clone(), JobConfig::from_env())));
+
+impl JobManager {
+    pub fn new(tools: Arc<ToolManager>, config: JobConfig) -> Self {
+        Self {
+            tools,
+            config,
+            jobs: RwLock::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Start a tool in the background and return the job id
+    ///
+    /// `tool_id` may pin a version as `tool_id@requirement`. Unknown tools and
+    /// invalid parameters are rejected before a job is created. `timeout` can
+    /// shorten but not extend the tool's job timeout.
+    pub fn start(
+        &self,
+        tool_id: &str,
+        context: ToolContext,
+        timeout: Option<Duration>,
+    ) -> Result<String, JobError> {
+        let tool = self.tools.get_tool(tool_id)?;
+        tool.validate_parameters(&context.parameters)?;
+        self.prune_finished();
+
+        let configured = self.config.timeout_for(&tool.metadata().id);
+        let timeout = timeout.map_or(configured, |t| t.min(configured));
+        let job_id = format!(
+            "job-{}-{}",
+            now_secs(),
+            self.next_id.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let (state, _) = watch::channel(JobSnapshot {
+            job_id: job_id.clone(),
+            tool_id: tool_id.to_string(),
+            status: JobStatus::Running,
+            progress: 0.0,
+            progress_message: String::new(),
+            output: String::new(),
+            output_truncated: false,
+            error: String::new(),
+            metadata: HashMap::new(),
+            created_at: now_secs(),
+            finished_at: 0,
+        });
+        let job = Arc::new(Job {
+            state,
+            abort: Mutex::new(None),
+            finished: Mutex::new(None),
+            max_output_bytes: self.config.max_output_bytes,
+        });
+
+        let tools = Arc::clone(&self.tools);
+        let task_job = Arc::clone(&job);
+        let task_tool_id = tool_id.to_string();
+        let handle = tokio::spawn(async move {
+            let execution = CURRENT_JOB.scope(
+                Arc::clone(&task_job),
+                tokio::time::timeout(timeout, tools.execute_tool(&task_tool_id, context)),
+            );
+            let cap = task_job.max_output_bytes;
+
+            match execution.await {
+                Ok(Ok(result)) if result.success => {
+                    let (output, truncated) = truncate_to(&result.data, cap);
+                    task_job.finish(|s| {
+                        s.status = JobStatus::Succeeded;
+                        s.progress = 1.0;
+                        s.output = output.to_string();
+                        s.output_truncated = truncated;
+                        s.metadata = result.metadata;
+                    });
+                }
+                Ok(Ok(result)) => {
+                    task_job.finish(|s| {
+                        s.status = JobStatus::Failed;
+                        s.error = result.error;
+                        s.metadata = result.metadata;
+                    });
+                }
+                Ok(Err(e)) => {
+                    task_job.finish(|s| {
+                        s.status = JobStatus::Failed;
+                        s.error = e.to_string();
+                    });
+                }
+                Err(_) => {
+                    task_job.finish(|s| {
+                        s.status = JobStatus::TimedOut;
+                        s.error = format!("Job timed out after {}s", timeout.as_secs());
+                    });
+                }
+            }
+            let status = task_job.state.borrow().status;
+            log::info!(
+                "Job for tool {} finished: {}",
+                task_tool_id,
+                status.as_str()
+            );
+        });
+        *job.abort.lock().unwrap() = Some(handle.abort_handle());
+
+        self.jobs.write().unwrap().insert(job_id.clone(), job);
+        log::info!("Started job {} for tool {}", job_id, tool_id);
+        Ok(job_id)
+    }
+
+    fn job(&self, job_id: &str) -> Result<Arc<Job>, JobError> {
+        self.jobs
+            .read()
+            .unwrap()
+            .get(job_id)
+            .cloned()
+            .ok_or_else(|| JobError::NotFound(job_id.to_string()))
+    }
+
+    /// Current state of a job
+    pub fn get(&self, job_id: &str) -> Result<JobSnapshot, JobError> {
+        self.prune_finished();
+        Ok(self.job(job_id)?.state.borrow().clone())
+    }
+
+    /// Stop a running job; finished jobs are returned unchanged
+    pub fn cancel(&self, job_id: &str) -> Result<JobSnapshot, JobError> {
+        let job = self.job(job_id)?;
+        if job.finish(|s| {
+            s.status = JobStatus::Cancelled;
+            s.error = "Job cancelled".to_string();
+        }) {
+            if let Some(handle) = job.abort.lock().unwrap().take() {
+                handle.abort();
+            }
+            log::info!("Cancelled job {}", job_id);
+        }
+        let snapshot = job.state.borrow().clone();
+        Ok(snapshot)
+    }
+
+    /// Subscribe to updates of a job; the receiver holds the current state
+    pub fn watch(&self, job_id: &str) -> Result<watch::Receiver<JobSnapshot>, JobError> {
+        Ok(self.job(job_id)?.state.subscribe())
+    }
+
+    /// Drop finished jobs older than the retention period
+    pub fn prune_finished(&self) {
+        let retention = self.config.retention;
+        self.jobs.write().unwrap().retain(|_, job| {
+            job.finished
+                .lock()
+                .unwrap()
+                .is_none_or(|at| at.elapsed() < retention)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool_manager::{Capability, ParameterDefinition, Tool, ToolMetadata, ToolResult};
+    use async_trait::async_trait;
+    use std::collections::HashSet;
+
+    /// Emits `chunks` pieces of output, sleeping `delay_ms` between them
+    struct SlowTool {
+        metadata: ToolMetadata,
+    }
+
+    impl SlowTool {
+        fn new() -> Self {
+            Self {
+                metadata: ToolMetadata {
+                    id: "slow".to_string(),
+                    name: "Slow".to_string(),
+                    description: "Emits output slowly".to_string(),
+                    version: "1.0.0".to_string(),
+                    author: "test".to_string(),
+                    category: "testing".to_string(),
+                    parameters: vec![ParameterDefinition {
+                        name: "chunks".to_string(),
+                        description: String::new(),
+                        required: true,
+                        param_type: "integer".to_string(),
+                        default: None,
+                        validation: Some(r"^\d+$".to_string()),
+                    }],
+                    capabilities: HashSet::<Capability>::new(),
+                    enabled: true,
+                    created_at: 0,
+                    updated_at: 0,
+                    deprecated: None,
+                },
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Tool for SlowTool {
+        fn metadata(&self) -> &ToolMetadata {
+            &self.metadata
+        }
+
+        async fn execute(&self, context: ToolContext) -> Result<ToolResult, ToolManagerError> {
+            let chunks: u32 = context.parameters["chunks"].parse().unwrap();
+            let delay: u64 = context
+                .parameters
+                .get("delay_ms")
+                .map_or(10, |d| d.parse().unwrap());
+            for i in 0..chunks {
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                append_output("chunk;");
+                if let Some(reporter) = current_reporter() {
+                    reporter.report_progress(
+                        (i + 1) as f32 / chunks as f32,
+                        format!("chunk {}", i + 1),
+                    );
+                }
+            }
+            Ok(ToolResult {
+                success: true,
+                data: "done".repeat(chunks as usize),
+                error: String::new(),
+                metadata: HashMap::new(),
+                duration_ms: 0,
+            })
+        }
+
+        fn validate_parameters(
+            &self,
+            parameters: &HashMap<String, String>,
+        ) -> Result<(), ToolManagerError> {
+            crate::tool_manager::validate_parameter_definitions(
+                &self.metadata.parameters,
+                parameters,
+            )
+        }
+    }
+
+    fn manager(config: JobConfig) -> JobManager {
+        let tools = Arc::new(ToolManager::new());
+        tools.register_tool(Arc::new(SlowTool::new())).unwrap();
+        JobManager::new(tools, config)
+    }
+
+    fn context(parameters: &[(&str, &str)]) -> ToolContext {
+        ToolContext {
+            parameters: parameters
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            user_id: None,
+            session_id: None,
+            request_id: "test".to_string(),
+            context_data: HashMap::new(),
+        }
+    }
+
+    async fn wait_finished(jobs: &JobManager, job_id: &str) -> JobSnapshot {
+        let mut updates = jobs.watch(job_id).unwrap();
+        let snapshot = updates
+            .wait_for(|s| s.status.is_finished())
+            .await
+            .unwrap()
+            .clone();
+        snapshot
+    }
+
+    #[tokio::test]
+    async fn test_job_reports_progress_and_result() {
+        let jobs = manager(JobConfig::default());
+        assert!(matches!(
+            jobs.start("slow", context(&[]), None),
+            Err(JobError::Tool(ToolManagerError::ValidationError(_)))
+        ));
+        assert!(matches!(
+            jobs.start("missing", context(&[]), None),
+            Err(JobError::Tool(ToolManagerError::ToolNotFound(_)))
+        ));
+
+        let job_id = jobs
+            .start("slow", context(&[("chunks", "3")]), None)
+            .unwrap();
+        let mut updates = jobs.watch(&job_id).unwrap();
+        let partial = updates
+            .wait_for(|s| !s.output.is_empty())
+            .await
+            .unwrap()
+            .clone();
+        if partial.status == JobStatus::Running {
+            assert!(partial.output.starts_with("chunk;"));
+        }
+
+        let finished = wait_finished(&jobs, &job_id).await;
+        assert_eq!(finished.status, JobStatus::Succeeded);
+        assert_eq!(finished.output, "donedonedone");
+        assert_eq!(finished.progress, 1.0);
+        assert!(finished.finished_at > 0);
+        assert_eq!(jobs.get(&job_id).unwrap().status, JobStatus::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_timeout_and_output_cap() {
+        let config = JobConfig {
+            tool_timeouts: HashMap::from([("slow".to_string(), Duration::from_millis(100))]),
+            max_output_bytes: 8,
+            ..JobConfig::default()
+        };
+        let jobs = manager(config);
+
+        let job_id = jobs
+            .start(
+                "slow",
+                context(&[("chunks", "100"), ("delay_ms", "5")]),
+                None,
+            )
+            .unwrap();
+        let finished = wait_finished(&jobs, &job_id).await;
+        assert_eq!(finished.status, JobStatus::TimedOut);
+        assert_eq!(finished.output, "chunk;ch");
+        assert!(finished.output_truncated);
+
+        let job_id = jobs
+            .start(
+                "slow",
+                context(&[("chunks", "1"), ("delay_ms", "5000")]),
+                Some(Duration::from_secs(60)),
+            )
+            .unwrap();
+        let cancelled = jobs.cancel(&job_id).unwrap();
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+        // Cancelling again leaves the job unchanged
+        assert_eq!(
+            jobs.cancel(&job_id).unwrap().finished_at,
+            cancelled.finished_at
+        );
+        assert!(matches!(jobs.get("job-0-0"), Err(JobError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_finished_jobs_are_pruned_after_retention() {
+        let jobs = manager(JobConfig {
+            retention: Duration::from_millis(50),
+            ..JobConfig::default()
+        });
+        let job_id = jobs
+            .start("slow", context(&[("chunks", "1")]), None)
+            .unwrap();
+        wait_finished(&jobs, &job_id).await;
+        assert!(jobs.get(&job_id).is_ok());
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(matches!(jobs.get(&job_id), Err(JobError::NotFound(_))));
+    }
+}
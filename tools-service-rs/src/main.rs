@@ -8,8 +8,10 @@ use serde_json::json;
 use std::collections::HashMap;
 use std::env;
 use std::net::SocketAddr;
+use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tokio_stream::{wrappers::ReceiverStream, Stream};
 use tonic::{transport::Server, Request, Response, Status};
 
 // Import the tool-sdk for API client management
@@ -21,9 +23,20 @@ use tool_sdk::{
 };
 
 // Import our validation module (used by tool_manager and tools)
+mod auth_client;
+mod executor_client;
+mod fetch;
+mod http_tools;
+mod jobs;
+mod mcp_client;
+mod patch;
+mod secrets_client;
+mod sql_query;
 mod tool_manager;
 mod tools;
 mod validation;
+mod wasm_plugins;
+mod workspace;
 
 // Track service start time for uptime reporting
 static START_TIME: Lazy<Instant> = Lazy::new(Instant::now);
@@ -36,8 +49,12 @@ pub mod agi_core {
 use agi_core::{
     health_service_server::{HealthService, HealthServiceServer},
     tools_service_server::{ToolsService, ToolsServiceServer},
-    DirectiveResponse, EmergencyDirective, HealthRequest, HealthResponse, ListToolsRequest,
-    ListToolsResponse, ToolRequest, ToolResponse,
+    CapabilityGrant, DescribeToolsRequest, DescribeToolsResponse, DirectiveResponse,
+    EmergencyDirective, GrantCapabilitiesRequest, GrantCapabilitiesResponse, HealthRequest,
+    HealthResponse, ListSessionCapabilitiesRequest, ListSessionCapabilitiesResponse,
+    ListToolsRequest, ListToolsResponse, RevokeCapabilitiesRequest, RevokeCapabilitiesResponse,
+    StartToolJobRequest, StartToolJobResponse, ToolDescription, ToolJobRequest, ToolJobStatus,
+    ToolRequest, ToolResponse, ToolVersionInfo,
 };
 
 // Define the Tools Server Structure
@@ -73,15 +90,14 @@ impl Default for ToolsServer {
             max_retries: 3,
             initial_interval: std::time::Duration::from_millis(500),
             max_interval: std::time::Duration::from_secs(10),
-            backoff_factor: 2.0,
-            retry_status_codes: vec![429, 500, 502, 503, 504],
+            multiplier: 2.0,
             ..RetryConfig::default()
         };
 
         let circuit_breaker_config = CircuitBreakerConfig {
             failure_threshold: 5,
             reset_timeout: std::time::Duration::from_secs(30),
-            half_open_success_threshold: 2,
+            success_threshold: 2,
             ..CircuitBreakerConfig::default()
         };
 
@@ -112,6 +128,103 @@ impl ToolsServer {
     // No internal helper methods are required for the general-purpose tools.
 }
 
+fn job_status(snapshot: jobs::JobSnapshot) -> ToolJobStatus {
+    ToolJobStatus {
+        job_id: snapshot.job_id,
+        tool_name: snapshot.tool_id,
+        status: snapshot.status.as_str().to_string(),
+        progress: snapshot.progress,
+        progress_message: snapshot.progress_message,
+        output: snapshot.output,
+        output_truncated: snapshot.output_truncated,
+        error: snapshot.error,
+        metadata: snapshot.metadata,
+        created_at: snapshot.created_at as i64,
+        finished_at: snapshot.finished_at as i64,
+    }
+}
+
+fn job_error_status(error: jobs::JobError) -> Status {
+    use tool_manager::ToolManagerError;
+    match &error {
+        jobs::JobError::NotFound(_) | jobs::JobError::Tool(ToolManagerError::ToolNotFound(_)) => {
+            Status::not_found(error.to_string())
+        }
+        jobs::JobError::Tool(ToolManagerError::ValidationError(_)) => {
+            Status::invalid_argument(error.to_string())
+        }
+        jobs::JobError::Tool(ToolManagerError::MissingCapability(_)) => {
+            Status::permission_denied(error.to_string())
+        }
+        jobs::JobError::Tool(_) => Status::internal(error.to_string()),
+    }
+}
+
+const ALL_CAPABILITIES: [tool_manager::Capability; 6] = [
+    tool_manager::Capability::ExecuteCommand,
+    tool_manager::Capability::FileSystem,
+    tool_manager::Capability::Network,
+    tool_manager::Capability::ExecuteCode,
+    tool_manager::Capability::SimulateInput,
+    tool_manager::Capability::AccessSensitiveData,
+];
+
+/// Issuer token from the `authorization` metadata
+fn bearer_token<T>(request: &Request<T>) -> Result<String, Status> {
+    let value = request
+        .metadata()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| Status::unauthenticated("missing authorization metadata"))?;
+    Ok(value.strip_prefix("Bearer ").unwrap_or(value).to_string())
+}
+
+fn parse_capabilities(names: &[String]) -> Result<Vec<tool_manager::Capability>, Status> {
+    names
+        .iter()
+        .map(|name| {
+            name.parse().map_err(|e: tool_manager::ToolManagerError| {
+                Status::invalid_argument(e.to_string())
+            })
+        })
+        .collect()
+}
+
+/// Requested grant TTL; 0 uses TOOLS_GRANT_DEFAULT_TTL_SECS, capped at TOOLS_GRANT_MAX_TTL_SECS
+fn grant_ttl(requested_secs: u64) -> Duration {
+    let env_secs = |name: &str, default: u64| {
+        env::var(name)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    };
+    let secs = if requested_secs == 0 {
+        env_secs("TOOLS_GRANT_DEFAULT_TTL_SECS", 3600)
+    } else {
+        requested_secs
+    };
+    Duration::from_secs(secs.min(env_secs("TOOLS_GRANT_MAX_TTL_SECS", 86400)))
+}
+
+fn capability_grant(grant: tool_manager::CapabilityGrant) -> CapabilityGrant {
+    CapabilityGrant {
+        capability: format!("{:?}", grant.capability),
+        tool_id: grant.tool_id.unwrap_or_default(),
+        issuer: grant.issuer,
+        granted_at: grant.granted_at as i64,
+        expires_at: grant.expires_at as i64,
+    }
+}
+
+fn auth_error_status(error: auth_client::AuthError) -> Status {
+    match error {
+        auth_client::AuthError::Unauthenticated(msg) => Status::unauthenticated(msg),
+        auth_client::AuthError::PermissionDenied(msg) => Status::permission_denied(msg),
+        auth_client::AuthError::ConnectionError(msg) => Status::unavailable(msg),
+        auth_client::AuthError::GrpcError(status) => status,
+    }
+}
+
 // Implement the ToolsService Trait
 #[tonic::async_trait]
 impl ToolsService for ToolsServer {
@@ -122,6 +235,7 @@ impl ToolsService for ToolsServer {
         let req_data = request.into_inner();
         let tool_name = req_data.tool_name;
         let parameters = req_data.parameters;
+        let session_id = (!req_data.session_id.is_empty()).then_some(req_data.session_id);
 
         log::info!("Received ExecuteTool request: tool_name={}", tool_name);
 
@@ -137,7 +251,7 @@ impl ToolsService for ToolsServer {
         let context = crate::tool_manager::ToolContext {
             parameters,
             user_id: None,
-            session_id: None,
+            session_id,
             request_id,
             context_data: HashMap::new(),
         };
@@ -160,14 +274,20 @@ impl ToolsService for ToolsServer {
                     } else {
                         tool_result.error
                     },
+                    metadata: tool_result.metadata,
                 }))
             }
+            Err(e @ tool_manager::ToolManagerError::MissingCapability(_)) => {
+                log::warn!("Tool '{}' denied: {}", tool_name, e);
+                Err(Status::permission_denied(e.to_string()))
+            }
             Err(e) => {
                 log::error!("Tool '{}' failed: {}", tool_name, e);
                 Ok(Response::new(ToolResponse {
                     success: false,
                     result: String::new(),
                     error: e.to_string(),
+                    metadata: HashMap::new(),
                 }))
             }
         }
@@ -206,6 +326,260 @@ impl ToolsService for ToolsServer {
         Ok(Response::new(reply))
     }
 
+    async fn describe_tools(
+        &self,
+        request: Request<DescribeToolsRequest>,
+    ) -> Result<Response<DescribeToolsResponse>, Status> {
+        let req_data = request.into_inner();
+
+        log::info!(
+            "Received DescribeTools request: category={:?}, tool_ids={:?}",
+            req_data.category,
+            req_data.tool_ids
+        );
+
+        let manager = crate::tool_manager::TOOL_MANAGER.clone();
+        let category = if req_data.category.is_empty() {
+            None
+        } else {
+            Some(req_data.category.as_str())
+        };
+
+        let mut tools = manager
+            .list_tools(category)
+            .into_iter()
+            .filter(|m| req_data.tool_ids.is_empty() || req_data.tool_ids.contains(&m.id))
+            .map(|m| {
+                let mut required_capabilities = m
+                    .capabilities
+                    .iter()
+                    .map(|c| format!("{:?}", c))
+                    .collect::<Vec<String>>();
+                required_capabilities.sort();
+
+                let versions = manager
+                    .list_tool_versions(&m.id)
+                    .into_iter()
+                    .map(|(version, usage)| ToolVersionInfo {
+                        version: version.version,
+                        deprecation: version.deprecated.unwrap_or_default(),
+                        calls: usage.calls,
+                        failures: usage.failures,
+                        total_duration_ms: usage.total_duration_ms,
+                        last_used_at: usage.last_used_at as i64,
+                    })
+                    .collect();
+
+                ToolDescription {
+                    parameters_schema: tool_manager::parameters_json_schema(&m.parameters)
+                        .to_string(),
+                    id: m.id,
+                    name: m.name,
+                    description: m.description,
+                    version: m.version,
+                    category: m.category,
+                    author: m.author,
+                    required_capabilities,
+                    deprecation: m.deprecated.unwrap_or_default(),
+                    versions,
+                }
+            })
+            .collect::<Vec<ToolDescription>>();
+        tools.sort_by(|a, b| a.id.cmp(&b.id));
+
+        log::info!("Describing {} tool(s)", tools.len());
+
+        Ok(Response::new(DescribeToolsResponse { tools }))
+    }
+
+    async fn start_tool_job(
+        &self,
+        request: Request<StartToolJobRequest>,
+    ) -> Result<Response<StartToolJobResponse>, Status> {
+        let req_data = request.into_inner();
+
+        log::info!(
+            "Received StartToolJob request: tool_name={}",
+            req_data.tool_name
+        );
+
+        let request_id = format!(
+            "tools-job-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis()
+        );
+
+        let context = crate::tool_manager::ToolContext {
+            parameters: req_data.parameters,
+            user_id: None,
+            session_id: (!req_data.session_id.is_empty()).then_some(req_data.session_id),
+            request_id,
+            context_data: HashMap::new(),
+        };
+        let timeout = (req_data.timeout_secs > 0)
+            .then(|| std::time::Duration::from_secs(req_data.timeout_secs));
+
+        let job_id = jobs::JOB_MANAGER
+            .start(&req_data.tool_name, context, timeout)
+            .map_err(job_error_status)?;
+
+        Ok(Response::new(StartToolJobResponse { job_id }))
+    }
+
+    async fn get_tool_job(
+        &self,
+        request: Request<ToolJobRequest>,
+    ) -> Result<Response<ToolJobStatus>, Status> {
+        let job_id = request.into_inner().job_id;
+        let snapshot = jobs::JOB_MANAGER.get(&job_id).map_err(job_error_status)?;
+        Ok(Response::new(job_status(snapshot)))
+    }
+
+    async fn cancel_tool_job(
+        &self,
+        request: Request<ToolJobRequest>,
+    ) -> Result<Response<ToolJobStatus>, Status> {
+        let job_id = request.into_inner().job_id;
+        log::info!("Received CancelToolJob request: job_id={}", job_id);
+        let snapshot = jobs::JOB_MANAGER
+            .cancel(&job_id)
+            .map_err(job_error_status)?;
+        Ok(Response::new(job_status(snapshot)))
+    }
+
+    type WatchToolJobStream =
+        Pin<Box<dyn Stream<Item = Result<ToolJobStatus, Status>> + Send + 'static>>;
+
+    async fn watch_tool_job(
+        &self,
+        request: Request<ToolJobRequest>,
+    ) -> Result<Response<Self::WatchToolJobStream>, Status> {
+        let job_id = request.into_inner().job_id;
+        let mut updates = jobs::JOB_MANAGER.watch(&job_id).map_err(job_error_status)?;
+
+        // Updates are coalesced: a slow watcher only sees the latest state
+        let (status_tx, status_rx) = tokio::sync::mpsc::channel(16);
+        tokio::spawn(async move {
+            loop {
+                let snapshot = updates.borrow_and_update().clone();
+                let finished = snapshot.status.is_finished();
+                if status_tx.send(Ok(job_status(snapshot))).await.is_err() || finished {
+                    break;
+                }
+                if updates.changed().await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(status_rx))))
+    }
+
+    async fn grant_capabilities(
+        &self,
+        request: Request<GrantCapabilitiesRequest>,
+    ) -> Result<Response<GrantCapabilitiesResponse>, Status> {
+        let token = bearer_token(&request)?;
+        let req_data = request.into_inner();
+        if req_data.session_id.is_empty() {
+            return Err(Status::invalid_argument("session_id is required"));
+        }
+        let capabilities = parse_capabilities(&req_data.capabilities)?;
+        if capabilities.is_empty() {
+            return Err(Status::invalid_argument("capabilities are required"));
+        }
+        let tool_id = (!req_data.tool_id.is_empty()).then_some(req_data.tool_id.as_str());
+
+        let issuer = auth_client::AuthClient::shared()
+            .await
+            .map_err(auth_error_status)?
+            .authorize_capabilities(&token, "grant_capability", &capabilities)
+            .await
+            .map_err(auth_error_status)?;
+
+        log::info!(
+            "Received GrantCapabilities request: session_id={} issuer={}",
+            req_data.session_id,
+            issuer
+        );
+        let grants = tool_manager::TOOL_MANAGER
+            .grant_session_capabilities(
+                &req_data.session_id,
+                &capabilities,
+                tool_id,
+                &issuer,
+                grant_ttl(req_data.ttl_secs),
+            )
+            .await;
+
+        Ok(Response::new(GrantCapabilitiesResponse {
+            grants: grants.into_iter().map(capability_grant).collect(),
+        }))
+    }
+
+    async fn revoke_capabilities(
+        &self,
+        request: Request<RevokeCapabilitiesRequest>,
+    ) -> Result<Response<RevokeCapabilitiesResponse>, Status> {
+        let token = bearer_token(&request)?;
+        let req_data = request.into_inner();
+        if req_data.session_id.is_empty() {
+            return Err(Status::invalid_argument("session_id is required"));
+        }
+        let capabilities = parse_capabilities(&req_data.capabilities)?;
+        let tool_id = (!req_data.tool_id.is_empty()).then_some(req_data.tool_id.as_str());
+
+        // Revoking everything requires the permission on every capability
+        let checked = if capabilities.is_empty() {
+            ALL_CAPABILITIES.to_vec()
+        } else {
+            capabilities.clone()
+        };
+        let issuer = auth_client::AuthClient::shared()
+            .await
+            .map_err(auth_error_status)?
+            .authorize_capabilities(&token, "revoke_capability", &checked)
+            .await
+            .map_err(auth_error_status)?;
+
+        log::info!(
+            "Received RevokeCapabilities request: session_id={} issuer={}",
+            req_data.session_id,
+            issuer
+        );
+        let revoked = tool_manager::TOOL_MANAGER
+            .revoke_session_capabilities(&req_data.session_id, &capabilities, tool_id, &issuer)
+            .await;
+
+        Ok(Response::new(RevokeCapabilitiesResponse {
+            revoked: revoked as u32,
+        }))
+    }
+
+    async fn list_session_capabilities(
+        &self,
+        request: Request<ListSessionCapabilitiesRequest>,
+    ) -> Result<Response<ListSessionCapabilitiesResponse>, Status> {
+        let token = bearer_token(&request)?;
+        auth_client::AuthClient::shared()
+            .await
+            .map_err(auth_error_status)?
+            .token_subject(&token)
+            .await
+            .map_err(auth_error_status)?;
+
+        let session_id = request.into_inner().session_id;
+        let grants = tool_manager::TOOL_MANAGER
+            .list_session_capabilities(&session_id)
+            .await;
+
+        Ok(Response::new(ListSessionCapabilitiesResponse {
+            grants: grants.into_iter().map(capability_grant).collect(),
+        }))
+    }
+
     async fn execute_emergency_directive(
         &self,
         _request: Request<EmergencyDirective>,
@@ -228,12 +602,50 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize logging
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
 
+    // Forward capability grant and use audit events to the auth service
+    let (audit_tx, audit_rx) = tokio::sync::mpsc::unbounded_channel();
+    tool_manager::TOOL_MANAGER.set_audit_sink(audit_tx);
+    auth_client::spawn_audit_forwarder(audit_rx);
+
     // Register general-purpose tools in the ToolManager
     if let Err(e) = tools::register_all_tools().await {
         log::error!("Failed to register tools: {}", e);
         return Err(Box::new(e) as Box<dyn std::error::Error>);
     }
 
+    // Load manifest-defined tools (WebAssembly plugins, HTTP tools) and keep them in sync
+    let plugin_dir = env::var("TOOLS_PLUGIN_DIR").unwrap_or_else(|_| "plugins".to_string());
+    if std::path::Path::new(&plugin_dir).is_dir() {
+        let manager = tool_manager::TOOL_MANAGER.clone();
+        match manager.load_tools_from_directory(&plugin_dir) {
+            Ok(loaded) => log::info!(
+                "Loaded {} manifest tool(s) from {}",
+                loaded.len(),
+                plugin_dir
+            ),
+            Err(e) => log::error!("Failed to load manifest tools from {}: {}", plugin_dir, e),
+        }
+
+        let reload_secs: u64 = env::var("TOOLS_PLUGIN_RELOAD_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10);
+        if reload_secs > 0 {
+            manager.spawn_plugin_reload(plugin_dir, std::time::Duration::from_secs(reload_secs));
+        }
+    }
+
+    // Register tools discovered on configured MCP servers
+    match mcp_client::McpConfig::from_env() {
+        Ok(config) if !config.servers.is_empty() => {
+            let registered =
+                mcp_client::register_mcp_tools(&tool_manager::TOOL_MANAGER, config).await;
+            log::info!("Registered {} MCP tool(s)", registered.len());
+        }
+        Ok(_) => {}
+        Err(e) => log::error!("Failed to load MCP server config: {}", e),
+    }
+
     // Read address from environment variable or use the default port 50054
     let addr_str = env::var("TOOLS_SERVICE_ADDR").unwrap_or_else(|_| "0.0.0.0:50054".to_string());
 
@@ -0,0 +1,1025 @@
+//! Model Context Protocol (MCP) Client Bridge
+//!
+//! Connects to configured MCP servers, discovers their tools and registers each
+//! one with the `ToolManager`. Executions are proxied to the server with
+//! `tools/call`. Servers are listed in a TOML file (TOOLS_MCP_CONFIG, default
+//! `mcp_servers.toml`; skipped if missing):
+//!
+//! ```toml
+//! [[servers]]
+//! name = "github"
+//! transport = "stdio"                  # stdio | http
+//! command = "npx"
+//! args = ["-y", "@modelcontextprotocol/server-github"]
+//! env = { GITHUB_API_URL = "https://api.github.com" }
+//! capabilities = ["Network"]           # Required capabilities of every tool from this server
+//! tool_prefix = "github"               # Registered ids are <prefix>_<tool> (default: name)
+//! timeout_secs = 30
+//!
+//! [[servers]]
+//! name = "search"
+//! transport = "http"                   # Streamable HTTP endpoint
+//! url = "http://localhost:3001/mcp"
+//! headers = { X-Team = "agents" }
+//! auth_secret = "mcp/search-token"     # Bearer token resolved through secrets-service
+//! ```
+//!
+//! A stdio server is restarted on the next call after its process exits; an
+//! HTTP session is re-initialized when the server forgets it.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::{mpsc, oneshot, Mutex as AsyncMutex, RwLock};
+
+use crate::http_tools::typed_value;
+use crate::jobs::{self, JobReporter};
+use crate::secrets_client::SecretsClient;
+use crate::tool_manager::{
+    parse_version, validate_parameter_definitions, Capability, ParameterDefinition, Tool,
+    ToolContext, ToolManager, ToolManagerError, ToolMetadata, ToolResult,
+};
+
+/// Protocol revision sent in `initialize`
+const PROTOCOL_VERSION: &str = "2025-03-26";
+
+/// JSON-RPC "method not found", returned for server-initiated requests we don't serve
+const METHOD_NOT_FOUND: i64 = -32601;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpTransportKind {
+    Stdio,
+    Http,
+}
+
+/// One `[[servers]]` entry
+#[derive(Debug, Clone, Deserialize)]
+pub struct McpServerConfig {
+    pub name: String,
+    pub transport: McpTransportKind,
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub auth_secret: Option<String>,
+    #[serde(default)]
+    pub capabilities: HashSet<Capability>,
+    #[serde(default)]
+    pub tool_prefix: Option<String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct McpConfig {
+    #[serde(default)]
+    pub servers: Vec<McpServerConfig>,
+}
+
+impl McpConfig {
+    pub fn parse(source: &str) -> Result<Self, ToolManagerError> {
+        let config: Self = toml::from_str(source)
+            .map_err(|e| ToolManagerError::InvalidMetadata(format!("Invalid MCP config: {}", e)))?;
+
+        for server in &config.servers {
+            let complete = match server.transport {
+                McpTransportKind::Stdio => server.command.is_some(),
+                McpTransportKind::Http => server.url.is_some(),
+            };
+            if !complete {
+                return Err(ToolManagerError::InvalidMetadata(format!(
+                    "MCP server {} needs {}",
+                    server.name,
+                    match server.transport {
+                        McpTransportKind::Stdio => "a command",
+                        McpTransportKind::Http => "a url",
+                    }
+                )));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Load TOOLS_MCP_CONFIG; a missing file means no servers
+    pub fn from_env() -> Result<Self, ToolManagerError> {
+        let path =
+            std::env::var("TOOLS_MCP_CONFIG").unwrap_or_else(|_| "mcp_servers.toml".to_string());
+        if !Path::new(&path).is_file() {
+            return Ok(Self::default());
+        }
+        let source = std::fs::read_to_string(&path)
+            .map_err(|e| ToolManagerError::LoadingError(format!("{}: {}", path, e)))?;
+        Self::parse(&source)
+    }
+}
+
+/// Reporters of the jobs waiting on requests, keyed by the request's progress token
+type ProgressSinks = Arc<Mutex<HashMap<String, JobReporter>>>;
+
+/// Forward a `notifications/progress` message to the job that sent the token
+fn dispatch_progress(sinks: &ProgressSinks, message: &Value) {
+    if message.get("method").and_then(Value::as_str) != Some("notifications/progress") {
+        return;
+    }
+    let params = &message["params"];
+    let token = match &params["progressToken"] {
+        Value::String(token) => token.clone(),
+        Value::Number(token) => token.to_string(),
+        _ => return,
+    };
+    let Some(reporter) = sinks.lock().unwrap().get(&token).cloned() else {
+        return;
+    };
+
+    let progress = params["progress"].as_f64().unwrap_or(0.0);
+    let fraction = match params["total"].as_f64() {
+        Some(total) if total > 0.0 => progress / total,
+        _ => 0.0,
+    };
+    let message = params["message"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{}", progress));
+    reporter.report_progress(fraction as f32, message);
+}
+
+fn rpc_error(server: &str, message: impl std::fmt::Display) -> ToolManagerError {
+    ToolManagerError::ExecutionError(format!("MCP server {}: {}", server, message))
+}
+
+/// Response `result`, or the JSON-RPC error it carries
+fn into_result(server: &str, response: Value) -> Result<Value, ToolManagerError> {
+    if let Some(error) = response.get("error") {
+        let message = error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown error");
+        return Err(rpc_error(server, message));
+    }
+    Ok(response.get("result").cloned().unwrap_or(Value::Null))
+}
+
+/// Newline-delimited JSON-RPC over a child process's stdin/stdout
+struct StdioTransport {
+    _child: Child,
+    outgoing: mpsc::UnboundedSender<String>,
+    pending: Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>,
+    closed: Arc<AtomicBool>,
+}
+
+impl StdioTransport {
+    fn spawn(config: &McpServerConfig, progress: ProgressSinks) -> Result<Self, ToolManagerError> {
+        let command = config.command.as_deref().unwrap_or_default();
+        let mut child = Command::new(command)
+            .args(&config.args)
+            .envs(&config.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                ToolManagerError::LoadingError(format!(
+                    "Failed to start MCP server {} ({}): {}",
+                    config.name, command, e
+                ))
+            })?;
+
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+        let pending: Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>> = Arc::default();
+        let closed = Arc::new(AtomicBool::new(false));
+        let (outgoing, mut queue) = mpsc::unbounded_channel::<String>();
+
+        tokio::spawn(async move {
+            while let Some(line) = queue.recv().await {
+                if stdin.write_all(line.as_bytes()).await.is_err()
+                    || stdin.write_all(b"\n").await.is_err()
+                    || stdin.flush().await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let name = config.name.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                log::debug!("[mcp:{}] {}", name, line);
+            }
+        });
+
+        let name = config.name.clone();
+        let reader_pending = Arc::clone(&pending);
+        let reader_closed = Arc::clone(&closed);
+        let replies = outgoing.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                    log::warn!("MCP server {} sent invalid JSON: {}", name, line);
+                    continue;
+                };
+                let id = message.get("id").cloned();
+                match (message.get("method").and_then(Value::as_str), id) {
+                    // Server-initiated request: answer pings, decline everything else
+                    (Some(method), Some(id)) => {
+                        let reply = if method == "ping" {
+                            json!({"jsonrpc": "2.0", "id": id, "result": {}})
+                        } else {
+                            json!({"jsonrpc": "2.0", "id": id, "error": {
+                                "code": METHOD_NOT_FOUND,
+                                "message": format!("Method not supported: {}", method)
+                            }})
+                        };
+                        let _ = replies.send(reply.to_string());
+                    }
+                    (Some(_), None) => dispatch_progress(&progress, &message),
+                    (None, Some(id)) => {
+                        let waiter = id
+                            .as_u64()
+                            .and_then(|id| reader_pending.lock().unwrap().remove(&id));
+                        if let Some(waiter) = waiter {
+                            let _ = waiter.send(message);
+                        }
+                    }
+                    (None, None) => {}
+                }
+            }
+            reader_closed.store(true, Ordering::SeqCst);
+            // Dropping the senders fails every in-flight request
+            reader_pending.lock().unwrap().clear();
+            log::warn!("MCP server {} closed its output", name);
+        });
+
+        Ok(Self {
+            _child: child,
+            outgoing,
+            pending,
+            closed,
+        })
+    }
+
+    async fn request(
+        &self,
+        server: &str,
+        id: u64,
+        message: Value,
+    ) -> Result<Value, ToolManagerError> {
+        let (sender, receiver) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, sender);
+        self.outgoing
+            .send(message.to_string())
+            .map_err(|_| rpc_error(server, "connection closed"))?;
+        receiver
+            .await
+            .map_err(|_| rpc_error(server, "connection closed"))
+    }
+
+    fn notify(&self, message: Value) {
+        let _ = self.outgoing.send(message.to_string());
+    }
+}
+
+/// Streamable HTTP transport: JSON-RPC POSTs answered with JSON or an SSE stream
+struct HttpTransport {
+    client: reqwest::Client,
+    url: String,
+    headers: HashMap<String, String>,
+    auth_secret: Option<String>,
+    session_id: RwLock<Option<String>>,
+    expired: AtomicBool,
+}
+
+impl HttpTransport {
+    async fn post(
+        &self,
+        server: &str,
+        message: &Value,
+    ) -> Result<reqwest::Response, ToolManagerError> {
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header("Accept", "application/json, text/event-stream")
+            .json(message);
+        for (name, value) in &self.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(session_id) = self.session_id.read().await.as_ref() {
+            request = request.header("Mcp-Session-Id", session_id.as_str());
+        }
+        if let Some(key) = &self.auth_secret {
+            let secrets = SecretsClient::shared()
+                .await
+                .map_err(|e| rpc_error(server, format!("secrets service unavailable: {}", e)))?;
+            let token = secrets.get_secret(key).await.map_err(|e| {
+                rpc_error(server, format!("failed to resolve secret {}: {}", key, e))
+            })?;
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await.map_err(|e| rpc_error(server, e))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND
+            && self.session_id.read().await.is_some()
+        {
+            // The server dropped our session; the next call re-initializes
+            self.expired.store(true, Ordering::SeqCst);
+            return Err(rpc_error(server, "session expired"));
+        }
+        if !response.status().is_success() {
+            return Err(rpc_error(server, format!("HTTP {}", response.status())));
+        }
+        Ok(response)
+    }
+
+    async fn request(
+        &self,
+        server: &str,
+        id: u64,
+        message: Value,
+        progress: &ProgressSinks,
+    ) -> Result<Value, ToolManagerError> {
+        let response = self.post(server, &message).await?;
+        if let Some(session_id) = response
+            .headers()
+            .get("Mcp-Session-Id")
+            .and_then(|v| v.to_str().ok())
+        {
+            *self.session_id.write().await = Some(session_id.to_string());
+        }
+
+        let is_sse = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("text/event-stream"));
+        let body = response.text().await.map_err(|e| rpc_error(server, e))?;
+
+        let messages = if is_sse {
+            sse_messages(&body)
+        } else {
+            match serde_json::from_str::<Value>(&body).map_err(|e| rpc_error(server, e))? {
+                Value::Array(batch) => batch,
+                single => vec![single],
+            }
+        };
+        // The body is read in full, so progress arrives just before the response
+        for message in &messages {
+            dispatch_progress(progress, message);
+        }
+        messages
+            .into_iter()
+            .find(|m| m.get("id").and_then(Value::as_u64) == Some(id))
+            .ok_or_else(|| rpc_error(server, "no response to request"))
+    }
+
+    async fn notify(&self, server: &str, message: Value) -> Result<(), ToolManagerError> {
+        self.post(server, &message).await.map(|_| ())
+    }
+}
+
+/// JSON messages carried by the `data:` fields of an SSE body
+fn sse_messages(body: &str) -> Vec<Value> {
+    body.split("\n\n")
+        .filter_map(|event| {
+            let data: Vec<&str> = event
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(str::trim_start)
+                .collect();
+            serde_json::from_str(&data.join("\n")).ok()
+        })
+        .collect()
+}
+
+enum Transport {
+    Stdio(StdioTransport),
+    Http(HttpTransport),
+}
+
+/// An initialized connection to one server
+struct McpSession {
+    transport: Transport,
+    next_id: AtomicU64,
+    server_version: String,
+    progress: ProgressSinks,
+}
+
+impl McpSession {
+    fn is_alive(&self) -> bool {
+        match &self.transport {
+            Transport::Stdio(stdio) => !stdio.closed.load(Ordering::SeqCst),
+            Transport::Http(http) => !http.expired.load(Ordering::SeqCst),
+        }
+    }
+
+    async fn request(
+        &self,
+        config: &McpServerConfig,
+        method: &str,
+        mut params: Value,
+        progress: Option<JobReporter>,
+    ) -> Result<Value, ToolManagerError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        // Ask for progress notifications on behalf of the job making the call
+        let token = progress.map(|reporter| {
+            let token = reporter.job_id();
+            params["_meta"] = json!({"progressToken": token});
+            self.progress
+                .lock()
+                .unwrap()
+                .insert(token.clone(), reporter);
+            token
+        });
+        let message = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params});
+        let call = async {
+            match &self.transport {
+                Transport::Stdio(stdio) => stdio.request(&config.name, id, message).await,
+                Transport::Http(http) => {
+                    http.request(&config.name, id, message, &self.progress)
+                        .await
+                }
+            }
+        };
+        let response = tokio::time::timeout(Duration::from_secs(config.timeout_secs), call).await;
+        if let Some(token) = token {
+            self.progress.lock().unwrap().remove(&token);
+        }
+        let response = response.map_err(|_| {
+            rpc_error(
+                &config.name,
+                format!("{} timed out after {}s", method, config.timeout_secs),
+            )
+        })??;
+        into_result(&config.name, response)
+    }
+
+    async fn notify(&self, config: &McpServerConfig, method: &str) -> Result<(), ToolManagerError> {
+        let message = json!({"jsonrpc": "2.0", "method": method});
+        match &self.transport {
+            Transport::Stdio(stdio) => {
+                stdio.notify(message);
+                Ok(())
+            }
+            Transport::Http(http) => http.notify(&config.name, message).await,
+        }
+    }
+}
+
+/// A configured server, connected lazily and reconnected after failures
+pub struct McpServer {
+    config: McpServerConfig,
+    session: AsyncMutex<Option<Arc<McpSession>>>,
+}
+
+impl McpServer {
+    pub fn new(config: McpServerConfig) -> Self {
+        Self {
+            config,
+            session: AsyncMutex::new(None),
+        }
+    }
+
+    async fn connect(&self) -> Result<McpSession, ToolManagerError> {
+        let progress = ProgressSinks::default();
+        let transport = match self.config.transport {
+            McpTransportKind::Stdio => {
+                Transport::Stdio(StdioTransport::spawn(&self.config, Arc::clone(&progress))?)
+            }
+            McpTransportKind::Http => Transport::Http(HttpTransport {
+                client: reqwest::Client::new(),
+                url: self.config.url.clone().unwrap_or_default(),
+                headers: self.config.headers.clone(),
+                auth_secret: self.config.auth_secret.clone(),
+                session_id: RwLock::new(None),
+                expired: AtomicBool::new(false),
+            }),
+        };
+        let mut session = McpSession {
+            transport,
+            next_id: AtomicU64::new(1),
+            server_version: String::new(),
+            progress,
+        };
+
+        let init = session
+            .request(
+                &self.config,
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {"name": "tools-service", "version": env!("CARGO_PKG_VERSION")},
+                }),
+                None,
+            )
+            .await?;
+        session.server_version = init
+            .pointer("/serverInfo/version")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        session
+            .notify(&self.config, "notifications/initialized")
+            .await?;
+
+        log::info!(
+            "Connected to MCP server {} ({})",
+            self.config.name,
+            init.pointer("/serverInfo/name")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown")
+        );
+        Ok(session)
+    }
+
+    async fn session(&self) -> Result<Arc<McpSession>, ToolManagerError> {
+        let mut current = self.session.lock().await;
+        if let Some(session) = current.as_ref().filter(|s| s.is_alive()) {
+            return Ok(Arc::clone(session));
+        }
+        let session = Arc::new(self.connect().await?);
+        *current = Some(Arc::clone(&session));
+        Ok(session)
+    }
+
+    async fn request(
+        &self,
+        method: &str,
+        params: Value,
+        progress: Option<JobReporter>,
+    ) -> Result<Value, ToolManagerError> {
+        let session = self.session().await?;
+        let result = session
+            .request(&self.config, method, params, progress)
+            .await;
+        if result.is_err() && !session.is_alive() {
+            *self.session.lock().await = None;
+        }
+        result
+    }
+
+    /// All tools the server exposes, following pagination
+    async fn list_tools(&self) -> Result<Vec<Value>, ToolManagerError> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({"cursor": cursor}),
+                None => json!({}),
+            };
+            let page = self.request("tools/list", params, None).await?;
+            if let Some(items) = page.get("tools").and_then(Value::as_array) {
+                tools.extend(items.iter().cloned());
+            }
+            cursor = page
+                .get("nextCursor")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            if cursor.is_none() {
+                return Ok(tools);
+            }
+        }
+    }
+}
+
+/// Parameter definitions from a tool's JSON Schema `inputSchema`
+pub fn parameters_from_schema(schema: &Value) -> Vec<ParameterDefinition> {
+    let required: HashSet<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+
+    let mut parameters: Vec<ParameterDefinition> = properties
+        .iter()
+        .map(|(name, property)| {
+            // `"type": ["string", "null"]` is common for optional fields
+            let param_type = match property.get("type") {
+                Some(Value::String(t)) => t.clone(),
+                Some(Value::Array(types)) => types
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .find(|t| *t != "null")
+                    .unwrap_or("string")
+                    .to_string(),
+                _ => "string".to_string(),
+            };
+            ParameterDefinition {
+                name: name.clone(),
+                description: property
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                required: required.contains(name.as_str()),
+                param_type,
+                default: property.get("default").map(|v| match v {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                }),
+                validation: property
+                    .get("pattern")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            }
+        })
+        .collect();
+    parameters.sort_by(|a, b| b.required.cmp(&a.required).then(a.name.cmp(&b.name)));
+    parameters
+}
+
+/// Text of a `tools/call` result's content blocks
+fn content_text(result: &Value) -> String {
+    let parts: Vec<String> = result
+        .get("content")
+        .and_then(Value::as_array)
+        .map(|blocks| {
+            blocks
+                .iter()
+                .map(|block| match block.get("type").and_then(Value::as_str) {
+                    Some("text") => block
+                        .get("text")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    Some("resource") => block
+                        .pointer("/resource/text")
+                        .and_then(Value::as_str)
+                        .map(str::to_string)
+                        .unwrap_or_else(|| {
+                            format!(
+                                "[resource: {}]",
+                                block
+                                    .pointer("/resource/uri")
+                                    .and_then(Value::as_str)
+                                    .unwrap_or("unknown")
+                            )
+                        }),
+                    Some(kind) => format!(
+                        "[{}: {}]",
+                        kind,
+                        block
+                            .get("mimeType")
+                            .and_then(Value::as_str)
+                            .unwrap_or("unknown")
+                    ),
+                    None => String::new(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if parts.is_empty() {
+        if let Some(structured) = result.get("structuredContent") {
+            return structured.to_string();
+        }
+    }
+    parts.join("\n")
+}
+
+/// A tool proxied to an MCP server
+pub struct McpTool {
+    metadata: ToolMetadata,
+    remote_name: String,
+    server: Arc<McpServer>,
+}
+
+impl McpTool {
+    fn new(server: &Arc<McpServer>, definition: &Value, server_version: &str) -> Option<Self> {
+        let remote_name = definition.get("name").and_then(Value::as_str)?.to_string();
+        let config = &server.config;
+        let prefix = config.tool_prefix.as_deref().unwrap_or(&config.name);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        Some(Self {
+            metadata: ToolMetadata {
+                id: format!("{}_{}", prefix, remote_name),
+                name: definition
+                    .pointer("/annotations/title")
+                    .or_else(|| definition.get("title"))
+                    .and_then(Value::as_str)
+                    .unwrap_or(&remote_name)
+                    .to_string(),
+                description: definition
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                version: if server_version.contains('.') && parse_version(server_version).is_ok() {
+                    server_version.to_string()
+                } else {
+                    "1.0.0".to_string()
+                },
+                author: config.name.clone(),
+                category: "mcp".to_string(),
+                parameters: parameters_from_schema(
+                    definition.get("inputSchema").unwrap_or(&Value::Null),
+                ),
+                capabilities: config.capabilities.clone(),
+                enabled: true,
+                created_at: now,
+                updated_at: now,
+                deprecated: None,
+            },
+            remote_name,
+            server: Arc::clone(server),
+        })
+    }
+}
+
+#[async_trait]
+impl Tool for McpTool {
+    fn metadata(&self) -> &ToolMetadata {
+        &self.metadata
+    }
+
+    fn validate_parameters(
+        &self,
+        parameters: &HashMap<String, String>,
+    ) -> Result<(), ToolManagerError> {
+        validate_parameter_definitions(&self.metadata.parameters, parameters)
+    }
+
+    async fn execute(&self, context: ToolContext) -> Result<ToolResult, ToolManagerError> {
+        let start_time = Instant::now();
+        let arguments: Map<String, Value> = self
+            .metadata
+            .parameters
+            .iter()
+            .filter_map(|param| {
+                context
+                    .parameters
+                    .get(&param.name)
+                    .map(|value| (param.name.clone(), typed_value(&param.param_type, value)))
+            })
+            .collect();
+
+        let result = self
+            .server
+            .request(
+                "tools/call",
+                json!({"name": self.remote_name, "arguments": arguments}),
+                jobs::current_reporter(),
+            )
+            .await?;
+        let is_error = result
+            .get("isError")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let text = content_text(&result);
+
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        let mut metadata = HashMap::new();
+        metadata.insert("mcp_server".to_string(), self.server.config.name.clone());
+        metadata.insert("mcp_tool".to_string(), self.remote_name.clone());
+        metadata.insert("execution_time_ms".to_string(), duration_ms.to_string());
+
+        Ok(ToolResult {
+            success: !is_error,
+            data: if is_error {
+                String::new()
+            } else {
+                text.clone()
+            },
+            error: if is_error { text } else { String::new() },
+            metadata,
+            duration_ms,
+        })
+    }
+}
+
+/// Connect to each server, discover its tools and register them
+///
+/// A server that can't be reached is logged and skipped. Returns the ids of
+/// the registered tools.
+pub async fn register_mcp_tools(manager: &ToolManager, config: McpConfig) -> Vec<String> {
+    let mut registered = Vec::new();
+
+    for server_config in config.servers {
+        let name = server_config.name.clone();
+        let server = Arc::new(McpServer::new(server_config));
+        let definitions = match server.list_tools().await {
+            Ok(definitions) => definitions,
+            Err(e) => {
+                log::error!("Skipping MCP server {}: {}", name, e);
+                continue;
+            }
+        };
+        let server_version = match server.session().await {
+            Ok(session) => session.server_version.clone(),
+            Err(_) => String::new(),
+        };
+
+        for definition in &definitions {
+            let Some(tool) = McpTool::new(&server, definition, &server_version) else {
+                continue;
+            };
+            let tool_id = tool.metadata.id.clone();
+            let result = manager
+                .validate_tool_metadata(&tool.metadata)
+                .and_then(|_| manager.register_tool(Arc::new(tool)));
+            match result {
+                Ok(()) => registered.push(tool_id),
+                Err(e) => log::error!("Failed to register MCP tool {}: {}", tool_id, e),
+            }
+        }
+    }
+
+    registered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    /// Minimal Streamable HTTP MCP server exposing an `add` tool
+    async fn serve_fake_mcp() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 4096];
+                let (head_end, length) = loop {
+                    let n = socket.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                    let text = String::from_utf8_lossy(&buf).to_string();
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text[..end]
+                            .lines()
+                            .find_map(|l| {
+                                l.to_lowercase()
+                                    .strip_prefix("content-length:")
+                                    .map(|v| v.trim().parse::<usize>().unwrap())
+                            })
+                            .unwrap_or(0);
+                        break (end + 4, length);
+                    }
+                };
+                while buf.len() < head_end + length {
+                    let n = socket.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                let request: Value =
+                    serde_json::from_slice(&buf[head_end..head_end + length]).unwrap();
+                let id = request["id"].clone();
+                let (content_type, body) = match request["method"].as_str().unwrap() {
+                    "initialize" => ("application/json", json!({"jsonrpc": "2.0", "id": id, "result": {
+                        "protocolVersion": PROTOCOL_VERSION,
+                        "capabilities": {"tools": {}},
+                        "serverInfo": {"name": "fake", "version": "0.3.1"}
+                    }}).to_string()),
+                    "tools/list" => ("application/json", json!({"jsonrpc": "2.0", "id": id, "result": {"tools": [{
+                        "name": "add",
+                        "description": "Add two numbers",
+                        "inputSchema": {"type": "object", "required": ["a", "b"], "properties": {
+                            "a": {"type": "integer"}, "b": {"type": "integer"}
+                        }}
+                    }]}}).to_string()),
+                    "tools/call" => {
+                        let args = &request["params"]["arguments"];
+                        let sum = args["a"].as_i64().unwrap() + args["b"].as_i64().unwrap();
+                        let message = json!({"jsonrpc": "2.0", "id": id, "result": {
+                            "content": [{"type": "text", "text": sum.to_string()}]
+                        }});
+                        let mut events = String::new();
+                        if let Some(token) = request["params"].pointer("/_meta/progressToken") {
+                            let progress = json!({"jsonrpc": "2.0", "method": "notifications/progress", "params": {
+                                "progressToken": token, "progress": 1, "total": 2, "message": "adding"
+                            }});
+                            events.push_str(&format!("event: message\ndata: {}\n\n", progress));
+                        }
+                        events.push_str(&format!("event: message\ndata: {}\n\n", message));
+                        ("text/event-stream", events)
+                    }
+                    _ => ("application/json", String::new()),
+                };
+                let status = if body.is_empty() {
+                    "202 Accepted"
+                } else {
+                    "200 OK"
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: {}\r\nMcp-Session-Id: s-1\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status, content_type, body.len(), body
+                );
+                tokio::io::AsyncWriteExt::write_all(&mut socket, response.as_bytes())
+                    .await
+                    .unwrap();
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_config_and_schema_mapping() {
+        let config = McpConfig::parse(
+            "[[servers]]\nname = \"fs\"\ntransport = \"stdio\"\ncommand = \"mcp-fs\"\ncapabilities = [\"FileSystem\"]\n",
+        )
+        .unwrap();
+        assert_eq!(config.servers[0].transport, McpTransportKind::Stdio);
+        assert!(McpConfig::parse("[[servers]]\nname = \"x\"\ntransport = \"http\"\n").is_err());
+
+        let parameters = parameters_from_schema(&json!({
+            "type": "object",
+            "required": ["path"],
+            "properties": {
+                "path": {"type": "string", "description": "File path"},
+                "depth": {"type": ["integer", "null"], "default": 2}
+            }
+        }));
+        assert_eq!(parameters[0].name, "path");
+        assert!(parameters[0].required);
+        assert_eq!(parameters[1].param_type, "integer");
+        assert_eq!(parameters[1].default.as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn test_content_blocks_and_sse_parsing() {
+        let result = json!({"content": [
+            {"type": "text", "text": "hello"},
+            {"type": "image", "mimeType": "image/png", "data": "..."}
+        ]});
+        assert_eq!(content_text(&result), "hello\n[image: image/png]");
+
+        let messages = sse_messages("event: message\ndata: {\"id\":1}\n\n: keep-alive\n\n");
+        assert_eq!(messages, vec![json!({"id": 1})]);
+    }
+
+    #[tokio::test]
+    async fn test_http_server_tools_are_registered_and_proxied() {
+        let url = serve_fake_mcp().await;
+        let config = McpConfig::parse(&format!(
+            "[[servers]]\nname = \"calc\"\ntransport = \"http\"\nurl = \"{}\"\n",
+            url
+        ))
+        .unwrap();
+        let manager = Arc::new(ToolManager::new());
+
+        let registered = register_mcp_tools(&manager, config).await;
+        assert_eq!(registered, vec!["calc_add"]);
+        let tool = manager.get_tool("calc_add").unwrap();
+        assert_eq!(tool.metadata().version, "0.3.1");
+
+        let context = ToolContext {
+            parameters: HashMap::from([
+                ("a".to_string(), "2".to_string()),
+                ("b".to_string(), "3".to_string()),
+            ]),
+            user_id: None,
+            session_id: None,
+            request_id: "test".to_string(),
+            context_data: HashMap::new(),
+        };
+        let result = manager
+            .execute_tool("calc_add", context.clone())
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.data, "5");
+
+        // Progress notifications reach the job running the tool
+        let jobs = jobs::JobManager::new(Arc::clone(&manager), jobs::JobConfig::default());
+        let job_id = jobs.start("calc_add", context, None).unwrap();
+        let mut updates = jobs.watch(&job_id).unwrap();
+        let finished = updates
+            .wait_for(|s| s.status.is_finished())
+            .await
+            .unwrap()
+            .clone();
+        assert_eq!(finished.output, "5");
+        assert_eq!(finished.progress_message, "adding");
+    }
+}
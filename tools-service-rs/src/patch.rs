@@ -0,0 +1,668 @@
+//! Apply Patch Tool
+//!
+//! `apply_patch` applies a unified diff (as produced by `diff -u` or
+//! `git diff`) to files in the session workspace. Hunks are matched at their
+//! stated line first and then at the nearest position where their context
+//! matches, so patches made against a slightly different version still apply.
+//! Patches are all-or-nothing: if any hunk does not match, nothing is written
+//! and every conflict is reported with the lines that differ.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+
+use crate::tool_manager::{
+    Capability, ParameterDefinition, Tool, ToolContext, ToolManagerError, ToolMetadata, ToolResult,
+};
+use crate::validation::ToolValidationError;
+use crate::workspace::{self, Workspace};
+
+/// One `@@` section of a diff
+#[derive(Debug, Clone, PartialEq)]
+struct Hunk {
+    header: String,
+    /// 1-based first line in the old file; 0 for an empty old file
+    old_start: usize,
+    old_lines: Vec<String>,
+    new_lines: Vec<String>,
+    /// Lines starting with '+' / '-'
+    added: usize,
+    removed: usize,
+    /// "\ No newline at end of file" followed the last old / new line
+    old_no_newline: bool,
+    new_no_newline: bool,
+}
+
+/// Changes to one file; None paths stand for /dev/null
+#[derive(Debug, Clone, PartialEq)]
+struct FilePatch {
+    old_path: Option<String>,
+    new_path: Option<String>,
+    hunks: Vec<Hunk>,
+}
+
+/// File contents as lines plus whether it ends with a newline
+struct Lines {
+    lines: Vec<String>,
+    trailing_newline: bool,
+}
+
+impl Lines {
+    fn parse(content: &str) -> Self {
+        let trailing_newline = content.is_empty() || content.ends_with('\n');
+        let body = content.strip_suffix('\n').unwrap_or(content);
+        let lines = if content.is_empty() {
+            Vec::new()
+        } else {
+            body.split('\n').map(str::to_string).collect()
+        };
+        Self {
+            lines,
+            trailing_newline,
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut content = self.lines.join("\n");
+        if self.trailing_newline && !self.lines.is_empty() {
+            content.push('\n');
+        }
+        content
+    }
+}
+
+/// Path from a `---`/`+++` line without timestamp and a/ b/ prefix
+fn diff_path(spec: &str) -> Option<String> {
+    let spec = spec.split('\t').next().unwrap_or("").trim();
+    if spec == "/dev/null" {
+        return None;
+    }
+    let spec = spec.trim_matches('"');
+    Some(
+        spec.strip_prefix("a/")
+            .or_else(|| spec.strip_prefix("b/"))
+            .unwrap_or(spec)
+            .to_string(),
+    )
+}
+
+/// Parse "@@ -l[,s] +l[,s] @@" into (old_start, old_count, new_count)
+fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize)> {
+    let mut parts = line.strip_prefix("@@ ")?.split_whitespace();
+    let range = |part: Option<&str>, sign: char| -> Option<(usize, usize)> {
+        let range = part?.strip_prefix(sign)?;
+        match range.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_count) = range(parts.next(), '-')?;
+    let (_, new_count) = range(parts.next(), '+')?;
+    Some((old_start, old_count, new_count))
+}
+
+/// Parse a unified diff into per-file patches
+fn parse_patch(patch: &str) -> Result<Vec<FilePatch>, String> {
+    let lines: Vec<&str> = patch.lines().collect();
+    let mut files = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let Some(old) = lines[i].strip_prefix("--- ") else {
+            i += 1;
+            continue;
+        };
+        let new = lines
+            .get(i + 1)
+            .and_then(|l| l.strip_prefix("+++ "))
+            .ok_or_else(|| format!("line {}: expected '+++' after '---'", i + 2))?;
+        let mut file = FilePatch {
+            old_path: diff_path(old),
+            new_path: diff_path(new),
+            hunks: Vec::new(),
+        };
+        if file.old_path.is_none() && file.new_path.is_none() {
+            return Err(format!("line {}: both paths are /dev/null", i + 1));
+        }
+        i += 2;
+
+        while i < lines.len() && lines[i].starts_with("@@") {
+            let (old_start, old_count, new_count) = parse_hunk_header(lines[i])
+                .ok_or_else(|| format!("line {}: malformed hunk header", i + 1))?;
+            let mut hunk = Hunk {
+                header: lines[i].to_string(),
+                old_start,
+                old_lines: Vec::new(),
+                new_lines: Vec::new(),
+                added: 0,
+                removed: 0,
+                old_no_newline: false,
+                new_no_newline: false,
+            };
+            i += 1;
+
+            // The marker refers to the line before it: old side for '-', new for '+', both for ' '
+            let mut last_kind = ' ';
+            while i < lines.len()
+                && (hunk.old_lines.len() < old_count
+                    || hunk.new_lines.len() < new_count
+                    || lines[i].starts_with('\\'))
+            {
+                let line = lines[i];
+                let (kind, text) = match line.chars().next() {
+                    Some(kind @ (' ' | '-' | '+' | '\\')) => (kind, &line[1..]),
+                    // Some editors strip the space of empty context lines
+                    None => (' ', ""),
+                    Some(_) => break,
+                };
+                match kind {
+                    ' ' => {
+                        hunk.old_lines.push(text.to_string());
+                        hunk.new_lines.push(text.to_string());
+                    }
+                    '-' => {
+                        hunk.old_lines.push(text.to_string());
+                        hunk.removed += 1;
+                    }
+                    '+' => {
+                        hunk.new_lines.push(text.to_string());
+                        hunk.added += 1;
+                    }
+                    _ => {
+                        if last_kind != '+' {
+                            hunk.old_no_newline = true;
+                        }
+                        if last_kind != '-' {
+                            hunk.new_no_newline = true;
+                        }
+                    }
+                }
+                if kind != '\\' {
+                    last_kind = kind;
+                }
+                i += 1;
+            }
+
+            if hunk.old_lines.len() != old_count || hunk.new_lines.len() != new_count {
+                return Err(format!(
+                    "hunk '{}' has {} old and {} new lines, header says {} and {}",
+                    hunk.header,
+                    hunk.old_lines.len(),
+                    hunk.new_lines.len(),
+                    old_count,
+                    new_count
+                ));
+            }
+            file.hunks.push(hunk);
+        }
+
+        if file.hunks.is_empty() {
+            return Err(format!(
+                "no hunks for {}",
+                file.new_path
+                    .as_deref()
+                    .or(file.old_path.as_deref())
+                    .unwrap_or("")
+            ));
+        }
+        files.push(file);
+    }
+
+    if files.is_empty() {
+        return Err("no file changes found in patch".to_string());
+    }
+    Ok(files)
+}
+
+fn lines_match(actual: &[String], expected: &[String]) -> bool {
+    actual.len() == expected.len()
+        && actual
+            .iter()
+            .zip(expected)
+            .all(|(a, e)| a.trim_end_matches('\r') == e.trim_end_matches('\r'))
+}
+
+/// Describe why a hunk does not match at its stated position
+fn describe_mismatch(lines: &[String], hunk: &Hunk, pos: usize) -> String {
+    for (offset, expected) in hunk.old_lines.iter().enumerate() {
+        match lines.get(pos + offset) {
+            Some(actual) if actual.trim_end_matches('\r') == expected.trim_end_matches('\r') => {}
+            Some(actual) => {
+                return format!(
+                    "line {}: expected {:?}, found {:?}",
+                    pos + offset + 1,
+                    expected,
+                    actual
+                )
+            }
+            None => {
+                return format!(
+                    "line {}: expected {:?}, found end of file",
+                    pos + offset + 1,
+                    expected
+                )
+            }
+        }
+    }
+    "context not found".to_string()
+}
+
+/// Apply hunks in order; returns the conflicts if any hunk does not match
+fn apply_hunks(content: &mut Lines, hunks: &[Hunk]) -> Result<(), Vec<String>> {
+    let mut conflicts = Vec::new();
+    let mut offset: isize = 0;
+    let mut min_pos = 0;
+
+    for hunk in hunks {
+        let expected = (hunk.old_start.saturating_sub(1) as isize + offset).max(0) as usize;
+        let old_len = hunk.old_lines.len();
+        let last_start = content.lines.len().saturating_sub(old_len);
+
+        // Nearest matching position to the expected one, not before earlier hunks
+        let found = (0..=content.lines.len()).find_map(|distance| {
+            [expected.checked_sub(distance), Some(expected + distance)]
+                .into_iter()
+                .flatten()
+                .filter(|pos| *pos >= min_pos && *pos <= last_start)
+                .find(|pos| lines_match(&content.lines[*pos..*pos + old_len], &hunk.old_lines))
+        });
+
+        let Some(pos) = found else {
+            conflicts.push(format!(
+                "{}: {}",
+                hunk.header,
+                describe_mismatch(&content.lines, hunk, expected.min(content.lines.len()))
+            ));
+            continue;
+        };
+
+        if pos + old_len == content.lines.len() {
+            if hunk.new_no_newline {
+                content.trailing_newline = false;
+            } else if hunk.old_no_newline || !content.trailing_newline {
+                content.trailing_newline = true;
+            }
+        }
+        content
+            .lines
+            .splice(pos..pos + old_len, hunk.new_lines.iter().cloned());
+        offset += hunk.new_lines.len() as isize - old_len as isize;
+        min_pos = pos + hunk.new_lines.len();
+    }
+
+    if conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(conflicts)
+    }
+}
+
+/// A file write or removal decided by the patch
+enum Change {
+    Write(PathBuf, String),
+    Remove(PathBuf),
+}
+
+/// Apply Patch Tool
+pub struct ApplyPatchTool {
+    metadata: ToolMetadata,
+    workspace_base: PathBuf,
+}
+
+impl ApplyPatchTool {
+    pub fn new() -> Self {
+        Self::with_workspace_base(workspace::workspace_base())
+    }
+
+    pub fn with_workspace_base(workspace_base: PathBuf) -> Self {
+        let parameters = vec![
+            ParameterDefinition {
+                name: "patch".to_string(),
+                description: "Unified diff with paths relative to the workspace".to_string(),
+                required: true,
+                param_type: "string".to_string(),
+                default: None,
+                validation: None,
+            },
+            ParameterDefinition {
+                name: "dry_run".to_string(),
+                description: "Only check whether the patch applies".to_string(),
+                required: false,
+                param_type: "boolean".to_string(),
+                default: Some("false".to_string()),
+                validation: None,
+            },
+        ];
+
+        let mut capabilities = HashSet::new();
+        capabilities.insert(Capability::FileSystem);
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        Self {
+            metadata: ToolMetadata {
+                id: "apply_patch".to_string(),
+                name: "Apply Patch".to_string(),
+                description:
+                    "Applies a unified diff to files in the session workspace, reporting conflicting hunks"
+                        .to_string(),
+                version: "1.0.0".to_string(),
+                author: "System".to_string(),
+                category: "filesystem".to_string(),
+                parameters,
+                capabilities,
+                enabled: true,
+                created_at: now,
+                updated_at: now,
+                deprecated: None,
+            },
+            workspace_base,
+        }
+    }
+
+    /// Work out the file changes, or the conflicts preventing them
+    fn plan(
+        &self,
+        workspace: &Workspace,
+        files: &[FilePatch],
+    ) -> Result<(Vec<Change>, Vec<String>), ToolManagerError> {
+        let mut changes = Vec::new();
+        let mut report = Vec::new();
+        let mut conflicts = Vec::new();
+
+        for file in files {
+            let display = file
+                .new_path
+                .as_deref()
+                .or(file.old_path.as_deref())
+                .unwrap_or_default();
+            let old_path = file
+                .old_path
+                .as_deref()
+                .map(|p| workspace.resolve(p))
+                .transpose()?;
+            let new_path = file
+                .new_path
+                .as_deref()
+                .map(|p| workspace.resolve(p))
+                .transpose()?;
+
+            let mut content = match &old_path {
+                Some(path) => match fs::read_to_string(path) {
+                    Ok(text) => Lines::parse(&text),
+                    Err(e) => {
+                        conflicts.push(format!("{}: cannot read file: {}", display, e));
+                        continue;
+                    }
+                },
+                None => {
+                    if new_path.as_ref().is_some_and(|p| p.exists()) {
+                        conflicts.push(format!("{}: file already exists", display));
+                        continue;
+                    }
+                    Lines::parse("")
+                }
+            };
+
+            if let Err(hunk_conflicts) = apply_hunks(&mut content, &file.hunks) {
+                conflicts.extend(
+                    hunk_conflicts
+                        .into_iter()
+                        .map(|c| format!("{}: {}", display, c)),
+                );
+                continue;
+            }
+
+            let added: usize = file.hunks.iter().map(|h| h.added).sum();
+            let removed: usize = file.hunks.iter().map(|h| h.removed).sum();
+            match (&old_path, &new_path) {
+                (Some(old), None) => {
+                    if !content.lines.is_empty() {
+                        conflicts.push(format!(
+                            "{}: file is not empty after removing the deleted lines",
+                            display
+                        ));
+                        continue;
+                    }
+                    report.push(format!("D {}", display));
+                    changes.push(Change::Remove(old.clone()));
+                }
+                (old, Some(new)) => {
+                    let status = match old {
+                        None => "A",
+                        Some(old) if old != new => "R",
+                        Some(_) => "M",
+                    };
+                    report.push(format!("{} {} (+{} -{})", status, display, added, removed));
+                    changes.push(Change::Write(new.clone(), content.render()));
+                    if let Some(old) = old.as_ref().filter(|old| *old != new) {
+                        changes.push(Change::Remove(old.clone()));
+                    }
+                }
+                (None, None) => unreachable!("rejected while parsing"),
+            }
+        }
+
+        if conflicts.is_empty() {
+            Ok((changes, report))
+        } else {
+            Err(ToolManagerError::ExecutionError(conflicts.join("\n")))
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ApplyPatchTool {
+    fn metadata(&self) -> &ToolMetadata {
+        &self.metadata
+    }
+
+    fn validate_parameters(
+        &self,
+        parameters: &HashMap<String, String>,
+    ) -> Result<(), ToolManagerError> {
+        let patch = parameters.get("patch").ok_or_else(|| {
+            ToolManagerError::ValidationError(ToolValidationError::Other(
+                "Missing required parameter: patch".to_string(),
+            ))
+        })?;
+        let files = parse_patch(patch).map_err(|e| {
+            ToolManagerError::ValidationError(ToolValidationError::Other(format!(
+                "Invalid patch: {}",
+                e
+            )))
+        })?;
+        for path in files
+            .iter()
+            .flat_map(|f| [f.old_path.as_deref(), f.new_path.as_deref()])
+            .flatten()
+        {
+            workspace::check_path_syntax(path).map_err(ToolManagerError::ValidationError)?;
+        }
+
+        match parameters.get("dry_run").map(String::as_str) {
+            None | Some("true") | Some("false") => Ok(()),
+            Some(_) => Err(ToolManagerError::ValidationError(
+                ToolValidationError::Other("dry_run must be 'true' or 'false'".to_string()),
+            )),
+        }
+    }
+
+    async fn execute(&self, context: ToolContext) -> Result<ToolResult, ToolManagerError> {
+        let start_time = Instant::now();
+
+        let workspace =
+            Workspace::for_session(&self.workspace_base, context.session_id.as_deref())?;
+        let dry_run = context
+            .parameters
+            .get("dry_run")
+            .is_some_and(|v| v == "true");
+        let files = parse_patch(context.parameters.get("patch").unwrap())
+            .map_err(|e| ToolManagerError::ExecutionError(format!("Invalid patch: {}", e)))?;
+        let hunk_count: usize = files.iter().map(|f| f.hunks.len()).sum();
+
+        let mut metadata = HashMap::new();
+        metadata.insert("dry_run".to_string(), dry_run.to_string());
+        metadata.insert("files".to_string(), files.len().to_string());
+        metadata.insert("hunks".to_string(), hunk_count.to_string());
+
+        let (changes, report) = match self.plan(&workspace, &files) {
+            Ok(planned) => planned,
+            Err(ToolManagerError::ExecutionError(conflicts)) => {
+                let duration_ms = start_time.elapsed().as_millis() as u64;
+                let conflict_count = conflicts.lines().count();
+                metadata.insert("conflicts".to_string(), conflict_count.to_string());
+                metadata.insert("execution_time_ms".to_string(), duration_ms.to_string());
+                return Ok(ToolResult {
+                    success: false,
+                    data: format!("Patch not applied, no files were changed:\n{}", conflicts),
+                    error: format!("{} conflict(s)", conflict_count),
+                    metadata,
+                    duration_ms,
+                });
+            }
+            Err(e) => return Err(e),
+        };
+
+        if !dry_run {
+            for change in &changes {
+                let result = match change {
+                    Change::Write(path, content) => path
+                        .parent()
+                        .map_or(Ok(()), fs::create_dir_all)
+                        .and_then(|_| fs::write(path, content)),
+                    Change::Remove(path) => fs::remove_file(path),
+                };
+                result.map_err(|e| {
+                    ToolManagerError::ExecutionError(format!(
+                        "Failed to update {}: {}",
+                        workspace.display(match change {
+                            Change::Write(path, _) | Change::Remove(path) => path,
+                        }),
+                        e
+                    ))
+                })?;
+            }
+        }
+
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        metadata.insert("conflicts".to_string(), "0".to_string());
+        metadata.insert("execution_time_ms".to_string(), duration_ms.to_string());
+
+        Ok(ToolResult {
+            success: true,
+            data: format!(
+                "{} {} hunk(s) to {} file(s):\n{}",
+                if dry_run { "Patch applies:" } else { "Applied" },
+                hunk_count,
+                files.len(),
+                report.join("\n")
+            ),
+            error: String::new(),
+            metadata,
+            duration_ms,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(name: &str) -> (ApplyPatchTool, PathBuf) {
+        let base = std::env::temp_dir().join(format!("patch-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let root = Workspace::for_session(&base, None)
+            .unwrap()
+            .root()
+            .to_path_buf();
+        (ApplyPatchTool::with_workspace_base(base), root)
+    }
+
+    fn context(patch: &str) -> ToolContext {
+        let mut parameters = HashMap::new();
+        parameters.insert("patch".to_string(), patch.to_string());
+        ToolContext {
+            parameters,
+            user_id: None,
+            session_id: None,
+            request_id: "test".to_string(),
+            context_data: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_patch() {
+        let patch = "diff --git a/src/lib.rs b/src/lib.rs\n--- a/src/lib.rs\t2024-01-01\n+++ b/src/lib.rs\n@@ -1,2 +1,2 @@\n-old\n+new\n ctx\n\\ No newline at end of file\n--- /dev/null\n+++ b/notes.txt\n@@ -0,0 +1 @@\n+hello\n";
+        let files = parse_patch(patch).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].old_path.as_deref(), Some("src/lib.rs"));
+        assert_eq!(files[0].hunks[0].old_lines, vec!["old", "ctx"]);
+        assert_eq!(files[0].hunks[0].new_lines, vec!["new", "ctx"]);
+        assert!(files[0].hunks[0].old_no_newline && files[0].hunks[0].new_no_newline);
+        assert_eq!(files[1].old_path, None);
+        assert_eq!(files[1].hunks[0].new_lines, vec!["hello"]);
+
+        assert!(parse_patch("just text").is_err());
+        assert!(parse_patch("--- a/x\n+++ b/x\n@@ -1,2 +1,1 @@\n-a\n").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_apply_with_offset_and_new_file() {
+        let (tool, root) = setup("apply");
+        fs::write(
+            root.join("main.txt"),
+            "header\nextra\none\ntwo\nthree\nfour\n",
+        )
+        .unwrap();
+
+        // Hunk says line 1 but the context moved down by one line
+        let patch = "--- a/main.txt\n+++ b/main.txt\n@@ -1,3 +1,3 @@\n one\n-two\n+TWO\n three\n--- /dev/null\n+++ b/docs/new.md\n@@ -0,0 +1,2 @@\n+# New\n+text\n";
+        let result = tool.execute(context(patch)).await.unwrap();
+        assert!(result.success, "{}", result.data);
+        assert_eq!(
+            fs::read_to_string(root.join("main.txt")).unwrap(),
+            "header\nextra\none\nTWO\nthree\nfour\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("docs/new.md")).unwrap(),
+            "# New\ntext\n"
+        );
+        assert!(result.data.contains("M main.txt (+1 -1)"));
+        assert!(result.data.contains("A docs/new.md (+2 -0)"));
+    }
+
+    #[tokio::test]
+    async fn test_conflicts_leave_files_untouched() {
+        let (tool, root) = setup("conflict");
+        fs::write(root.join("a.txt"), "alpha\nbeta\n").unwrap();
+        fs::write(root.join("b.txt"), "one\ntwo\n").unwrap();
+
+        let patch = "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n alpha\n-beta\n+BETA\n--- a/b.txt\n+++ b/b.txt\n@@ -1,2 +1,2 @@\n one\n-zwei\n+drei\n";
+        let result = tool.execute(context(patch)).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.metadata["conflicts"], "1");
+        assert!(
+            result
+                .data
+                .contains("b.txt: @@ -1,2 +1,2 @@: line 2: expected \"zwei\", found \"two\""),
+            "{}",
+            result.data
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("a.txt")).unwrap(),
+            "alpha\nbeta\n"
+        );
+
+        let escape = "--- a/../outside.txt\n+++ b/../outside.txt\n@@ -0,0 +1 @@\n+x\n";
+        assert!(tool
+            .validate_parameters(&context(escape).parameters)
+            .is_err());
+    }
+}
@@ -0,0 +1,172 @@
+//! Secrets Service Client
+//!
+//! Resolves secret references (API keys, tokens) used by manifest-defined tools
+//! through secrets-service-rs. The client authenticates with the service
+//! credentials from TOOLS_SERVICE_SECRET and caches secrets until they expire.
+//! One shared client is connected lazily on first use.
+
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tokio::sync::{OnceCell, RwLock};
+use tonic::transport::Channel;
+use tonic::Request;
+
+pub mod secrets_service {
+    tonic::include_proto!("secrets_service");
+}
+
+use secrets_service::secrets_service_client::SecretsServiceClient;
+use secrets_service::{SecretRequest, TokenRequest};
+
+#[derive(Debug, Error)]
+pub enum SecretsError {
+    #[error("Secret not found: {0}")]
+    SecretNotFound(String),
+
+    #[error("Authentication error: {0}")]
+    AuthenticationError(String),
+
+    #[error("Connection error: {0}")]
+    ConnectionError(String),
+
+    #[error("Configuration error: {0}")]
+    ConfigurationError(String),
+
+    #[error("GRPC error: {0}")]
+    GrpcError(#[from] tonic::Status),
+}
+
+// Secret value and its expiry (unix seconds), if any
+type CachedSecret = (String, Option<u64>);
+
+/// Client for secrets-service-rs
+pub struct SecretsClient {
+    client: SecretsServiceClient<Channel>,
+    service_id: String,
+    service_secret: String,
+    // Token and its expiry (unix seconds)
+    auth_token: RwLock<Option<(String, u64)>>,
+    cache: RwLock<HashMap<String, CachedSecret>>,
+}
+
+static SHARED: OnceCell<Arc<SecretsClient>> = OnceCell::const_new();
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl SecretsClient {
+    /// Connect using SERVICE_ID, TOOLS_SERVICE_SECRET and SECRETS_SERVICE_ADDR/PORT
+    pub async fn new() -> Result<Self, SecretsError> {
+        let service_id = env::var("SERVICE_ID").unwrap_or_else(|_| "tools-service".to_string());
+        let service_secret = env::var("TOOLS_SERVICE_SECRET").map_err(|_| {
+            SecretsError::ConfigurationError(
+                "TOOLS_SERVICE_SECRET environment variable not set".to_string(),
+            )
+        })?;
+        let secrets_addr = config_rs::get_client_address("SECRETS", 50080, None);
+
+        log::debug!("Connecting to secrets service at: {}", secrets_addr);
+        let client = SecretsServiceClient::connect(secrets_addr)
+            .await
+            .map_err(|e| SecretsError::ConnectionError(e.to_string()))?;
+
+        Ok(Self {
+            client,
+            service_id,
+            service_secret,
+            auth_token: RwLock::new(None),
+            cache: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Shared client, connected on first successful call
+    pub async fn shared() -> Result<Arc<SecretsClient>, SecretsError> {
+        SHARED
+            .get_or_try_init(|| async { Self::new().await.map(Arc::new) })
+            .await
+            .cloned()
+    }
+
+    async fn authenticate(&self) -> Result<String, SecretsError> {
+        if let Some((token, expires_at)) = self.auth_token.read().await.as_ref() {
+            // Keep at least 5 minutes of validity
+            if *expires_at > now_secs() + 300 {
+                return Ok(token.clone());
+            }
+        }
+
+        let response = self
+            .client
+            .clone()
+            .generate_token(Request::new(TokenRequest {
+                service_id: self.service_id.clone(),
+                service_secret: self.service_secret.clone(),
+                ttl: 1800,
+                roles: vec!["tools-service".to_string(), "read".to_string()],
+            }))
+            .await?
+            .into_inner();
+
+        if !response.success {
+            return Err(SecretsError::AuthenticationError(response.error));
+        }
+
+        *self.auth_token.write().await = Some((response.token.clone(), response.expires_at as u64));
+        Ok(response.token)
+    }
+
+    /// Get a secret by key
+    pub async fn get_secret(&self, key: &str) -> Result<String, SecretsError> {
+        if let Some((value, expires_at)) = self.cache.read().await.get(key) {
+            if expires_at.is_none_or(|exp| exp > now_secs()) {
+                return Ok(value.clone());
+            }
+        }
+
+        let auth_token = self.authenticate().await?;
+        let result = self
+            .client
+            .clone()
+            .get_secret(Request::new(SecretRequest {
+                key: key.to_string(),
+                service_id: self.service_id.clone(),
+                auth_token,
+            }))
+            .await;
+
+        let response = match result {
+            Ok(response) => response.into_inner(),
+            Err(status) if status.code() == tonic::Code::NotFound => {
+                return Err(SecretsError::SecretNotFound(key.to_string()))
+            }
+            Err(status) => {
+                if status.code() == tonic::Code::Unauthenticated {
+                    *self.auth_token.write().await = None;
+                }
+                return Err(SecretsError::GrpcError(status));
+            }
+        };
+
+        if !response.success {
+            return Err(if response.error.contains("not found") {
+                SecretsError::SecretNotFound(key.to_string())
+            } else {
+                SecretsError::AuthenticationError(response.error)
+            });
+        }
+
+        let expires_at = (response.expires_at > 0).then_some(response.expires_at as u64);
+        self.cache
+            .write()
+            .await
+            .insert(key.to_string(), (response.secret_value.clone(), expires_at));
+        Ok(response.secret_value)
+    }
+}
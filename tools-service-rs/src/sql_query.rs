@@ -0,0 +1,829 @@
+//! SQL Query Tool
+//!
+//! `sql_query` answers questions over local tabular data with read-only SQL.
+//! Data sources are configured with environment variables:
+//! - TOOLS_SQL_SQLITE: comma-separated SQLite files, `name=path` or just `path`
+//!   (named after the file stem); each is attached read-only as schema `name`
+//! - TOOLS_SQL_CSV_DIRS: comma-separated directories; every `*.csv` file is
+//!   loaded into a table named after the file, with INTEGER/REAL/TEXT columns
+//!   inferred from the values
+//!
+//! Only single SELECT (or WITH ... SELECT) statements run, on a connection in
+//! `query_only` mode. Results are capped at TOOLS_SQL_MAX_ROWS rows and
+//! TOOLS_SQL_TIMEOUT_SECS of execution, and returned as a markdown table with
+//! the rows as JSON in the `json` metadata field. The tables and columns are
+//! listed in the tool description so the planner can discover them.
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use serde_json::Value;
+
+use crate::tool_manager::{
+    Capability, ParameterDefinition, Tool, ToolContext, ToolManagerError, ToolMetadata, ToolResult,
+};
+use crate::validation::ToolValidationError;
+
+/// Characters shown per markdown table cell
+const MAX_CELL_CHARS: usize = 120;
+
+/// Data sources and limits for `sql_query`
+#[derive(Debug, Clone)]
+pub struct SqlConfig {
+    /// SQLite files as (schema name, path)
+    pub sqlite: Vec<(String, PathBuf)>,
+    /// Directories of CSV files
+    pub csv_dirs: Vec<PathBuf>,
+    /// Rows returned per query
+    pub max_rows: usize,
+    /// Execution time limit per query
+    pub timeout: Duration,
+}
+
+impl Default for SqlConfig {
+    fn default() -> Self {
+        Self {
+            sqlite: Vec::new(),
+            csv_dirs: Vec::new(),
+            max_rows: 200,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl SqlConfig {
+    /// Read TOOLS_SQL_* variables, falling back to the defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let list = |name: &str| -> Vec<String> {
+            env::var(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+                .collect()
+        };
+
+        let sqlite = list("TOOLS_SQL_SQLITE")
+            .into_iter()
+            .map(|entry| match entry.split_once('=') {
+                Some((name, path)) => (name.trim().to_string(), PathBuf::from(path.trim())),
+                None => {
+                    let path = PathBuf::from(&entry);
+                    let name = path
+                        .file_stem()
+                        .map(|s| sql_identifier(&s.to_string_lossy(), 0))
+                        .unwrap_or_default();
+                    (name, path)
+                }
+            })
+            .collect();
+
+        Self {
+            sqlite,
+            csv_dirs: list("TOOLS_SQL_CSV_DIRS")
+                .into_iter()
+                .map(PathBuf::from)
+                .collect(),
+            max_rows: env::var("TOOLS_SQL_MAX_ROWS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_rows),
+            timeout: env::var("TOOLS_SQL_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.timeout),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sqlite.is_empty() && self.csv_dirs.is_empty()
+    }
+}
+
+/// A table the tool exposes
+#[derive(Debug, Clone)]
+struct TableInfo {
+    /// Name to use in queries, schema-qualified for SQLite sources
+    name: String,
+    /// Where the data comes from, e.g. "CSV data/sales.csv"
+    source: String,
+    /// Columns as "name TYPE"
+    columns: Vec<String>,
+}
+
+/// SQL Query Tool
+pub struct SqlQueryTool {
+    metadata: ToolMetadata,
+    conn: Arc<Mutex<Connection>>,
+    max_rows: usize,
+    timeout: Duration,
+}
+
+impl SqlQueryTool {
+    /// Tool over the configured data sources, or None if there are none
+    pub fn from_env() -> Result<Option<Self>, ToolManagerError> {
+        let config = SqlConfig::from_env();
+        if config.is_empty() {
+            return Ok(None);
+        }
+        Self::open(config).map(Some)
+    }
+
+    /// Load CSV files and attach SQLite files into a query-only connection
+    pub fn open(config: SqlConfig) -> Result<Self, ToolManagerError> {
+        let conn = Connection::open_in_memory_with_flags(
+            OpenFlags::SQLITE_OPEN_READ_WRITE
+                | OpenFlags::SQLITE_OPEN_CREATE
+                | OpenFlags::SQLITE_OPEN_URI
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )
+        .map_err(sql_loading_error)?;
+
+        let mut tables = Vec::new();
+        for dir in &config.csv_dirs {
+            let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+                .map_err(|e| {
+                    ToolManagerError::LoadingError(format!(
+                        "Failed to read CSV directory {}: {}",
+                        dir.display(),
+                        e
+                    ))
+                })?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+                })
+                .collect();
+            files.sort();
+
+            for path in files {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                let table = sql_identifier(&stem, 0);
+                if tables.iter().any(|t: &TableInfo| t.name == table) {
+                    return Err(ToolManagerError::LoadingError(format!(
+                        "Duplicate CSV table name '{}' ({})",
+                        table,
+                        path.display()
+                    )));
+                }
+                let columns = load_csv(&conn, &table, &path)?;
+                tables.push(TableInfo {
+                    name: table,
+                    source: format!("CSV {}", path.display()),
+                    columns,
+                });
+            }
+        }
+
+        for (schema, path) in &config.sqlite {
+            if schema.is_empty() || sql_identifier(schema, 0) != *schema {
+                return Err(ToolManagerError::InvalidMetadata(format!(
+                    "Invalid SQLite data source name '{}'",
+                    schema
+                )));
+            }
+            if !path.is_file() {
+                return Err(ToolManagerError::LoadingError(format!(
+                    "SQLite file not found: {}",
+                    path.display()
+                )));
+            }
+            let uri = format!("file:{}?mode=ro", uri_path(path));
+            conn.execute(&format!("ATTACH DATABASE ?1 AS \"{}\"", schema), [&uri])
+                .map_err(sql_loading_error)?;
+            tables.extend(sqlite_tables(&conn, schema, path)?);
+        }
+
+        conn.execute_batch("PRAGMA query_only = ON;")
+            .map_err(sql_loading_error)?;
+
+        let mut capabilities = HashSet::new();
+        capabilities.insert(Capability::FileSystem);
+
+        let parameters = vec![
+            ParameterDefinition {
+                name: "query".to_string(),
+                description: "SQLite SELECT statement over the listed tables".to_string(),
+                required: true,
+                param_type: "string".to_string(),
+                default: None,
+                validation: None,
+            },
+            ParameterDefinition {
+                name: "max_rows".to_string(),
+                description: format!("Rows to return, at most {}", config.max_rows),
+                required: false,
+                param_type: "int".to_string(),
+                default: Some(config.max_rows.to_string()),
+                validation: Some(r"^[1-9][0-9]*$".to_string()),
+            },
+        ];
+
+        let mut description =
+            "Runs read-only SQL SELECT queries (SQLite dialect) over local datasets. Tables:"
+                .to_string();
+        for table in &tables {
+            description.push_str(&format!(
+                "\n- {} ({}): {}",
+                table.name,
+                table.source,
+                table.columns.join(", ")
+            ));
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        Ok(Self {
+            metadata: ToolMetadata {
+                id: "sql_query".to_string(),
+                name: "SQL Query".to_string(),
+                description,
+                version: "1.0.0".to_string(),
+                author: "System".to_string(),
+                category: "data".to_string(),
+                parameters,
+                capabilities,
+                enabled: true,
+                created_at: now,
+                updated_at: now,
+                deprecated: None,
+            },
+            conn: Arc::new(Mutex::new(conn)),
+            max_rows: config.max_rows,
+            timeout: config.timeout,
+        })
+    }
+}
+
+#[async_trait]
+impl Tool for SqlQueryTool {
+    fn metadata(&self) -> &ToolMetadata {
+        &self.metadata
+    }
+
+    fn validate_parameters(
+        &self,
+        parameters: &HashMap<String, String>,
+    ) -> Result<(), ToolManagerError> {
+        let query = parameters.get("query").ok_or_else(|| {
+            ToolManagerError::ValidationError(ToolValidationError::Other(
+                "Missing required parameter: query".to_string(),
+            ))
+        })?;
+        check_select(query).map_err(ToolManagerError::ValidationError)?;
+
+        if let Some(max_rows) = parameters.get("max_rows") {
+            if !matches!(max_rows.parse::<usize>(), Ok(n) if n > 0) {
+                return Err(ToolManagerError::ValidationError(
+                    ToolValidationError::Other("max_rows must be a positive number".to_string()),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn execute(&self, context: ToolContext) -> Result<ToolResult, ToolManagerError> {
+        let start_time = Instant::now();
+
+        let query = context.parameters.get("query").unwrap().clone();
+        let max_rows = context
+            .parameters
+            .get("max_rows")
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(self.max_rows)
+            .min(self.max_rows);
+
+        let conn = self.conn.clone();
+        let timeout = self.timeout;
+        let (columns, rows, truncated) = tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            run_query(&conn, &query, max_rows, timeout)
+        })
+        .await
+        .map_err(|e| ToolManagerError::ExecutionError(format!("Query task failed: {}", e)))??;
+
+        let json_rows: Vec<Value> = rows
+            .iter()
+            .map(|row| {
+                Value::Object(
+                    columns
+                        .iter()
+                        .cloned()
+                        .zip(row.iter().cloned())
+                        .collect::<serde_json::Map<String, Value>>(),
+                )
+            })
+            .collect();
+
+        let mut data = markdown_table(&columns, &rows);
+        if truncated {
+            data.push_str(&format!("\n\n_Showing the first {} rows_", max_rows));
+        }
+
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        let mut metadata = HashMap::new();
+        metadata.insert("columns".to_string(), columns.join(","));
+        metadata.insert("row_count".to_string(), rows.len().to_string());
+        metadata.insert("truncated".to_string(), truncated.to_string());
+        metadata.insert("json".to_string(), Value::Array(json_rows).to_string());
+        metadata.insert("execution_time_ms".to_string(), duration_ms.to_string());
+
+        Ok(ToolResult {
+            success: true,
+            data,
+            error: String::new(),
+            metadata,
+            duration_ms,
+        })
+    }
+}
+
+/// Skip leading whitespace and comments
+fn skip_comments(mut rest: &str) -> &str {
+    rest = rest.trim_start();
+    loop {
+        if let Some(line) = rest.strip_prefix("--") {
+            rest = line
+                .split_once('\n')
+                .map(|(_, r)| r)
+                .unwrap_or("")
+                .trim_start();
+        } else if let Some(block) = rest.strip_prefix("/*") {
+            rest = block
+                .split_once("*/")
+                .map(|(_, r)| r)
+                .unwrap_or("")
+                .trim_start();
+        } else {
+            return rest;
+        }
+    }
+}
+
+/// Whether anything but comments follows the first `;` outside quotes and comments
+fn has_trailing_statement(query: &str) -> bool {
+    let mut chars = query.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' | '"' | '`' | '[' => {
+                let close = if c == '[' { ']' } else { c };
+                for (_, next) in chars.by_ref() {
+                    if next == close {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.peek().is_some_and(|(_, n)| *n == '-') => {
+                for (_, next) in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek().is_some_and(|(_, n)| *n == '*') => {
+                chars.next();
+                let mut prev = ' ';
+                for (_, next) in chars.by_ref() {
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+            }
+            ';' => {
+                return !skip_comments(&query[i + 1..])
+                    .trim_start_matches(';')
+                    .is_empty()
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Require a single SELECT or WITH statement
+fn check_select(query: &str) -> Result<(), ToolValidationError> {
+    let rest = skip_comments(query);
+    if has_trailing_statement(rest) {
+        return Err(ToolValidationError::SecurityThreat(
+            "Only a single statement is allowed".to_string(),
+        ));
+    }
+
+    let keyword: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_ascii_uppercase();
+    if keyword != "SELECT" && keyword != "WITH" {
+        return Err(ToolValidationError::SecurityThreat(
+            "Only SELECT statements are allowed".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Run a query with row and time limits; returns columns, rows and whether rows were cut
+#[allow(clippy::type_complexity)]
+fn run_query(
+    conn: &Connection,
+    query: &str,
+    max_rows: usize,
+    timeout: Duration,
+) -> Result<(Vec<String>, Vec<Vec<Value>>, bool), ToolManagerError> {
+    check_select(query).map_err(ToolManagerError::ValidationError)?;
+
+    let deadline = Instant::now() + timeout;
+    conn.progress_handler(1000, Some(move || Instant::now() > deadline));
+    let result = (|| {
+        let mut stmt = conn.prepare(query).map_err(sql_query_error)?;
+        if !stmt.readonly() {
+            return Err(ToolManagerError::ValidationError(
+                ToolValidationError::SecurityThreat(
+                    "Only read-only statements are allowed".to_string(),
+                ),
+            ));
+        }
+
+        let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+        let mut rows = Vec::new();
+        let mut truncated = false;
+        let mut cursor = stmt.query([]).map_err(sql_query_error)?;
+        while let Some(row) = cursor.next().map_err(sql_query_error)? {
+            if rows.len() == max_rows {
+                truncated = true;
+                break;
+            }
+            let values = (0..columns.len())
+                .map(|i| row.get_ref(i).map(json_value))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(sql_query_error)?;
+            rows.push(values);
+        }
+        Ok((columns, rows, truncated))
+    })();
+    conn.progress_handler(0, None::<fn() -> bool>);
+
+    result.map_err(|e| match e {
+        ToolManagerError::ExecutionError(msg) if msg.contains("interrupted") => {
+            ToolManagerError::ExecutionError(format!(
+                "Query exceeded the time limit of {}s",
+                timeout.as_secs()
+            ))
+        }
+        other => other,
+    })
+}
+
+fn json_value(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => Value::from(i),
+        ValueRef::Real(f) => serde_json::Number::from_f64(f)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        ValueRef::Text(t) => Value::String(String::from_utf8_lossy(t).to_string()),
+        ValueRef::Blob(b) => Value::String(format!("<blob {} bytes>", b.len())),
+    }
+}
+
+/// Render rows as a compact markdown table
+fn markdown_table(columns: &[String], rows: &[Vec<Value>]) -> String {
+    let cell = |value: &Value| -> String {
+        let text = match value {
+            Value::Null => String::new(),
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        let mut text = text.replace('|', "\\|").replace(['\r', '\n'], " ");
+        if text.chars().count() > MAX_CELL_CHARS {
+            text = text.chars().take(MAX_CELL_CHARS - 1).collect::<String>() + "…";
+        }
+        text
+    };
+
+    let mut table = format!("| {} |\n", columns.join(" | "));
+    table.push_str(&format!("|{}\n", " --- |".repeat(columns.len())));
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(cell).collect();
+        table.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    if rows.is_empty() {
+        table.push_str("\n(no rows)");
+    }
+    table.trim_end().to_string()
+}
+
+/// Make a name usable as an unquoted SQL identifier
+fn sql_identifier(name: &str, index: usize) -> String {
+    let mut ident: String = name
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if ident.is_empty() {
+        ident = format!("column_{}", index + 1);
+    }
+    if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+/// Load a CSV file into a new table; returns its columns as "name TYPE"
+fn load_csv(conn: &Connection, table: &str, path: &Path) -> Result<Vec<String>, ToolManagerError> {
+    let csv_error = |e: csv::Error| {
+        ToolManagerError::LoadingError(format!("Failed to read {}: {}", path.display(), e))
+    };
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_path(path)
+        .map_err(csv_error)?;
+
+    let mut names: Vec<String> = Vec::new();
+    for (i, header) in reader.headers().map_err(csv_error)?.iter().enumerate() {
+        let mut name = sql_identifier(header, i);
+        while names.contains(&name) {
+            name.push('_');
+        }
+        names.push(name);
+    }
+    let records = reader
+        .records()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(csv_error)?;
+
+    // A column is INTEGER or REAL if every non-empty value parses as one
+    let types: Vec<&str> = (0..names.len())
+        .map(|i| {
+            let values = || {
+                records
+                    .iter()
+                    .filter_map(move |r| r.get(i).map(str::trim))
+                    .filter(|v| !v.is_empty())
+            };
+            if values().all(|v| v.parse::<i64>().is_ok()) {
+                "INTEGER"
+            } else if values().all(|v| v.parse::<f64>().is_ok()) {
+                "REAL"
+            } else {
+                "TEXT"
+            }
+        })
+        .collect();
+
+    let columns: Vec<String> = names
+        .iter()
+        .zip(&types)
+        .map(|(name, ty)| format!("{} {}", name, ty))
+        .collect();
+    conn.execute_batch(&format!(
+        "CREATE TABLE \"{}\" ({});",
+        table,
+        names
+            .iter()
+            .zip(&types)
+            .map(|(name, ty)| format!("\"{}\" {}", name, ty))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+    .map_err(sql_loading_error)?;
+
+    let insert = format!(
+        "INSERT INTO \"{}\" VALUES ({})",
+        table,
+        vec!["?"; names.len()].join(", ")
+    );
+    conn.execute_batch("BEGIN").map_err(sql_loading_error)?;
+    {
+        let mut stmt = conn.prepare(&insert).map_err(sql_loading_error)?;
+        for record in &records {
+            let values: Vec<rusqlite::types::Value> = types
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| {
+                    let raw = record.get(i).unwrap_or("").trim();
+                    match (*ty, raw) {
+                        (_, "") => rusqlite::types::Value::Null,
+                        ("INTEGER", v) => v.parse::<i64>().unwrap().into(),
+                        ("REAL", v) => v.parse::<f64>().unwrap().into(),
+                        (_, v) => v.to_string().into(),
+                    }
+                })
+                .collect();
+            stmt.execute(rusqlite::params_from_iter(values))
+                .map_err(sql_loading_error)?;
+        }
+    }
+    conn.execute_batch("COMMIT").map_err(sql_loading_error)?;
+
+    Ok(columns)
+}
+
+/// Tables and views of an attached SQLite database
+fn sqlite_tables(
+    conn: &Connection,
+    schema: &str,
+    path: &Path,
+) -> Result<Vec<TableInfo>, ToolManagerError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT name FROM \"{}\".sqlite_master WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%' ORDER BY name",
+            schema
+        ))
+        .map_err(sql_loading_error)?;
+    let names = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(sql_loading_error)?;
+
+    let mut tables = Vec::new();
+    for name in names {
+        let mut info = conn
+            .prepare(&format!(
+                "PRAGMA \"{}\".table_info(\"{}\")",
+                schema,
+                name.replace('"', "\"\"")
+            ))
+            .map_err(sql_loading_error)?;
+        let columns = info
+            .query_map([], |row| {
+                let column: String = row.get(1)?;
+                let ty: String = row.get(2)?;
+                Ok(if ty.is_empty() {
+                    column
+                } else {
+                    format!("{} {}", column, ty)
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(sql_loading_error)?;
+        tables.push(TableInfo {
+            name: format!("{}.{}", schema, name),
+            source: format!("SQLite {}", path.display()),
+            columns,
+        });
+    }
+    Ok(tables)
+}
+
+/// Escape characters with a meaning in SQLite URI filenames
+fn uri_path(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('%', "%25")
+        .replace('?', "%3f")
+        .replace('#', "%23")
+}
+
+fn sql_loading_error(e: rusqlite::Error) -> ToolManagerError {
+    ToolManagerError::LoadingError(format!("SQL data source error: {}", e))
+}
+
+fn sql_query_error(e: rusqlite::Error) -> ToolManagerError {
+    ToolManagerError::ExecutionError(format!("Query failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sql-query-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn context(query: &str) -> ToolContext {
+        let mut parameters = HashMap::new();
+        parameters.insert("query".to_string(), query.to_string());
+        ToolContext {
+            parameters,
+            user_id: None,
+            session_id: None,
+            request_id: "test".to_string(),
+            context_data: HashMap::new(),
+        }
+    }
+
+    fn open_tool(name: &str, max_rows: usize) -> (SqlQueryTool, PathBuf) {
+        let dir = data_dir(name);
+        std::fs::write(
+            dir.join("sales.csv"),
+            "region,amount,units,note\nnorth,10.5,3,first\nsouth,4,1,\nnorth,2.5,2,\"has | pipe\"\n",
+        )
+        .unwrap();
+
+        let db = dir.join("shop.db");
+        let conn = Connection::open(&db).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE regions (name TEXT PRIMARY KEY, manager TEXT);
+             INSERT INTO regions VALUES ('north', 'Ada'), ('south', 'Grace');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let tool = SqlQueryTool::open(SqlConfig {
+            sqlite: vec![("shop".to_string(), db)],
+            csv_dirs: vec![dir.clone()],
+            max_rows,
+            timeout: Duration::from_secs(1),
+        })
+        .unwrap();
+        (tool, dir)
+    }
+
+    #[tokio::test]
+    async fn test_select_across_csv_and_sqlite() {
+        let (tool, _dir) = open_tool("select", 100);
+
+        let description = &tool.metadata().description;
+        assert!(description.contains("sales (CSV "));
+        assert!(description.contains("region TEXT, amount REAL, units INTEGER, note TEXT"));
+        assert!(description.contains("shop.regions (SQLite "));
+
+        let result = tool
+            .execute(context(
+                "SELECT s.region, r.manager, SUM(s.amount) AS total FROM sales s \
+                 JOIN shop.regions r ON r.name = s.region GROUP BY s.region ORDER BY s.region",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            result.data,
+            "| region | manager | total |\n| --- | --- | --- |\n| north | Ada | 13.0 |\n| south | Grace | 4.0 |"
+        );
+        let json: Value = serde_json::from_str(&result.metadata["json"]).unwrap();
+        assert_eq!(
+            json[0],
+            serde_json::json!({"region": "north", "manager": "Ada", "total": 13.0})
+        );
+        assert_eq!(result.metadata["truncated"], "false");
+
+        let result = tool
+            .execute(context("SELECT note FROM sales WHERE units = 2"))
+            .await
+            .unwrap();
+        assert!(result.data.ends_with("| has \\| pipe |"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_writes() {
+        let (tool, _dir) = open_tool("writes", 100);
+        let validate = |query: &str| tool.validate_parameters(&context(query).parameters);
+
+        assert!(validate("-- totals\nSELECT 1").is_ok());
+        assert!(validate("DELETE FROM sales").is_err());
+        assert!(validate("ATTACH DATABASE '/tmp/x.db' AS x").is_err());
+        assert!(validate("PRAGMA query_only = OFF").is_err());
+        assert!(validate("/* hi */ DROP TABLE sales").is_err());
+        assert!(validate("SELECT ';' AS semi; -- done").is_ok());
+        assert!(validate("SELECT 1; DELETE FROM sales").is_err());
+
+        // Writes that pass the keyword check are still refused by SQLite
+        assert!(tool
+            .execute(context("WITH x AS (SELECT 1) DELETE FROM sales"))
+            .await
+            .is_err());
+        let count = tool
+            .execute(context("SELECT COUNT(*) AS n FROM sales"))
+            .await
+            .unwrap();
+        assert!(count.data.ends_with("| 3 |"));
+    }
+
+    #[tokio::test]
+    async fn test_row_and_time_limits() {
+        let (tool, _dir) = open_tool("limits", 2);
+
+        let result = tool
+            .execute(context("SELECT region FROM sales"))
+            .await
+            .unwrap();
+        assert_eq!(result.metadata["row_count"], "2");
+        assert_eq!(result.metadata["truncated"], "true");
+        assert!(result.data.ends_with("_Showing the first 2 rows_"));
+
+        let err = tool
+            .execute(context(
+                "WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n) \
+                 SELECT COUNT(*) FROM n",
+            ))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("time limit"), "{}", err);
+    }
+}
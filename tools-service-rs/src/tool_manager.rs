@@ -8,11 +8,15 @@ use log::{debug, error, info, warn};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::sync::{Arc, RwLock};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use thiserror::Error;
-use tokio::sync::Mutex as AsyncMutex;
+use tokio::sync::{mpsc, Mutex as AsyncMutex};
 
+use crate::http_tools::{self, HttpTool};
 use crate::validation::{validate_command_name, ToolValidationError};
+use crate::wasm_plugins::{self, LoadedPlugin, WasmPluginTool};
 
 /// Tool capability flags
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -31,6 +35,97 @@ pub enum Capability {
     AccessSensitiveData,
 }
 
+impl std::str::FromStr for Capability {
+    type Err = ToolManagerError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "ExecuteCommand" => Ok(Capability::ExecuteCommand),
+            "FileSystem" => Ok(Capability::FileSystem),
+            "Network" => Ok(Capability::Network),
+            "ExecuteCode" => Ok(Capability::ExecuteCode),
+            "SimulateInput" => Ok(Capability::SimulateInput),
+            "AccessSensitiveData" => Ok(Capability::AccessSensitiveData),
+            _ => Err(ToolManagerError::InvalidMetadata(format!(
+                "Unknown capability: {}",
+                name
+            ))),
+        }
+    }
+}
+
+/// A capability granted to a session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapabilityGrant {
+    pub capability: Capability,
+    /// Tool the grant is restricted to; None for any tool
+    pub tool_id: Option<String>,
+    /// Identity that issued the grant
+    pub issuer: String,
+    /// Unix seconds
+    pub granted_at: u64,
+    /// Unix seconds; the grant is inactive from this point on
+    pub expires_at: u64,
+}
+
+impl CapabilityGrant {
+    fn is_active(&self, now: u64) -> bool {
+        self.expires_at > now
+    }
+
+    fn covers(&self, capability: Capability, tool_id: &str) -> bool {
+        self.capability == capability && self.tool_id.as_deref().is_none_or(|id| id == tool_id)
+    }
+}
+
+/// Kinds of capability audit events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditEventType {
+    Grant,
+    Revoke,
+    Use,
+    Denied,
+}
+
+impl AuditEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditEventType::Grant => "capability_grant",
+            AuditEventType::Revoke => "capability_revoke",
+            AuditEventType::Use => "capability_use",
+            AuditEventType::Denied => "capability_denied",
+        }
+    }
+}
+
+/// Audit record of a capability grant, revocation or use
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    pub event_type: AuditEventType,
+    /// Issuer for grants and revocations, the session for uses
+    pub actor: String,
+    pub session_id: String,
+    pub tool_id: Option<String>,
+    pub capabilities: Vec<Capability>,
+    pub allowed: bool,
+    pub detail: String,
+    /// Unix seconds
+    pub timestamp: u64,
+}
+
+impl AuditEvent {
+    /// Capabilities as a sorted, comma-separated list
+    pub fn capability_list(&self) -> String {
+        let mut names: Vec<String> = self
+            .capabilities
+            .iter()
+            .map(|c| format!("{:?}", c))
+            .collect();
+        names.sort();
+        names.join(",")
+    }
+}
+
 /// Tool parameter definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParameterDefinition {
@@ -73,6 +168,9 @@ pub struct ToolMetadata {
     pub created_at: u64,
     /// Last updated timestamp
     pub updated_at: u64,
+    /// Deprecation notice; deprecated versions still run but warn in the result metadata
+    #[serde(default)]
+    pub deprecated: Option<String>,
 }
 
 /// Tool Manager Error types
@@ -84,6 +182,9 @@ pub enum ToolManagerError {
     #[error("Tool already exists: {0}")]
     ToolAlreadyExists(String),
 
+    #[error("No matching tool version: {0}")]
+    VersionNotFound(String),
+
     #[error("Invalid tool metadata: {0}")]
     InvalidMetadata(String),
 
@@ -233,16 +334,188 @@ pub trait Tool: Send + Sync {
     }
 }
 
+/// Check required parameters and validation patterns against their definitions
+pub fn validate_parameter_definitions(
+    definitions: &[ParameterDefinition],
+    parameters: &HashMap<String, String>,
+) -> Result<(), ToolManagerError> {
+    for param in definitions {
+        let value = match parameters.get(&param.name) {
+            Some(value) => value,
+            None if param.required => {
+                return Err(ToolManagerError::ValidationError(
+                    ToolValidationError::Other(format!(
+                        "Missing required parameter: {}",
+                        param.name
+                    )),
+                ));
+            }
+            None => continue,
+        };
+
+        if let Some(pattern) = &param.validation {
+            let matches = regex::Regex::new(pattern)
+                .map(|re| re.is_match(value))
+                .unwrap_or(false);
+            if !matches {
+                return Err(ToolManagerError::ValidationError(
+                    ToolValidationError::Other(format!(
+                        "Parameter {} does not match {}",
+                        param.name, pattern
+                    )),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Render parameter definitions as a JSON Schema object
+pub fn parameters_json_schema(definitions: &[ParameterDefinition]) -> serde_json::Value {
+    let mut properties = serde_json::Map::new();
+    let mut required = Vec::new();
+
+    for param in definitions {
+        let param_type = match param.param_type.as_str() {
+            "string" | "number" | "integer" | "boolean" | "object" | "array" => {
+                param.param_type.as_str()
+            }
+            "int" => "integer",
+            "float" => "number",
+            "bool" => "boolean",
+            _ => "string",
+        };
+
+        let mut property = serde_json::Map::new();
+        property.insert("type".to_string(), param_type.into());
+        if !param.description.is_empty() {
+            property.insert("description".to_string(), param.description.clone().into());
+        }
+        if let Some(default) = &param.default {
+            property.insert(
+                "default".to_string(),
+                http_tools::typed_value(param_type, default),
+            );
+        }
+        if let Some(pattern) = &param.validation {
+            if param_type == "string" {
+                property.insert("pattern".to_string(), pattern.clone().into());
+            }
+        }
+
+        properties.insert(param.name.clone(), property.into());
+        if param.required {
+            required.push(serde_json::Value::from(param.name.clone()));
+        }
+    }
+
+    serde_json::json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+/// Parse a tool version, accepting a leading `v` and missing minor/patch parts
+pub fn parse_version(version: &str) -> Result<semver::Version, ToolManagerError> {
+    let trimmed = version.trim();
+    let trimmed = trimmed.strip_prefix('v').unwrap_or(trimmed);
+    let core_len = trimmed.find(['-', '+']).unwrap_or(trimmed.len());
+    let padding = match trimmed[..core_len].matches('.').count() {
+        0 => ".0.0",
+        1 => ".0",
+        _ => "",
+    };
+    let normalized = format!(
+        "{}{}{}",
+        &trimmed[..core_len],
+        padding,
+        &trimmed[core_len..]
+    );
+    semver::Version::parse(&normalized).map_err(|e| {
+        ToolManagerError::InvalidMetadata(format!("Invalid tool version '{}': {}", version, e))
+    })
+}
+
+/// Split a `tool_id@requirement` reference into the tool id and version requirement
+///
+/// A bare version pins it (`1.2` matches any 1.2.x, `1.2.3` only itself), while
+/// operators give ranges (`^1.2`, `~1.2.3`, `>=1.0, <2.0`). `latest` or no
+/// requirement selects the default version.
+pub fn parse_tool_reference(
+    reference: &str,
+) -> Result<(&str, Option<semver::VersionReq>), ToolManagerError> {
+    let Some((tool_id, requirement)) = reference.split_once('@') else {
+        return Ok((reference, None));
+    };
+    let requirement = requirement.trim();
+    if requirement.is_empty() || requirement == "latest" {
+        return Ok((tool_id, None));
+    }
+
+    let requirement = requirement.strip_prefix('v').unwrap_or(requirement);
+    let pinned = requirement.starts_with(|c: char| c.is_ascii_digit());
+    let source = if pinned {
+        format!("={}", requirement)
+    } else {
+        requirement.to_string()
+    };
+    semver::VersionReq::parse(&source)
+        .map(|req| (tool_id, Some(req)))
+        .map_err(|e| {
+            ToolManagerError::ValidationError(ToolValidationError::Other(format!(
+                "Invalid version requirement '{}': {}",
+                requirement, e
+            )))
+        })
+}
+
+/// Usage counters of one tool version
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ToolUsage {
+    /// Executions that reached the tool
+    pub calls: u64,
+    /// Executions that returned an error or an unsuccessful result
+    pub failures: u64,
+    /// Summed execution time in milliseconds
+    pub total_duration_ms: u64,
+    /// Unix seconds of the last execution; 0 if never used
+    pub last_used_at: u64,
+}
+
+/// A registered version of a tool
+struct ToolVersion {
+    version: semver::Version,
+    tool: Arc<dyn Tool>,
+}
+
+impl ToolVersion {
+    fn is_stable(&self) -> bool {
+        self.version.pre.is_empty() && self.tool.metadata().deprecated.is_none()
+    }
+}
+
 /// The central Tool Manager
 pub struct ToolManager {
-    /// Registry of all available tools
-    tools: RwLock<HashMap<String, Arc<dyn Tool>>>,
-    /// Active tool version tracking
-    versions: RwLock<HashMap<String, Vec<String>>>,
-    /// Permission and capability registry
-    capability_registry: RwLock<HashMap<String, HashSet<Capability>>>,
+    /// Registry of all available tools, every registered version sorted oldest first
+    tools: RwLock<HashMap<String, Vec<ToolVersion>>>,
+    /// Execution counters per tool id and version
+    usage: Mutex<HashMap<(String, String), ToolUsage>>,
     /// Active user sessions with capability grants
-    session_grants: AsyncMutex<HashMap<String, HashSet<Capability>>>,
+    session_grants: AsyncMutex<HashMap<String, Vec<CapabilityGrant>>>,
+    /// Receiver of capability audit events, in addition to the `audit` log target
+    audit_sink: Mutex<Option<mpsc::UnboundedSender<AuditEvent>>>,
+    /// WebAssembly plugins loaded from disk, keyed by manifest path
+    plugins: Mutex<HashMap<PathBuf, LoadedPlugin>>,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
 }
 
 /// Global instance of ToolManager
@@ -253,50 +526,57 @@ impl ToolManager {
     pub fn new() -> Self {
         Self {
             tools: RwLock::new(HashMap::new()),
-            versions: RwLock::new(HashMap::new()),
-            capability_registry: RwLock::new(HashMap::new()),
+            usage: Mutex::new(HashMap::new()),
             session_grants: AsyncMutex::new(HashMap::new()),
+            audit_sink: Mutex::new(None),
+            plugins: Mutex::new(HashMap::new()),
         }
     }
 
     /// Register a new tool with the manager
+    ///
+    /// Several versions of a tool id can be registered side by side; only an
+    /// already registered id and version pair is rejected.
     pub fn register_tool(&self, tool: Arc<dyn Tool>) -> Result<(), ToolManagerError> {
         let metadata = tool.metadata();
         let tool_id = metadata.id.clone();
-        let version = metadata.version.clone();
+        let version = parse_version(&metadata.version)?;
 
-        // Check if tool already exists
-        {
-            let tools = self.tools.read().unwrap();
-            if tools.contains_key(&tool_id) {
-                return Err(ToolManagerError::ToolAlreadyExists(tool_id));
-            }
-        }
-
-        // Register tool
         {
             let mut tools = self.tools.write().unwrap();
-            let mut versions = self.versions.write().unwrap();
-
-            tools.insert(tool_id.clone(), tool.clone());
-
-            // Update version tracking
-            let tool_versions = versions.entry(tool_id.clone()).or_insert_with(Vec::new);
-            tool_versions.push(version);
-
-            // Update capability registry
-            let mut capability_registry = self.capability_registry.write().unwrap();
-            capability_registry.insert(tool_id.clone(), metadata.capabilities.clone());
+            let versions = tools.entry(tool_id.clone()).or_default();
+            let position = match versions.binary_search_by(|v| v.version.cmp(&version)) {
+                Ok(_) => {
+                    return Err(ToolManagerError::ToolAlreadyExists(format!(
+                        "{}@{}",
+                        tool_id, metadata.version
+                    )))
+                }
+                Err(position) => position,
+            };
+            versions.insert(
+                position,
+                ToolVersion {
+                    version,
+                    tool: tool.clone(),
+                },
+            );
         }
 
-        info!(
-            "Tool {} v{} registered successfully",
-            metadata.name, metadata.version
-        );
+        match &metadata.deprecated {
+            Some(notice) => warn!(
+                "Tool {} v{} registered as deprecated: {}",
+                metadata.name, metadata.version, notice
+            ),
+            None => info!(
+                "Tool {} v{} registered successfully",
+                metadata.name, metadata.version
+            ),
+        }
         Ok(())
     }
 
-    /// Unregister a tool from the manager
+    /// Unregister every version of a tool from the manager
     pub fn unregister_tool(&self, tool_id: &str) -> Result<(), ToolManagerError> {
         let mut tools = self.tools.write().unwrap();
 
@@ -304,34 +584,72 @@ impl ToolManager {
             return Err(ToolManagerError::ToolNotFound(tool_id.to_string()));
         }
 
-        // Remove from version tracking
-        let mut versions = self.versions.write().unwrap();
-        versions.remove(tool_id);
+        info!("Tool {} unregistered successfully", tool_id);
+        Ok(())
+    }
 
-        // Remove from capability registry
-        let mut capability_registry = self.capability_registry.write().unwrap();
-        capability_registry.remove(tool_id);
+    /// Unregister one version of a tool, keeping its other versions
+    pub fn unregister_tool_version(
+        &self,
+        tool_id: &str,
+        version: &str,
+    ) -> Result<(), ToolManagerError> {
+        let parsed = parse_version(version)?;
+        let mut tools = self.tools.write().unwrap();
+        let versions = tools
+            .get_mut(tool_id)
+            .ok_or_else(|| ToolManagerError::ToolNotFound(tool_id.to_string()))?;
+
+        let position = versions
+            .iter()
+            .position(|v| v.version == parsed)
+            .ok_or_else(|| ToolManagerError::VersionNotFound(format!("{}@{}", tool_id, version)))?;
+        versions.remove(position);
+        if versions.is_empty() {
+            tools.remove(tool_id);
+        }
 
-        info!("Tool {} unregistered successfully", tool_id);
+        info!("Tool {} v{} unregistered successfully", tool_id, version);
         Ok(())
     }
 
-    /// Get a tool by ID
-    pub fn get_tool(&self, tool_id: &str) -> Result<Arc<dyn Tool>, ToolManagerError> {
+    /// Get a tool by ID, optionally pinned to a version as `tool_id@requirement`
+    ///
+    /// Without a requirement the latest stable version is returned: the newest
+    /// version that is neither a pre-release nor deprecated, falling back to
+    /// the newest version. With a requirement the newest matching version is
+    /// returned, deprecated or not.
+    pub fn get_tool(&self, tool_ref: &str) -> Result<Arc<dyn Tool>, ToolManagerError> {
+        let (tool_id, requirement) = parse_tool_reference(tool_ref)?;
         let tools = self.tools.read().unwrap();
-
-        match tools.get(tool_id) {
-            Some(tool) => Ok(tool.clone()),
-            None => Err(ToolManagerError::ToolNotFound(tool_id.to_string())),
-        }
+        let versions = tools
+            .get(tool_id)
+            .ok_or_else(|| ToolManagerError::ToolNotFound(tool_id.to_string()))?;
+
+        let selected = match &requirement {
+            Some(requirement) => versions
+                .iter()
+                .rev()
+                .find(|v| requirement.matches(&v.version)),
+            None => versions
+                .iter()
+                .rev()
+                .find(|v| v.is_stable())
+                .or_else(|| versions.last()),
+        };
+
+        selected
+            .map(|v| v.tool.clone())
+            .ok_or_else(|| ToolManagerError::VersionNotFound(tool_ref.to_string()))
     }
 
-    /// List all registered tools
+    /// List all registered tools, with the default version of each
     pub fn list_tools(&self, category: Option<&str>) -> Vec<ToolMetadata> {
-        let tools = self.tools.read().unwrap();
+        let tool_ids: Vec<String> = self.tools.read().unwrap().keys().cloned().collect();
 
-        tools
-            .values()
+        tool_ids
+            .iter()
+            .filter_map(|tool_id| self.get_tool(tool_id).ok())
             .map(|tool| tool.metadata().clone())
             .filter(|metadata| {
                 // Filter by category if provided
@@ -345,116 +663,495 @@ impl ToolManager {
             .collect()
     }
 
+    /// Metadata and usage of every registered version of a tool, oldest first
+    pub fn list_tool_versions(&self, tool_id: &str) -> Vec<(ToolMetadata, ToolUsage)> {
+        let metadata: Vec<ToolMetadata> = self
+            .tools
+            .read()
+            .unwrap()
+            .get(tool_id)
+            .map(|versions| versions.iter().map(|v| v.tool.metadata().clone()).collect())
+            .unwrap_or_default();
+
+        metadata
+            .into_iter()
+            .map(|metadata| {
+                let usage = self.tool_usage(tool_id, &metadata.version);
+                (metadata, usage)
+            })
+            .collect()
+    }
+
+    /// Usage counters of one tool version
+    pub fn tool_usage(&self, tool_id: &str, version: &str) -> ToolUsage {
+        self.usage
+            .lock()
+            .unwrap()
+            .get(&(tool_id.to_string(), version.to_string()))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn record_usage(&self, metadata: &ToolMetadata, success: bool, duration: Duration) {
+        let mut usage = self.usage.lock().unwrap();
+        let entry = usage
+            .entry((metadata.id.clone(), metadata.version.clone()))
+            .or_default();
+        entry.calls += 1;
+        if !success {
+            entry.failures += 1;
+        }
+        entry.total_duration_ms += duration.as_millis() as u64;
+        entry.last_used_at = unix_now();
+    }
+
     /// Check if a tool has the required capabilities
     pub fn check_tool_capabilities(
         &self,
-        tool_id: &str,
+        tool_ref: &str,
         required: &HashSet<Capability>,
     ) -> Result<(), ToolManagerError> {
-        let capability_registry = self.capability_registry.read().unwrap();
+        let tool = self.get_tool(tool_ref)?;
+        let capabilities = &tool.metadata().capabilities;
 
-        match capability_registry.get(tool_id) {
-            Some(capabilities) => {
-                for cap in required {
-                    if !capabilities.contains(cap) {
-                        return Err(ToolManagerError::MissingCapability(format!("{:?}", cap)));
-                    }
-                }
-                Ok(())
+        for cap in required {
+            if !capabilities.contains(cap) {
+                return Err(ToolManagerError::MissingCapability(format!("{:?}", cap)));
             }
-            None => Err(ToolManagerError::ToolNotFound(tool_id.to_string())),
         }
+        Ok(())
     }
 
-    /// Grant capabilities to a session
+    /// Send capability audit events to `sink` as well as the `audit` log target
+    pub fn set_audit_sink(&self, sink: mpsc::UnboundedSender<AuditEvent>) {
+        *self.audit_sink.lock().unwrap() = Some(sink);
+    }
+
+    fn audit(&self, event: AuditEvent) {
+        info!(
+            target: "audit",
+            "{} actor={} session={} tool={} capabilities=[{}] allowed={}: {}",
+            event.event_type.as_str(),
+            event.actor,
+            event.session_id,
+            event.tool_id.as_deref().unwrap_or("*"),
+            event.capability_list(),
+            event.allowed,
+            event.detail
+        );
+        if let Some(sink) = self.audit_sink.lock().unwrap().as_ref() {
+            let _ = sink.send(event);
+        }
+    }
+
+    /// Grant capabilities to a session for `ttl`, optionally scoped to one tool
+    ///
+    /// Re-granting a capability with the same scope replaces the earlier grant.
     pub async fn grant_session_capabilities(
         &self,
         session_id: &str,
-        capabilities: HashSet<Capability>,
-    ) {
-        let mut session_grants = self.session_grants.lock().await;
+        capabilities: &[Capability],
+        tool_id: Option<&str>,
+        issuer: &str,
+        ttl: Duration,
+    ) -> Vec<CapabilityGrant> {
+        let now = unix_now();
+        let granted: Vec<CapabilityGrant> = capabilities
+            .iter()
+            .map(|capability| CapabilityGrant {
+                capability: *capability,
+                tool_id: tool_id.map(str::to_string),
+                issuer: issuer.to_string(),
+                granted_at: now,
+                expires_at: now + ttl.as_secs(),
+            })
+            .collect();
 
-        let session_capabilities = session_grants
-            .entry(session_id.to_string())
-            .or_insert_with(HashSet::new);
-        for cap in capabilities {
-            session_capabilities.insert(cap);
+        {
+            let mut session_grants = self.session_grants.lock().await;
+            let grants = session_grants.entry(session_id.to_string()).or_default();
+            grants.retain(|grant| {
+                grant.is_active(now)
+                    && !granted.iter().any(|new| {
+                        new.capability == grant.capability && new.tool_id == grant.tool_id
+                    })
+            });
+            grants.extend(granted.iter().cloned());
         }
 
         debug!(
             "Granted capabilities to session {}: {:?}",
-            session_id, session_capabilities
+            session_id, capabilities
         );
+        self.audit(AuditEvent {
+            event_type: AuditEventType::Grant,
+            actor: issuer.to_string(),
+            session_id: session_id.to_string(),
+            tool_id: tool_id.map(str::to_string),
+            capabilities: capabilities.to_vec(),
+            allowed: true,
+            detail: format!("granted for {}s", ttl.as_secs()),
+            timestamp: now,
+        });
+        granted
+    }
+
+    /// Revoke capabilities of a session; returns how many grants were removed
+    ///
+    /// No capabilities revokes all of them. With a tool only grants scoped to
+    /// that tool are removed, otherwise grants of every scope are.
+    pub async fn revoke_session_capabilities(
+        &self,
+        session_id: &str,
+        capabilities: &[Capability],
+        tool_id: Option<&str>,
+        issuer: &str,
+    ) -> usize {
+        let now = unix_now();
+        let revoked = {
+            let mut session_grants = self.session_grants.lock().await;
+            let Some(grants) = session_grants.get_mut(session_id) else {
+                return 0;
+            };
+            // Expired grants are pruned separately so they don't count as revoked
+            grants.retain(|grant| grant.is_active(now));
+            let before = grants.len();
+            grants.retain(|grant| {
+                let matches = (capabilities.is_empty() || capabilities.contains(&grant.capability))
+                    && tool_id.is_none_or(|id| grant.tool_id.as_deref() == Some(id));
+                !matches
+            });
+            let revoked = before - grants.len();
+            if grants.is_empty() {
+                session_grants.remove(session_id);
+            }
+            revoked
+        };
+
+        self.audit(AuditEvent {
+            event_type: AuditEventType::Revoke,
+            actor: issuer.to_string(),
+            session_id: session_id.to_string(),
+            tool_id: tool_id.map(str::to_string),
+            capabilities: capabilities.to_vec(),
+            allowed: true,
+            detail: format!("revoked {} grant(s)", revoked),
+            timestamp: now,
+        });
+        revoked
+    }
+
+    /// Active grants of a session
+    pub async fn list_session_capabilities(&self, session_id: &str) -> Vec<CapabilityGrant> {
+        let now = unix_now();
+        let mut session_grants = self.session_grants.lock().await;
+        let Some(grants) = session_grants.get_mut(session_id) else {
+            return Vec::new();
+        };
+        grants.retain(|grant| grant.is_active(now));
+        let active = grants.clone();
+        if active.is_empty() {
+            session_grants.remove(session_id);
+        }
+        active
     }
 
-    /// Check if a session has the required capabilities
+    /// Check if a session holds active grants covering the capabilities a tool requires
     pub async fn check_session_capabilities(
         &self,
         session_id: &str,
+        tool_id: &str,
         required: &HashSet<Capability>,
     ) -> Result<(), ToolManagerError> {
-        let session_grants = self.session_grants.lock().await;
+        if required.is_empty() {
+            return Ok(());
+        }
 
-        match session_grants.get(session_id) {
-            Some(capabilities) => {
-                for cap in required {
-                    if !capabilities.contains(cap) {
-                        return Err(ToolManagerError::MissingCapability(format!("{:?}", cap)));
-                    }
-                }
-                Ok(())
-            }
-            None => Err(ToolManagerError::MissingCapability(
-                "Session has no capabilities".to_string(),
-            )),
+        let now = unix_now();
+        let missing: Vec<Capability> = {
+            let session_grants = self.session_grants.lock().await;
+            let grants = session_grants.get(session_id);
+            required
+                .iter()
+                .filter(|cap| {
+                    !grants.is_some_and(|grants| {
+                        grants
+                            .iter()
+                            .any(|grant| grant.is_active(now) && grant.covers(**cap, tool_id))
+                    })
+                })
+                .copied()
+                .collect()
+        };
+
+        let allowed = missing.is_empty();
+        self.audit(AuditEvent {
+            event_type: if allowed {
+                AuditEventType::Use
+            } else {
+                AuditEventType::Denied
+            },
+            actor: session_id.to_string(),
+            session_id: session_id.to_string(),
+            tool_id: Some(tool_id.to_string()),
+            capabilities: if allowed {
+                required.iter().copied().collect()
+            } else {
+                missing.clone()
+            },
+            allowed,
+            detail: if allowed {
+                "capabilities used".to_string()
+            } else {
+                "missing capabilities".to_string()
+            },
+            timestamp: now,
+        });
+
+        match missing.first() {
+            None => Ok(()),
+            Some(cap) => Err(ToolManagerError::MissingCapability(format!("{:?}", cap))),
         }
     }
 
     /// Execute a tool with the given context
+    ///
+    /// `tool_ref` is a tool id, optionally pinned as `tool_id@requirement`.
+    /// The result metadata records the version that ran and, for deprecated
+    /// versions, a `deprecation_warning`.
     pub async fn execute_tool(
         &self,
-        tool_id: &str,
+        tool_ref: &str,
         context: ToolContext,
     ) -> Result<ToolResult, ToolManagerError> {
         // Get the tool
-        let tool = self.get_tool(tool_id)?;
+        let tool = self.get_tool(tool_ref)?;
+        let metadata = tool.metadata();
 
         // Validate parameters
         tool.validate_parameters(&context.parameters)?;
 
-        // Check if session has required capabilities
-        if let Some(session_id) = &context.session_id {
-            let required_capabilities = tool.metadata().capabilities.clone();
-            self.check_session_capabilities(session_id, &required_capabilities)
-                .await?;
+        // Check if session has required capabilities; only tools that declare
+        // none may run without a session
+        match &context.session_id {
+            Some(session_id) => {
+                self.check_session_capabilities(session_id, &metadata.id, &metadata.capabilities)
+                    .await?;
+            }
+            None if !metadata.capabilities.is_empty() => {
+                let event = AuditEvent {
+                    event_type: AuditEventType::Denied,
+                    actor: "anonymous".to_string(),
+                    session_id: String::new(),
+                    tool_id: Some(metadata.id.clone()),
+                    capabilities: metadata.capabilities.iter().copied().collect(),
+                    allowed: false,
+                    detail: "no session".to_string(),
+                    timestamp: unix_now(),
+                };
+                let capabilities = event.capability_list();
+                self.audit(event);
+                return Err(ToolManagerError::MissingCapability(format!(
+                    "{} (no session)",
+                    capabilities
+                )));
+            }
+            None => {}
         }
 
         // Execute the tool
-        let result = tool.execute(context).await?;
+        let start = std::time::Instant::now();
+        let result = tool.execute(context).await;
+        self.record_usage(
+            metadata,
+            result.as_ref().is_ok_and(|r| r.success),
+            start.elapsed(),
+        );
+        let mut result = result?;
+
+        result
+            .metadata
+            .insert("tool_version".to_string(), metadata.version.clone());
+        if let Some(notice) = &metadata.deprecated {
+            warn!(
+                "Deprecated tool {} v{} executed: {}",
+                metadata.id, metadata.version, notice
+            );
+            result.metadata.insert(
+                "deprecation_warning".to_string(),
+                format!(
+                    "{} v{} is deprecated: {}",
+                    metadata.id, metadata.version, notice
+                ),
+            );
+        }
 
         Ok(result)
     }
 
-    /// Load tools dynamically from a directory
+    /// Load manifest-defined tools (WebAssembly plugins and HTTP tools) from a directory
+    ///
+    /// Repeated calls sync the registry with the directory: new manifests are
+    /// registered, changed tools are replaced and tools whose manifest was
+    /// removed are unregistered. Manifests for different versions of one tool
+    /// id register side by side. A tool that fails to load keeps its previous
+    /// version registered. Returns the ids of tools registered or updated.
     pub fn load_tools_from_directory(
         &self,
         directory: &str,
     ) -> Result<Vec<String>, ToolManagerError> {
-        // This would be implemented to dynamically load tool plugins
-        // In a real implementation, this would use libloading or similar
-        // For this example, we'll just return an error
+        let dir = Path::new(directory);
+        let manifests = wasm_plugins::find_manifests(dir)?;
+        let mut plugins = self.plugins.lock().unwrap();
+
+        let removed: Vec<PathBuf> = plugins
+            .keys()
+            .filter(|path| path.parent() == Some(dir) && !manifests.contains(path))
+            .cloned()
+            .collect();
+        for path in removed {
+            if let Some((tool_id, version)) = plugins.remove(&path).and_then(|plugin| plugin.tool) {
+                if let Err(e) = self.unregister_tool_version(&tool_id, &version) {
+                    warn!(
+                        "Plugin {} v{} was already unregistered: {}",
+                        tool_id, version, e
+                    );
+                }
+            }
+        }
+
+        let mut loaded = Vec::new();
+        for manifest in manifests {
+            let previous = plugins.get(&manifest).cloned();
+            if let Some(previous) = &previous {
+                if previous.fingerprint
+                    == wasm_plugins::fingerprint(&manifest, &previous.dependency)
+                {
+                    continue;
+                }
+            }
+            let previous_tool = previous.and_then(|plugin| plugin.tool);
+
+            match self.load_plugin(&manifest, previous_tool.as_ref()) {
+                Ok((tool, dependency)) => {
+                    let tool_id = tool.metadata().id.clone();
+                    let version = tool.metadata().version.clone();
+                    if let Some((previous_id, previous_version)) = &previous_tool {
+                        let _ = self.unregister_tool_version(previous_id, previous_version);
+                    }
+                    self.register_tool(tool)?;
+                    plugins.insert(
+                        manifest.clone(),
+                        LoadedPlugin {
+                            tool: Some((tool_id.clone(), version)),
+                            fingerprint: wasm_plugins::fingerprint(&manifest, &dependency),
+                            dependency,
+                        },
+                    );
+                    loaded.push(tool_id);
+                }
+                Err(e) => {
+                    error!("Failed to load tool manifest {}: {}", manifest.display(), e);
+                    // Remember the broken files so they aren't retried on every sync
+                    let dependency = if http_tools::is_http_manifest(&manifest) {
+                        manifest.clone()
+                    } else {
+                        wasm_plugins::module_path_for(&manifest)
+                    };
+                    plugins.insert(
+                        manifest.clone(),
+                        LoadedPlugin {
+                            tool: previous_tool,
+                            fingerprint: wasm_plugins::fingerprint(&manifest, &dependency),
+                            dependency,
+                        },
+                    );
+                }
+            }
+        }
 
-        Err(ToolManagerError::LoadingError(
-            "Dynamic loading not implemented yet".to_string(),
-        ))
+        Ok(loaded)
     }
 
-    /// Get tool versions
+    /// Load and validate a manifest-defined tool that may replace `previous` (id and version)
+    ///
+    /// Also returns the file the manifest depends on (WebAssembly module or
+    /// OpenAPI spec) so changes to it trigger a reload.
+    fn load_plugin(
+        &self,
+        manifest: &Path,
+        previous: Option<&(String, String)>,
+    ) -> Result<(Arc<dyn Tool>, PathBuf), ToolManagerError> {
+        let (tool, dependency): (Arc<dyn Tool>, PathBuf) = if http_tools::is_http_manifest(manifest)
+        {
+            let tool = HttpTool::load(manifest)?;
+            let spec = tool.spec_path().unwrap_or(manifest).to_path_buf();
+            (Arc::new(tool), spec)
+        } else {
+            let tool = WasmPluginTool::load(manifest)?;
+            let module = tool.module_path().to_path_buf();
+            (Arc::new(tool), module)
+        };
+        let metadata = tool.metadata();
+        self.validate_tool_metadata(metadata)?;
+
+        let version = parse_version(&metadata.version)?;
+        let replaces_previous = previous.is_some_and(|(id, previous_version)| {
+            *id == metadata.id && parse_version(previous_version).ok().as_ref() == Some(&version)
+        });
+        let registered = self
+            .tools
+            .read()
+            .unwrap()
+            .get(&metadata.id)
+            .is_some_and(|versions| versions.iter().any(|v| v.version == version));
+        if registered && !replaces_previous {
+            return Err(ToolManagerError::ToolAlreadyExists(format!(
+                "{}@{}",
+                metadata.id, metadata.version
+            )));
+        }
+
+        Ok((tool, dependency))
+    }
+
+    /// Re-sync a tool manifest directory every `interval` so plugin changes apply without a restart
+    pub fn spawn_plugin_reload(self: &Arc<Self>, directory: String, interval: Duration) {
+        let manager = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // The first tick completes immediately; the directory was just loaded
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let manager = Arc::clone(&manager);
+                let directory = directory.clone();
+                // Compiling modules is CPU-bound, keep it off the async workers
+                let result = tokio::task::spawn_blocking(move || {
+                    manager.load_tools_from_directory(&directory)
+                })
+                .await;
+                match result {
+                    Ok(Ok(loaded)) if !loaded.is_empty() => {
+                        info!("Reloaded tools: {}", loaded.join(", "))
+                    }
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) => warn!("Tool manifest reload failed: {}", e),
+                    Err(e) => error!("Tool manifest reload task panicked: {}", e),
+                }
+            }
+        });
+    }
+
+    /// Get tool versions, oldest first
     pub fn get_tool_versions(&self, tool_id: &str) -> Result<Vec<String>, ToolManagerError> {
-        let versions = self.versions.read().unwrap();
+        let tools = self.tools.read().unwrap();
 
-        match versions.get(tool_id) {
-            Some(v) => Ok(v.clone()),
+        match tools.get(tool_id) {
+            Some(versions) => Ok(versions
+                .iter()
+                .map(|v| v.tool.metadata().version.clone())
+                .collect()),
             None => Err(ToolManagerError::ToolNotFound(tool_id.to_string())),
         }
     }
@@ -486,6 +1183,7 @@ impl ToolManager {
                 "Tool version should follow semver format".to_string(),
             ));
         }
+        parse_version(&metadata.version)?;
 
         // Check parameters
         for param in &metadata.parameters {
@@ -558,6 +1256,13 @@ mod tests {
         }
     }
 
+    fn versioned_tool(version: &str, deprecated: Option<&str>) -> Arc<dyn Tool> {
+        let mut tool = create_mock_tool();
+        tool.metadata.version = version.to_string();
+        tool.metadata.deprecated = deprecated.map(str::to_string);
+        Arc::new(tool)
+    }
+
     fn create_mock_tool() -> MockTool {
         let mut capabilities = HashSet::new();
         capabilities.insert(Capability::ExecuteCommand);
@@ -590,6 +1295,7 @@ mod tests {
                     .duration_since(UNIX_EPOCH)
                     .unwrap()
                     .as_secs(),
+                deprecated: None,
             },
         }
     }
@@ -647,4 +1353,298 @@ mod tests {
         let invalid_tools = manager.list_tools(Some("invalid"));
         assert_eq!(invalid_tools.len(), 0);
     }
+
+    #[test]
+    fn test_parameters_json_schema() {
+        let definitions = vec![
+            ParameterDefinition {
+                name: "query".to_string(),
+                description: "Search query".to_string(),
+                required: true,
+                param_type: "string".to_string(),
+                default: None,
+                validation: Some("^.{1,100}$".to_string()),
+            },
+            ParameterDefinition {
+                name: "limit".to_string(),
+                description: String::new(),
+                required: false,
+                param_type: "int".to_string(),
+                default: Some("10".to_string()),
+                validation: None,
+            },
+        ];
+
+        let schema = parameters_json_schema(&definitions);
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["required"], serde_json::json!(["query"]));
+        assert_eq!(schema["properties"]["query"]["type"], "string");
+        assert_eq!(schema["properties"]["query"]["pattern"], "^.{1,100}$");
+        assert_eq!(schema["properties"]["limit"]["type"], "integer");
+        assert_eq!(schema["properties"]["limit"]["default"], 10);
+        assert!(schema["properties"]["limit"].get("description").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_session_grants_scoping_and_expiry() {
+        let manager = ToolManager::new();
+        let required: HashSet<Capability> = [Capability::Network].into_iter().collect();
+
+        manager
+            .grant_session_capabilities(
+                "s1",
+                &[Capability::Network],
+                Some("fetch_url"),
+                "admin",
+                Duration::from_secs(60),
+            )
+            .await;
+        assert!(manager
+            .check_session_capabilities("s1", "fetch_url", &required)
+            .await
+            .is_ok());
+        assert!(matches!(
+            manager
+                .check_session_capabilities("s1", "web_search", &required)
+                .await,
+            Err(ToolManagerError::MissingCapability(_))
+        ));
+
+        // A zero TTL grant is already expired
+        manager
+            .grant_session_capabilities("s2", &[Capability::Network], None, "admin", Duration::ZERO)
+            .await;
+        assert!(manager
+            .check_session_capabilities("s2", "web_search", &required)
+            .await
+            .is_err());
+        assert!(manager.list_session_capabilities("s2").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execute_without_session_denies_capability_tools() {
+        let manager = ToolManager::new();
+        manager
+            .register_tool(Arc::new(crate::fetch::FetchUrlTool::new()))
+            .unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        manager.set_audit_sink(tx);
+
+        let context = ToolContext {
+            parameters: [("url".to_string(), "https://example.com/".to_string())].into(),
+            user_id: None,
+            session_id: None,
+            request_id: "test".to_string(),
+            context_data: HashMap::new(),
+        };
+        let result = manager.execute_tool("fetch_url", context).await;
+        assert!(matches!(
+            result,
+            Err(ToolManagerError::MissingCapability(_))
+        ));
+
+        let event = rx.try_recv().unwrap();
+        assert_eq!(event.event_type, AuditEventType::Denied);
+        assert_eq!(event.tool_id.as_deref(), Some("fetch_url"));
+        assert_eq!(event.capabilities, vec![Capability::Network]);
+        assert!(!event.allowed);
+        assert!(rx.try_recv().is_err());
+
+        // Tools without capabilities still run without a session
+        let mut tool = create_mock_tool();
+        tool.metadata.capabilities.clear();
+        manager.register_tool(Arc::new(tool)).unwrap();
+        let context = ToolContext {
+            parameters: [("command".to_string(), "ls".to_string())].into(),
+            user_id: None,
+            session_id: None,
+            request_id: "test".to_string(),
+            context_data: HashMap::new(),
+        };
+        assert!(manager.execute_tool("mock_tool", context).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_session_capabilities_and_audit() {
+        let manager = ToolManager::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        manager.set_audit_sink(tx);
+
+        let ttl = Duration::from_secs(60);
+        let caps = [Capability::Network, Capability::FileSystem];
+        manager
+            .grant_session_capabilities("s1", &caps, None, "admin", ttl)
+            .await;
+        manager
+            .grant_session_capabilities("s1", &caps, Some("read_file"), "admin", ttl)
+            .await;
+        assert_eq!(manager.list_session_capabilities("s1").await.len(), 4);
+
+        // Expired matching grants are not counted as revoked
+        manager
+            .grant_session_capabilities(
+                "s1",
+                &[Capability::Network],
+                Some("web_search"),
+                "admin",
+                Duration::ZERO,
+            )
+            .await;
+
+        let revoked = manager
+            .revoke_session_capabilities("s1", &[Capability::Network], None, "admin")
+            .await;
+        assert_eq!(revoked, 2);
+        let revoked = manager
+            .revoke_session_capabilities("s1", &[], Some("read_file"), "admin")
+            .await;
+        assert_eq!(revoked, 1);
+
+        let remaining = manager.list_session_capabilities("s1").await;
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].capability, Capability::FileSystem);
+        assert_eq!(remaining[0].tool_id, None);
+
+        let required: HashSet<Capability> = [Capability::FileSystem].into_iter().collect();
+        manager
+            .check_session_capabilities("s1", "read_file", &required)
+            .await
+            .unwrap();
+
+        let mut events = Vec::new();
+        let mut revoke_details = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            if event.event_type == AuditEventType::Revoke {
+                revoke_details.push(event.detail.clone());
+            }
+            events.push(event.event_type);
+        }
+        assert_eq!(
+            revoke_details,
+            vec!["revoked 2 grant(s)", "revoked 1 grant(s)"]
+        );
+        assert_eq!(
+            events,
+            vec![
+                AuditEventType::Grant,
+                AuditEventType::Grant,
+                AuditEventType::Grant,
+                AuditEventType::Revoke,
+                AuditEventType::Revoke,
+                AuditEventType::Use,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_version_selection() {
+        let manager = ToolManager::new();
+        for (version, deprecated) in [
+            ("1.2.0", None),
+            ("1.2.5", None),
+            ("1.10.0", Some("use 2.x")),
+            ("2.0.0", None),
+            ("3.0.0-beta.1", None),
+        ] {
+            manager
+                .register_tool(versioned_tool(version, deprecated))
+                .unwrap();
+        }
+        assert!(matches!(
+            manager.register_tool(versioned_tool("2.0", None)),
+            Err(ToolManagerError::ToolAlreadyExists(_))
+        ));
+
+        let version_of = |reference: &str| {
+            manager
+                .get_tool(reference)
+                .map(|tool| tool.metadata().version.clone())
+        };
+        // Latest stable skips pre-releases
+        assert_eq!(version_of("mock_tool").unwrap(), "2.0.0");
+        assert_eq!(version_of("mock_tool@latest").unwrap(), "2.0.0");
+        assert_eq!(version_of("mock_tool@1.2").unwrap(), "1.2.5");
+        assert_eq!(version_of("mock_tool@1.2.0").unwrap(), "1.2.0");
+        assert_eq!(version_of("mock_tool@^1.2").unwrap(), "1.10.0");
+        assert_eq!(version_of("mock_tool@>=1.0, <1.10").unwrap(), "1.2.5");
+        assert_eq!(
+            version_of("mock_tool@3.0.0-beta.1").unwrap(),
+            "3.0.0-beta.1"
+        );
+        assert!(matches!(
+            version_of("mock_tool@4"),
+            Err(ToolManagerError::VersionNotFound(_))
+        ));
+        assert!(version_of("mock_tool@not-a-version").is_err());
+        assert_eq!(manager.list_tools(None)[0].version, "2.0.0");
+
+        manager
+            .unregister_tool_version("mock_tool", "2.0.0")
+            .unwrap();
+        // Without a stable version left, the newest one is the default
+        manager
+            .unregister_tool_version("mock_tool", "1.2.0")
+            .unwrap();
+        manager
+            .unregister_tool_version("mock_tool", "1.2.5")
+            .unwrap();
+        assert_eq!(version_of("mock_tool").unwrap(), "3.0.0-beta.1");
+        assert_eq!(
+            manager.get_tool_versions("mock_tool").unwrap(),
+            vec!["1.10.0", "3.0.0-beta.1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deprecation_warning_and_version_usage() {
+        let manager = ToolManager::new();
+        manager
+            .register_tool(versioned_tool("1.0.0", Some("use 2.0")))
+            .unwrap();
+        manager
+            .register_tool(versioned_tool("2.0.0", None))
+            .unwrap();
+        manager
+            .grant_session_capabilities(
+                "s1",
+                &[Capability::ExecuteCommand],
+                None,
+                "admin",
+                Duration::from_secs(60),
+            )
+            .await;
+
+        let context = ToolContext {
+            parameters: [("command".to_string(), "ls".to_string())].into(),
+            user_id: None,
+            session_id: Some("s1".to_string()),
+            request_id: "test".to_string(),
+            context_data: HashMap::new(),
+        };
+        let pinned = manager
+            .execute_tool("mock_tool@1", context.clone())
+            .await
+            .unwrap();
+        assert_eq!(pinned.metadata["tool_version"], "1.0.0");
+        assert!(pinned.metadata["deprecation_warning"].contains("use 2.0"));
+
+        let latest = manager
+            .execute_tool("mock_tool", context.clone())
+            .await
+            .unwrap();
+        assert_eq!(latest.metadata["tool_version"], "2.0.0");
+        assert!(!latest.metadata.contains_key("deprecation_warning"));
+        manager.execute_tool("mock_tool", context).await.unwrap();
+
+        let usage: Vec<(String, u64)> = manager
+            .list_tool_versions("mock_tool")
+            .into_iter()
+            .map(|(metadata, usage)| (metadata.version, usage.calls))
+            .collect();
+        assert_eq!(
+            usage,
+            vec![("1.0.0".to_string(), 1), ("2.0.0".to_string(), 2)]
+        );
+        assert_eq!(manager.tool_usage("mock_tool", "2.0.0").failures, 0);
+    }
 }
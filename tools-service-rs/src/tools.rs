@@ -2,41 +2,57 @@
 //!
 //! General-purpose tools exposed by tools-service-rs:
 //! - web_search
-//! - execute_code
-//! - read_file
-//! - write_file
+//! - fetch_url (see `fetch`)
+//! - sql_query when data sources are configured (see `sql_query`)
+//! - execute_code, through executor-rs when configured (see `executor_client`)
+//! - read_file, write_file, list_dir, grep and apply_patch, jailed to the
+//!   session workspace (see `workspace`)
 
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
 use log::{info, warn};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
 use tool_sdk::ServiceClient;
 
+use crate::agi_core::CommandRequest;
+use crate::executor_client::{self, ExecutorClient};
+use crate::fetch::FetchUrlTool;
+use crate::jobs;
+use crate::patch::ApplyPatchTool;
+use crate::sql_query::SqlQueryTool;
+
 use crate::tool_manager::{
     Capability, ParameterDefinition, Tool, ToolContext, ToolManagerError, ToolMetadata, ToolResult,
     TOOL_MANAGER,
 };
 
 use crate::validation::ToolValidationError;
+use crate::workspace::{self, GrepTool, ListDirTool, Workspace};
 
 /// Read File Tool
 pub struct ReadFileTool {
     metadata: ToolMetadata,
+    workspace_base: PathBuf,
 }
 
 impl ReadFileTool {
     pub fn new() -> Self {
+        Self::with_workspace_base(workspace::workspace_base())
+    }
+
+    pub fn with_workspace_base(workspace_base: PathBuf) -> Self {
         let mut capabilities = std::collections::HashSet::new();
         capabilities.insert(Capability::FileSystem);
 
         let parameters = vec![
             ParameterDefinition {
                 name: "path".to_string(),
-                description: "Path to the file to read".to_string(),
+                description: "Path to the file to read, relative to the workspace".to_string(),
                 required: true,
                 param_type: "string".to_string(),
                 default: None,
@@ -61,7 +77,7 @@ impl ReadFileTool {
             metadata: ToolMetadata {
                 id: "read_file".to_string(),
                 name: "Read File".to_string(),
-                description: "Reads content from a file".to_string(),
+                description: "Reads content from a file in the session workspace".to_string(),
                 version: "1.0.0".to_string(),
                 author: "System".to_string(),
                 category: "filesystem".to_string(),
@@ -70,7 +86,9 @@ impl ReadFileTool {
                 enabled: true,
                 created_at: now,
                 updated_at: now,
+                deprecated: None,
             },
+            workspace_base,
         }
     }
 }
@@ -94,19 +112,7 @@ impl Tool for ReadFileTool {
             }
         };
 
-        let file_path = Path::new(path);
-
-        if !file_path.exists() {
-            return Err(ToolManagerError::ValidationError(
-                ToolValidationError::Other(format!("File does not exist: {}", path)),
-            ));
-        }
-
-        if !file_path.is_file() {
-            return Err(ToolManagerError::ValidationError(
-                ToolValidationError::Other(format!("Path is not a file: {}", path)),
-            ));
-        }
+        workspace::check_path_syntax(path).map_err(ToolManagerError::ValidationError)?;
 
         if let Some(line_numbers) = parameters.get("line_numbers") {
             match line_numbers.as_str() {
@@ -134,7 +140,23 @@ impl Tool for ReadFileTool {
             .map(|v| v == "true")
             .unwrap_or(true);
 
-        let content = fs::read_to_string(&path)
+        let workspace =
+            Workspace::for_session(&self.workspace_base, context.session_id.as_deref())?;
+        let file_path = workspace.resolve(&path)?;
+        if !file_path.exists() {
+            return Err(ToolManagerError::ExecutionError(format!(
+                "File does not exist: {}",
+                path
+            )));
+        }
+        if !file_path.is_file() {
+            return Err(ToolManagerError::ExecutionError(format!(
+                "Path is not a file: {}",
+                path
+            )));
+        }
+
+        let content = fs::read_to_string(&file_path)
             .map_err(|e| ToolManagerError::ExecutionError(format!("Failed to read file: {}", e)))?;
 
         let result = if line_numbers {
@@ -150,7 +172,7 @@ impl Tool for ReadFileTool {
 
         let duration_ms = start_time.elapsed().as_millis() as u64;
         let mut metadata = HashMap::new();
-        metadata.insert("path".to_string(), path);
+        metadata.insert("path".to_string(), workspace.display(&file_path));
         metadata.insert(
             "line_count".to_string(),
             content.lines().count().to_string(),
@@ -170,17 +192,22 @@ impl Tool for ReadFileTool {
 /// Write File Tool
 pub struct WriteFileTool {
     metadata: ToolMetadata,
+    workspace_base: PathBuf,
 }
 
 impl WriteFileTool {
     pub fn new() -> Self {
+        Self::with_workspace_base(workspace::workspace_base())
+    }
+
+    pub fn with_workspace_base(workspace_base: PathBuf) -> Self {
         let mut capabilities = std::collections::HashSet::new();
         capabilities.insert(Capability::FileSystem);
 
         let parameters = vec![
             ParameterDefinition {
                 name: "path".to_string(),
-                description: "Path to write the file".to_string(),
+                description: "Path to write the file, relative to the workspace".to_string(),
                 required: true,
                 param_type: "string".to_string(),
                 default: None,
@@ -213,7 +240,7 @@ impl WriteFileTool {
             metadata: ToolMetadata {
                 id: "write_file".to_string(),
                 name: "Write File".to_string(),
-                description: "Writes content to a file".to_string(),
+                description: "Writes content to a file in the session workspace".to_string(),
                 version: "1.0.0".to_string(),
                 author: "System".to_string(),
                 category: "filesystem".to_string(),
@@ -222,7 +249,9 @@ impl WriteFileTool {
                 enabled: true,
                 created_at: now,
                 updated_at: now,
+                deprecated: None,
             },
+            workspace_base,
         }
     }
 }
@@ -263,28 +292,7 @@ impl Tool for WriteFileTool {
             }
         }
 
-        let path_obj = Path::new(path);
-        for component in path_obj.components() {
-            let comp_str = component.as_os_str().to_string_lossy();
-            if comp_str.contains("..") {
-                return Err(ToolManagerError::ValidationError(
-                    ToolValidationError::SecurityThreat(
-                        "Path contains potentially dangerous components".to_string(),
-                    ),
-                ));
-            }
-        }
-
-        if let Some(parent) = path_obj.parent() {
-            if !parent.is_dir() && !parent.as_os_str().is_empty() {
-                return Err(ToolManagerError::ValidationError(
-                    ToolValidationError::Other(format!(
-                        "Parent directory does not exist: {:?}",
-                        parent
-                    )),
-                ));
-            }
-        }
+        workspace::check_path_syntax(path).map_err(ToolManagerError::ValidationError)?;
 
         Ok(())
     }
@@ -300,7 +308,9 @@ impl Tool for WriteFileTool {
             .map(|v| v == "true")
             .unwrap_or(false);
 
-        let path_obj = Path::new(&path);
+        let workspace =
+            Workspace::for_session(&self.workspace_base, context.session_id.as_deref())?;
+        let path_obj = workspace.resolve(&path)?;
         if let Some(parent) = path_obj.parent() {
             if !parent.exists() && !parent.as_os_str().is_empty() {
                 fs::create_dir_all(parent).map_err(|e| {
@@ -316,19 +326,20 @@ impl Tool for WriteFileTool {
             fs::OpenOptions::new()
                 .create(true)
                 .append(true)
-                .open(&path)
+                .open(&path_obj)
                 .and_then(|mut file| std::io::Write::write_all(&mut file, content.as_bytes()))
                 .map_err(|e| {
                     ToolManagerError::ExecutionError(format!("Failed to write file: {}", e))
                 })?;
         } else {
-            fs::write(&path, &content).map_err(|e| {
+            fs::write(&path_obj, &content).map_err(|e| {
                 ToolManagerError::ExecutionError(format!("Failed to write file: {}", e))
             })?;
         }
 
         let duration_ms = start_time.elapsed().as_millis() as u64;
         let mut metadata = HashMap::new();
+        let path = workspace.display(&path_obj);
         metadata.insert("path".to_string(), path.clone());
         metadata.insert("size".to_string(), content.len().to_string());
         metadata.insert("append".to_string(), append.to_string());
@@ -345,12 +356,20 @@ impl Tool for WriteFileTool {
 }
 
 /// Execute Code Tool (for various languages)
+///
+/// Python runs in the executor service when EXECUTOR_ADDR is set; other
+/// languages, and Python without an executor, run as local processes.
 pub struct ExecuteCodeTool {
     metadata: ToolMetadata,
+    executor: Option<ExecutorClient>,
 }
 
 impl ExecuteCodeTool {
     pub fn new() -> Self {
+        Self::with_executor(ExecutorClient::from_env())
+    }
+
+    pub fn with_executor(executor: Option<ExecutorClient>) -> Self {
         let mut capabilities = std::collections::HashSet::new();
         capabilities.insert(Capability::ExecuteCode);
 
@@ -379,6 +398,14 @@ impl ExecuteCodeTool {
                 default: Some("5".to_string()),
                 validation: Some(r"^[1-9][0-9]{0,2}$".to_string()),
             },
+            ParameterDefinition {
+                name: "output_globs".to_string(),
+                description: "Comma-separated globs of files the code writes to keep as artifacts, e.g. out/*.csv (executor only)".to_string(),
+                required: false,
+                param_type: "string".to_string(),
+                default: None,
+                validation: None,
+            },
         ];
 
         let now = SystemTime::now()
@@ -387,6 +414,7 @@ impl ExecuteCodeTool {
             .as_secs();
 
         Self {
+            executor,
             metadata: ToolMetadata {
                 id: "execute_code".to_string(),
                 name: "Execute Code".to_string(),
@@ -399,6 +427,7 @@ impl ExecuteCodeTool {
                 enabled: true,
                 created_at: now,
                 updated_at: now,
+                deprecated: None,
             },
         }
     }
@@ -469,6 +498,12 @@ impl Tool for ExecuteCodeTool {
             .and_then(|t| t.parse::<u32>().ok())
             .unwrap_or(5);
 
+        if language == "python" {
+            if let Some(executor) = &self.executor {
+                return execute_in_executor(executor, &context, code, start_time).await;
+            }
+        }
+
         let temp_dir = std::env::temp_dir();
         let file_name = match language.as_str() {
             "python" => format!("script_{}.py", context.request_id),
@@ -511,46 +546,135 @@ impl Tool for ExecuteCodeTool {
             }
         };
 
-        let output = AsyncCommand::new(cmd)
+        // Killed if the execution is dropped, e.g. when a tool job is cancelled or times out
+        let mut child = AsyncCommand::new(cmd)
             .args(&args)
-            .output()
-            .await
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
             .map_err(|e| {
                 let _ = fs::remove_file(&file_path);
                 ToolManagerError::ExecutionError(format!("Failed to execute code: {}", e))
             })?;
 
+        // Forward stdout lines as partial output when running as a job
+        let stdout_pipe = child.stdout.take();
+        let read_stdout = async move {
+            let mut stdout = String::new();
+            if let Some(pipe) = stdout_pipe {
+                let mut lines = BufReader::new(pipe).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let line = format!("{}\n", line);
+                    jobs::append_output(&line);
+                    stdout.push_str(&line);
+                }
+            }
+            stdout
+        };
+        let mut stderr_pipe = child.stderr.take();
+        let read_stderr = async move {
+            let mut stderr = Vec::new();
+            if let Some(pipe) = stderr_pipe.as_mut() {
+                let _ = pipe.read_to_end(&mut stderr).await;
+            }
+            String::from_utf8_lossy(&stderr).to_string()
+        };
+        let (stdout, stderr, status) = tokio::join!(read_stdout, read_stderr, child.wait());
+
         let _ = fs::remove_file(&file_path);
 
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        let exit_code = output.status.code().unwrap_or(-1);
-        let duration_ms = start_time.elapsed().as_millis() as u64;
+        let status = status.map_err(|e| {
+            ToolManagerError::ExecutionError(format!("Failed to execute code: {}", e))
+        })?;
+        let exit_code = status.code().unwrap_or(-1);
 
-        let mut metadata = HashMap::new();
-        metadata.insert("language".to_string(), language);
-        metadata.insert("exit_code".to_string(), exit_code.to_string());
-        metadata.insert("execution_time_ms".to_string(), duration_ms.to_string());
+        Ok(code_result(language, exit_code, stdout, stderr, start_time))
+    }
+}
 
-        if exit_code == 0 {
-            Ok(ToolResult {
-                success: true,
-                data: stdout,
-                error: String::new(),
-                metadata,
-                duration_ms,
-            })
-        } else {
-            let error_message = format!("Code execution failed (exit {}): {}", exit_code, stderr);
-            metadata.insert("stdout".to_string(), stdout);
+/// Run Python code in the executor, keeping files matching `output_globs` as artifacts
+async fn execute_in_executor(
+    executor: &ExecutorClient,
+    context: &ToolContext,
+    code: String,
+    start_time: Instant,
+) -> Result<ToolResult, ToolManagerError> {
+    let output_globs = context
+        .parameters
+        .get("output_globs")
+        .map(|globs| {
+            globs
+                .split(',')
+                .map(str::trim)
+                .filter(|glob| !glob.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // The executor runs the code of a python command in its sandbox
+    let response = executor
+        .execute_command(CommandRequest {
+            command: "python".to_string(),
+            args: vec![code],
+            output_globs,
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| ToolManagerError::ExecutionError(format!("Executor request failed: {}", e)))?;
+
+    jobs::append_output(&response.stdout);
+    let artifact_urls = executor_client::artifact_urls(&response);
+
+    let mut result = code_result(
+        "python".to_string(),
+        response.exit_code,
+        response.stdout,
+        response.stderr,
+        start_time,
+    );
+    result
+        .metadata
+        .insert("executor".to_string(), "true".to_string());
+    if let Some(urls) = artifact_urls {
+        result.metadata.insert("artifact_urls".to_string(), urls);
+    }
+    Ok(result)
+}
 
-            Ok(ToolResult {
-                success: false,
-                data: String::new(),
-                error: error_message,
-                metadata,
-                duration_ms,
-            })
+fn code_result(
+    language: String,
+    exit_code: i32,
+    stdout: String,
+    stderr: String,
+    start_time: Instant,
+) -> ToolResult {
+    let duration_ms = start_time.elapsed().as_millis() as u64;
+
+    let mut metadata = HashMap::new();
+    metadata.insert("language".to_string(), language);
+    metadata.insert("exit_code".to_string(), exit_code.to_string());
+    metadata.insert("execution_time_ms".to_string(), duration_ms.to_string());
+
+    if exit_code == 0 {
+        ToolResult {
+            success: true,
+            data: stdout,
+            error: String::new(),
+            metadata,
+            duration_ms,
+        }
+    } else {
+        let error_message = format!("Code execution failed (exit {}): {}", exit_code, stderr);
+        metadata.insert("stdout".to_string(), stdout);
+
+        ToolResult {
+            success: false,
+            data: String::new(),
+            error: error_message,
+            metadata,
+            duration_ms,
         }
     }
 }
@@ -605,6 +729,7 @@ impl WebSearchTool {
                 enabled: true,
                 created_at: now,
                 updated_at: now,
+                deprecated: None,
             },
             serpapi_client,
         }
@@ -761,23 +886,103 @@ fn format_search_results_from_response(
 pub async fn register_all_tools() -> Result<(), ToolManagerError> {
     let tool_manager = TOOL_MANAGER.clone();
 
-    let tools: Vec<Arc<dyn Tool>> = vec![
+    let mut tools: Vec<Arc<dyn Tool>> = vec![
         Arc::new(ReadFileTool::new()),
         Arc::new(WriteFileTool::new()),
         Arc::new(ExecuteCodeTool::new()),
         Arc::new(WebSearchTool::new()),
+        Arc::new(ListDirTool::new()),
+        Arc::new(GrepTool::new()),
+        Arc::new(ApplyPatchTool::new()),
+        Arc::new(FetchUrlTool::new()),
     ];
 
+    match SqlQueryTool::from_env() {
+        Ok(Some(tool)) => tools.push(Arc::new(tool)),
+        Ok(None) => {}
+        Err(e) => warn!("SQL query tool disabled: {}", e),
+    }
+
     for tool in tools {
         tool_manager.register_tool(tool)?;
     }
 
     info!("General-purpose tools registered successfully");
     Ok(())
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agi_core::executor_service_server::{ExecutorService, ExecutorServiceServer};
+    use crate::agi_core::{
+        ArtifactInfo, CommandResponse, CommandStreamEvent, GetArtifactRequest,
+        GetArtifactResponse, InputRequest, InputResponse, UploadFilesRequest, UploadFilesResponse,
+    };
+    use tonic::{Request, Response, Status};
+
+    /// Executor that reports one artifact per requested output glob
+    struct MockExecutor;
+
+    #[tonic::async_trait]
+    impl ExecutorService for MockExecutor {
+        async fn execute_command(
+            &self,
+            request: Request<CommandRequest>,
+        ) -> Result<Response<CommandResponse>, Status> {
+            let req = request.into_inner();
+            assert_eq!(req.command, "python");
+            assert_eq!(req.args, vec!["print('done')".to_string()]);
+
+            Ok(Response::new(CommandResponse {
+                stdout: "done\n".to_string(),
+                stderr: String::new(),
+                exit_code: 0,
+                artifacts: req
+                    .output_globs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, glob)| ArtifactInfo {
+                        artifact_id: format!("id{}", i),
+                        path: glob.clone(),
+                        size_bytes: 1,
+                        url: format!("artifact://id{}", i),
+                    })
+                    .collect(),
+            }))
+        }
+
+        type ExecuteCommandStreamStream =
+            tokio_stream::Empty<Result<CommandStreamEvent, Status>>;
+
+        async fn execute_command_stream(
+            &self,
+            _request: Request<CommandRequest>,
+        ) -> Result<Response<Self::ExecuteCommandStreamStream>, Status> {
+            Err(Status::unimplemented("not used"))
+        }
+
+        async fn upload_files(
+            &self,
+            _request: Request<UploadFilesRequest>,
+        ) -> Result<Response<UploadFilesResponse>, Status> {
+            Err(Status::unimplemented("not used"))
+        }
+
+        async fn get_artifact(
+            &self,
+            _request: Request<GetArtifactRequest>,
+        ) -> Result<Response<GetArtifactResponse>, Status> {
+            Err(Status::unimplemented("not used"))
+        }
+
+        async fn simulate_input(
+            &self,
+            _request: Request<InputRequest>,
+        ) -> Result<Response<InputResponse>, Status> {
+            Err(Status::unimplemented("not used"))
+        }
+    }
 
     #[tokio::test]
     async fn test_register_all_tools() {
@@ -791,5 +996,53 @@ mod tests {
         assert!(tool_manager.get_tool("write_file").is_ok());
         assert!(tool_manager.get_tool("execute_code").is_ok());
         assert!(tool_manager.get_tool("web_search").is_ok());
+        assert!(tool_manager.get_tool("fetch_url").is_ok());
+        assert!(tool_manager.get_tool("list_dir").is_ok());
+        assert!(tool_manager.get_tool("grep").is_ok());
+        assert!(tool_manager.get_tool("apply_patch").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_execute_code_reports_executor_artifacts() {
+        let incoming =
+            tonic::transport::server::TcpIncoming::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = incoming.local_addr().unwrap();
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(ExecutorServiceServer::new(MockExecutor))
+                .serve_with_incoming(incoming),
+        );
+
+        let executor = ExecutorClient::new(&addr.to_string()).unwrap();
+        let tool = ExecuteCodeTool::with_executor(Some(executor));
+        let execute = |output_globs: Option<&str>| {
+            let mut parameters = HashMap::new();
+            parameters.insert("language".to_string(), "python".to_string());
+            parameters.insert("code".to_string(), "print('done')".to_string());
+            if let Some(globs) = output_globs {
+                parameters.insert("output_globs".to_string(), globs.to_string());
+            }
+            tool.execute(ToolContext {
+                parameters,
+                user_id: None,
+                session_id: None,
+                request_id: "req-1".to_string(),
+                context_data: HashMap::new(),
+            })
+        };
+
+        let result = execute(Some("out/*.csv, plots/*.png")).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data, "done\n");
+        assert_eq!(result.metadata.get("executor").unwrap(), "true");
+        assert_eq!(
+            result.metadata.get("artifact_urls").unwrap(),
+            "artifact://id0,artifact://id1"
+        );
+
+        // No artifacts, no metadata key
+        let result = execute(None).await.unwrap();
+        assert!(result.success);
+        assert!(!result.metadata.contains_key("artifact_urls"));
     }
 }
@@ -0,0 +1,630 @@
+//! WebAssembly Tool Plugins
+//!
+//! Tools compiled to WebAssembly (WASI preview 1) and loaded from a plugin
+//! directory by `ToolManager::load_tools_from_directory`. Each plugin is a
+//! `<name>.toml` manifest next to its `<name>.wasm` module:
+//!
+//! ```toml
+//! id = "word_count"
+//! name = "Word Count"
+//! description = "Counts words in a text"
+//! version = "1.0.0"
+//! author = "Tools Team"
+//! category = "text"
+//! module = "word_count.wasm"        # Optional, defaults to <manifest stem>.wasm
+//! capabilities = ["FileSystem"]     # FileSystem enables mounts, Network enables sockets
+//!
+//! [limits]                          # Optional, clamped to the service maximums
+//! fuel = 1000000000
+//! memory_mb = 64
+//! timeout_ms = 30000
+//! max_output_bytes = 1048576
+//!
+//! [[parameters]]
+//! name = "text"
+//! description = "Text to count"
+//! required = true
+//! param_type = "string"
+//!
+//! [[mounts]]                        # Host directories (relative to the manifest)
+//! host = "data"
+//! guest = "/data"
+//! writable = false
+//! ```
+//!
+//! A call runs the module's `_start` in a fresh store. The plugin reads
+//! `{"request_id": "...", "parameters": {...}}` as JSON from stdin, writes its
+//! result to stdout and reports failure with a non-zero exit code, in which case
+//! stderr becomes the error message. Plugins get no environment variables, no
+//! host stdio, and no filesystem or network access beyond what their declared
+//! capabilities allow.
+
+use async_trait::async_trait;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use wasmtime::{
+    Config, Engine, InstancePre, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap,
+};
+use wasmtime_wasi::pipe::{MemoryInputPipe, MemoryOutputPipe};
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::{DirPerms, FilePerms, I32Exit, WasiCtxBuilder};
+
+use crate::tool_manager::{
+    validate_parameter_definitions, Capability, ParameterDefinition, Tool, ToolContext,
+    ToolManagerError, ToolMetadata, ToolResult,
+};
+
+/// Upper bounds for manifest limits
+pub const MAX_FUEL: u64 = 20_000_000_000;
+pub const MAX_MEMORY_MB: u64 = 1024;
+pub const MAX_TIMEOUT_MS: u64 = 300_000;
+pub const MAX_OUTPUT_BYTES: usize = 16 * 1024 * 1024;
+
+/// Fuel consumed between yields to the async runtime, so timeouts can fire
+const FUEL_YIELD_INTERVAL: u64 = 1_000_000;
+
+static ENGINE: OnceCell<Engine> = OnceCell::new();
+
+/// Shared engine with fuel metering and async support enabled
+fn engine() -> Result<&'static Engine, ToolManagerError> {
+    ENGINE.get_or_try_init(|| {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        config.async_support(true);
+        Engine::new(&config).map_err(|e| {
+            ToolManagerError::LoadingError(format!("Failed to create WebAssembly engine: {}", e))
+        })
+    })
+}
+
+/// Resource limits for one plugin call
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PluginLimits {
+    /// Instruction budget (wasmtime fuel)
+    pub fuel: u64,
+    /// Maximum linear memory in MiB
+    pub memory_mb: u64,
+    /// Wall-clock timeout in milliseconds
+    pub timeout_ms: u64,
+    /// Maximum bytes captured from stdout and from stderr
+    pub max_output_bytes: usize,
+}
+
+impl Default for PluginLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 1_000_000_000,
+            memory_mb: 64,
+            timeout_ms: 30_000,
+            max_output_bytes: 1024 * 1024,
+        }
+    }
+}
+
+impl PluginLimits {
+    fn clamped(self) -> Self {
+        Self {
+            fuel: self.fuel.min(MAX_FUEL),
+            memory_mb: self.memory_mb.min(MAX_MEMORY_MB),
+            timeout_ms: self.timeout_ms.min(MAX_TIMEOUT_MS),
+            max_output_bytes: self.max_output_bytes.min(MAX_OUTPUT_BYTES),
+        }
+    }
+}
+
+/// Host directory exposed to a plugin
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginMount {
+    /// Host path, relative to the manifest directory unless absolute
+    pub host: PathBuf,
+    /// Path the plugin sees
+    pub guest: String,
+    /// Whether the plugin may create, modify and delete files
+    #[serde(default)]
+    pub writable: bool,
+}
+
+/// Plugin manifest file
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub version: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default = "default_category")]
+    pub category: String,
+    /// Module path relative to the manifest (default: `<manifest stem>.wasm`)
+    #[serde(default)]
+    pub module: Option<String>,
+    #[serde(default)]
+    pub parameters: Vec<ParameterDefinition>,
+    #[serde(default)]
+    pub capabilities: HashSet<Capability>,
+    #[serde(default)]
+    pub limits: PluginLimits,
+    #[serde(default)]
+    pub mounts: Vec<PluginMount>,
+    /// Deprecation notice shown when this version runs
+    #[serde(default)]
+    pub deprecated: Option<String>,
+}
+
+fn default_category() -> String {
+    "plugin".to_string()
+}
+
+impl PluginManifest {
+    pub fn parse(source: &str) -> Result<Self, ToolManagerError> {
+        let manifest: Self = toml::from_str(source).map_err(|e| {
+            ToolManagerError::InvalidMetadata(format!("Invalid plugin manifest: {}", e))
+        })?;
+
+        if !manifest.mounts.is_empty() && !manifest.capabilities.contains(&Capability::FileSystem) {
+            return Err(ToolManagerError::MissingCapability(format!(
+                "Plugin {} declares mounts without the FileSystem capability",
+                manifest.id
+            )));
+        }
+
+        Ok(manifest)
+    }
+
+    /// Module path for a manifest stored at `manifest_path`
+    pub fn module_path(&self, manifest_path: &Path) -> PathBuf {
+        let dir = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+        match &self.module {
+            Some(module) => dir.join(module),
+            None => manifest_path.with_extension("wasm"),
+        }
+    }
+}
+
+/// Store state for one plugin call
+struct PluginState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+/// A tool backed by a WebAssembly module
+pub struct WasmPluginTool {
+    metadata: ToolMetadata,
+    limits: PluginLimits,
+    mounts: Vec<PluginMount>,
+    module_path: PathBuf,
+    instance_pre: InstancePre<PluginState>,
+}
+
+impl WasmPluginTool {
+    /// Load a plugin from its manifest, compiling and linking the module
+    pub fn load(manifest_path: &Path) -> Result<Self, ToolManagerError> {
+        let source = std::fs::read_to_string(manifest_path).map_err(|e| {
+            ToolManagerError::LoadingError(format!("{}: {}", manifest_path.display(), e))
+        })?;
+        let manifest = PluginManifest::parse(&source)?;
+        let module_path = manifest.module_path(manifest_path);
+        let manifest_dir = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+
+        let mut mounts = Vec::with_capacity(manifest.mounts.len());
+        for mount in &manifest.mounts {
+            let host = manifest_dir.join(&mount.host);
+            if !host.is_dir() {
+                return Err(ToolManagerError::LoadingError(format!(
+                    "Mount {} of plugin {} is not a directory",
+                    host.display(),
+                    manifest.id
+                )));
+            }
+            mounts.push(PluginMount {
+                host,
+                ..mount.clone()
+            });
+        }
+
+        let engine = engine()?;
+        let module = Module::from_file(engine, &module_path).map_err(|e| {
+            ToolManagerError::LoadingError(format!("{}: {}", module_path.display(), e))
+        })?;
+
+        // Link up front so modules importing anything beyond WASI fail at load time
+        let mut linker: Linker<PluginState> = Linker::new(engine);
+        preview1::add_to_linker_async(&mut linker, |state: &mut PluginState| &mut state.wasi)
+            .map_err(|e| ToolManagerError::LoadingError(e.to_string()))?;
+        let instance_pre = linker.instantiate_pre(&module).map_err(|e| {
+            ToolManagerError::LoadingError(format!("{}: {}", module_path.display(), e))
+        })?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        Ok(Self {
+            metadata: ToolMetadata {
+                id: manifest.id,
+                name: manifest.name,
+                description: manifest.description,
+                version: manifest.version,
+                author: manifest.author,
+                category: manifest.category,
+                parameters: manifest.parameters,
+                capabilities: manifest.capabilities,
+                enabled: true,
+                created_at: now,
+                updated_at: now,
+                deprecated: manifest.deprecated,
+            },
+            limits: manifest.limits.clamped(),
+            mounts,
+            module_path,
+            instance_pre,
+        })
+    }
+
+    /// Path of the compiled module, watched for hot reload
+    pub fn module_path(&self) -> &Path {
+        &self.module_path
+    }
+
+    fn build_wasi(
+        &self,
+        input: Vec<u8>,
+        stdout: &MemoryOutputPipe,
+        stderr: &MemoryOutputPipe,
+    ) -> Result<WasiP1Ctx, ToolManagerError> {
+        let mut builder = WasiCtxBuilder::new();
+        builder
+            .stdin(MemoryInputPipe::new(input))
+            .stdout(stdout.clone())
+            .stderr(stderr.clone())
+            .args(&[&self.metadata.id]);
+
+        if self.metadata.capabilities.contains(&Capability::Network) {
+            builder.inherit_network().allow_ip_name_lookup(true);
+        }
+
+        for mount in &self.mounts {
+            let (dir_perms, file_perms) = if mount.writable {
+                (DirPerms::all(), FilePerms::all())
+            } else {
+                (DirPerms::READ, FilePerms::READ)
+            };
+            builder
+                .preopened_dir(&mount.host, &mount.guest, dir_perms, file_perms)
+                .map_err(|e| {
+                    ToolManagerError::ExecutionError(format!(
+                        "Failed to mount {}: {}",
+                        mount.host.display(),
+                        e
+                    ))
+                })?;
+        }
+
+        Ok(builder.build_p1())
+    }
+}
+
+#[async_trait]
+impl Tool for WasmPluginTool {
+    fn metadata(&self) -> &ToolMetadata {
+        &self.metadata
+    }
+
+    fn validate_parameters(
+        &self,
+        parameters: &HashMap<String, String>,
+    ) -> Result<(), ToolManagerError> {
+        validate_parameter_definitions(&self.metadata.parameters, parameters)
+    }
+
+    async fn execute(&self, context: ToolContext) -> Result<ToolResult, ToolManagerError> {
+        let start_time = Instant::now();
+        let id = &self.metadata.id;
+
+        let input = serde_json::to_vec(&serde_json::json!({
+            "request_id": context.request_id,
+            "parameters": context.parameters,
+        }))
+        .map_err(|e| ToolManagerError::SerializationError(e.to_string()))?;
+
+        let stdout = MemoryOutputPipe::new(self.limits.max_output_bytes);
+        let stderr = MemoryOutputPipe::new(self.limits.max_output_bytes);
+        let state = PluginState {
+            wasi: self.build_wasi(input, &stdout, &stderr)?,
+            limits: StoreLimitsBuilder::new()
+                .memory_size((self.limits.memory_mb * 1024 * 1024) as usize)
+                .instances(1)
+                .build(),
+        };
+
+        let mut store = Store::new(self.instance_pre.module().engine(), state);
+        store.limiter(|state| &mut state.limits);
+        store
+            .set_fuel(self.limits.fuel)
+            .and_then(|_| store.fuel_async_yield_interval(Some(FUEL_YIELD_INTERVAL)))
+            .map_err(|e| ToolManagerError::ExecutionError(e.to_string()))?;
+
+        let run = async {
+            let instance = self.instance_pre.instantiate_async(&mut store).await?;
+            let entry = instance.get_typed_func::<(), ()>(&mut store, "_start")?;
+            entry.call_async(&mut store, ()).await
+        };
+        let timeout = Duration::from_millis(self.limits.timeout_ms);
+        let outcome = tokio::time::timeout(timeout, run).await.map_err(|_| {
+            ToolManagerError::ExecutionError(format!(
+                "Plugin {} timed out after {} ms",
+                id, self.limits.timeout_ms
+            ))
+        })?;
+
+        let exit_code = match outcome {
+            Ok(()) => 0,
+            Err(e) => match e.downcast_ref::<I32Exit>() {
+                Some(exit) => exit.0,
+                None if e.downcast_ref::<Trap>() == Some(&Trap::OutOfFuel) => {
+                    return Err(ToolManagerError::ExecutionError(format!(
+                        "Plugin {} exceeded its fuel limit of {}",
+                        id, self.limits.fuel
+                    )));
+                }
+                None => {
+                    return Err(ToolManagerError::ExecutionError(format!(
+                        "Plugin {} failed: {:#}",
+                        id, e
+                    )));
+                }
+            },
+        };
+
+        let fuel_consumed = self.limits.fuel - store.get_fuel().unwrap_or(0);
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        let data = String::from_utf8_lossy(&stdout.contents()).into_owned();
+        let error = if exit_code == 0 {
+            String::new()
+        } else {
+            let stderr = String::from_utf8_lossy(&stderr.contents())
+                .trim()
+                .to_string();
+            if stderr.is_empty() {
+                format!("Plugin {} exited with code {}", id, exit_code)
+            } else {
+                stderr
+            }
+        };
+
+        let mut metadata = HashMap::new();
+        metadata.insert("plugin".to_string(), self.module_path.display().to_string());
+        metadata.insert("exit_code".to_string(), exit_code.to_string());
+        metadata.insert("fuel_consumed".to_string(), fuel_consumed.to_string());
+        metadata.insert("execution_time_ms".to_string(), duration_ms.to_string());
+
+        Ok(ToolResult {
+            success: exit_code == 0,
+            data,
+            error,
+            metadata,
+            duration_ms,
+        })
+    }
+}
+
+/// (path, modified, len) of a manifest and the file it depends on, used to detect changes
+pub type PluginFingerprint = Vec<(PathBuf, Option<SystemTime>, u64)>;
+
+/// A manifest seen by `ToolManager::load_tools_from_directory`
+#[derive(Debug, Clone)]
+pub struct LoadedPlugin {
+    /// Registered tool id and version, or `None` if the current files failed to load
+    pub tool: Option<(String, String)>,
+    /// Module or OpenAPI spec the manifest refers to
+    pub dependency: PathBuf,
+    pub fingerprint: PluginFingerprint,
+}
+
+pub fn fingerprint(manifest_path: &Path, dependency: &Path) -> PluginFingerprint {
+    [manifest_path, dependency]
+        .iter()
+        .map(|path| {
+            let meta = std::fs::metadata(path).ok();
+            let modified = meta.as_ref().and_then(|m| m.modified().ok());
+            let len = meta.map(|m| m.len()).unwrap_or(0);
+            (path.to_path_buf(), modified, len)
+        })
+        .collect()
+}
+
+/// Module path a manifest refers to, falling back to the default when it doesn't parse
+pub fn module_path_for(manifest_path: &Path) -> PathBuf {
+    std::fs::read_to_string(manifest_path)
+        .ok()
+        .and_then(|source| toml::from_str::<PluginManifest>(&source).ok())
+        .map(|manifest| manifest.module_path(manifest_path))
+        .unwrap_or_else(|| manifest_path.with_extension("wasm"))
+}
+
+/// Tool manifests (`*.toml`, `*.yaml`, `*.yml`) in a directory, sorted by path
+pub fn find_manifests(dir: &Path) -> Result<Vec<PathBuf>, ToolManagerError> {
+    let entries = std::fs::read_dir(dir).map_err(|e| {
+        ToolManagerError::LoadingError(format!(
+            "Cannot read plugin directory {}: {}",
+            dir.display(),
+            e
+        ))
+    })?;
+
+    let mut manifests: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext == "toml" || ext == "yaml" || ext == "yml")
+        })
+        .collect();
+    manifests.sort();
+    Ok(manifests)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool_manager::ToolManager;
+
+    const ECHO_WAT: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+          (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (func (export "_start")
+            (i32.store (i32.const 0) (i32.const 64))
+            (i32.store (i32.const 4) (i32.const 4096))
+            (drop (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8)))
+            (i32.store (i32.const 4) (i32.load (i32.const 8)))
+            (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 12)))))
+    "#;
+
+    const EXIT_WAT: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+          (memory (export "memory") 1)
+          (func (export "_start") (call $proc_exit (i32.const 3))))
+    "#;
+
+    const LOOP_WAT: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "_start") (loop $spin (br $spin))))
+    "#;
+
+    fn plugin_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("wasm-plugins-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Write `<id>.toml` plus a text-format module; wasmtime compiles WAT directly
+    fn write_plugin(dir: &Path, id: &str, version: &str, wat: &str, extra: &str) -> PathBuf {
+        std::fs::write(dir.join(format!("{}.wat", id)), wat).unwrap();
+        let manifest = format!(
+            "id = \"{id}\"\nname = \"{id}\"\nversion = \"{version}\"\nmodule = \"{id}.wat\"\n{extra}"
+        );
+        let path = dir.join(format!("{}.toml", id));
+        std::fs::write(&path, manifest).unwrap();
+        path
+    }
+
+    fn context(parameters: &[(&str, &str)]) -> ToolContext {
+        ToolContext {
+            parameters: parameters
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            user_id: None,
+            session_id: None,
+            request_id: "test".to_string(),
+            context_data: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_manifest_mounts_require_filesystem_capability() {
+        let source = "id = \"a\"\nname = \"A\"\nversion = \"1.0.0\"\n[[mounts]]\nhost = \"data\"\nguest = \"/data\"\n";
+        assert!(matches!(
+            PluginManifest::parse(source),
+            Err(ToolManagerError::MissingCapability(_))
+        ));
+
+        let granted = format!("capabilities = [\"FileSystem\"]\n{}", source);
+        let manifest = PluginManifest::parse(&granted).unwrap();
+        assert_eq!(manifest.category, "plugin");
+        assert_eq!(
+            manifest.module_path(Path::new("plugins/a.toml")),
+            PathBuf::from("plugins/a.wasm")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plugin_receives_parameters_and_returns_stdout() {
+        let dir = plugin_dir("echo");
+        let manifest = write_plugin(
+            &dir,
+            "echo",
+            "1.0.0",
+            ECHO_WAT,
+            "[[parameters]]\nname = \"text\"\ndescription = \"Text\"\nrequired = true\nparam_type = \"string\"\n",
+        );
+        let tool = WasmPluginTool::load(&manifest).unwrap();
+
+        assert!(tool.validate_parameters(&HashMap::new()).is_err());
+        let result = tool.execute(context(&[("text", "hello")])).await.unwrap();
+        assert!(result.success);
+        let echoed: serde_json::Value = serde_json::from_str(&result.data).unwrap();
+        assert_eq!(echoed["parameters"]["text"], "hello");
+        assert_eq!(result.metadata["exit_code"], "0");
+    }
+
+    #[tokio::test]
+    async fn test_plugin_exit_code_and_fuel_limit() {
+        let dir = plugin_dir("limits");
+        let exit =
+            WasmPluginTool::load(&write_plugin(&dir, "exit", "1.0.0", EXIT_WAT, "")).unwrap();
+        let result = exit.execute(context(&[])).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.metadata["exit_code"], "3");
+
+        let spin = WasmPluginTool::load(&write_plugin(
+            &dir,
+            "spin",
+            "1.0.0",
+            LOOP_WAT,
+            "[limits]\nfuel = 100000\n",
+        ))
+        .unwrap();
+        let err = spin.execute(context(&[])).await.unwrap_err();
+        assert!(err.to_string().contains("fuel"));
+    }
+
+    #[test]
+    fn test_directory_sync_registers_updates_and_unregisters() {
+        let dir = plugin_dir("reload");
+        let manager = ToolManager::new();
+        let directory = dir.to_str().unwrap();
+
+        write_plugin(&dir, "echo", "1.0.0", ECHO_WAT, "");
+        assert_eq!(
+            manager.load_tools_from_directory(directory).unwrap(),
+            vec!["echo"]
+        );
+        // Unchanged files are not reloaded
+        assert!(manager
+            .load_tools_from_directory(directory)
+            .unwrap()
+            .is_empty());
+
+        write_plugin(&dir, "echo", "1.10.0", ECHO_WAT, "");
+        assert_eq!(
+            manager.load_tools_from_directory(directory).unwrap(),
+            vec!["echo"]
+        );
+        assert_eq!(
+            manager.get_tool("echo").unwrap().metadata().version,
+            "1.10.0"
+        );
+
+        std::fs::remove_file(dir.join("echo.toml")).unwrap();
+        assert!(manager
+            .load_tools_from_directory(directory)
+            .unwrap()
+            .is_empty());
+        assert!(manager.get_tool("echo").is_err());
+    }
+}
@@ -0,0 +1,785 @@
+//! Session Workspaces
+//!
+//! Every file tool works inside a workspace root: `<TOOLS_WORKSPACE_ROOT>/session-<id>`
+//! for requests with a session, `<TOOLS_WORKSPACE_ROOT>/shared` otherwise
+//! (default root `workspaces`). Tool paths are relative to that root; absolute
+//! paths are only accepted if they already point inside it. Paths are checked
+//! with the `input-validation-rs` path sanitizers and, after resolving
+//! symlinks, must still lie inside the root.
+//!
+//! This module also provides the `list_dir` and `grep` tools.
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use input_validation_rs::sanitizers;
+use regex::{Regex, RegexBuilder};
+
+use crate::tool_manager::{
+    Capability, ParameterDefinition, Tool, ToolContext, ToolManagerError, ToolMetadata, ToolResult,
+};
+use crate::validation::ToolValidationError;
+
+/// Files larger than this are skipped by `grep`
+const MAX_GREP_FILE_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Directories `list_dir` and `grep` do not descend into
+const SKIPPED_DIRS: [&str; 3] = [".git", "node_modules", "target"];
+
+/// A directory file tools are jailed to
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    root: PathBuf,
+}
+
+/// Directory holding the session workspaces, from TOOLS_WORKSPACE_ROOT
+pub fn workspace_base() -> PathBuf {
+    PathBuf::from(env::var("TOOLS_WORKSPACE_ROOT").unwrap_or_else(|_| "workspaces".to_string()))
+}
+
+impl Workspace {
+    /// Workspace of a session below `base`, created on first use
+    pub fn for_session(base: &Path, session_id: Option<&str>) -> Result<Self, ToolManagerError> {
+        let dir = match session_id {
+            Some(id) => format!(
+                "session-{}",
+                sanitizers::sanitize_filename(id)
+                    .sanitized
+                    .replace(' ', "_")
+            ),
+            None => "shared".to_string(),
+        };
+        Self::at(base.join(dir))
+    }
+
+    /// Workspace rooted at `root`, created if missing
+    pub fn at(root: impl AsRef<Path>) -> Result<Self, ToolManagerError> {
+        let root = root.as_ref();
+        fs::create_dir_all(root).map_err(|e| {
+            ToolManagerError::ExecutionError(format!(
+                "Failed to create workspace {}: {}",
+                root.display(),
+                e
+            ))
+        })?;
+        let root = root.canonicalize().map_err(|e| {
+            ToolManagerError::ExecutionError(format!(
+                "Failed to resolve workspace {}: {}",
+                root.display(),
+                e
+            ))
+        })?;
+        Ok(Self { root })
+    }
+
+    #[cfg(test)]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolve a tool path to an absolute path inside the workspace
+    ///
+    /// The path does not have to exist; its deepest existing ancestor must
+    /// resolve inside the workspace.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, ToolManagerError> {
+        check_path_syntax(path).map_err(ToolManagerError::ValidationError)?;
+
+        let requested = Path::new(path);
+        let joined = if requested.is_absolute() {
+            requested.to_path_buf()
+        } else {
+            self.root.join(requested)
+        };
+
+        let root = self.root.to_string_lossy();
+        let confined = sanitizers::confine_path(&joined.to_string_lossy(), &root);
+        if confined.was_modified {
+            return Err(outside_workspace(path));
+        }
+
+        // Lexically normalise, then resolve symlinks of the existing part
+        let mut normalised = PathBuf::new();
+        for component in joined.components() {
+            match component {
+                Component::ParentDir => {
+                    normalised.pop();
+                }
+                Component::CurDir => {}
+                other => normalised.push(other),
+            }
+        }
+        if !normalised.starts_with(&self.root) {
+            return Err(outside_workspace(path));
+        }
+
+        let mut existing = normalised.as_path();
+        let mut rest = Vec::new();
+        while !existing.exists() {
+            match (existing.parent(), existing.file_name()) {
+                (Some(parent), Some(name)) => {
+                    rest.push(name.to_os_string());
+                    existing = parent;
+                }
+                _ => return Err(outside_workspace(path)),
+            }
+        }
+        let mut resolved = existing.canonicalize().map_err(|e| {
+            ToolManagerError::ExecutionError(format!("Failed to resolve {}: {}", path, e))
+        })?;
+        if !resolved.starts_with(&self.root) {
+            return Err(outside_workspace(path));
+        }
+        for name in rest.into_iter().rev() {
+            resolved.push(name);
+        }
+        Ok(resolved)
+    }
+
+    /// Path relative to the workspace root, for output
+    pub fn display(&self, path: &Path) -> String {
+        match path.strip_prefix(&self.root) {
+            Ok(rel) if rel.as_os_str().is_empty() => ".".to_string(),
+            Ok(rel) => rel.to_string_lossy().to_string(),
+            Err(_) => path.to_string_lossy().to_string(),
+        }
+    }
+}
+
+/// Reject empty paths, traversal sequences and well-known system paths
+pub fn check_path_syntax(path: &str) -> Result<(), ToolValidationError> {
+    if path.trim().is_empty() || path.contains('\0') {
+        return Err(ToolValidationError::Other("Invalid path".to_string()));
+    }
+    if sanitizers::remove_path_traversal(path).was_modified
+        || Path::new(path)
+            .components()
+            .any(|c| c == Component::ParentDir)
+    {
+        return Err(ToolValidationError::SecurityThreat(
+            "Path contains traversal sequences".to_string(),
+        ));
+    }
+    if sanitizers::remove_dangerous_paths(path).was_modified {
+        return Err(ToolValidationError::SecurityThreat(
+            "Path points to a protected system location".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn outside_workspace(path: &str) -> ToolManagerError {
+    ToolManagerError::ValidationError(ToolValidationError::SecurityThreat(format!(
+        "Path is outside the workspace: {}",
+        path
+    )))
+}
+
+fn bool_parameter(
+    parameters: &HashMap<String, String>,
+    name: &str,
+) -> Result<(), ToolManagerError> {
+    match parameters.get(name).map(String::as_str) {
+        None | Some("true") | Some("false") => Ok(()),
+        Some(_) => Err(ToolManagerError::ValidationError(
+            ToolValidationError::Other(format!("{} must be 'true' or 'false'", name)),
+        )),
+    }
+}
+
+fn number_parameter(
+    parameters: &HashMap<String, String>,
+    name: &str,
+    max: usize,
+) -> Result<(), ToolManagerError> {
+    match parameters.get(name).map(|v| v.parse::<usize>()) {
+        None => Ok(()),
+        Some(Ok(n)) if n <= max => Ok(()),
+        Some(_) => Err(ToolManagerError::ValidationError(
+            ToolValidationError::Other(format!("{} must be a number between 0 and {}", name, max)),
+        )),
+    }
+}
+
+fn tool_metadata(
+    id: &str,
+    name: &str,
+    description: &str,
+    parameters: Vec<ParameterDefinition>,
+) -> ToolMetadata {
+    let mut capabilities = HashSet::new();
+    capabilities.insert(Capability::FileSystem);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    ToolMetadata {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        version: "1.0.0".to_string(),
+        author: "System".to_string(),
+        category: "filesystem".to_string(),
+        parameters,
+        capabilities,
+        enabled: true,
+        created_at: now,
+        updated_at: now,
+        deprecated: None,
+    }
+}
+
+fn parameter(
+    name: &str,
+    description: &str,
+    param_type: &str,
+    default: Option<&str>,
+) -> ParameterDefinition {
+    ParameterDefinition {
+        name: name.to_string(),
+        description: description.to_string(),
+        required: default.is_none(),
+        param_type: param_type.to_string(),
+        default: default.map(str::to_string),
+        validation: None,
+    }
+}
+
+/// Files below `dir` in path order, skipping SKIPPED_DIRS and symlinks
+fn walk_files(dir: &Path, files: &mut Vec<PathBuf>, limit: usize) -> std::io::Result<()> {
+    let mut entries: Vec<_> = fs::read_dir(dir)?.filter_map(|e| e.ok()).collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        if files.len() >= limit {
+            break;
+        }
+        let file_type = entry.file_type()?;
+        let path = entry.path();
+        if file_type.is_dir() {
+            if !SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref()) {
+                walk_files(&path, files, limit)?;
+            }
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// List Directory Tool
+pub struct ListDirTool {
+    metadata: ToolMetadata,
+    workspace_base: PathBuf,
+}
+
+impl ListDirTool {
+    pub fn new() -> Self {
+        Self::with_workspace_base(workspace_base())
+    }
+
+    pub fn with_workspace_base(workspace_base: PathBuf) -> Self {
+        Self {
+            workspace_base,
+            metadata: tool_metadata(
+                "list_dir",
+                "List Directory",
+                "Lists files and directories in the session workspace",
+                vec![
+                    parameter(
+                        "path",
+                        "Directory relative to the workspace",
+                        "string",
+                        Some("."),
+                    ),
+                    parameter(
+                        "recursive",
+                        "Whether to list subdirectories recursively",
+                        "boolean",
+                        Some("false"),
+                    ),
+                    parameter("max_entries", "Entries to return", "int", Some("500")),
+                ],
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ListDirTool {
+    fn metadata(&self) -> &ToolMetadata {
+        &self.metadata
+    }
+
+    fn validate_parameters(
+        &self,
+        parameters: &HashMap<String, String>,
+    ) -> Result<(), ToolManagerError> {
+        if let Some(path) = parameters.get("path") {
+            check_path_syntax(path).map_err(ToolManagerError::ValidationError)?;
+        }
+        bool_parameter(parameters, "recursive")?;
+        number_parameter(parameters, "max_entries", 10_000)
+    }
+
+    async fn execute(&self, context: ToolContext) -> Result<ToolResult, ToolManagerError> {
+        let start_time = Instant::now();
+
+        let workspace =
+            Workspace::for_session(&self.workspace_base, context.session_id.as_deref())?;
+        let path = context
+            .parameters
+            .get("path")
+            .cloned()
+            .unwrap_or_else(|| ".".to_string());
+        let recursive = context
+            .parameters
+            .get("recursive")
+            .is_some_and(|v| v == "true");
+        let max_entries = context
+            .parameters
+            .get("max_entries")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(500);
+
+        let dir = workspace.resolve(&path)?;
+        if !dir.is_dir() {
+            return Err(ToolManagerError::ExecutionError(format!(
+                "Not a directory: {}",
+                path
+            )));
+        }
+
+        let mut lines = Vec::new();
+        let mut truncated = false;
+        let mut pending = vec![dir.clone()];
+        while let Some(current) = pending.pop() {
+            let mut entries: Vec<_> = fs::read_dir(&current)
+                .map_err(|e| {
+                    ToolManagerError::ExecutionError(format!("Failed to list {}: {}", path, e))
+                })?
+                .filter_map(|e| e.ok())
+                .collect();
+            entries.sort_by_key(|e| e.file_name());
+
+            let mut subdirs = Vec::new();
+            for entry in entries {
+                if lines.len() >= max_entries {
+                    truncated = true;
+                    break;
+                }
+                let entry_path = entry.path();
+                let name = workspace.display(&entry_path);
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if file_type.is_dir() {
+                    lines.push(format!("{}/", name));
+                    if recursive
+                        && !SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref())
+                    {
+                        subdirs.push(entry_path);
+                    }
+                } else if file_type.is_symlink() {
+                    lines.push(format!("{}@", name));
+                } else {
+                    let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                    lines.push(format!("{} ({} bytes)", name, size));
+                }
+            }
+            if truncated {
+                break;
+            }
+            pending.extend(subdirs.into_iter().rev());
+        }
+        if recursive {
+            lines.sort();
+        }
+
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        let mut metadata = HashMap::new();
+        metadata.insert("path".to_string(), workspace.display(&dir));
+        metadata.insert("entry_count".to_string(), lines.len().to_string());
+        metadata.insert("truncated".to_string(), truncated.to_string());
+        metadata.insert("execution_time_ms".to_string(), duration_ms.to_string());
+
+        let data = if lines.is_empty() {
+            "(empty directory)".to_string()
+        } else {
+            lines.join("\n")
+        };
+
+        Ok(ToolResult {
+            success: true,
+            data,
+            error: String::new(),
+            metadata,
+            duration_ms,
+        })
+    }
+}
+
+/// Grep Tool
+pub struct GrepTool {
+    metadata: ToolMetadata,
+    workspace_base: PathBuf,
+}
+
+impl GrepTool {
+    pub fn new() -> Self {
+        Self::with_workspace_base(workspace_base())
+    }
+
+    pub fn with_workspace_base(workspace_base: PathBuf) -> Self {
+        Self {
+            workspace_base,
+            metadata: tool_metadata(
+                "grep",
+                "Grep",
+                "Searches files in the session workspace with a regular expression, showing context lines",
+                vec![
+                    parameter("pattern", "Regular expression to search for", "string", None),
+                    parameter(
+                        "path",
+                        "File or directory relative to the workspace",
+                        "string",
+                        Some("."),
+                    ),
+                    parameter(
+                        "include",
+                        "Only search files whose name matches this glob, e.g. *.rs",
+                        "string",
+                        Some(""),
+                    ),
+                    parameter(
+                        "context",
+                        "Lines of context before and after each match",
+                        "int",
+                        Some("2"),
+                    ),
+                    parameter(
+                        "case_insensitive",
+                        "Whether to ignore case",
+                        "boolean",
+                        Some("false"),
+                    ),
+                    parameter("max_matches", "Matches to return", "int", Some("200")),
+                ],
+            ),
+        }
+    }
+}
+
+/// Regex matching a file name glob with `*` and `?`
+fn glob_regex(glob: &str) -> Result<Regex, regex::Error> {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            other => pattern.push_str(&regex::escape(&other.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern)
+}
+
+#[async_trait]
+impl Tool for GrepTool {
+    fn metadata(&self) -> &ToolMetadata {
+        &self.metadata
+    }
+
+    fn validate_parameters(
+        &self,
+        parameters: &HashMap<String, String>,
+    ) -> Result<(), ToolManagerError> {
+        let pattern = parameters.get("pattern").ok_or_else(|| {
+            ToolManagerError::ValidationError(ToolValidationError::Other(
+                "Missing required parameter: pattern".to_string(),
+            ))
+        })?;
+        RegexBuilder::new(pattern)
+            .size_limit(1 << 20)
+            .build()
+            .map_err(|e| {
+                ToolManagerError::ValidationError(ToolValidationError::Other(format!(
+                    "Invalid pattern: {}",
+                    e
+                )))
+            })?;
+        if let Some(path) = parameters.get("path") {
+            check_path_syntax(path).map_err(ToolManagerError::ValidationError)?;
+        }
+        bool_parameter(parameters, "case_insensitive")?;
+        number_parameter(parameters, "context", 20)?;
+        number_parameter(parameters, "max_matches", 5_000)
+    }
+
+    async fn execute(&self, context: ToolContext) -> Result<ToolResult, ToolManagerError> {
+        let start_time = Instant::now();
+
+        let workspace =
+            Workspace::for_session(&self.workspace_base, context.session_id.as_deref())?;
+        let params = &context.parameters;
+        let path = params.get("path").map(String::as_str).unwrap_or(".");
+        let context_lines: usize = params
+            .get("context")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(2);
+        let max_matches: usize = params
+            .get("max_matches")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(200);
+        let regex = RegexBuilder::new(params.get("pattern").unwrap())
+            .case_insensitive(params.get("case_insensitive").is_some_and(|v| v == "true"))
+            .size_limit(1 << 20)
+            .build()
+            .map_err(|e| ToolManagerError::ExecutionError(format!("Invalid pattern: {}", e)))?;
+        let include = match params.get("include").filter(|g| !g.is_empty()) {
+            Some(glob) => Some(glob_regex(glob).map_err(|e| {
+                ToolManagerError::ExecutionError(format!("Invalid include glob: {}", e))
+            })?),
+            None => None,
+        };
+
+        let target = workspace.resolve(path)?;
+        let mut files = Vec::new();
+        if target.is_dir() {
+            walk_files(&target, &mut files, 100_000).map_err(|e| {
+                ToolManagerError::ExecutionError(format!("Failed to walk {}: {}", path, e))
+            })?;
+        } else if target.is_file() {
+            files.push(target.clone());
+        } else {
+            return Err(ToolManagerError::ExecutionError(format!(
+                "No such file or directory: {}",
+                path
+            )));
+        }
+
+        let mut output = Vec::new();
+        let mut match_count = 0;
+        let mut files_with_matches = 0;
+        let mut truncated = false;
+        'files: for file in &files {
+            if let Some(include) = &include {
+                let name = file.file_name().unwrap_or_default().to_string_lossy();
+                if !include.is_match(&name) {
+                    continue;
+                }
+            }
+            if fs::metadata(file).map(|m| m.len()).unwrap_or(0) > MAX_GREP_FILE_BYTES {
+                continue;
+            }
+            let Ok(bytes) = fs::read(file) else {
+                continue;
+            };
+            if bytes[..bytes.len().min(8192)].contains(&0) {
+                continue;
+            }
+            let text = String::from_utf8_lossy(&bytes);
+            let lines: Vec<&str> = text.lines().collect();
+            let name = workspace.display(file);
+
+            // Emit grep-style groups: "file:n:match", "file-n-context", "--" between groups
+            let mut last_printed: Option<usize> = None;
+            let mut file_matched = false;
+            for (i, line) in lines.iter().enumerate() {
+                if !regex.is_match(line) {
+                    continue;
+                }
+                if match_count >= max_matches {
+                    truncated = true;
+                    break 'files;
+                }
+                match_count += 1;
+                file_matched = true;
+
+                let start = i.saturating_sub(context_lines);
+                let start = last_printed.map_or(start, |last| start.max(last + 1));
+                if last_printed.is_none_or(|last| start > last + 1) && !output.is_empty() {
+                    output.push("--".to_string());
+                }
+                for (j, context_line) in lines.iter().enumerate().take(i).skip(start) {
+                    output.push(format!("{}-{}-{}", name, j + 1, context_line));
+                }
+                output.push(format!("{}:{}:{}", name, i + 1, line));
+                last_printed = Some(i);
+
+                // Trailing context stops before the next match, which prints itself
+                let end = (i + context_lines).min(lines.len() - 1);
+                for (j, context_line) in lines.iter().enumerate().take(end + 1).skip(i + 1) {
+                    if regex.is_match(context_line) {
+                        break;
+                    }
+                    output.push(format!("{}-{}-{}", name, j + 1, context_line));
+                    last_printed = Some(j);
+                }
+            }
+            if file_matched {
+                files_with_matches += 1;
+            }
+        }
+
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        let mut metadata = HashMap::new();
+        metadata.insert("match_count".to_string(), match_count.to_string());
+        metadata.insert(
+            "files_with_matches".to_string(),
+            files_with_matches.to_string(),
+        );
+        metadata.insert("files_searched".to_string(), files.len().to_string());
+        metadata.insert("truncated".to_string(), truncated.to_string());
+        metadata.insert("execution_time_ms".to_string(), duration_ms.to_string());
+
+        let data = if output.is_empty() {
+            "No matches found.".to_string()
+        } else {
+            output.join("\n")
+        };
+
+        Ok(ToolResult {
+            success: true,
+            data,
+            error: String::new(),
+            metadata,
+            duration_ms,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workspace_base(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("workspace-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn context(params: &[(&str, &str)]) -> ToolContext {
+        ToolContext {
+            parameters: params
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            user_id: None,
+            session_id: None,
+            request_id: "test".to_string(),
+            context_data: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_stays_inside_workspace() {
+        let base = workspace_base("resolve");
+        let workspace = Workspace::for_session(&base, Some("abc/../1")).unwrap();
+        let root = workspace.root().to_path_buf();
+        assert_eq!(root.file_name().unwrap(), "session-abc_.._1");
+        fs::create_dir_all(root.join("src")).unwrap();
+
+        assert_eq!(
+            workspace.resolve("src/main.rs").unwrap(),
+            root.join("src/main.rs")
+        );
+        assert_eq!(
+            workspace.resolve("./new/file.txt").unwrap(),
+            root.join("new/file.txt")
+        );
+        assert_eq!(
+            workspace
+                .resolve(&root.join("src").to_string_lossy())
+                .unwrap(),
+            root.join("src")
+        );
+
+        assert!(workspace.resolve("../escape.txt").is_err());
+        assert!(workspace.resolve("src/../../escape.txt").is_err());
+        assert!(workspace.resolve("/etc/passwd").is_err());
+        assert!(workspace.resolve("/tmp/elsewhere").is_err());
+
+        // A sibling sharing the root as a string prefix is still outside
+        let sibling = format!("{}-other/file", root.to_string_lossy());
+        assert!(workspace.resolve(&sibling).is_err());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("/tmp", root.join("link")).unwrap();
+            assert!(workspace.resolve("link/file.txt").is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_dir() {
+        let base = workspace_base("list");
+        let root = Workspace::for_session(&base, None)
+            .unwrap()
+            .root()
+            .to_path_buf();
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        fs::write(root.join("README.md"), "hello").unwrap();
+        fs::write(root.join("src/lib.rs"), "").unwrap();
+        fs::write(root.join("src/nested/mod.rs"), "x").unwrap();
+
+        let tool = ListDirTool::with_workspace_base(base);
+        let result = tool.execute(context(&[])).await.unwrap();
+        assert_eq!(result.data, "README.md (5 bytes)\nsrc/");
+
+        let result = tool
+            .execute(context(&[("recursive", "true")]))
+            .await
+            .unwrap();
+        assert_eq!(
+            result.data,
+            "README.md (5 bytes)\nsrc/\nsrc/lib.rs (0 bytes)\nsrc/nested/\nsrc/nested/mod.rs (1 bytes)"
+        );
+        assert!(tool.execute(context(&[("path", "..")])).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_grep_with_context() {
+        let base = workspace_base("grep");
+        let root = Workspace::for_session(&base, None)
+            .unwrap()
+            .root()
+            .to_path_buf();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(
+            root.join("src/lib.rs"),
+            "one\ntwo\nfn alpha() {}\nthree\nfour\nfive\nsix\nfn beta() {}\nseven\n",
+        )
+        .unwrap();
+        fs::write(root.join("notes.txt"), "fn gamma\n").unwrap();
+
+        let tool = GrepTool::with_workspace_base(base);
+        let result = tool
+            .execute(context(&[
+                ("pattern", r"fn \w+\("),
+                ("context", "1"),
+                ("include", "*.rs"),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(
+            result.data,
+            "src/lib.rs-2-two\nsrc/lib.rs:3:fn alpha() {}\nsrc/lib.rs-4-three\n--\n\
+             src/lib.rs-7-six\nsrc/lib.rs:8:fn beta() {}\nsrc/lib.rs-9-seven"
+        );
+        assert_eq!(result.metadata["match_count"], "2");
+
+        let result = tool
+            .execute(context(&[
+                ("pattern", "FN GAMMA"),
+                ("case_insensitive", "true"),
+            ]))
+            .await
+            .unwrap();
+        assert_eq!(result.data, "notes.txt:1:fn gamma");
+    }
+}